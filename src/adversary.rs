@@ -0,0 +1,145 @@
+use crate::mock::{Action, Behaviours, DeclarativeBehaviour, Mock, Response};
+use crate::utils::{rlp_list, rlp_string};
+use discv5::enr::{CombinedKey, EnrKey, EnrPublicKey, NodeId};
+use discv5::Enr;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+
+// The number of ENRs in the oversized NODES response, which exceeds the maximum packet size of
+// 1280 bytes.
+const OVERSIZED_NODES: usize = 24;
+
+/// The crafted NODES responses an adversary returns to FINDNODE.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub(crate) enum Attack {
+    /// ENRs with a tampered signature, or with a node id inconsistent with the signing key.
+    Forged,
+    /// A validly signed ENR claiming the IP address of another node.
+    SpoofedIp,
+    /// More validly signed ENRs than fit in a NODES packet.
+    Oversized,
+}
+
+/// The ENRs of a crafted NODES response.
+pub(crate) struct Crafted {
+    /// The node ids claimed by the ENRs.
+    pub(crate) node_ids: Vec<NodeId>,
+    /// The RLP-encoded ENRs.
+    pub(crate) enrs: Vec<Vec<u8>>,
+}
+
+/// Craft the ENRs of `attack`. The ENRs claim `socket`; for `Attack::SpoofedIp`, it should be the
+/// socket address of someone else.
+pub(crate) fn craft(
+    attack: Attack,
+    socket: SocketAddr,
+) -> Result<Crafted, Box<dyn std::error::Error>> {
+    let (claimed, enrs) = match attack {
+        // - An ENR whose signature has been tampered with.
+        // - An ENR whose node id, i.e. the public key, is inconsistent with the key it's signed
+        //   with.
+        Attack::Forged => {
+            let claimed = vec![
+                CombinedKey::generate_secp256k1(),
+                CombinedKey::generate_secp256k1(),
+            ];
+            let enrs = vec![
+                forge_enr(&claimed[0], &claimed[0], socket, true)?,
+                forge_enr(
+                    &CombinedKey::generate_secp256k1(),
+                    &claimed[1],
+                    socket,
+                    false,
+                )?,
+            ];
+            (claimed, enrs)
+        }
+        Attack::SpoofedIp => {
+            let claimed = vec![CombinedKey::generate_secp256k1()];
+            let enrs = vec![forge_enr(&claimed[0], &claimed[0], socket, false)?];
+            (claimed, enrs)
+        }
+        Attack::Oversized => {
+            let claimed = (0..OVERSIZED_NODES)
+                .map(|_| CombinedKey::generate_secp256k1())
+                .collect::<Vec<_>>();
+            let enrs = claimed
+                .iter()
+                .map(|key| forge_enr(key, key, socket, false))
+                .collect::<Result<Vec<_>, _>>()?;
+            (claimed, enrs)
+        }
+    };
+
+    let node_ids = claimed
+        .iter()
+        .map(|key| NodeId::from(key.public()))
+        .collect();
+
+    Ok(Crafted { node_ids, enrs })
+}
+
+/// Start a responder which completes the handshake and responds to any request with the crafted
+/// ENRs.
+// NOTE: discv5 doesn't allow responding to FINDNODE with arbitrary ENRs, so the responder is the
+// mock, which sends the ENRs as is via `Response::RawNodes`.
+pub(crate) async fn start(
+    enr: Enr,
+    enr_key: CombinedKey,
+    config: discv5::Config,
+    crafted: Crafted,
+) -> Mock {
+    let response = Action::SendResponse(Response::RawNodes(crafted.enrs));
+    let behaviours = Behaviours::Declarative(DeclarativeBehaviour {
+        whoareyou: vec![],
+        message_without_session: vec![Action::SendWhoAreYou],
+        handshake: vec![Action::EstablishSession, response.clone()],
+        message: vec![response],
+    });
+    Mock::start(enr, enr_key, config, behaviours).await
+}
+
+// Forge an RLP-encoded ENR which claims the public key of `claimed_key`, but is signed with
+// `signing_key`. If `tamper` is true, the signature is tampered with after signing.
+fn forge_enr(
+    signing_key: &CombinedKey,
+    claimed_key: &CombinedKey,
+    socket: SocketAddr,
+    tamper: bool,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let (ip_key, udp_key, ip): (&[u8], &[u8], _) = match socket.ip() {
+        IpAddr::V4(ip) => (b"ip", b"udp", ip.octets().to_vec()),
+        IpAddr::V6(ip) => (b"ip6", b"udp6", ip.octets().to_vec()),
+    };
+    let udp = socket
+        .port()
+        .to_be_bytes()
+        .into_iter()
+        .skip_while(|b| *b == 0)
+        .collect::<Vec<_>>();
+
+    // The key/value pairs must be sorted by key.
+    let content = vec![
+        rlp_string(&[1]),
+        rlp_string(b"id"),
+        rlp_string(b"v4"),
+        rlp_string(ip_key),
+        rlp_string(&ip),
+        rlp_string(b"secp256k1"),
+        rlp_string(&claimed_key.public().encode()),
+        rlp_string(udp_key),
+        rlp_string(&udp),
+    ];
+
+    let mut signature = signing_key
+        .sign_v4(&rlp_list(&content))
+        .map_err(|e| format!("Failed to sign the ENR: {e:?}"))?;
+    if tamper {
+        signature[0] ^= 0xff;
+    }
+
+    let mut record = vec![rlp_string(&signature)];
+    record.extend(content);
+
+    Ok(rlp_list(&record))
+}
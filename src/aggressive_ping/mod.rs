@@ -0,0 +1,190 @@
+mod params;
+
+use crate::aggressive_ping::params::Params;
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, listen_config,
+    publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::Discv5;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_OBSERVE: &str = "state_completed_to_observe";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("aggressive-ping", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+        .ping_interval(Duration::from_secs(params.ping_interval))
+        .build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // Every node knows all the other nodes, and then establishes sessions with them.
+    for p in participants.iter().filter(|p| p.seq != instance_info.seq) {
+        discv5.add_enr(p.enr.clone())?;
+    }
+    if let Err(e) = rpc_stats.find_node(&discv5, NodeId::random()).await {
+        error!("Failed to run FINDNODE query: {e}");
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Observe the PING rate, CPU usage and freshness of the table
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+    let peers = participants.len() - 1;
+    // Each peer is expected to PING this node once per `ping_interval`. Allow twice that to
+    // tolerate requests caused by session re-establishment.
+    let max_ping_rate = 2.0 * peers as f64 / params.ping_interval.max(1) as f64;
+
+    let started_at = Instant::now();
+    let initial_cpu_ticks = cpu_ticks();
+    let mut max_observed_rate = 0_f64;
+
+    while started_at.elapsed() < Duration::from_secs(params.duration) {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let metrics = discv5.metrics();
+        let connected = connected_peers(&discv5);
+        let cpu_ticks = cpu_ticks()
+            .zip(initial_cpu_ticks)
+            .map(|(current, initial)| current - initial)
+            .unwrap_or_default();
+        max_observed_rate = max_observed_rate.max(metrics.unsolicited_requests_per_second);
+        info!(
+            "ping rate: {}, connected: {connected}/{peers}, cpu ticks: {cpu_ticks}",
+            metrics.unsolicited_requests_per_second
+        );
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("elapsed_ms", started_at.elapsed().as_millis() as u64)
+        .add_field("ping_rate", metrics.unsolicited_requests_per_second)
+        .add_field("bytes_sent", metrics.bytes_sent as u64)
+        .add_field("bytes_recv", metrics.bytes_recv as u64)
+        .add_field("connected", connected as u64)
+        .add_field("cpu_ticks", cpu_ticks)
+        .add_tag("instance_seq", instance_info.seq);
+        record_metric(&client, write_query).await;
+    }
+
+    if max_observed_rate > max_ping_rate {
+        error!("Too many PINGs. max observed rate: {max_observed_rate}, allowed: {max_ping_rate}");
+        failed = true;
+    }
+
+    let connected = connected_peers(&discv5);
+    if connected != peers {
+        error!("The table isn't fresh. connected: {connected}, expected: {peers}");
+        failed = true;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_OBSERVE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+fn connected_peers(discv5: &Discv5) -> usize {
+    discv5
+        .kbuckets()
+        .iter()
+        .filter(|entry| entry.status.is_connected())
+        .count()
+}
+
+// The CPU time consumed by this process (user + system) in clock ticks.
+fn cpu_ticks() -> Option<u64> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // The fields after the command name, which is wrapped in parentheses, are space-separated.
+    // `utime` and `stime` are the 14th and 15th fields.
+    let mut fields = stat.rsplit_once(')')?.1.split_whitespace().skip(11);
+    let utime = fields.next()?.parse::<u64>().ok()?;
+    let stime = fields.next()?.parse::<u64>().ok()?;
+    Some(utime + stime)
+}
@@ -0,0 +1,225 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, listen_config,
+    publish_and_collect, record_metric, InstanceInfo, MAX_DISTANCE,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Key};
+use std::collections::HashSet;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+// The maximum number of nodes the responder returns in a NODES response.
+const MAX_NODES_RESPONSE: usize = 16;
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("all-distances", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+        .max_nodes_response(MAX_NODES_RESPONSE)
+        .build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the responder. It knows all the other participants, so the content of its
+    // buckets can be derived from the collected information.
+    let responder = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("responder")
+        .clone();
+
+    if instance_info.seq == responder.seq {
+        for p in participants.iter().filter(|p| p.seq != responder.seq) {
+            if let Err(e) = discv5.add_enr(p.enr.clone()) {
+                error!("Failed to add ENR: {e}");
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run FINDNODE request with all the distances
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if instance_info.seq != responder.seq {
+        let responder_key: Key<NodeId> = responder.enr.node_id().into();
+
+        // Distance 0 asks for the responder itself, and the rest cover all of its buckets. The
+        // requester itself is excluded since the responder never returns the requester's own ENR.
+        let distances = (0..=MAX_DISTANCE).collect::<Vec<_>>();
+        let expected = participants
+            .iter()
+            .filter(|p| p.seq != instance_info.seq)
+            .map(|p| p.enr.node_id())
+            .collect::<HashSet<_>>();
+
+        match rpc_stats
+            .find_node_designated_peer(&discv5, responder.enr.clone(), distances)
+            .await
+        {
+            Ok(enrs) => {
+                let received = enrs.iter().map(|enr| enr.node_id()).collect::<HashSet<_>>();
+                let unknown = received.difference(&expected).collect::<Vec<_>>();
+                let buckets = enrs
+                    .iter()
+                    .filter_map(|enr| responder_key.log2_distance(&Key::from(enr.node_id())))
+                    .collect::<HashSet<_>>();
+                client.record_message(format!(
+                    "Found {} ENRs across {} buckets. expected: {}",
+                    enrs.len(),
+                    buckets.len(),
+                    expected.len()
+                ));
+
+                if !unknown.is_empty() {
+                    error!("The response contains unknown nodes: {:?}", unknown);
+                    failed = true;
+                }
+
+                if received.len() != enrs.len() {
+                    error!(
+                        "The response contains duplicate nodes. returned: {}, unique: {}",
+                        enrs.len(),
+                        received.len()
+                    );
+                    failed = true;
+                }
+
+                let expected_size = expected.len().min(MAX_NODES_RESPONSE);
+                if enrs.len() > MAX_NODES_RESPONSE {
+                    error!(
+                        "The response exceeds the limit. limit: {MAX_NODES_RESPONSE}, actual: {}",
+                        enrs.len()
+                    );
+                    failed = true;
+                } else if received.len() < expected_size {
+                    error!(
+                        "The response is incomplete. expected: {expected_size}, actual: {}",
+                        received.len()
+                    );
+                    failed = true;
+                } else {
+                    info!(
+                        "Reassembled the nodes of all the buckets. nodes: {}",
+                        received.len()
+                    );
+                }
+
+                // //////////////////////////////////////////////////////////////
+                // Record metrics
+                // //////////////////////////////////////////////////////////////
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("response_size", enrs.len() as u64)
+                .add_field("expected_size", expected_size as u64)
+                .add_field("buckets", buckets.len() as u64)
+                .add_field("errors", 0_u64)
+                .add_tag("instance_seq", instance_info.seq);
+                record_metric(&client, write_query).await;
+            }
+            Err(e) => {
+                error!("Failed to run FINDNODE request: {e}");
+                failed = true;
+
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("response_size", 0_u64)
+                .add_field("errors", 1_u64)
+                .add_tag("instance_seq", instance_info.seq);
+                record_metric(&client, write_query).await;
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,388 @@
+mod params;
+
+use crate::alpha_benchmark::params::Params;
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr_on_port, check_enr_port, enr_addresses,
+    ip_version, percentile, publish_and_collect, publish_and_collect_on, record_metric, udp_port,
+};
+use chrono::Local;
+use discv5::enr::{CombinedKey, NodeId};
+use discv5::{Discv5, Enr};
+use rand::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const TOPIC_ALPHA_STATS: &str = "alpha_stats";
+
+// The ENRs of a test instance. Unlike `InstanceInfo`, an instance runs a node per alpha level, so
+// it has an ENR per alpha level.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct InstanceEnrs {
+    // The sequence number of this test instance within the test.
+    seq: u64,
+    // The ENRs used for each alpha level.
+    enrs: Vec<Enr>,
+}
+
+// The results of the workload under an alpha level, which are aggregated by the leader.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct AlphaStats {
+    seq: u64,
+    alpha: usize,
+    // The duration of the self-lookup to converge, which the bootstrap node doesn't run.
+    convergence_ms: Option<u64>,
+    // The latencies of the lookups which have found any ENR.
+    durations_ms: Vec<u64>,
+    failures: u64,
+    // The bytes this instance has sent and received under this alpha level.
+    bytes_sent: u64,
+    bytes_recv: u64,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("alpha-benchmark", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+    let ip_version = ip_version(&run_parameters.test_instance_params)?;
+    let udp_port = udp_port(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enrs
+    // ////////////////////////
+    // Like config-matrix, a fresh key and port are used for each alpha level so that nothing
+    // learned under the previous level is reused.
+    let mut keys = vec![];
+    let mut enrs = vec![];
+    let mut ports = vec![];
+    for i in 0..params.alpha_levels.len() {
+        let port = u16::try_from(i)
+            .ok()
+            .and_then(|i| udp_port.checked_add(i))
+            .ok_or("udp_port overflows with alpha_levels.")?;
+        let enr_key = CombinedKey::generate_secp256k1();
+        let enr = build_enr_on_port(
+            &run_parameters,
+            client.global_seq(),
+            ip_version,
+            &enr_key,
+            port,
+        )?;
+        keys.push(enr_key);
+        enrs.push(enr);
+        ports.push(port);
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceEnrs {
+        seq: client.global_seq(),
+        enrs,
+    };
+    client.record_message(format!(
+        "seq: {}, ip: {}",
+        instance_info.seq,
+        enr_addresses(&instance_info.enrs[0])
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run the same workload under each alpha level
+    // //////////////////////////////////////////////////////////////
+    let mut all_stats = vec![];
+
+    let rpc_stats = RpcStats::default();
+    for (i, (enr_key, alpha)) in keys
+        .into_iter()
+        .zip(params.alpha_levels.iter().copied())
+        .enumerate()
+    {
+        // ////////////////////////
+        // Start discv5
+        // ////////////////////////
+        let listen_config = ip_version.listen_config(ports[i]);
+        check_enr_port(&instance_info.enrs[i], &listen_config)?;
+        let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+            .query_parallelism(alpha)
+            .build();
+        let mut discv5: Discv5 = Discv5::new(instance_info.enrs[i].clone(), enr_key, config)?;
+        discv5.start().await.map_err(|e| {
+            format!("Failed to start the Discovery v5 server under alpha {alpha}: {e:?}")
+        })?;
+
+        // NOTE: The metrics of discv5 are cumulative, so the bandwidth of this level is the
+        // difference from the start.
+        let metrics_at_start = discv5.metrics();
+
+        // //////////////////////////////////////////////////////////////
+        // Star topology
+        // //////////////////////////////////////////////////////////////
+        // NOTE: #1 is the bootstrap node.
+        if instance_info.seq == 1 {
+            for p in participants.iter().filter(|p| p.seq != instance_info.seq) {
+                discv5.add_enr(p.enrs[i].clone())?;
+            }
+        } else {
+            let bootstrap_node = participants
+                .iter()
+                .find(|p| p.seq == 1)
+                .expect("Bootstrap node");
+            discv5.add_enr(bootstrap_node.enrs[i].clone())?;
+        }
+
+        barrier_with_timeout(
+            &client,
+            format!("state_completed_to_build_topology_{i}"),
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        // //////////////////////////////////////////////////////////////
+        // Converge
+        // //////////////////////////////////////////////////////////////
+        let convergence_ms = if instance_info.seq != 1 {
+            let started_at = Instant::now();
+            if let Err(e) = rpc_stats
+                .find_node(&discv5, instance_info.enrs[i].node_id())
+                .await
+            {
+                warn!("Failed to run the self-lookup: {e}");
+            }
+            Some(started_at.elapsed().as_millis() as u64)
+        } else {
+            None
+        };
+
+        barrier_with_timeout(
+            &client,
+            format!("state_converged_{i}"),
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        // //////////////////////////////////////////////////////////////
+        // Run lookups for random targets
+        // //////////////////////////////////////////////////////////////
+        // The targets are derived from the seq, so that every level runs the same workload.
+        let mut stats = AlphaStats {
+            seq: instance_info.seq,
+            alpha,
+            convergence_ms,
+            durations_ms: vec![],
+            failures: 0,
+            bytes_sent: 0,
+            bytes_recv: 0,
+        };
+        let rng = &mut rand_xorshift::XorShiftRng::seed_from_u64(instance_info.seq);
+        for _ in 0..params.lookups {
+            let mut raw = [0; 32];
+            rng.fill_bytes(&mut raw);
+            let target = NodeId::new(&raw);
+
+            let started_at = Instant::now();
+            match tokio::time::timeout(params.lookup_timeout, rpc_stats.find_node(&discv5, target))
+                .await
+            {
+                Ok(Ok(enrs)) if !enrs.is_empty() => stats
+                    .durations_ms
+                    .push(started_at.elapsed().as_millis() as u64),
+                Ok(Ok(_)) => {
+                    warn!("Found no ENRs for the target {target}.");
+                    stats.failures += 1;
+                }
+                Ok(Err(e)) => {
+                    warn!("Failed to run FINDNODE query: {e}");
+                    stats.failures += 1;
+                }
+                Err(_) => {
+                    warn!(
+                        "The query hasn't completed within {} seconds.",
+                        params.lookup_timeout.as_secs()
+                    );
+                    stats.failures += 1;
+                }
+            }
+        }
+        stats.durations_ms.sort_unstable();
+
+        let metrics = discv5.metrics();
+        stats.bytes_sent = metrics
+            .bytes_sent
+            .saturating_sub(metrics_at_start.bytes_sent) as u64;
+        stats.bytes_recv = metrics
+            .bytes_recv
+            .saturating_sub(metrics_at_start.bytes_recv) as u64;
+        info!(
+            "alpha: {alpha}, convergence: {:?}ms, lookups: {}, failures: {}, bytes sent: {}, bytes received: {}",
+            stats.convergence_ms,
+            stats.durations_ms.len(),
+            stats.failures,
+            stats.bytes_sent,
+            stats.bytes_recv
+        );
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = add_alpha_fields(
+            WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("query_parallelism", alpha as u64)
+            .add_tag("source", "instance_summary"),
+            &stats.durations_ms,
+            stats.failures,
+            stats.bytes_sent,
+            stats.bytes_recv,
+        );
+        let write_query = match stats.convergence_ms {
+            Some(convergence_ms) => write_query.add_field("convergence_ms", convergence_ms),
+            None => write_query,
+        };
+        record_metric(&client, write_query).await;
+        all_stats.push(stats);
+
+        barrier_with_timeout(
+            &client,
+            format!("state_completed_alpha_{i}"),
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        // The snapshot is taken of the last level, the previous ones are shut down as they are.
+        if i + 1 == params.alpha_levels.len() {
+            teardown(
+                &client,
+                discv5,
+                instance_info.seq,
+                run_parameters.test_instance_count,
+            )
+            .await?;
+        } else {
+            discv5.shutdown();
+        }
+    }
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    // //////////////////////////////////////////////////////////////
+    // Aggregate the results of all the instances
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the leader which records the comparison of the alpha levels.
+    let all_stats = publish_and_collect_on(&client, TOPIC_ALPHA_STATS, all_stats).await?;
+    let mut failed = false;
+
+    if instance_info.seq == 1 {
+        for alpha in params.alpha_levels.iter().copied() {
+            let stats = all_stats
+                .iter()
+                .flatten()
+                .filter(|s| s.alpha == alpha)
+                .collect::<Vec<_>>();
+            let mut durations = stats
+                .iter()
+                .flat_map(|s| s.durations_ms.iter().copied())
+                .collect::<Vec<_>>();
+            durations.sort_unstable();
+            let mut convergence = stats
+                .iter()
+                .filter_map(|s| s.convergence_ms)
+                .collect::<Vec<_>>();
+            convergence.sort_unstable();
+            let failures = stats.iter().map(|s| s.failures).sum::<u64>();
+            let bytes_sent = stats.iter().map(|s| s.bytes_sent).sum::<u64>();
+            let bytes_recv = stats.iter().map(|s| s.bytes_recv).sum::<u64>();
+
+            client.record_message(format!(
+                "alpha: {alpha}, convergence (ms): p50: {:?}, p90: {:?}, lookup latency (ms): p50: {:?}, p90: {:?}, p99: {:?}, lookups: {}, failures: {failures}, bytes sent: {bytes_sent}, bytes received: {bytes_recv}",
+                percentile(&convergence, 50),
+                percentile(&convergence, 90),
+                percentile(&durations, 50),
+                percentile(&durations, 90),
+                percentile(&durations, 99),
+                durations.len(),
+            ));
+
+            let mut write_query = add_alpha_fields(
+                WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_tag("instance_seq", instance_info.seq)
+                .add_tag("query_parallelism", alpha as u64)
+                .add_tag("source", "summary"),
+                &durations,
+                failures,
+                bytes_sent,
+                bytes_recv,
+            );
+            for p in [50, 90] {
+                if let Some(v) = percentile(&convergence, p) {
+                    write_query = write_query.add_field(format!("p{p}_convergence_ms"), v);
+                }
+            }
+            record_metric(&client, write_query).await;
+
+            if durations.is_empty() && params.lookups > 0 {
+                error!("None of the lookups has succeeded under alpha {alpha}.");
+                failed = true;
+            }
+        }
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Add the p50/p90/p99 of `durations`, the numbers of the successful and failed lookups, and the
+// bytes sent and received to `write_query`.
+fn add_alpha_fields(
+    write_query: WriteQuery,
+    durations: &[u64],
+    failures: u64,
+    bytes_sent: u64,
+    bytes_recv: u64,
+) -> WriteQuery {
+    let mut write_query = write_query
+        .add_field("lookups", durations.len() as u64)
+        .add_field("failures", failures)
+        .add_field("bytes_sent", bytes_sent)
+        .add_field("bytes_recv", bytes_recv);
+    for p in [50, 90, 99] {
+        if let Some(v) = percentile(durations, p) {
+            write_query = write_query.add_field(format!("p{p}_ms"), v);
+        }
+    }
+    write_query
+}
@@ -0,0 +1,35 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub(crate) struct Params {
+    pub alpha_levels: Vec<usize>,
+    pub lookups: u64,
+    pub lookup_timeout: Duration,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        let alpha_levels =
+            get_param_or::<String>("alpha_levels", instance_params, "1,2,3,5".to_owned())?
+                .split(',')
+                .map(|level| match level.trim().parse::<usize>() {
+                    Ok(0) => Err("alpha_levels must be non-zero.".to_owned()),
+                    Ok(alpha) => Ok(alpha),
+                    Err(_) => Err(format!("Failed to parse alpha_levels: {level}")),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Params {
+            alpha_levels,
+            lookups: get_param_or::<u64>("lookups", instance_params, 10)?,
+            lookup_timeout: Duration::from_secs(get_param_or::<u64>(
+                "lookup_timeout",
+                instance_params,
+                10,
+            )?),
+        })
+    }
+}
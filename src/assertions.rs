@@ -0,0 +1,138 @@
+use crate::utils::get_param;
+use std::collections::HashMap;
+use std::fmt::Display;
+use testground::client::Client;
+use tracing::error;
+
+/// The pass/fail thresholds of a test case, given by the optional params below. The assertions
+/// against the thresholds not specified are skipped.
+///
+/// - `min_peers_discovered`: the minimum number of the connected peers at the end of the test.
+/// - `max_lookup_latency_ms`: the maximum latency of a lookup.
+/// - `max_poisoning_ratio`: the maximum fraction of the lookup results controlled by attackers.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Thresholds {
+    pub min_peers_discovered: Option<usize>,
+    pub max_lookup_latency_ms: Option<u64>,
+    pub max_poisoning_ratio: Option<f64>,
+}
+
+impl Thresholds {
+    pub(crate) fn from_params(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Thresholds {
+            min_peers_discovered: optional_param("min_peers_discovered", instance_params)?,
+            max_lookup_latency_ms: optional_param("max_lookup_latency_ms", instance_params)?,
+            max_poisoning_ratio: optional_param("max_poisoning_ratio", instance_params)?,
+        })
+    }
+}
+
+fn optional_param<T: std::str::FromStr>(
+    k: &str,
+    instance_params: &HashMap<String, String>,
+) -> Result<Option<T>, String> {
+    if instance_params.contains_key(k) {
+        Ok(Some(get_param(k, instance_params)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The failed assertions of a test instance, which are accumulated while running the test case and
+/// turned into the result of the instance by `record`.
+#[derive(Debug, Default)]
+pub(crate) struct Assertions {
+    failures: Vec<String>,
+}
+
+impl Assertions {
+    pub(crate) fn new() -> Self {
+        Assertions::default()
+    }
+
+    /// Assert that `actual` is `threshold` or more.
+    pub(crate) fn assert_at_least<T: PartialOrd + Display>(
+        &mut self,
+        name: &str,
+        actual: T,
+        threshold: T,
+    ) {
+        if actual < threshold {
+            self.fail(format!(
+                "{name} is {actual}, below the threshold {threshold}."
+            ));
+        }
+    }
+
+    /// Assert that `actual` is `threshold` or less.
+    pub(crate) fn assert_at_most<T: PartialOrd + Display>(
+        &mut self,
+        name: &str,
+        actual: T,
+        threshold: T,
+    ) {
+        if actual > threshold {
+            self.fail(format!(
+                "{name} is {actual}, above the threshold {threshold}."
+            ));
+        }
+    }
+
+    /// Same as `assert_at_least`, but skipped if the threshold is not specified.
+    pub(crate) fn assert_at_least_opt<T: PartialOrd + Display>(
+        &mut self,
+        name: &str,
+        actual: T,
+        threshold: Option<T>,
+    ) {
+        if let Some(threshold) = threshold {
+            self.assert_at_least(name, actual, threshold);
+        }
+    }
+
+    /// Same as `assert_at_most`, but skipped if the threshold is not specified.
+    pub(crate) fn assert_at_most_opt<T: PartialOrd + Display>(
+        &mut self,
+        name: &str,
+        actual: T,
+        threshold: Option<T>,
+    ) {
+        if let Some(threshold) = threshold {
+            self.assert_at_most(name, actual, threshold);
+        }
+    }
+
+    /// Record a failure which isn't a comparison against a threshold.
+    pub(crate) fn fail(&mut self, failure: impl Into<String>) {
+        let failure = failure.into();
+        error!("{failure}");
+        self.failures.push(failure);
+    }
+
+    pub(crate) fn failures(&self) -> &[String] {
+        &self.failures
+    }
+
+    /// Record the result of this instance: a failure with the summary of the failed assertions, or
+    /// a success if all of them have passed.
+    pub(crate) async fn record(self, client: &Client) -> Result<(), Box<dyn std::error::Error>> {
+        if self.failures.is_empty() {
+            client.record_success().await?;
+            return Ok(());
+        }
+
+        for failure in self.failures.iter() {
+            client.record_message(format!("Assertion failed: {failure}"));
+        }
+        client
+            .record_failure(format!(
+                "{} assertion(s) failed: {}",
+                self.failures.len(),
+                self.failures.join(" ")
+            ))
+            .await?;
+        Ok(())
+    }
+}
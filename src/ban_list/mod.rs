@@ -0,0 +1,278 @@
+mod params;
+
+use crate::ban_list::params::Params;
+use crate::config::build_config;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, listen_config,
+    publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::Discv5;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_BANNED: &str = "state_banned";
+const STATE_COMPLETED_TO_OBSERVE: &str = "state_completed_to_observe";
+
+// The margin around the expiry of the ban, within which the probes aren't judged, since the
+// instances don't share the exact moment of the ban.
+const BAN_EXPIRY_MARGIN: Duration = Duration::from_secs(2);
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("ban-list", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = build_config(listen_config, &run_parameters)?;
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // NOTE: #1 is the peer which gets banned by all the others, i.e. the honest nodes.
+    let banned = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("banned peer")
+        .clone();
+    let honest = participants
+        .iter()
+        .filter(|p| p.seq != banned.seq)
+        .collect::<Vec<_>>();
+    let is_banned = instance_info.seq == banned.seq;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // Everyone knows everyone, and establishes the sessions so that the entries are connected.
+    for p in participants.iter().filter(|p| p.seq != instance_info.seq) {
+        if let Err(e) = discv5.add_enr(p.enr.clone()) {
+            error!("Failed to add the ENR of #{}: {e}", p.seq);
+        }
+        if let Err(e) = discv5.send_ping(p.enr.clone()).await {
+            warn!("Failed to send PING to #{}: {e}", p.seq);
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Ban
+    // //////////////////////////////////////////////////////////////
+    if !is_banned {
+        discv5.ban_node(&banned.enr.node_id(), Some(params.ban_duration));
+        info!(
+            "Banned #{} for {} seconds.",
+            banned.seq,
+            params.ban_duration.as_secs()
+        );
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_BANNED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+    let banned_at = Instant::now();
+
+    // //////////////////////////////////////////////////////////////
+    // Observe
+    // //////////////////////////////////////////////////////////////
+    // Every `probe_interval`, the banned peer sends PINGs to the honest nodes, which should fail
+    // until the ban expires. The honest nodes check that the banned peer doesn't re-enter their
+    // routing tables until then, and that their unbanned peer, the next honest node, stays
+    // reachable.
+    let control = if is_banned {
+        None
+    } else {
+        let position = honest
+            .iter()
+            .position(|p| p.seq == instance_info.seq)
+            .expect("Self");
+        Some(honest[(position + 1) % honest.len()])
+    };
+
+    let mut failed = false;
+    let mut violations = 0_u64;
+    let mut unaffected_failures = 0_u64;
+    let mut recovered_after = None;
+    let observation_duration = params.ban_duration + params.post_ban_duration;
+    let mut ticker = tokio::time::interval(params.probe_interval);
+
+    loop {
+        ticker.tick().await;
+        let elapsed = banned_at.elapsed();
+        if elapsed >= observation_duration {
+            break;
+        }
+        let during_ban = elapsed + BAN_EXPIRY_MARGIN < params.ban_duration;
+        let after_ban = elapsed > params.ban_duration + BAN_EXPIRY_MARGIN;
+
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("elapsed_ms", elapsed.as_millis() as u64)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag(
+            "phase",
+            if elapsed < params.ban_duration {
+                "banned"
+            } else {
+                "unbanned"
+            },
+        );
+
+        let write_query = if is_banned {
+            let results =
+                futures::future::join_all(honest.iter().map(|p| discv5.send_ping(p.enr.clone())))
+                    .await;
+            let successes = results.iter().filter(|r| r.is_ok()).count();
+            if during_ban && successes > 0 {
+                error!(
+                    "{successes} PINGs have succeeded while banned, {}ms after the ban.",
+                    elapsed.as_millis()
+                );
+                violations += 1;
+            }
+            if after_ban && successes > 0 && recovered_after.is_none() {
+                recovered_after = Some(elapsed);
+            }
+            write_query.add_field("successful_pings", successes as u64)
+        } else {
+            let in_table = discv5
+                .kbuckets()
+                .iter()
+                .any(|entry| *entry.node.key.preimage() == banned.enr.node_id());
+            if during_ban && in_table {
+                error!(
+                    "The banned peer is in the routing table {}ms after the ban.",
+                    elapsed.as_millis()
+                );
+                violations += 1;
+            }
+            if after_ban && in_table && recovered_after.is_none() {
+                recovered_after = Some(elapsed);
+            }
+
+            let control = control.expect("Control peer");
+            let control_reachable = match discv5.send_ping(control.enr.clone()).await {
+                Ok(_) => true,
+                Err(e) => {
+                    error!(
+                        "Failed to send PING to the unbanned peer #{}: {e}",
+                        control.seq
+                    );
+                    unaffected_failures += 1;
+                    false
+                }
+            };
+            write_query
+                .add_field("banned_in_table", in_table)
+                .add_field("control_reachable", control_reachable)
+        };
+        record_metric(&client, write_query).await;
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    client.record_message(format!(
+        "violations: {violations}, unaffected peer failures: {unaffected_failures}, recovered after: {:?}",
+        recovered_after
+    ));
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("violations", violations)
+    .add_field("unaffected_failures", unaffected_failures)
+    .add_field("recovered", recovered_after.is_some())
+    .add_tag("instance_seq", instance_info.seq)
+    .add_tag("source", "summary");
+    let write_query = match recovered_after {
+        Some(elapsed) => write_query.add_field("recovered_ms", elapsed.as_millis() as u64),
+        None => write_query,
+    };
+    record_metric(&client, write_query).await;
+
+    if violations > 0 || unaffected_failures > 0 {
+        failed = true;
+    }
+    // The banned peer should be able to talk to the honest nodes again once the ban expires.
+    if is_banned && recovered_after.is_none() {
+        error!(
+            "The PINGs haven't succeeded within {} seconds after the ban expired.",
+            params.post_ban_duration.as_secs()
+        );
+        failed = true;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_OBSERVE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
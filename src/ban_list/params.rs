@@ -0,0 +1,40 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub(crate) struct Params {
+    pub ban_duration: Duration,
+    pub post_ban_duration: Duration,
+    pub probe_interval: Duration,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            ban_duration: Duration::from_secs(non_zero("ban_duration", instance_params, 20)?),
+            post_ban_duration: Duration::from_secs(non_zero(
+                "post_ban_duration",
+                instance_params,
+                20,
+            )?),
+            probe_interval: Duration::from_millis(non_zero(
+                "probe_interval",
+                instance_params,
+                1000,
+            )?),
+        })
+    }
+}
+
+fn non_zero(
+    k: &str,
+    instance_params: &HashMap<String, String>,
+    default: u64,
+) -> Result<u64, String> {
+    match get_param_or::<u64>(k, instance_params, default)? {
+        0 => Err(format!("{k} must be non-zero.")),
+        v => Ok(v),
+    }
+}
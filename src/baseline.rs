@@ -0,0 +1,62 @@
+use crate::utils::{get_param, get_param_or};
+use serde_json::Value;
+use std::collections::HashMap;
+
+// The metrics of the summary compared against the baseline, and whether a lower value is better.
+const METRICS: [(&str, bool); 5] = [
+    ("p50_lookup_latency_ms", true),
+    ("p90_lookup_latency_ms", true),
+    ("p99_lookup_latency_ms", true),
+    ("min_peers_discovered", false),
+    ("mean_peers_discovered", false),
+];
+
+/// Compare the aggregated metrics of this run against the baseline given by the optional
+/// `baseline_path` param, i.e. the path to the `summary.json` of a previous run in the container.
+/// A metric has regressed if it is worse than the baseline by more than `regression_tolerance`
+/// percent (default: 20). Returns the regressions, which are empty if no baseline is specified.
+pub(crate) fn regressions(
+    instance_params: &HashMap<String, String>,
+    current: &Value,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    if !instance_params.contains_key("baseline_path") {
+        return Ok(vec![]);
+    }
+    let path = get_param::<String>("baseline_path", instance_params)?;
+    let tolerance = get_param_or::<f64>("regression_tolerance", instance_params, 20.0)?;
+    if tolerance < 0.0 {
+        return Err(
+            format!("regression_tolerance must not be negative, but got {tolerance}.").into(),
+        );
+    }
+
+    let baseline: Value = serde_json::from_str(
+        &std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read the baseline {path}: {e}"))?,
+    )?;
+
+    let mut regressions = vec![];
+    for (metric, lower_is_better) in METRICS {
+        // The metrics missing on either side, e.g. no lookup has completed, aren't comparable.
+        let (Some(base), Some(actual)) = (
+            baseline.get(metric).and_then(Value::as_f64),
+            current.get(metric).and_then(Value::as_f64),
+        ) else {
+            continue;
+        };
+
+        let factor = tolerance / 100.0;
+        let regressed = if lower_is_better {
+            actual > base * (1.0 + factor)
+        } else {
+            actual < base * (1.0 - factor)
+        };
+        if regressed {
+            regressions.push(format!(
+                "{metric} has regressed from {base} to {actual}, beyond the tolerance of {tolerance}%."
+            ));
+        }
+    }
+
+    Ok(regressions)
+}
@@ -0,0 +1,443 @@
+mod params;
+
+use crate::blackhole::params::Params;
+use crate::config::build_config;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::topology::{join_topology, Topology};
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, bootstrap_count, build_enr, check_enr_port,
+    collect_instance_info, drop_rules, enr_ip, link_shape, listen_config, percentile,
+    reconfigure_network_with_rules, record_metric, InstanceInfo, MAX_ENTRIES_PER_BUCKET,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Key, RequestError};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_BASELINE: &str = "state_completed_baseline";
+const STATE_COMPLETED: &str = "state_completed";
+
+// A lookup run by an honest node.
+struct Lookup {
+    elapsed: Duration,
+    found: Option<usize>,
+    // Whether any blackholed peer was among the closest entries to the target when the lookup
+    // started, i.e. the lookup is likely to have queried it.
+    touched: bool,
+}
+
+// A blackholed peer in the routing table of an honest node when it has been blackholed.
+struct DeadEntry {
+    seq: u64,
+    disconnected_after: Option<Duration>,
+    evicted_after: Option<Duration>,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("blackhole", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+    if params.blackholed + bootstrap_count(&client)? >= run_parameters.test_instance_count {
+        return Err("`blackholed` must leave at least one honest non-bootstrap node.".into());
+    }
+    // NOTE: The instances with the largest seqs are blackholed, so that the bootstrap nodes
+    // never are.
+    let is_blackholed = |seq: u64| seq > run_parameters.test_instance_count - params.blackholed;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5: Discv5 = Discv5::new(
+        enr.clone(),
+        enr_key,
+        build_config(listen_config, &run_parameters)?,
+    )?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    let blackholed = is_blackholed(instance_info.seq);
+    client.record_message(format!(
+        "seq: {}, node_id: {}, blackholed: {blackholed}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+    ));
+    let other_instances = collect_instance_info(&client, &instance_info).await?;
+    let blackholed_peers = other_instances
+        .iter()
+        .filter(|i| is_blackholed(i.seq))
+        .map(|i| (i.enr.node_id(), i.clone()))
+        .collect::<HashMap<_, _>>();
+
+    // //////////////////////////////////////////////////////////////
+    // Star topology
+    // //////////////////////////////////////////////////////////////
+    let rpc_stats = RpcStats::default();
+    join_topology(
+        &discv5,
+        &rpc_stats,
+        Topology::Star,
+        &instance_info,
+        &other_instances,
+    )
+    .await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run lookups while all the peers are reachable
+    // //////////////////////////////////////////////////////////////
+    // NOTE: The blackholed nodes only respond to the lookups of the honest nodes, in both of the
+    // phases.
+    let baseline = if blackholed {
+        tokio::time::sleep(Duration::from_secs(params.phase_duration)).await;
+        vec![]
+    } else {
+        let blackholed_peers = blackholed_peers.keys().copied().collect::<HashSet<_>>();
+        let mut lookups = vec![];
+        run_lookups(
+            &discv5,
+            &rpc_stats,
+            &params,
+            Duration::from_secs(params.phase_duration),
+            &blackholed_peers,
+            |lookup| lookups.push(lookup),
+        )
+        .await;
+        lookups
+    };
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_BASELINE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Blackhole the peers
+    // //////////////////////////////////////////////////////////////
+    // NOTE: The network rules apply to the outgoing traffic, so the honest nodes drop the packets
+    // to the blackholed nodes, which silently lose all their inbound traffic.
+    let rules = if blackholed {
+        None
+    } else {
+        let mut ips = vec![];
+        for peer in blackholed_peers.values() {
+            ips.push(enr_ip(&peer.enr)?);
+        }
+        Some(drop_rules(&run_parameters.test_instance_params, &ips)?)
+    };
+    reconfigure_network_with_rules(
+        &client,
+        link_shape(&run_parameters.test_instance_params)?,
+        rules,
+        "state_network_blackholed".to_owned(),
+    )
+    .await?;
+
+    let mut failed = false;
+
+    if !blackholed {
+        let blackholed_at = Instant::now();
+        let metric = |source: &str| {
+            WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("source", source.to_owned())
+        };
+
+        // //////////////////////////////////////////////////////////////
+        // Keep running lookups and watch the entries of the dead peers
+        // //////////////////////////////////////////////////////////////
+        let mut dead_entries = table_statuses(&discv5)
+            .into_keys()
+            .filter_map(|node_id| {
+                blackholed_peers.get(&node_id).map(|peer| {
+                    (
+                        node_id,
+                        DeadEntry {
+                            seq: peer.seq,
+                            disconnected_after: None,
+                            evicted_after: None,
+                        },
+                    )
+                })
+            })
+            .collect::<HashMap<_, _>>();
+        let mut observed = vec![];
+        {
+            let blackholed_peers = blackholed_peers.keys().copied().collect::<HashSet<_>>();
+            run_lookups(
+                &discv5,
+                &rpc_stats,
+                &params,
+                Duration::from_secs(params.observation_duration),
+                &blackholed_peers,
+                |lookup| {
+                    let statuses = table_statuses(&discv5);
+                    for (node_id, entry) in dead_entries.iter_mut() {
+                        match statuses.get(node_id) {
+                            Some(true) => {}
+                            Some(false) => {
+                                entry
+                                    .disconnected_after
+                                    .get_or_insert_with(|| blackholed_at.elapsed());
+                            }
+                            None => {
+                                let elapsed = blackholed_at.elapsed();
+                                entry.disconnected_after.get_or_insert(elapsed);
+                                entry.evicted_after.get_or_insert(elapsed);
+                            }
+                        }
+                    }
+                    observed.push(lookup);
+                },
+            )
+            .await;
+        }
+
+        // //////////////////////////////////////////////////////////////
+        // Probe the dead peers for the request timeout
+        // //////////////////////////////////////////////////////////////
+        // NOTE: The probes are sent after the observation, so that they don't hasten the dead
+        // entries to be disconnected.
+        let probes = futures::future::join_all(blackholed_peers.values().map(|peer| async {
+            let started_at = Instant::now();
+            let result = discv5.send_ping(peer.enr.clone()).await;
+            (result, started_at.elapsed())
+        }))
+        .await;
+        let mut timeouts = 0_u64;
+        let mut timeout_ms = vec![];
+        for (result, elapsed) in probes.iter() {
+            match result {
+                Err(RequestError::Timeout) => {
+                    timeouts += 1;
+                    timeout_ms.push(elapsed.as_millis() as u64);
+                }
+                Err(e) => warn!("The PING to a blackholed peer has failed without timeout: {e}"),
+                Ok(_) => {
+                    error!("A blackholed peer has responded to the PING.");
+                    failed = true;
+                }
+            }
+        }
+        timeout_ms.sort_unstable();
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        for (phase, lookups) in [("baseline", &baseline), ("blackholed", &observed)] {
+            for lookup in lookups.iter() {
+                let write_query = metric("lookup")
+                    .add_field("elapsed_ms", lookup.elapsed.as_millis() as u64)
+                    .add_field("succeeded", lookup.found.is_some())
+                    .add_field("touched", lookup.touched)
+                    .add_tag("phase", phase);
+                let write_query = match lookup.found {
+                    Some(found) => write_query.add_field("found", found as u64),
+                    None => write_query,
+                };
+                record_metric(&client, write_query).await;
+            }
+        }
+
+        for entry in dead_entries.values() {
+            let mut write_query = metric("dead_entry")
+                .add_field("disconnected", entry.disconnected_after.is_some())
+                .add_field("evicted", entry.evicted_after.is_some())
+                .add_tag("target_seq", entry.seq);
+            if let Some(elapsed) = entry.disconnected_after {
+                write_query = write_query.add_field("disconnected_ms", elapsed.as_millis() as u64);
+            }
+            if let Some(elapsed) = entry.evicted_after {
+                write_query = write_query.add_field("evicted_ms", elapsed.as_millis() as u64);
+            }
+            record_metric(&client, write_query).await;
+        }
+
+        let mut write_query = metric("probe")
+            .add_field("probes", probes.len() as u64)
+            .add_field("timeouts", timeouts);
+        if let Some(median) = percentile(&timeout_ms, 50) {
+            write_query = write_query.add_field("timeout_p50_ms", median);
+        }
+        record_metric(&client, write_query).await;
+
+        // The added tail latency of the lookups which have touched the dead peers, compared to
+        // the lookups before the peers have been blackholed.
+        let baseline_p99 = percentile(&latencies(baseline.iter()), 99);
+        for (name, lookups) in [
+            ("baseline", baseline.iter().collect::<Vec<_>>()),
+            (
+                "touched",
+                observed.iter().filter(|l| l.touched).collect::<Vec<_>>(),
+            ),
+            (
+                "untouched",
+                observed.iter().filter(|l| !l.touched).collect::<Vec<_>>(),
+            ),
+        ] {
+            let latencies = latencies(lookups.iter().copied());
+            let succeeded = lookups.iter().filter(|l| l.found.is_some()).count() as u64;
+            let mut write_query = metric("summary")
+                .add_field("lookups", lookups.len() as u64)
+                .add_field("succeeded", succeeded)
+                .add_tag("lookups", name);
+            for (field, p) in [("p50_ms", 50), ("p90_ms", 90), ("p99_ms", 99)] {
+                if let Some(ms) = percentile(&latencies, p) {
+                    write_query = write_query.add_field(field, ms);
+                }
+            }
+            if let (Some(p99), Some(baseline_p99)) = (percentile(&latencies, 99), baseline_p99) {
+                write_query =
+                    write_query.add_field("added_p99_ms", p99 as i64 - baseline_p99 as i64);
+            }
+            record_metric(&client, write_query).await;
+        }
+
+        let succeeded = observed.iter().filter(|l| l.found.is_some()).count();
+        info!(
+            "succeeded: {succeeded}/{}, touched: {}, disconnected: {}/{}, evicted: {}/{}, timeouts: {timeouts}/{}",
+            observed.len(),
+            observed.iter().filter(|l| l.touched).count(),
+            dead_entries.values().filter(|e| e.disconnected_after.is_some()).count(),
+            dead_entries.len(),
+            dead_entries.values().filter(|e| e.evicted_after.is_some()).count(),
+            dead_entries.len(),
+            probes.len(),
+        );
+        // The lookups are expected to route around the dead peers.
+        if !observed.is_empty() && succeeded == 0 {
+            error!("No lookup has succeeded after the peers have been blackholed.");
+            failed = true;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Run random lookups every `lookup_interval` for `duration`, passing each of them to `on_lookup`.
+async fn run_lookups(
+    discv5: &Discv5,
+    rpc_stats: &RpcStats,
+    params: &Params,
+    duration: Duration,
+    blackholed_peers: &HashSet<NodeId>,
+    mut on_lookup: impl FnMut(Lookup),
+) {
+    let deadline = Instant::now() + duration;
+    let mut interval = tokio::time::interval(Duration::from_millis(params.lookup_interval));
+    while Instant::now() < deadline {
+        interval.tick().await;
+
+        let target = NodeId::random();
+        let touched = closest_entries(discv5, &target)
+            .iter()
+            .any(|node_id| blackholed_peers.contains(node_id));
+        let started_at = Instant::now();
+        let found = match rpc_stats.find_node(discv5, target).await {
+            Ok(enrs) => Some(enrs.len()),
+            Err(e) => {
+                warn!("Failed to run FINDNODE query: {e}");
+                None
+            }
+        };
+        on_lookup(Lookup {
+            elapsed: started_at.elapsed(),
+            found,
+            touched,
+        });
+    }
+}
+
+// The entries closest to `target`, which a lookup for it starts with.
+fn closest_entries(discv5: &Discv5, target: &NodeId) -> Vec<NodeId> {
+    let target: Key<NodeId> = (*target).into();
+    let mut node_ids = table_statuses(discv5).into_keys().collect::<Vec<_>>();
+    node_ids.sort_by_key(|node_id| target.distance(&Key::from(*node_id)));
+    node_ids.truncate(MAX_ENTRIES_PER_BUCKET);
+    node_ids
+}
+
+// The entries in the routing table, and whether each of them is connected.
+fn table_statuses(discv5: &Discv5) -> HashMap<NodeId, bool> {
+    discv5
+        .kbuckets()
+        .iter()
+        .map(|entry| (*entry.node.key.preimage(), entry.status.is_connected()))
+        .collect()
+}
+
+fn latencies<'a>(lookups: impl Iterator<Item = &'a Lookup>) -> Vec<u64> {
+    let mut latencies = lookups
+        .map(|l| l.elapsed.as_millis() as u64)
+        .collect::<Vec<_>>();
+    latencies.sort_unstable();
+    latencies
+}
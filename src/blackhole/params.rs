@@ -0,0 +1,22 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub blackholed: u64,
+    pub phase_duration: u64,
+    pub observation_duration: u64,
+    pub lookup_interval: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            blackholed: get_param_or::<u64>("blackholed", instance_params, 2)?,
+            phase_duration: get_param_or::<u64>("phase_duration", instance_params, 30)?,
+            observation_duration: get_param_or::<u64>("observation_duration", instance_params, 60)?,
+            lookup_interval: get_param_or::<u64>("lookup_interval", instance_params, 1000)?,
+        })
+    }
+}
@@ -0,0 +1,146 @@
+use crate::scenario::Registry;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, bootstrap_count, build_enr, collect_from_stream,
+    publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_COMPARE_VIEWS: &str = "state_completed_to_compare_views";
+const TOPIC_BOOTSTRAP_VIEW: &str = "bootstrap_view";
+
+// The bootstrap nodes seen by an instance.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct BootstrapView {
+    seq: u64,
+    bootstrap_nodes: Vec<u64>,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("bootstrap-flag-exchange", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, _) = build_enr(&run_parameters, client.global_seq())?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, is_bootstrap_node: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        instance_info.is_bootstrap_node,
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Check the bootstrap flags
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    let mut bootstrap_nodes = participants
+        .iter()
+        .filter(|p| p.is_bootstrap_node)
+        .map(|p| p.seq)
+        .collect::<Vec<_>>();
+    bootstrap_nodes.sort_unstable();
+    info!("bootstrap nodes: {:?}", bootstrap_nodes);
+
+    let expected_bootstrap_nodes = bootstrap_count(&client)?;
+    if bootstrap_nodes.len() as u64 != expected_bootstrap_nodes {
+        error!(
+            "Unexpected number of bootstrap nodes. expected: {expected_bootstrap_nodes}, actual: {}",
+            bootstrap_nodes.len()
+        );
+        failed = true;
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Compare the views across the fleet
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the designated node which compares the views of all the nodes with its own.
+    client
+        .publish(
+            TOPIC_BOOTSTRAP_VIEW,
+            Cow::Owned(serde_json::to_value(BootstrapView {
+                seq: instance_info.seq,
+                bootstrap_nodes: bootstrap_nodes.clone(),
+            })?),
+        )
+        .await?;
+
+    if instance_info.seq == 1 {
+        let mut disagreements = 0_u64;
+
+        let views: Vec<BootstrapView> = collect_from_stream(
+            &client,
+            client
+                .subscribe(TOPIC_BOOTSTRAP_VIEW, u16::MAX.into())
+                .await,
+            run_parameters.test_instance_count,
+        )
+        .await?;
+
+        for view in views {
+            if view.bootstrap_nodes != bootstrap_nodes {
+                error!(
+                    "Disagreement on the bootstrap nodes. #{}: {:?}, #1: {:?}",
+                    view.seq, view.bootstrap_nodes, bootstrap_nodes
+                );
+                disagreements += 1;
+            }
+        }
+
+        if disagreements > 0 {
+            failed = true;
+        }
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("bootstrap_nodes", bootstrap_nodes.len() as u64)
+        .add_field("disagreements", disagreements)
+        .add_tag("instance_seq", instance_info.seq);
+        record_metric(&client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_COMPARE_VIEWS,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
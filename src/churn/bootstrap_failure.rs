@@ -0,0 +1,244 @@
+use crate::assertions::{Assertions, Thresholds};
+use crate::churn::params::BootstrapFailureParams;
+use crate::churn::{connected_node_ids, join, start_discv5};
+use crate::rpc_stats::RpcStats;
+use crate::teardown::{final_barrier, teardown};
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, collect_instance_info, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::time::Instant;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{info, warn};
+
+const STATE_JOINED: &str = "state_joined";
+const STATE_BOOTSTRAP_NODES_SHUT_DOWN: &str = "state_bootstrap_nodes_shut_down";
+const STATE_COMPLETED: &str = "state_completed";
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = BootstrapFailureParams::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let mut discv5 = start_discv5(&client).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, discv5.local_enr())?;
+    let other_instances = collect_instance_info(&client, &instance_info).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Join via the bootstrap nodes
+    // //////////////////////////////////////////////////////////////
+    let rpc_stats = RpcStats::default();
+    join(&discv5, &rpc_stats, &instance_info, &other_instances).await;
+
+    barrier_with_timeout(
+        &client,
+        STATE_JOINED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Shut down the bootstrap nodes
+    // //////////////////////////////////////////////////////////////
+    if instance_info.is_bootstrap_node {
+        tokio::time::sleep(params.bootstrap_uptime).await;
+        discv5.shutdown();
+        drop(discv5);
+        info!("Shut down the Discovery v5 server.");
+
+        barrier_with_timeout(
+            &client,
+            STATE_BOOTSTRAP_NODES_SHUT_DOWN,
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+        barrier_with_timeout(
+            &client,
+            STATE_COMPLETED,
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+        final_barrier(&client, run_parameters.test_instance_count).await?;
+
+        client.record_success().await?;
+        return Ok(());
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_BOOTSTRAP_NODES_SHUT_DOWN,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+    let shut_down_at = Instant::now();
+
+    // //////////////////////////////////////////////////////////////
+    // Discover the surviving nodes
+    // //////////////////////////////////////////////////////////////
+    let bootstrap_seqs = other_instances
+        .iter()
+        .filter(|i| i.is_bootstrap_node)
+        .map(|i| (i.enr.node_id(), i.seq))
+        .collect::<HashMap<_, _>>();
+    let survivors = other_instances
+        .iter()
+        .filter(|i| !i.is_bootstrap_node)
+        .collect::<Vec<_>>();
+
+    // The dead entries of the bootstrap nodes which are still connected.
+    let mut lingering = connected_node_ids(&discv5)
+        .into_iter()
+        .filter(|node_id| bootstrap_seqs.contains_key(node_id))
+        .collect::<Vec<_>>();
+    let initially_lingering = lingering.clone();
+    let mut evicted_after = HashMap::new();
+
+    // Every `lookup_interval`, look up one of the surviving nodes at random, which can only be
+    // found through the peers already learned.
+    let mut lookups = 0_u64;
+    let mut successful_lookups = 0_u64;
+    let mut ticker = tokio::time::interval(params.lookup_interval);
+    loop {
+        ticker.tick().await;
+        if shut_down_at.elapsed() >= params.observation_duration {
+            break;
+        }
+
+        let connected = connected_node_ids(&discv5);
+        lingering.retain(|node_id| {
+            if connected.contains(node_id) {
+                true
+            } else {
+                evicted_after.insert(*node_id, shut_down_at.elapsed());
+                false
+            }
+        });
+
+        let Some(target) = survivors.choose(&mut rand::thread_rng()) else {
+            break;
+        };
+        let lookup_started_at = Instant::now();
+        let found = match rpc_stats.find_node(&discv5, target.enr.node_id()).await {
+            Ok(enrs) => enrs.iter().any(|enr| enr.node_id() == target.enr.node_id()),
+            Err(e) => {
+                warn!("Failed to run FINDNODE query for #{}: {e}", target.seq);
+                false
+            }
+        };
+        lookups += 1;
+        if found {
+            successful_lookups += 1;
+        }
+
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("elapsed_secs", shut_down_at.elapsed().as_secs())
+        .add_field("lookup_success", found)
+        .add_field("lookup_ms", lookup_started_at.elapsed().as_millis() as u64)
+        .add_field("lingering_entries", lingering.len() as u64)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("target_seq", target.seq);
+        record_metric(&client, write_query).await;
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    for node_id in initially_lingering.iter() {
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("target_seq", bootstrap_seqs[node_id]);
+
+        let write_query = match evicted_after.get(node_id) {
+            Some(elapsed) => write_query
+                .add_field("evicted", true)
+                .add_field("stale_ms", elapsed.as_millis() as u64),
+            None => write_query.add_field("evicted", false),
+        };
+        record_metric(&client, write_query).await;
+    }
+
+    let connected = connected_node_ids(&discv5);
+    let peers_discovered = survivors
+        .iter()
+        .filter(|i| connected.contains(&i.enr.node_id()))
+        .count();
+    let success_rate = if lookups == 0 {
+        0.0
+    } else {
+        successful_lookups as f64 / lookups as f64
+    };
+    client.record_message(format!(
+        "{successful_lookups} of {lookups} lookups have succeeded ({success_rate:.3}), {peers_discovered} of {} surviving peers are connected, and {} of {} dead bootstrap entries have been evicted.",
+        survivors.len(),
+        evicted_after.len(),
+        initially_lingering.len()
+    ));
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("lookups", lookups)
+    .add_field("successful_lookups", successful_lookups)
+    .add_field("success_rate", success_rate)
+    .add_field("peers_discovered", peers_discovered as u64)
+    .add_field("survivors", survivors.len() as u64)
+    .add_tag("instance_seq", instance_info.seq)
+    .add_tag("source", "summary");
+    record_metric(&client, write_query).await;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // Whether the network survives the bootstrap nodes is the result itself, so the test case only
+    // fails against the thresholds given.
+    let thresholds = Thresholds::from_params(&run_parameters.test_instance_params)?;
+    let mut assertions = Assertions::new();
+    assertions.assert_at_least_opt(
+        "peers_discovered",
+        peers_discovered,
+        thresholds.min_peers_discovered,
+    );
+    assertions.record(&client).await
+}
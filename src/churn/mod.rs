@@ -0,0 +1,316 @@
+mod bootstrap_failure;
+mod params;
+mod restart;
+mod schedule;
+
+use crate::churn::params::Params;
+use crate::config::build_config;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::{final_barrier, teardown};
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, bootstrap_count, build_enr, build_enr_with_key,
+    check_enr_port, collect_instance_info, deterministic_key, ip_version, listen_config,
+    record_metric, InstanceInfo, WAIT_FOR_SHUTDOWN,
+};
+use chrono::Local;
+use discv5::enr::{CombinedKey, NodeId};
+use discv5::{Discv5, Enr};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const STATE_CONVERGED: &str = "state_converged";
+const STATE_CHURNED_NODES_SHUT_DOWN: &str = "state_churned_nodes_shut_down";
+const STATE_CHURNED_NODES_REVIVED: &str = "state_churned_nodes_revived";
+const STATE_COMPLETED: &str = "state_completed";
+
+// The interval between checks of the routing table for the stale entries.
+const TABLE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("churn", run);
+    registry.register("churn-schedule", schedule::run);
+    registry.register("restart-recovery", restart::run);
+    registry.register("bootstrap-failure", bootstrap_failure::run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let mut discv5 = start_discv5(&client).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, discv5.local_enr())?;
+    let other_instances = collect_instance_info(&client, &instance_info).await?;
+    let rpc_stats = RpcStats::default();
+
+    // The instances with the highest seqs are churned. The bootstrap nodes are never churned.
+    let churn_count = churn_count(&client, params.churn_fraction)?;
+    let is_churned = |seq: u64| seq > run_parameters.test_instance_count - churn_count;
+    let churned_node_ids = other_instances
+        .iter()
+        .filter(|i| is_churned(i.seq))
+        .map(|i| i.enr.node_id())
+        .collect::<HashSet<_>>();
+    client.record_message(format!(
+        "seq: {}, churned: {}, churn_count: {churn_count}",
+        instance_info.seq,
+        is_churned(instance_info.seq)
+    ));
+
+    // //////////////////////////////////////////////////////////////
+    // Converge
+    // //////////////////////////////////////////////////////////////
+    join(&discv5, &rpc_stats, &instance_info, &other_instances).await;
+
+    barrier_with_timeout(
+        &client,
+        STATE_CONVERGED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Churn
+    // //////////////////////////////////////////////////////////////
+    let discv5 = if is_churned(instance_info.seq) {
+        // Shut down discv5 gracefully, so that the survivors can only notice it by the lack of
+        // responses.
+        discv5.shutdown();
+        drop(discv5);
+        tokio::time::sleep(WAIT_FOR_SHUTDOWN).await;
+        info!("Shut down the Discovery v5 server.");
+
+        barrier_with_timeout(
+            &client,
+            STATE_CHURNED_NODES_SHUT_DOWN,
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        tokio::time::sleep(Duration::from_secs(params.offline_duration)).await;
+
+        // Rejoin with a fresh ENR.
+        if params.revive {
+            let discv5 = start_discv5(&client).await?;
+            info!("Revived with node_id: {}", discv5.local_enr().node_id());
+            let revived_info = InstanceInfo::new(&client, discv5.local_enr())?;
+            join(&discv5, &rpc_stats, &revived_info, &other_instances).await;
+            Some(discv5)
+        } else {
+            None
+        }
+    } else {
+        barrier_with_timeout(
+            &client,
+            STATE_CHURNED_NODES_SHUT_DOWN,
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+        let shut_down_at = Instant::now();
+
+        // Observe how long the entries of the churned nodes stay connected in the routing table.
+        let mut stale = connected_node_ids(&discv5)
+            .intersection(&churned_node_ids)
+            .copied()
+            .collect::<HashSet<_>>();
+        let initially_stale = stale.clone();
+        let mut evicted_after = HashMap::new();
+        let offline_duration = Duration::from_secs(params.offline_duration);
+        while !stale.is_empty() && shut_down_at.elapsed() < offline_duration {
+            tokio::time::sleep(TABLE_POLL_INTERVAL).await;
+            let connected = connected_node_ids(&discv5);
+            stale.retain(|node_id| {
+                if connected.contains(node_id) {
+                    true
+                } else {
+                    evicted_after.insert(*node_id, shut_down_at.elapsed());
+                    false
+                }
+            });
+        }
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        for node_id in initially_stale.iter() {
+            let target_seq = other_instances
+                .iter()
+                .find(|i| i.enr.node_id() == *node_id)
+                .map(|i| i.seq)
+                .expect("The churned instance");
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("target_seq", target_seq);
+
+            let write_query = match evicted_after.get(node_id) {
+                Some(elapsed) => write_query
+                    .add_field("evicted", true)
+                    .add_field("stale_ms", elapsed.as_millis() as u64),
+                None => write_query.add_field("evicted", false),
+            };
+            record_metric(&client, write_query).await;
+        }
+        client.record_message(format!(
+            "{} of {} stale entries have been evicted within {} seconds.",
+            evicted_after.len(),
+            initially_stale.len(),
+            params.offline_duration
+        ));
+
+        Some(discv5)
+    };
+
+    barrier_with_timeout(
+        &client,
+        STATE_CHURNED_NODES_REVIVED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // The revived nodes have contacted the bootstrap nodes with their fresh ENRs, whereas the old
+    // entries may still be there.
+    if instance_info.is_bootstrap_node {
+        if let Some(discv5) = discv5.as_ref() {
+            let connected = connected_node_ids(discv5);
+            client.record_message(format!(
+                "{} connected entries after the revival, {} of which are the old entries of the churned nodes.",
+                connected.len(),
+                churned_node_ids.intersection(&connected).count()
+            ));
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    // The churned nodes which haven't been revived have nothing to tear down, but still take part
+    // in the final barrier.
+    match discv5 {
+        Some(discv5) => {
+            teardown(
+                &client,
+                discv5,
+                instance_info.seq,
+                run_parameters.test_instance_count,
+            )
+            .await?
+        }
+        None => final_barrier(&client, run_parameters.test_instance_count).await?,
+    }
+    client.record_success().await?;
+    Ok(())
+}
+
+// The number of the churned instances, i.e. the `churn_fraction` of the non-bootstrap instances.
+fn churn_count(client: &Client, churn_fraction: f64) -> Result<u64, Box<dyn std::error::Error>> {
+    let non_bootstrap_count =
+        client.run_parameters().test_instance_count - bootstrap_count(client)?;
+    Ok((churn_fraction * non_bootstrap_count as f64).round() as u64)
+}
+
+// Start a discv5 server with a fresh ENR.
+async fn start_discv5(client: &Client) -> Result<Discv5, Box<dyn std::error::Error>> {
+    let (enr, enr_key) = build_enr(&client.run_parameters(), client.global_seq())?;
+    start_discv5_with_enr(client, enr, enr_key).await
+}
+
+// Start a discv5 server with the identity derived from `key_seed`.
+async fn start_discv5_with_key_seed(
+    client: &Client,
+    key_seed: u64,
+) -> Result<Discv5, Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let seq = client.global_seq();
+    let enr_key = deterministic_key(key_seed, seq);
+    let ip_version = ip_version(&run_parameters.test_instance_params)?;
+    let enr = build_enr_with_key(&run_parameters, seq, ip_version, &enr_key)?;
+    start_discv5_with_enr(client, enr, enr_key).await
+}
+
+async fn start_discv5_with_enr(
+    client: &Client,
+    enr: Enr,
+    enr_key: CombinedKey,
+) -> Result<Discv5, Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5 = Discv5::new(enr, enr_key, build_config(listen_config, &run_parameters)?)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    Ok(discv5)
+}
+
+// The bootstrap nodes know all the other nodes, and the other nodes look themselves up via the
+// bootstrap nodes.
+async fn join(
+    discv5: &Discv5,
+    rpc_stats: &RpcStats,
+    instance_info: &InstanceInfo,
+    other_instances: &[InstanceInfo],
+) {
+    if instance_info.is_bootstrap_node {
+        for i in other_instances.iter() {
+            if let Err(e) = discv5.add_enr(i.enr.clone()) {
+                warn!("Failed to add the ENR of #{}: {e}", i.seq);
+            }
+        }
+        return;
+    }
+
+    for i in other_instances.iter().filter(|i| i.is_bootstrap_node) {
+        if let Err(e) = rpc_stats
+            .find_node_designated_peer(discv5, i.enr.clone(), vec![0])
+            .await
+        {
+            error!(
+                "Failed to run FIND_NODE query against the bootstrap node #{}: {e}",
+                i.seq
+            );
+        }
+    }
+    if let Err(e) = rpc_stats
+        .find_node(discv5, instance_info.enr.node_id())
+        .await
+    {
+        warn!("Failed to run the self-lookup: {e}");
+    }
+}
+
+fn connected_node_ids(discv5: &Discv5) -> HashSet<NodeId> {
+    discv5
+        .kbuckets()
+        .iter()
+        .filter(|entry| entry.status.is_connected())
+        .map(|entry| *entry.node.key.preimage())
+        .collect()
+}
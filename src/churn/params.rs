@@ -0,0 +1,112 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub(crate) struct Params {
+    pub churn_fraction: f64,
+    pub offline_duration: u64,
+    pub revive: bool,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            churn_fraction: churn_fraction(instance_params)?,
+            offline_duration: get_param_or::<u64>("offline_duration", instance_params, 60)?,
+            revive: get_param_or::<bool>("revive", instance_params, true)?,
+        })
+    }
+}
+
+pub(crate) struct ScheduleParams {
+    pub churn_fraction: f64,
+    pub churn_interval: Duration,
+    pub churn_rounds: u64,
+    pub lookup_interval: Duration,
+}
+
+impl ScheduleParams {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<ScheduleParams, Box<dyn std::error::Error>> {
+        Ok(ScheduleParams {
+            churn_fraction: churn_fraction(instance_params)?,
+            churn_interval: Duration::from_secs(non_zero("churn_interval", instance_params, 30)?),
+            churn_rounds: non_zero("churn_rounds", instance_params, 3)?,
+            lookup_interval: Duration::from_secs(non_zero("lookup_interval", instance_params, 5)?),
+        })
+    }
+}
+
+pub(crate) struct RestartParams {
+    pub churn_fraction: f64,
+    pub downtime: Duration,
+    pub same_key: bool,
+    pub recovery_timeout: Duration,
+}
+
+impl RestartParams {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<RestartParams, Box<dyn std::error::Error>> {
+        Ok(RestartParams {
+            churn_fraction: churn_fraction(instance_params)?,
+            downtime: Duration::from_secs(non_zero("downtime", instance_params, 30)?),
+            same_key: get_param_or::<bool>("same_key", instance_params, true)?,
+            recovery_timeout: Duration::from_secs(non_zero(
+                "recovery_timeout",
+                instance_params,
+                60,
+            )?),
+        })
+    }
+}
+
+pub(crate) struct BootstrapFailureParams {
+    pub bootstrap_uptime: Duration,
+    pub observation_duration: Duration,
+    pub lookup_interval: Duration,
+}
+
+impl BootstrapFailureParams {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<BootstrapFailureParams, Box<dyn std::error::Error>> {
+        Ok(BootstrapFailureParams {
+            bootstrap_uptime: Duration::from_secs(get_param_or::<u64>(
+                "bootstrap_uptime",
+                instance_params,
+                5,
+            )?),
+            observation_duration: Duration::from_secs(non_zero(
+                "observation_duration",
+                instance_params,
+                60,
+            )?),
+            lookup_interval: Duration::from_secs(non_zero("lookup_interval", instance_params, 5)?),
+        })
+    }
+}
+
+fn churn_fraction(instance_params: &HashMap<String, String>) -> Result<f64, String> {
+    let churn_fraction = get_param_or::<f64>("churn_fraction", instance_params, 0.5)?;
+    if !(0.0..=1.0).contains(&churn_fraction) {
+        return Err(format!(
+            "churn_fraction must be between 0 and 1, but got {churn_fraction}."
+        ));
+    }
+    Ok(churn_fraction)
+}
+
+fn non_zero(
+    k: &str,
+    instance_params: &HashMap<String, String>,
+    default: u64,
+) -> Result<u64, String> {
+    match get_param_or::<u64>(k, instance_params, default)? {
+        0 => Err(format!("{k} must be non-zero.")),
+        v => Ok(v),
+    }
+}
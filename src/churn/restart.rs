@@ -0,0 +1,303 @@
+use crate::churn::params::RestartParams;
+use crate::churn::{churn_count, connected_node_ids, join, start_discv5_with_key_seed};
+use crate::rpc_stats::RpcStats;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, collect_from_stream, collect_instance_info,
+    record_metric, InstanceInfo, WAIT_FOR_SHUTDOWN,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_CONVERGED: &str = "state_converged";
+const STATE_RESTARTED_NODES_SHUT_DOWN: &str = "state_restarted_nodes_shut_down";
+const STATE_RESTARTED_NODES_STARTED: &str = "state_restarted_nodes_started";
+const STATE_COMPLETED: &str = "state_completed";
+const TOPIC_RESTARTED: &str = "TOPIC_RESTARTED";
+
+// The interval between checks of the routing table.
+const TABLE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = RestartParams::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    // The key is derived from a seed, so that the restarted instances can come back with the same
+    // identity if `same_key` is true.
+    let key_seed = rand::random::<u64>();
+    let mut discv5 = start_discv5_with_key_seed(&client, key_seed).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, discv5.local_enr())?;
+    let other_instances = collect_instance_info(&client, &instance_info).await?;
+
+    // The instances with the highest seqs restart. The bootstrap nodes never restart.
+    let restart_count = churn_count(&client, params.churn_fraction)?;
+    let is_restarted = |seq: u64| seq > run_parameters.test_instance_count - restart_count;
+    client.record_message(format!(
+        "seq: {}, restarted: {}, restart_count: {restart_count}, same_key: {}",
+        instance_info.seq,
+        is_restarted(instance_info.seq),
+        params.same_key
+    ));
+
+    // //////////////////////////////////////////////////////////////
+    // Converge
+    // //////////////////////////////////////////////////////////////
+    let rpc_stats = RpcStats::default();
+    join(&discv5, &rpc_stats, &instance_info, &other_instances).await;
+
+    barrier_with_timeout(
+        &client,
+        STATE_CONVERGED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Restart
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+    let discv5 = if is_restarted(instance_info.seq) {
+        let peers_before = connected_node_ids(&discv5).len();
+
+        discv5.shutdown();
+        drop(discv5);
+        tokio::time::sleep(WAIT_FOR_SHUTDOWN).await;
+        info!("Shut down the Discovery v5 server.");
+
+        barrier_with_timeout(
+            &client,
+            STATE_RESTARTED_NODES_SHUT_DOWN,
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        tokio::time::sleep(params.downtime).await;
+
+        let key_seed = if params.same_key {
+            key_seed
+        } else {
+            rand::random::<u64>()
+        };
+        let discv5 = start_discv5_with_key_seed(&client, key_seed).await?;
+        info!("Restarted with node_id: {}", discv5.local_enr().node_id());
+        let restarted_info = InstanceInfo::new(&client, discv5.local_enr())?;
+        client
+            .publish(
+                TOPIC_RESTARTED,
+                Cow::Owned(serde_json::to_value(&restarted_info)?),
+            )
+            .await?;
+
+        barrier_with_timeout(
+            &client,
+            STATE_RESTARTED_NODES_STARTED,
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+        let restarted_at = Instant::now();
+
+        // Rejoin via the bootstrap nodes, and wait until the routing table has as many connected
+        // peers as before the restart.
+        join(&discv5, &rpc_stats, &restarted_info, &other_instances).await;
+        let target = peers_before.max(1);
+        let mut peers_after = connected_node_ids(&discv5).len();
+        while peers_after < target && restarted_at.elapsed() < params.recovery_timeout {
+            tokio::time::sleep(TABLE_POLL_INTERVAL).await;
+            peers_after = connected_node_ids(&discv5).len();
+        }
+        let repopulated = peers_after >= target;
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("peers_before", peers_before as u64)
+        .add_field("peers_after", peers_after as u64)
+        .add_field("repopulated", repopulated)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("role", "restarted");
+        let write_query = if repopulated {
+            write_query.add_field("repopulate_ms", restarted_at.elapsed().as_millis() as u64)
+        } else {
+            write_query
+        };
+        record_metric(&client, write_query).await;
+        client.record_message(format!(
+            "{peers_after} of {peers_before} peers are connected after the restart, repopulated: {repopulated}"
+        ));
+
+        if peers_after == 0 {
+            error!(
+                "No peers are connected within {} seconds after the restart.",
+                params.recovery_timeout.as_secs()
+            );
+            failed = true;
+        }
+
+        discv5
+    } else {
+        let restarted_node_ids = other_instances
+            .iter()
+            .filter(|i| is_restarted(i.seq))
+            .map(|i| (i.enr.node_id(), i.seq))
+            .collect::<HashMap<_, _>>();
+
+        barrier_with_timeout(
+            &client,
+            STATE_RESTARTED_NODES_SHUT_DOWN,
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+        let shut_down_at = Instant::now();
+
+        // While the restarted nodes are down, observe how long their entries stay connected.
+        let initially_stale = connected_node_ids(&discv5)
+            .into_iter()
+            .filter(|node_id| restarted_node_ids.contains_key(node_id))
+            .collect::<HashSet<_>>();
+        let mut stale = initially_stale.clone();
+        let mut evicted_after = HashMap::new();
+        while !stale.is_empty() && shut_down_at.elapsed() < params.downtime {
+            tokio::time::sleep(TABLE_POLL_INTERVAL).await;
+            let connected = connected_node_ids(&discv5);
+            stale.retain(|node_id| {
+                if connected.contains(node_id) {
+                    true
+                } else {
+                    evicted_after.insert(*node_id, shut_down_at.elapsed());
+                    false
+                }
+            });
+        }
+
+        // The restarted nodes may come back with fresh node ids.
+        let restarted_instances: Vec<InstanceInfo> = collect_from_stream(
+            &client,
+            client.subscribe(TOPIC_RESTARTED, u16::MAX.into()).await,
+            restart_count,
+        )
+        .await?;
+        let new_node_ids = restarted_instances
+            .iter()
+            .map(|i| (i.seq, i.enr.node_id()))
+            .collect::<HashMap<_, _>>();
+
+        barrier_with_timeout(
+            &client,
+            STATE_RESTARTED_NODES_STARTED,
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+        let restarted_at = Instant::now();
+
+        // Observe how long it takes until the restarted nodes are connected again, with either
+        // the same or the fresh node id.
+        let mut pending = initially_stale
+            .iter()
+            .map(|node_id| new_node_ids[&restarted_node_ids[node_id]])
+            .collect::<HashSet<NodeId>>();
+        let mut refreshed_after = HashMap::new();
+        while !pending.is_empty() && restarted_at.elapsed() < params.recovery_timeout {
+            let connected = connected_node_ids(&discv5);
+            pending.retain(|node_id| {
+                if connected.contains(node_id) {
+                    refreshed_after.insert(*node_id, restarted_at.elapsed());
+                    false
+                } else {
+                    true
+                }
+            });
+            tokio::time::sleep(TABLE_POLL_INTERVAL).await;
+        }
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        for node_id in initially_stale.iter() {
+            let target_seq = restarted_node_ids[node_id];
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("target_seq", target_seq)
+            .add_tag("role", "stable");
+
+            let write_query = match evicted_after.get(node_id) {
+                Some(elapsed) => write_query
+                    .add_field("evicted", true)
+                    .add_field("stale_ms", elapsed.as_millis() as u64),
+                None => write_query.add_field("evicted", false),
+            };
+            let write_query = match refreshed_after.get(&new_node_ids[&target_seq]) {
+                Some(elapsed) => write_query
+                    .add_field("refreshed", true)
+                    .add_field("refresh_ms", elapsed.as_millis() as u64),
+                None => write_query.add_field("refreshed", false),
+            };
+            record_metric(&client, write_query).await;
+        }
+        client.record_message(format!(
+            "{} of {} stale entries have been evicted during the downtime, and {} have been refreshed within {} seconds after the restart.",
+            evicted_after.len(),
+            initially_stale.len(),
+            refreshed_after.len(),
+            params.recovery_timeout.as_secs()
+        ));
+
+        discv5
+    };
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+    Ok(())
+}
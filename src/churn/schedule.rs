@@ -0,0 +1,186 @@
+use crate::churn::params::ScheduleParams;
+use crate::churn::{churn_count, connected_node_ids, join, start_discv5_with_key_seed};
+use crate::rpc_stats::RpcStats;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, collect_instance_info, record_metric, InstanceInfo,
+    WAIT_FOR_SHUTDOWN,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use std::collections::HashSet;
+use testground::client::Client;
+use testground::WriteQuery;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+const STATE_CONVERGED: &str = "state_converged";
+const STATE_COMPLETED: &str = "state_completed";
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = ScheduleParams::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    // The churned instances restart with the same identity, so the key is derived from a seed
+    // rather than kept around.
+    let key_seed = rand::random::<u64>();
+    let mut discv5 = start_discv5_with_key_seed(&client, key_seed).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, discv5.local_enr())?;
+    let other_instances = collect_instance_info(&client, &instance_info).await?;
+
+    // The instances with the highest seqs are churned. The bootstrap nodes are never churned.
+    let churn_count = churn_count(&client, params.churn_fraction)?;
+    let is_churned = |seq: u64| seq > run_parameters.test_instance_count - churn_count;
+    let churned_node_ids = other_instances
+        .iter()
+        .filter(|i| is_churned(i.seq))
+        .map(|i| i.enr.node_id())
+        .collect::<HashSet<_>>();
+    client.record_message(format!(
+        "seq: {}, churned: {}, churn_count: {churn_count}",
+        instance_info.seq,
+        is_churned(instance_info.seq)
+    ));
+
+    // //////////////////////////////////////////////////////////////
+    // Converge
+    // //////////////////////////////////////////////////////////////
+    let rpc_stats = RpcStats::default();
+    join(&discv5, &rpc_stats, &instance_info, &other_instances).await;
+
+    barrier_with_timeout(
+        &client,
+        STATE_CONVERGED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Churn
+    // //////////////////////////////////////////////////////////////
+    // Every round, the churned instances are online for `churn_interval`, and then offline for
+    // `churn_interval`. They are back online at the end of the last round. The instances follow
+    // the schedule by the time elapsed since the barrier above.
+    let started_at = Instant::now();
+    let churn_duration = params.churn_interval * 2 * params.churn_rounds as u32;
+
+    if is_churned(instance_info.seq) {
+        for round in 1..=params.churn_rounds as u32 {
+            tokio::time::sleep_until(started_at + params.churn_interval * (2 * round - 1)).await;
+            discv5.shutdown();
+            drop(discv5);
+            tokio::time::sleep(WAIT_FOR_SHUTDOWN).await;
+            info!("Round {round}: Shut down the Discovery v5 server.");
+
+            tokio::time::sleep_until(started_at + params.churn_interval * (2 * round)).await;
+            discv5 = start_discv5_with_key_seed(&client, key_seed).await?;
+            join(&discv5, &rpc_stats, &instance_info, &other_instances).await;
+            info!("Round {round}: Restarted the Discovery v5 server.");
+        }
+    } else {
+        // The stable instances run lookups periodically, and count the entries of the churned
+        // instances which are still connected while they are offline, i.e. the stale entries.
+        let mut lookups = 0_u64;
+        let mut successful_lookups = 0_u64;
+        let mut ticker = tokio::time::interval(params.lookup_interval);
+        loop {
+            ticker.tick().await;
+            let elapsed = started_at.elapsed();
+            if elapsed >= churn_duration {
+                break;
+            }
+            let churned_offline =
+                (elapsed.as_secs_f64() / params.churn_interval.as_secs_f64()) as u64 % 2 == 1;
+
+            let lookup_started_at = Instant::now();
+            let found = match rpc_stats.find_node(&discv5, NodeId::random()).await {
+                Ok(enrs) => enrs.len(),
+                Err(e) => {
+                    warn!("Failed to run FINDNODE query: {e}");
+                    0
+                }
+            };
+            let lookup_elapsed = lookup_started_at.elapsed();
+            let success = found > 0;
+            lookups += 1;
+            if success {
+                successful_lookups += 1;
+            }
+
+            let churned_entries = connected_node_ids(&discv5)
+                .intersection(&churned_node_ids)
+                .count();
+            let stale_entries = if churned_offline { churned_entries } else { 0 };
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("elapsed_secs", elapsed.as_secs())
+            .add_field("lookup_success", success)
+            .add_field("lookup_ms", lookup_elapsed.as_millis() as u64)
+            .add_field("found_enrs", found as u64)
+            .add_field("churned_entries", churned_entries as u64)
+            .add_field("stale_entries", stale_entries as u64)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("phase", if churned_offline { "offline" } else { "online" });
+            record_metric(&client, write_query).await;
+        }
+
+        let success_rate = if lookups == 0 {
+            0.0
+        } else {
+            successful_lookups as f64 / lookups as f64
+        };
+        client.record_message(format!(
+            "{successful_lookups} of {lookups} lookups have succeeded ({success_rate:.3})."
+        ));
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("lookups", lookups)
+        .add_field("successful_lookups", successful_lookups)
+        .add_field("success_rate", success_rate)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("source", "summary");
+        record_metric(&client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    client.record_success().await?;
+    Ok(())
+}
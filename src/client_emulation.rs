@@ -0,0 +1,228 @@
+use crate::rpc_stats::RpcStats;
+use crate::utils::{get_param_or, record_metric};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::Discv5;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+// The maximum number of the extra lookups run in a round to get back to the target peer count.
+const MAX_MAINTENANCE_LOOKUPS: u64 = 3;
+
+/// The usage pattern of a consensus client, given by the optional params below.
+///
+/// - `client_emulation_duration`: how long the emulation runs, in seconds. `0` disables it.
+/// - `client_lookup_interval`: the interval between the random-target lookups which refresh the
+///   table, in seconds. Defaults to 30.
+/// - `client_target_peers`: the number of connected peers the client maintains. Extra lookups
+///   are run in a round while the peers are below it. Defaults to 16.
+/// - `client_ping_interval`: the interval between the liveness PINGs to all the connected peers,
+///   in seconds. `0` leaves the liveness to discv5. Defaults to 10.
+#[derive(Clone, Debug)]
+pub(crate) struct EmulationConfig {
+    pub duration: Duration,
+    pub lookup_interval: Duration,
+    pub target_peers: usize,
+    pub ping_interval: Option<Duration>,
+}
+
+impl EmulationConfig {
+    pub(crate) fn from_params(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let lookup_interval = get_param_or::<u64>("client_lookup_interval", instance_params, 30)?;
+        if lookup_interval == 0 {
+            return Err("client_lookup_interval must be non-zero.".into());
+        }
+        let ping_interval = get_param_or::<u64>("client_ping_interval", instance_params, 10)?;
+
+        Ok(EmulationConfig {
+            duration: Duration::from_secs(get_param_or::<u64>(
+                "client_emulation_duration",
+                instance_params,
+                0,
+            )?),
+            lookup_interval: Duration::from_secs(lookup_interval),
+            target_peers: get_param_or::<usize>("client_target_peers", instance_params, 16)?,
+            ping_interval: (ping_interval > 0).then(|| Duration::from_secs(ping_interval)),
+        })
+    }
+}
+
+/// The activity of the emulated client so far.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct EmulationStats {
+    pub lookups: u64,
+    pub failed_lookups: u64,
+    // The extra lookups run while the peers are below the target.
+    pub maintenance_lookups: u64,
+    pub pings: u64,
+    pub failed_pings: u64,
+}
+
+impl EmulationStats {
+    pub(crate) fn add_fields(&self, write_query: WriteQuery) -> WriteQuery {
+        write_query
+            .add_field("lookups", self.lookups)
+            .add_field("failed_lookups", self.failed_lookups)
+            .add_field("maintenance_lookups", self.maintenance_lookups)
+            .add_field("pings", self.pings)
+            .add_field("failed_pings", self.failed_pings)
+    }
+}
+
+/// A background task which runs the usage pattern of a consensus client against discv5 until
+/// the duration of the `EmulationConfig` has elapsed. Each round of the lookups is recorded with
+/// `source = client_emulation`. The task is cancelled when this is dropped, like `MetricsPoller`.
+pub(crate) struct ClientEmulation {
+    stats: Arc<Mutex<EmulationStats>>,
+    handle: JoinHandle<()>,
+}
+
+impl Drop for ClientEmulation {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl ClientEmulation {
+    /// Start the emulation with `config`, underneath the test case running on `discv5`.
+    pub(crate) fn start(
+        client: &Client,
+        discv5: Arc<Discv5>,
+        rpc_stats: RpcStats,
+        instance_seq: u64,
+        config: EmulationConfig,
+    ) -> Self {
+        let stats = Arc::new(Mutex::new(EmulationStats::default()));
+        let handle = tokio::spawn(emulate(
+            client.clone(),
+            discv5,
+            rpc_stats,
+            instance_seq,
+            config,
+            stats.clone(),
+        ));
+        ClientEmulation { stats, handle }
+    }
+
+    /// Whether the emulation has run for its duration.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    pub(crate) fn stats(&self) -> EmulationStats {
+        *self.stats.lock().expect("lock stats")
+    }
+}
+
+/// Start the emulation underneath a test case if opted in with `client_emulation_duration`, see
+/// `EmulationConfig`.
+pub(crate) fn spawn_client_emulation(
+    client: &Client,
+    discv5: Arc<Discv5>,
+    rpc_stats: RpcStats,
+    instance_seq: u64,
+) -> Result<Option<ClientEmulation>, Box<dyn std::error::Error>> {
+    let config = EmulationConfig::from_params(&client.run_parameters().test_instance_params)?;
+    if config.duration.is_zero() {
+        return Ok(None);
+    }
+    Ok(Some(ClientEmulation::start(
+        client,
+        discv5,
+        rpc_stats,
+        instance_seq,
+        config,
+    )))
+}
+
+async fn emulate(
+    client: Client,
+    discv5: Arc<Discv5>,
+    rpc_stats: RpcStats,
+    instance_seq: u64,
+    config: EmulationConfig,
+    stats: Arc<Mutex<EmulationStats>>,
+) {
+    let run_parameters = client.run_parameters();
+    let started_at = Instant::now();
+    let deadline = started_at + config.duration;
+    let mut lookup_ticker = tokio::time::interval(config.lookup_interval);
+    let mut ping_ticker = config.ping_interval.map(tokio::time::interval);
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline) => break,
+            _ = lookup_ticker.tick() => {
+                // The table refresh, followed by the extra lookups while the peers are short.
+                let mut lookups = 0;
+                loop {
+                    let failed = rpc_stats.find_node(&discv5, NodeId::random()).await.is_err();
+                    {
+                        let mut stats = stats.lock().expect("lock stats");
+                        stats.lookups += 1;
+                        stats.failed_lookups += failed as u64;
+                        stats.maintenance_lookups += (lookups > 0) as u64;
+                    }
+                    lookups += 1;
+                    if lookups > MAX_MAINTENANCE_LOOKUPS
+                        || discv5.connected_peers() >= config.target_peers
+                    {
+                        break;
+                    }
+                }
+
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("elapsed_secs", started_at.elapsed().as_secs())
+                .add_field("connected_peers", discv5.connected_peers() as u64)
+                .add_field("round_lookups", lookups)
+                .add_tag("instance_seq", instance_seq)
+                .add_tag("source", "client_emulation");
+                let snapshot = *stats.lock().expect("lock stats");
+                record_metric(&client, snapshot.add_fields(write_query)).await;
+            }
+            Some(_) = async {
+                match ping_ticker.as_mut() {
+                    Some(ticker) => Some(ticker.tick().await),
+                    None => None,
+                }
+            } => {
+                let peers = discv5
+                    .kbuckets()
+                    .iter()
+                    .filter(|entry| entry.status.is_connected())
+                    .map(|entry| entry.node.value.clone())
+                    .collect::<Vec<_>>();
+                let results = futures::future::join_all(
+                    peers.into_iter().map(|enr| discv5.send_ping(enr)),
+                )
+                .await;
+                let failed = results.iter().filter(|r| r.is_err()).count() as u64;
+                if failed > 0 {
+                    debug!("{failed} of {} liveness PINGs have failed.", results.len());
+                }
+                let mut stats = stats.lock().expect("lock stats");
+                stats.pings += results.len() as u64;
+                stats.failed_pings += failed;
+            }
+        }
+    }
+
+    let snapshot = *stats.lock().expect("lock stats");
+    if snapshot.lookups > 0 && snapshot.failed_lookups == snapshot.lookups {
+        warn!("All the lookups of the emulated client have failed.");
+    }
+}
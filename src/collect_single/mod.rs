@@ -0,0 +1,98 @@
+use crate::scenario::Registry;
+use crate::utils::{build_enr, get_param, publish_and_collect, record_metric, InstanceInfo};
+use chrono::Local;
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("collect-single", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let collect_timeout =
+        get_param::<u64>("collect_timeout", &run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, _) = build_enr(&run_parameters, client.global_seq())?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of the other participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+
+    let mut failed = false;
+    let expected = run_parameters.test_instance_count - 1;
+
+    let others = match tokio::time::timeout(
+        Duration::from_secs(collect_timeout),
+        publish_and_collect(&client, instance_info.clone()),
+    )
+    .await
+    {
+        Ok(Ok(mut participants)) => {
+            if let Some(pos) = participants.iter().position(|p| p.seq == instance_info.seq) {
+                participants.remove(pos);
+            }
+            Some(participants)
+        }
+        Ok(Err(e)) => {
+            error!("Failed to collect instance information: {e}");
+            None
+        }
+        Err(_) => {
+            error!("Collecting instance information hasn't completed within {collect_timeout} seconds.");
+            None
+        }
+    };
+
+    let collected = match others {
+        Some(others) => {
+            info!("others: {:?}", others);
+            if others.len() as u64 != expected {
+                error!(
+                    "Unexpected number of other instances. expected: {expected}, actual: {}",
+                    others.len()
+                );
+                failed = true;
+            }
+            others.len() as u64
+        }
+        None => {
+            failed = true;
+            0
+        }
+    };
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("expected", expected)
+    .add_field("collected", collected)
+    .add_tag("instance_seq", instance_info.seq);
+    record_metric(&client, write_query).await;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -1,8 +1,12 @@
 use crate::concurrent_requests::InstanceInfo;
-use crate::utils::publish_and_collect;
-use discv5::enr::CombinedKey;
-use discv5::{Discv5, Enr, ListenConfig};
-use std::net::Ipv4Addr;
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, listen_config,
+    publish_and_collect, unsupported_seq,
+};
+use discv5::Discv5;
 use std::time::Duration;
 use testground::client::Client;
 use tracing::{error, info};
@@ -12,19 +16,11 @@ const STATE_FINISHED: &str = "state_finished";
 
 pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
     let run_parameters = client.run_parameters();
-    let ip = run_parameters
-        .data_network_ip()?
-        .expect("IP address for the data network");
 
     // ////////////////////////
     // Construct local Enr
     // ////////////////////////
-    let enr_key = CombinedKey::generate_secp256k1();
-    let enr = Enr::builder()
-        .ip(ip)
-        .udp4(9000)
-        .build(&enr_key)
-        .expect("enr");
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
 
     // //////////////////////////////////////////////////////////////
     // Collect information of all participants in the test case
@@ -37,7 +33,7 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
         "seq: {}, node_id: {}, ip: {}",
         instance_info.seq,
         instance_info.enr.node_id(),
-        ip
+        enr_addresses(&instance_info.enr)
     ));
 
     let another_instance_info = {
@@ -56,11 +52,9 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
     // ////////////////////////
     // Discv5 config
     // ////////////////////////
-    let listen_config = ListenConfig::Ipv4 {
-        ip: Ipv4Addr::UNSPECIFIED,
-        port: 9000,
-    };
-    let config = discv5::ConfigBuilder::new(listen_config)
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
         .request_timeout(Duration::from_secs(5))
         .build();
 
@@ -68,21 +62,31 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
     // Start discv5
     // ////////////////////////
     let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
-    discv5.start().await.expect("Start Discovery v5 server");
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
 
-    client
-        .signal_and_wait(
-            STATE_DISCV5_STARTED,
-            client.run_parameters().test_instance_count,
-        )
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_DISCV5_STARTED,
+        client.run_parameters().test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
 
+    let rpc_stats = RpcStats::default();
     match client.global_seq() {
         1 => {
             // Sent requests in parallel.
             let mut handles = vec![];
             for i in 0..2 {
-                let fut = discv5.talk_req(another_instance_info.enr.clone(), vec![0], vec![i]);
+                let fut = rpc_stats.talk_req(
+                    &discv5,
+                    another_instance_info.enr.clone(),
+                    vec![0],
+                    vec![i],
+                );
                 handles.push(tokio::spawn(fut));
             }
 
@@ -118,12 +122,25 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
                 }
             }
         }
-        _ => unreachable!(),
+        seq => return Err(unsupported_seq(seq)),
     }
 
-    client
-        .signal_and_wait(STATE_FINISHED, client.run_parameters().test_instance_count)
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_FINISHED,
+        client.run_parameters().test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, client.global_seq()).await;
+    teardown(
+        &client,
+        discv5,
+        client.global_seq(),
+        client.run_parameters().test_instance_count,
+    )
+    .await?;
 
     client.record_success().await?;
     Ok(())
@@ -1,11 +1,15 @@
 pub(crate) mod before_establishing_session;
 pub(crate) mod whoareyou_timeout;
 
-use crate::utils::publish_and_collect;
-use discv5::enr::CombinedKey;
-use discv5::{Discv5, Enr, ListenConfig};
-use serde::{Deserialize, Serialize};
-use std::net::Ipv4Addr;
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, listen_config,
+    publish_and_collect, InstanceInfo,
+};
+use discv5::Discv5;
 use std::time::Duration;
 use testground::client::Client;
 use tracing::error;
@@ -16,60 +20,56 @@ const STATE_COMPLETED: &str = "state_completed";
 // Session timeout for Node2 (in second).
 const SESSION_TIMEOUT_NODE2: u64 = 5;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-struct InstanceInfo {
-    // The sequence number of this test instance within the test.
-    seq: u64,
-    enr: Enr,
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("concurrent-requests", run);
+    registry.register(
+        "concurrent-requests_whoareyou-timeout",
+        whoareyou_timeout::run,
+    );
+    registry.register(
+        "concurrent-requests_before-establishing-session",
+        before_establishing_session::run,
+    );
 }
 
 pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
     let run_parameters = client.run_parameters();
-    let ip = run_parameters
-        .data_network_ip()?
-        .expect("IP address for the data network");
 
     // ////////////////////////
     // Construct local Enr
     // ////////////////////////
-    let enr_key = CombinedKey::generate_secp256k1();
-    let enr = Enr::builder()
-        .ip(ip)
-        .udp4(9000)
-        .build(&enr_key)
-        .expect("enr");
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
 
     // ////////////////////////
     // Start discv5
     // ////////////////////////
-    let listen_config = ListenConfig::Ipv4 {
-        ip: Ipv4Addr::UNSPECIFIED,
-        port: 9000,
-    };
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
 
     let config = if client.global_seq() == 2 {
-        discv5::ConfigBuilder::new(listen_config)
+        config_builder(listen_config, &run_parameters.test_instance_params)?
             .session_timeout(Duration::from_secs(SESSION_TIMEOUT_NODE2))
             .build()
     } else {
-        discv5::ConfigBuilder::new(listen_config).build()
+        config_builder(listen_config, &run_parameters.test_instance_params)?.build()
     };
 
     let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
-    discv5.start().await.expect("Start Discovery v5 server");
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
 
     // //////////////////////////////////////////////////////////////
     // Collect information of all participants in the test case
     // //////////////////////////////////////////////////////////////
-    let instance_info = InstanceInfo {
-        seq: client.global_seq(),
-        enr,
-    };
+    let instance_info = InstanceInfo::new(&client, enr)?;
     client.record_message(format!(
         "seq: {}, node_id: {}, ip: {}",
         instance_info.seq,
         instance_info.enr.node_id(),
-        ip
+        enr_addresses(&instance_info.enr)
     ));
 
     let participants = publish_and_collect(&client, instance_info.clone()).await?;
@@ -83,15 +83,19 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
             .iter()
             .filter(|&p| p.seq != client.global_seq())
         {
-            let _ = discv5
-                .find_node_designated_peer(p.enr.clone(), vec![0])
+            let _ = rpc_stats
+                .find_node_designated_peer(&discv5, p.enr.clone(), vec![0])
                 .await;
         }
     }
 
-    client
-        .signal_and_wait(STATE_CONNECTED, run_parameters.test_instance_count)
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_CONNECTED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
 
     client.record_message(format!(
         "peers: {:?}",
@@ -99,7 +103,7 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
             .kbuckets()
             .iter()
             .map(|b| (
-                b.node.value.ip4().unwrap(),
+                enr_addresses(&b.node.value),
                 b.status.direction,
                 b.status.state
             ))
@@ -121,7 +125,7 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
         {
             let mut handles = vec![];
             for _ in 0..2 {
-                let fut = discv5.find_node_designated_peer(p.enr.clone(), vec![0]);
+                let fut = rpc_stats.find_node_designated_peer(&discv5, p.enr.clone(), vec![0]);
                 handles.push(tokio::spawn(fut));
             }
 
@@ -136,9 +140,23 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
 
     tokio::time::sleep(Duration::from_secs(3)).await;
 
-    client
-        .signal_and_wait(STATE_COMPLETED, run_parameters.test_instance_count)
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
 
     if succeeded {
         client.record_success().await?;
@@ -1,10 +1,15 @@
 use crate::concurrent_requests::InstanceInfo;
+use crate::config::config_builder;
 use crate::mock::{Action, Behaviour, Behaviours, Expect, Mock, Request};
-use crate::utils::publish_and_collect;
+use crate::rpc_stats::RpcStats;
+use crate::teardown::{final_barrier, teardown};
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, listen_config,
+    publish_and_collect, unsupported_seq,
+};
 use discv5::enr::CombinedKey;
-use discv5::{Discv5, Enr, ListenConfig};
+use discv5::{Discv5, Enr};
 use std::collections::VecDeque;
-use std::net::Ipv4Addr;
 use std::time::Duration;
 use testground::client::Client;
 use tracing::error;
@@ -15,19 +20,11 @@ const STATE_FINISHED: &str = "state_finished";
 
 pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
     let run_parameters = client.run_parameters();
-    let ip = run_parameters
-        .data_network_ip()?
-        .expect("IP address for the data network");
 
     // ////////////////////////
     // Construct local Enr
     // ////////////////////////
-    let enr_key = CombinedKey::generate_secp256k1();
-    let enr = Enr::builder()
-        .ip(ip)
-        .udp4(9000)
-        .build(&enr_key)
-        .expect("enr");
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
 
     // //////////////////////////////////////////////////////////////
     // Collect information of all participants in the test case
@@ -40,7 +37,7 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
         "seq: {}, node_id: {}, ip: {}",
         instance_info.seq,
         instance_info.enr.node_id(),
-        ip
+        enr_addresses(&instance_info.enr)
     ));
 
     let another_instance_info = {
@@ -59,18 +56,16 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
     // ////////////////////////
     // Discv5 config
     // ////////////////////////
-    let listen_config = ListenConfig::Ipv4 {
-        ip: Ipv4Addr::UNSPECIFIED,
-        port: 9000,
-    };
-    let config = discv5::ConfigBuilder::new(listen_config)
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
         .request_timeout(Duration::from_secs(5))
         .build();
 
     match client.global_seq() {
         1 => run_discv5(client, enr, enr_key, config, another_instance_info).await?,
         2 => run_mock(client, enr, enr_key, config, another_instance_info).await?,
-        _ => unreachable!(),
+        seq => return Err(unsupported_seq(seq)),
     }
 
     Ok(())
@@ -87,27 +82,37 @@ async fn run_discv5(
     // Start discv5
     // ////////////////////////
     let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
-    discv5.start().await.expect("Start Discovery v5 server");
-
-    client
-        .signal_and_wait(
-            STATE_DISCV5_STARTED,
-            client.run_parameters().test_instance_count,
-        )
-        .await?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    barrier_with_timeout(
+        &client,
+        STATE_DISCV5_STARTED,
+        client.run_parameters().test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
 
     // Wait until the mock send a random packet.
-    client
-        .signal_and_wait(
-            STATE_SENT_RANDOM_PACKET,
-            client.run_parameters().test_instance_count,
-        )
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_SENT_RANDOM_PACKET,
+        client.run_parameters().test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
 
     // Sent requests in parallel.
     let mut handles = vec![];
     for _ in 0..2 {
-        let fut = discv5.find_node_designated_peer(another_instance_info.enr.clone(), vec![0]);
+        let fut = rpc_stats.find_node_designated_peer(
+            &discv5,
+            another_instance_info.enr.clone(),
+            vec![0],
+        );
         handles.push(tokio::spawn(fut));
     }
 
@@ -116,10 +121,23 @@ async fn run_discv5(
             error!("FINDNODE request failed: {e}");
         }
     }
-
-    client
-        .signal_and_wait(STATE_FINISHED, client.run_parameters().test_instance_count)
-        .await?;
+    rpc_stats.finish(&client, client.global_seq()).await;
+
+    barrier_with_timeout(
+        &client,
+        STATE_FINISHED,
+        client.run_parameters().test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    teardown(
+        &client,
+        discv5,
+        client.global_seq(),
+        client.run_parameters().test_instance_count,
+    )
+    .await?;
 
     client.record_success().await?;
     Ok(())
@@ -157,12 +175,13 @@ async fn run_mock(
     // TODO: handle PING request
     let mut mock = Mock::start(enr, enr_key, config, Behaviours::Sequential(behaviours)).await;
 
-    client
-        .signal_and_wait(
-            STATE_DISCV5_STARTED,
-            client.run_parameters().test_instance_count,
-        )
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_DISCV5_STARTED,
+        client.run_parameters().test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
 
     // Send a random packet.
     // The receiver of the packet reply with WHOAREYOU packet but this mock drops it without replying.
@@ -171,16 +190,23 @@ async fn run_mock(
     }
     tokio::time::sleep(Duration::from_secs(1)).await;
 
-    client
-        .signal_and_wait(
-            STATE_SENT_RANDOM_PACKET,
-            client.run_parameters().test_instance_count,
-        )
-        .await?;
-
-    client
-        .signal_and_wait(STATE_FINISHED, client.run_parameters().test_instance_count)
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_SENT_RANDOM_PACKET,
+        client.run_parameters().test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_FINISHED,
+        client.run_parameters().test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    final_barrier(&client, client.run_parameters().test_instance_count).await?;
 
     client.record_success().await?;
     Ok(())
@@ -0,0 +1,138 @@
+use crate::utils::{get_param, get_param_or};
+use discv5::{Config, ConfigBuilder, ListenConfig, RateLimiter, RateLimiterBuilder};
+use std::collections::HashMap;
+use std::time::Duration;
+use testground::RunParameters;
+
+/// The discv5 config builder with the knobs given by the optional params below. The discv5
+/// defaults are used for the params not specified, and the test cases can set their own options on
+/// top of the builder.
+///
+/// - `request_timeout_secs`, `query_timeout_secs`, `ping_interval_secs`, `session_timeout_secs`
+/// - `request_retries`: the number of retries of a request which has timed out.
+/// - `query_parallelism`, `max_nodes_response`, `session_cache_capacity`,
+///   `incoming_bucket_limit`, `enr_peer_update_min`
+/// - `ip_limit`: whether to enforce the limit of the nodes per IP address in the routing table.
+/// - `filter_ip_limit`, `filter_total_limit`: the packet filter, see `rate_limiter`.
+pub(crate) fn config_builder(
+    listen_config: ListenConfig,
+    instance_params: &HashMap<String, String>,
+) -> Result<ConfigBuilder, Box<dyn std::error::Error>> {
+    let mut builder = ConfigBuilder::new(listen_config);
+
+    if let Some(request_timeout) = duration_param("request_timeout_secs", instance_params)? {
+        builder.request_timeout(request_timeout);
+    }
+    if instance_params.contains_key("request_retries") {
+        builder.request_retries(get_param::<u8>("request_retries", instance_params)?);
+    }
+    if let Some(query_timeout) = duration_param("query_timeout_secs", instance_params)? {
+        builder.query_timeout(query_timeout);
+    }
+    if let Some(ping_interval) = duration_param("ping_interval_secs", instance_params)? {
+        builder.ping_interval(ping_interval);
+    }
+    if let Some(session_timeout) = duration_param("session_timeout_secs", instance_params)? {
+        builder.session_timeout(session_timeout);
+    }
+    if let Some(query_parallelism) = count_param("query_parallelism", instance_params)? {
+        builder.query_parallelism(query_parallelism);
+    }
+    if let Some(max_nodes_response) = count_param("max_nodes_response", instance_params)? {
+        builder.max_nodes_response(max_nodes_response);
+    }
+    if let Some(capacity) = count_param("session_cache_capacity", instance_params)? {
+        builder.session_cache_capacity(capacity);
+    }
+    if let Some(limit) = count_param("incoming_bucket_limit", instance_params)? {
+        builder.incoming_bucket_limit(limit);
+    }
+    if let Some(min) = count_param("enr_peer_update_min", instance_params)? {
+        builder.enr_peer_update_min(min);
+    }
+    if get_param_or::<bool>("ip_limit", instance_params, false)? {
+        builder.ip_limit();
+    }
+    if let Some(rate_limiter) = rate_limiter(instance_params)? {
+        builder.enable_packet_filter();
+        builder.filter_rate_limiter(Some(rate_limiter));
+    }
+
+    Ok(builder)
+}
+
+/// Build the discv5 config from the params, see `config_builder`.
+pub(crate) fn build_config(
+    listen_config: ListenConfig,
+    run_parameters: &RunParameters,
+) -> Result<Config, Box<dyn std::error::Error>> {
+    Ok(config_builder(listen_config, &run_parameters.test_instance_params)?.build())
+}
+
+/// The rate limiter of the packet filter, which allows `filter_ip_limit` packets per second from
+/// each IP address and `filter_total_limit` packets per second in total. None if neither of them
+/// is specified.
+fn rate_limiter(
+    instance_params: &HashMap<String, String>,
+) -> Result<Option<RateLimiter>, Box<dyn std::error::Error>> {
+    let ip_limit = rate_param("filter_ip_limit", instance_params)?;
+    let total_limit = rate_param("filter_total_limit", instance_params)?;
+    if ip_limit.is_none() && total_limit.is_none() {
+        return Ok(None);
+    }
+
+    let mut builder = RateLimiterBuilder::new();
+    if let Some(n) = ip_limit {
+        builder = builder.ip_n_every(n, Duration::from_secs(1));
+    }
+    if let Some(n) = total_limit {
+        builder = builder.total_n_every(n, Duration::from_secs(1));
+    }
+
+    let rate_limiter = builder
+        .build()
+        .map_err(|e| format!("Failed to build the rate limiter: {e}"))?;
+    Ok(Some(rate_limiter))
+}
+
+/// A non-zero number of packets per second, if the param is specified.
+fn rate_param(k: &str, instance_params: &HashMap<String, String>) -> Result<Option<u64>, String> {
+    if !instance_params.contains_key(k) {
+        return Ok(None);
+    }
+
+    match get_param::<u64>(k, instance_params)? {
+        0 => Err(format!("{k} must be non-zero.")),
+        n => Ok(Some(n)),
+    }
+}
+
+/// A non-zero count, if the param is specified.
+fn count_param(
+    k: &str,
+    instance_params: &HashMap<String, String>,
+) -> Result<Option<usize>, String> {
+    if !instance_params.contains_key(k) {
+        return Ok(None);
+    }
+
+    match get_param::<usize>(k, instance_params)? {
+        0 => Err(format!("{k} must be non-zero.")),
+        n => Ok(Some(n)),
+    }
+}
+
+/// A non-zero duration in seconds, if the param is specified.
+fn duration_param(
+    k: &str,
+    instance_params: &HashMap<String, String>,
+) -> Result<Option<Duration>, String> {
+    if !instance_params.contains_key(k) {
+        return Ok(None);
+    }
+
+    match get_param::<u64>(k, instance_params)? {
+        0 => Err(format!("{k} must be non-zero.")),
+        secs => Ok(Some(Duration::from_secs(secs))),
+    }
+}
@@ -0,0 +1,233 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::config_matrix::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr_on_port, check_enr_port, enr_addresses,
+    ip_version, publish_and_collect, record_metric, udp_port,
+};
+use chrono::Local;
+use discv5::enr::CombinedKey;
+use discv5::{Discv5, Enr};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+// The ENRs of a test instance. Unlike `InstanceInfo`, an instance runs a node per combination of
+// the config options, so it has an ENR per combination.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct InstanceEnrs {
+    // The sequence number of this test instance within the test.
+    seq: u64,
+    // The ENRs used in each combination of the config options.
+    enrs: Vec<Enr>,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("config-matrix", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+    let ip_version = ip_version(&run_parameters.test_instance_params)?;
+    let udp_port = udp_port(&run_parameters.test_instance_params)?;
+    let combinations = params.combinations();
+
+    // ////////////////////////
+    // Construct local Enrs
+    // ////////////////////////
+    // A fresh key is used in each combination so that no session established in the previous
+    // combination is reused. A different port is used to avoid waiting for the previous socket to
+    // be released.
+    let mut keys = vec![];
+    let mut enrs = vec![];
+    let mut ports = vec![];
+    for i in 0..combinations.len() {
+        let port = u16::try_from(i)
+            .ok()
+            .and_then(|i| udp_port.checked_add(i))
+            .ok_or("udp_port overflows with the combinations.")?;
+        let enr_key = CombinedKey::generate_secp256k1();
+        let enr = build_enr_on_port(
+            &run_parameters,
+            client.global_seq(),
+            ip_version,
+            &enr_key,
+            port,
+        )?;
+        keys.push(enr_key);
+        enrs.push(enr);
+        ports.push(port);
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceEnrs {
+        seq: client.global_seq(),
+        enrs,
+    };
+    client.record_message(format!(
+        "seq: {}, ip: {}",
+        instance_info.seq,
+        enr_addresses(&instance_info.enrs[0])
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run the test in each combination of the config options
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    let combination_count = combinations.len();
+    let rpc_stats = RpcStats::default();
+    for (i, (enr_key, (query_parallelism, incoming_bucket_limit))) in
+        keys.into_iter().zip(combinations).enumerate()
+    {
+        // ////////////////////////
+        // Start discv5
+        // ////////////////////////
+        let listen_config = ip_version.listen_config(ports[i]);
+        check_enr_port(&instance_info.enrs[i], &listen_config)?;
+        let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+            .query_parallelism(query_parallelism)
+            .incoming_bucket_limit(incoming_bucket_limit)
+            .build();
+        let mut discv5: Discv5 = Discv5::new(instance_info.enrs[i].clone(), enr_key, config)?;
+        discv5
+            .start()
+            .await
+            .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+        // //////////////////////////////////////////////////////////////
+        // Star topology
+        // //////////////////////////////////////////////////////////////
+        // NOTE: #1 is the bootstrap node.
+        if instance_info.seq == 1 {
+            for p in participants.iter().filter(|p| p.seq != instance_info.seq) {
+                discv5.add_enr(p.enrs[i].clone())?;
+            }
+        } else {
+            let bootstrap_node = participants
+                .iter()
+                .find(|p| p.seq == 1)
+                .expect("Bootstrap node");
+            discv5.add_enr(bootstrap_node.enrs[i].clone())?;
+        }
+
+        barrier_with_timeout(
+            &client,
+            format!("state_completed_to_build_topology_{i}"),
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        // //////////////////////////////////////////////////////////////
+        // Run FINDNODE query
+        // //////////////////////////////////////////////////////////////
+        if instance_info.seq != 1 {
+            // The target is the next non-bootstrap node.
+            let targets = participants
+                .iter()
+                .filter(|p| p.seq != 1)
+                .map(|p| (p.seq, p.enrs[i].node_id()))
+                .collect::<Vec<_>>();
+            let position = targets
+                .iter()
+                .position(|(seq, _)| *seq == instance_info.seq)
+                .expect("Self");
+            let (_, target) = targets[(position + 1) % targets.len()];
+
+            let started_at = Instant::now();
+            let result = tokio::time::timeout(
+                Duration::from_secs(params.lookup_timeout),
+                rpc_stats.find_node(&discv5, target),
+            )
+            .await;
+            let elapsed = started_at.elapsed();
+
+            let success = match result {
+                Ok(Ok(enrs)) => enrs.iter().any(|enr| enr.node_id() == target),
+                Ok(Err(e)) => {
+                    error!("Failed to run FINDNODE query: {e}");
+                    false
+                }
+                Err(_) => {
+                    error!(
+                        "The query hasn't completed within {} seconds.",
+                        params.lookup_timeout
+                    );
+                    false
+                }
+            };
+            if !success {
+                failed = true;
+            }
+            info!(
+                "query_parallelism: {query_parallelism}, incoming_bucket_limit: {incoming_bucket_limit}, success: {success}, elapsed: {}ms",
+                elapsed.as_millis()
+            );
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("success", success)
+            .add_field("elapsed_ms", elapsed.as_millis() as u64)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("query_parallelism", query_parallelism as u64)
+            .add_tag("incoming_bucket_limit", incoming_bucket_limit as u64);
+            record_metric(&client, write_query).await;
+        }
+
+        barrier_with_timeout(
+            &client,
+            format!("state_completed_combination_{i}"),
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        // The snapshot is taken of the last combination, the previous ones are shut down as they
+        // are.
+        if i + 1 == combination_count {
+            teardown(
+                &client,
+                discv5,
+                instance_info.seq,
+                run_parameters.test_instance_count,
+            )
+            .await?;
+        } else {
+            discv5.shutdown();
+        }
+    }
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use crate::utils::get_param;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+pub(crate) struct Params {
+    pub query_parallelism_levels: Vec<usize>,
+    pub incoming_bucket_limit_levels: Vec<usize>,
+    pub lookup_timeout: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            query_parallelism_levels: parse_levels("query_parallelism_levels", instance_params)?,
+            incoming_bucket_limit_levels: parse_levels(
+                "incoming_bucket_limit_levels",
+                instance_params,
+            )?,
+            lookup_timeout: get_param::<u64>("lookup_timeout", instance_params)?,
+        })
+    }
+
+    /// All the combinations of the config options, in the form of
+    /// `(query_parallelism, incoming_bucket_limit)`.
+    pub(crate) fn combinations(&self) -> Vec<(usize, usize)> {
+        self.query_parallelism_levels
+            .iter()
+            .flat_map(|parallelism| {
+                self.incoming_bucket_limit_levels
+                    .iter()
+                    .map(move |limit| (*parallelism, *limit))
+            })
+            .collect()
+    }
+}
+
+fn parse_levels<T: FromStr>(
+    k: &str,
+    instance_params: &HashMap<String, String>,
+) -> Result<Vec<T>, String> {
+    get_param::<String>(k, instance_params)?
+        .split(',')
+        .map(|level| {
+            level
+                .trim()
+                .parse::<T>()
+                .map_err(|_| format!("Failed to parse {k}: {level}"))
+        })
+        .collect()
+}
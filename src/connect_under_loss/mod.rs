@@ -0,0 +1,20 @@
+use crate::link_sweep::{self, LinkSweep};
+use crate::scenario::Registry;
+use testground::client::Client;
+use testground::network_conf::LinkShape;
+
+const SWEEP: LinkSweep = LinkSweep {
+    levels_param: "loss_levels",
+    field: "loss",
+    unit: "%",
+    apply: |link_shape, loss| LinkShape {
+        loss: loss as f32,
+        ..link_shape
+    },
+};
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("connect-under-loss", |client: Client| {
+        link_sweep::run(client, &SWEEP)
+    });
+}
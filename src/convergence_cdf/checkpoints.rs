@@ -0,0 +1,287 @@
+use crate::config::build_config;
+use crate::rpc_stats::RpcStats;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, collect_from_stream,
+    get_param_or, listen_config, percentile, publish_and_collect, record_metric, InstanceInfo,
+    MAX_ENTRIES_PER_BUCKET,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Key};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_CONVERGE: &str = "state_completed_to_converge";
+const TOPIC_CONVERGENCE_TIMES: &str = "convergence_times";
+
+// The percentages of the reachable peers at which the convergence time is taken.
+const CHECKPOINTS: [u64; 3] = [50, 90, 100];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ConvergenceTimes {
+    seq: u64,
+    // The time to reach each of `CHECKPOINTS`, `None` if it hasn't been reached within the
+    // timeout.
+    millis: Vec<Option<u64>>,
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let instance_params = &run_parameters.test_instance_params;
+    let convergence_timeout = get_param_or::<u64>("convergence_timeout", instance_params, 120)?;
+    let check_interval = get_param_or::<u64>("check_interval", instance_params, 500)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5: Discv5 = Discv5::new(
+        enr.clone(),
+        enr_key,
+        build_config(listen_config, &run_parameters)?,
+    )?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+    let discv5 = Arc::new(discv5);
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the bootstrap node. The other nodes start cold, knowing only the bootstrap
+    // node.
+    let bootstrap_node = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Bootstrap node")
+        .clone();
+
+    if instance_info.seq == bootstrap_node.seq {
+        for p in participants.iter().filter(|p| p.seq != bootstrap_node.seq) {
+            discv5.add_enr(p.enr.clone())?;
+        }
+    } else {
+        discv5.add_enr(bootstrap_node.enr.clone())?;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run lookups and check the table against the participants
+    // //////////////////////////////////////////////////////////////
+    if instance_info.seq != bootstrap_node.seq {
+        let peers = participants
+            .iter()
+            .filter(|p| p.seq != instance_info.seq)
+            .map(|p| p.enr.node_id())
+            .collect::<HashSet<_>>();
+        let reachable = reachable_peers(&instance_info.enr.node_id(), &peers);
+
+        // The lookups spread the ENRs until the table is checked to have converged.
+        let lookups = {
+            let discv5 = discv5.clone();
+            let rpc_stats = rpc_stats.clone();
+            tokio::spawn(async move {
+                loop {
+                    if let Err(e) = rpc_stats.find_node(&discv5, NodeId::random()).await {
+                        warn!("Failed to run FINDNODE query: {e}");
+                    }
+                }
+            })
+        };
+
+        let started_at = Instant::now();
+        let mut millis = vec![None; CHECKPOINTS.len()];
+        let mut interval = tokio::time::interval(Duration::from_millis(check_interval));
+        while started_at.elapsed() < Duration::from_secs(convergence_timeout) {
+            interval.tick().await;
+
+            let present = discv5
+                .table_entries_id()
+                .into_iter()
+                .filter(|node_id| peers.contains(node_id))
+                .count() as u64;
+            let percentage = present * 100 / reachable.max(1);
+            for (checkpoint, millis) in CHECKPOINTS.iter().zip(millis.iter_mut()) {
+                if millis.is_none() && percentage >= *checkpoint {
+                    let elapsed = started_at.elapsed().as_millis() as u64;
+                    info!("Reached {checkpoint}% of the peers in {elapsed}ms. present: {present}/{reachable}");
+                    *millis = Some(elapsed);
+                }
+            }
+            if millis.iter().all(Option::is_some) {
+                break;
+            }
+        }
+        lookups.abort();
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        for (checkpoint, millis) in CHECKPOINTS.iter().zip(millis.iter()) {
+            let mut write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("reached", millis.is_some())
+            .add_field("reachable_peers", reachable)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("checkpoint", *checkpoint)
+            .add_tag("source", "checkpoint");
+            if let Some(millis) = millis {
+                write_query = write_query.add_field("convergence_time_ms", *millis);
+            }
+            record_metric(&client, write_query).await;
+        }
+
+        client
+            .publish(
+                TOPIC_CONVERGENCE_TIMES,
+                Cow::Owned(serde_json::to_value(ConvergenceTimes {
+                    seq: instance_info.seq,
+                    millis,
+                })?),
+            )
+            .await?;
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Aggregate the distribution of the convergence times
+    // //////////////////////////////////////////////////////////////
+    // NOTE: The bootstrap node is the designated node which collects the convergence times of
+    // all the other nodes.
+    let mut failed = false;
+
+    if instance_info.seq == bootstrap_node.seq {
+        let convergence_times: Vec<ConvergenceTimes> = collect_from_stream(
+            &client,
+            client
+                .subscribe(TOPIC_CONVERGENCE_TIMES, u16::MAX.into())
+                .await,
+            run_parameters.test_instance_count - 1,
+        )
+        .await?;
+
+        for (i, checkpoint) in CHECKPOINTS.iter().enumerate() {
+            let mut times = convergence_times
+                .iter()
+                .filter_map(|t| t.millis.get(i).copied().flatten())
+                .collect::<Vec<_>>();
+            times.sort_unstable();
+            let not_reached = convergence_times
+                .iter()
+                .filter(|t| t.millis.get(i).copied().flatten().is_none())
+                .map(|t| t.seq)
+                .collect::<Vec<_>>();
+            client.record_message(format!(
+                "{checkpoint}% of the peers (ms): p50: {:?}, p90: {:?}, max: {:?}, reached: {}/{}",
+                percentile(&times, 50),
+                percentile(&times, 90),
+                times.last(),
+                times.len(),
+                convergence_times.len(),
+            ));
+
+            let mut write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("reached", times.len() as u64)
+            .add_field("not_reached", not_reached.len() as u64)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("checkpoint", *checkpoint)
+            .add_tag("source", "distribution");
+            for (name, p) in [("p50", 50), ("p90", 90), ("p100", 100)] {
+                if let Some(millis) = percentile(&times, p) {
+                    write_query = write_query.add_field(format!("{name}_ms"), millis);
+                }
+            }
+            record_metric(&client, write_query).await;
+
+            if *checkpoint == 100 && !not_reached.is_empty() {
+                error!("Nodes which haven't converged: {:?}", not_reached);
+                failed = true;
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_CONVERGE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// The number of `peers` the routing table of `local` can hold, as each bucket holds
+// `MAX_ENTRIES_PER_BUCKET` entries at most.
+fn reachable_peers(local: &NodeId, peers: &HashSet<NodeId>) -> u64 {
+    let local_key: Key<NodeId> = (*local).into();
+    let mut per_distance = BTreeMap::new();
+    for node_id in peers {
+        if let Some(distance) = local_key.log2_distance(&Key::from(*node_id)) {
+            *per_distance.entry(distance).or_insert(0_usize) += 1;
+        }
+    }
+    per_distance
+        .values()
+        .map(|count| (*count).min(MAX_ENTRIES_PER_BUCKET) as u64)
+        .sum()
+}
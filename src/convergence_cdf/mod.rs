@@ -0,0 +1,228 @@
+mod checkpoints;
+mod params;
+
+use crate::config::config_builder;
+use crate::convergence_cdf::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, collect_from_stream,
+    enr_addresses, listen_config, percentile, publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::Discv5;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_CONVERGE: &str = "state_completed_to_converge";
+const TOPIC_CONVERGENCE_TIME: &str = "convergence_time";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ConvergenceTime {
+    seq: u64,
+    // `None` if the table hasn't reached the target size within the timeout.
+    millis: Option<u64>,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("convergence-cdf", run);
+    registry.register("convergence-checkpoints", checkpoints::run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the bootstrap node. The other nodes start cold, knowing only the bootstrap
+    // node.
+    let bootstrap_node = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Bootstrap node")
+        .clone();
+
+    if instance_info.seq == bootstrap_node.seq {
+        for p in participants.iter().filter(|p| p.seq != bootstrap_node.seq) {
+            discv5.add_enr(p.enr.clone())?;
+        }
+    } else {
+        discv5.add_enr(bootstrap_node.enr.clone())?;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run FINDNODE queries until the table reaches the target size
+    // //////////////////////////////////////////////////////////////
+    if instance_info.seq != bootstrap_node.seq {
+        let target_table_size = params
+            .target_table_size
+            .min(participants.len().saturating_sub(1));
+        let started_at = Instant::now();
+        let mut convergence_time = None;
+
+        while started_at.elapsed() < Duration::from_secs(params.convergence_timeout) {
+            if discv5.table_entries_id().len() >= target_table_size {
+                convergence_time = Some(started_at.elapsed());
+                break;
+            }
+            if let Err(e) = rpc_stats.find_node(&discv5, NodeId::random()).await {
+                error!("Failed to run FINDNODE query: {e}");
+            }
+        }
+        info!(
+            "convergence time: {:?}, table size: {}",
+            convergence_time,
+            discv5.table_entries_id().len()
+        );
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let millis = convergence_time.map(|d| d.as_millis() as u64);
+        let mut write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("converged", millis.is_some())
+        .add_tag("instance_seq", instance_info.seq);
+        if let Some(millis) = millis {
+            write_query = write_query.add_field("convergence_time_ms", millis);
+        }
+        record_metric(&client, write_query).await;
+
+        client
+            .publish(
+                TOPIC_CONVERGENCE_TIME,
+                Cow::Owned(serde_json::to_value(ConvergenceTime {
+                    seq: instance_info.seq,
+                    millis,
+                })?),
+            )
+            .await?;
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Compute the CDF of convergence times across the fleet
+    // //////////////////////////////////////////////////////////////
+    // NOTE: The bootstrap node is the designated node which collects the convergence times of
+    // all the other nodes.
+    let mut failed = false;
+
+    if instance_info.seq == bootstrap_node.seq {
+        let mut times = vec![];
+        let mut not_converged = vec![];
+
+        let convergence_times: Vec<ConvergenceTime> = collect_from_stream(
+            &client,
+            client
+                .subscribe(TOPIC_CONVERGENCE_TIME, u16::MAX.into())
+                .await,
+            run_parameters.test_instance_count - 1,
+        )
+        .await?;
+
+        for time in convergence_times {
+            match time.millis {
+                Some(millis) => times.push(millis),
+                None => not_converged.push(time.seq),
+            }
+        }
+        times.sort_unstable();
+
+        client.record_message(format!(
+            "convergence times (ms): p50: {:?}, p90: {:?}, p99: {:?}, max: {:?}, converged: {}/{}",
+            percentile(&times, 50),
+            percentile(&times, 90),
+            percentile(&times, 99),
+            times.last(),
+            times.len(),
+            run_parameters.test_instance_count - 1,
+        ));
+
+        if !not_converged.is_empty() {
+            error!("Nodes which haven't converged: {:?}", not_converged);
+            failed = true;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_CONVERGE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
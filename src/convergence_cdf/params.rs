@@ -0,0 +1,18 @@
+use crate::utils::get_param;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub target_table_size: usize,
+    pub convergence_timeout: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            target_table_size: get_param::<usize>("target_table_size", instance_params)?,
+            convergence_timeout: get_param::<u64>("convergence_timeout", instance_params)?,
+        })
+    }
+}
@@ -0,0 +1,211 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, listen_config,
+    publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Key};
+use std::collections::HashSet;
+use std::time::Instant;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("designated-vs-recursive", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Star topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the bootstrap node.
+    let bootstrap_node = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Bootstrap node")
+        .clone();
+
+    if instance_info.seq == bootstrap_node.seq {
+        for p in participants.iter().filter(|p| p.seq != bootstrap_node.seq) {
+            discv5.add_enr(p.enr.clone())?;
+        }
+    } else {
+        discv5.add_enr(bootstrap_node.enr.clone())?;
+        // Establish a session in advance so that the handshake doesn't skew the latency of the
+        // first request.
+        if let Err(e) = discv5.send_ping(bootstrap_node.enr.clone()).await {
+            error!("Failed to send PING to the bootstrap node: {e}");
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run both kinds of query against the same target
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+    let non_bootstrap_nodes = participants
+        .iter()
+        .filter(|p| p.seq != bootstrap_node.seq)
+        .collect::<Vec<_>>();
+
+    if instance_info.seq != bootstrap_node.seq && non_bootstrap_nodes.len() > 1 {
+        // The target is the next non-bootstrap node.
+        let position = non_bootstrap_nodes
+            .iter()
+            .position(|p| p.seq == instance_info.seq)
+            .expect("Self");
+        let target = non_bootstrap_nodes[(position + 1) % non_bootstrap_nodes.len()];
+        let target_id = target.enr.node_id();
+
+        // FINDNODE request to the bootstrap node for the bucket where the target is.
+        let bootstrap_key: Key<NodeId> = bootstrap_node.enr.node_id().into();
+        let distance = bootstrap_key
+            .log2_distance(&Key::from(target_id))
+            .expect("Distance");
+        let started_at = Instant::now();
+        let designated = rpc_stats
+            .find_node_designated_peer(&discv5, bootstrap_node.enr.clone(), vec![distance])
+            .await;
+        let designated_elapsed = started_at.elapsed();
+
+        // Recursive lookup for the target.
+        let started_at = Instant::now();
+        let recursive = rpc_stats.find_node(&discv5, target_id).await;
+        let recursive_elapsed = started_at.elapsed();
+
+        match (designated, recursive) {
+            (Ok(designated), Ok(recursive)) => {
+                let designated = designated
+                    .iter()
+                    .map(|enr| enr.node_id())
+                    .collect::<HashSet<_>>();
+                let recursive = recursive
+                    .iter()
+                    .map(|enr| enr.node_id())
+                    .collect::<HashSet<_>>();
+                let intersection = designated.intersection(&recursive).count();
+
+                info!(
+                    "target: {target_id}, designated: {} ({}ms, found target: {}), recursive: {} ({}ms, found target: {}), intersection: {intersection}",
+                    designated.len(),
+                    designated_elapsed.as_millis(),
+                    designated.contains(&target_id),
+                    recursive.len(),
+                    recursive_elapsed.as_millis(),
+                    recursive.contains(&target_id),
+                );
+
+                // //////////////////////////////////////////////////////////////
+                // Record metrics
+                // //////////////////////////////////////////////////////////////
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("designated_size", designated.len() as u64)
+                .add_field(
+                    "designated_elapsed_ms",
+                    designated_elapsed.as_millis() as u64,
+                )
+                .add_field("designated_found_target", designated.contains(&target_id))
+                .add_field("recursive_size", recursive.len() as u64)
+                .add_field("recursive_elapsed_ms", recursive_elapsed.as_millis() as u64)
+                .add_field("recursive_found_target", recursive.contains(&target_id))
+                .add_field("intersection", intersection as u64)
+                .add_tag("instance_seq", instance_info.seq);
+                record_metric(&client, write_query).await;
+            }
+            (designated, recursive) => {
+                if let Err(e) = designated {
+                    error!("Failed to run FINDNODE request: {e}");
+                }
+                if let Err(e) = recursive {
+                    error!("Failed to run FINDNODE query: {e}");
+                }
+                failed = true;
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
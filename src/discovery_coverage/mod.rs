@@ -0,0 +1,187 @@
+mod params;
+
+use crate::config::build_config;
+use crate::discovery_coverage::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, listen_config,
+    publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::Discv5;
+use std::collections::HashSet;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_DISCOVER: &str = "state_completed_to_discover";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("discovery-coverage", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = build_config(listen_config, &run_parameters)?;
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+    let others = participants
+        .iter()
+        .filter(|p| p.seq != instance_info.seq)
+        .map(|p| p.enr.node_id())
+        .collect::<HashSet<_>>();
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // The bootstrap nodes know all the other nodes, and the other nodes know only the bootstrap
+    // nodes. Each bucket holds up to 16 entries, so the bootstrap nodes can't return everyone.
+    for p in participants.iter().filter(|p| {
+        p.seq != instance_info.seq && (instance_info.is_bootstrap_node || p.is_bootstrap_node)
+    }) {
+        if let Err(e) = discv5.add_enr(p.enr.clone()) {
+            error!("Failed to add the ENR of #{}: {e}", p.seq);
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run lookups until the routing table stops growing
+    // //////////////////////////////////////////////////////////////
+    // The discovered nodes include the ones returned by the lookups, since the routing table can't
+    // hold all the participants in a large network.
+    let mut discovered = HashSet::new();
+    let mut table_size = 0;
+    let mut stable_for = 0;
+    let mut lookups = 0;
+    while stable_for < params.stable_lookups && lookups < params.max_lookups {
+        match rpc_stats.find_node(&discv5, NodeId::random()).await {
+            Ok(enrs) => discovered.extend(enrs.iter().map(|enr| enr.node_id())),
+            Err(e) => warn!("Failed to run FINDNODE query: {e}"),
+        }
+        lookups += 1;
+
+        let table = discv5.table_entries_id();
+        discovered.extend(table.iter().copied());
+        if table.len() > table_size {
+            table_size = table.len();
+            stable_for = 0;
+        } else {
+            stable_for += 1;
+        }
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Check coverage
+    // //////////////////////////////////////////////////////////////
+    let covered = discovered.intersection(&others).count();
+    let coverage = if others.is_empty() {
+        100.0
+    } else {
+        covered as f64 / others.len() as f64 * 100.0
+    };
+    client.record_message(format!(
+        "Discovered {covered} of {} nodes ({coverage:.1}%) with {lookups} lookups. table size: {table_size}",
+        others.len()
+    ));
+
+    let mut failed = false;
+    if coverage < params.min_coverage {
+        let missing = participants
+            .iter()
+            .filter(|p| p.seq != instance_info.seq && !discovered.contains(&p.enr.node_id()))
+            .map(|p| p.seq)
+            .collect::<Vec<_>>();
+        error!(
+            "The coverage {coverage:.1}% is below {}%. undiscovered: {missing:?}",
+            params.min_coverage
+        );
+        failed = true;
+    } else {
+        info!(
+            "The coverage {coverage:.1}% meets {}%.",
+            params.min_coverage
+        );
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("coverage", coverage)
+    .add_field("discovered", covered as u64)
+    .add_field("lookups", lookups)
+    .add_field("table_size", table_size as u64)
+    .add_tag("instance_seq", instance_info.seq);
+    record_metric(&client, write_query).await;
+
+    // Keep responding to the lookups of the other nodes until all of them have finished.
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_DISCOVER,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub min_coverage: f64,
+    pub stable_lookups: u64,
+    pub max_lookups: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        let min_coverage = get_param_or::<f64>("min_coverage", instance_params, 90.0)?;
+        if !(0.0..=100.0).contains(&min_coverage) {
+            return Err(format!(
+                "min_coverage must be a percentage between 0 and 100, but got {min_coverage}."
+            )
+            .into());
+        }
+
+        Ok(Params {
+            min_coverage,
+            stable_lookups: get_param_or::<u64>("stable_lookups", instance_params, 3)?,
+            max_lookups: get_param_or::<u64>("max_lookups", instance_params, 50)?,
+        })
+    }
+}
@@ -0,0 +1,207 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr_with_ip_version, check_enr_port,
+    publish_and_collect, record_metric, udp_port, InstanceInfo, IpVersion, MAX_DISTANCE,
+};
+use chrono::Local;
+use discv5::Discv5;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_DISCOVER: &str = "state_completed_to_discover";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Participant {
+    #[serde(flatten)]
+    instance: InstanceInfo,
+    ip_version: String,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("dual-stack", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let seq = client.global_seq();
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    // NOTE: #1 is the dual-stack bridge. The other instances are IPv4-only if their seq is even,
+    // and IPv6-only otherwise.
+    let ip_version = if seq == 1 {
+        IpVersion::Dual
+    } else if seq % 2 == 0 {
+        IpVersion::V4
+    } else {
+        IpVersion::V6
+    };
+    let (enr, enr_key) = build_enr_with_ip_version(&run_parameters, seq, ip_version)?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = ip_version.listen_config(udp_port(&run_parameters.test_instance_params)?);
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = Participant {
+        instance: InstanceInfo::new(&client, enr)?,
+        ip_version: format!("{ip_version:?}"),
+    };
+    client.record_message(format!(
+        "seq: {seq}, ip_version: {ip_version:?}, node_id: {}",
+        instance_info.instance.enr.node_id()
+    ));
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // The bridge knows all the other participants, whichever IP version they are.
+    let bridge = participants
+        .iter()
+        .find(|p| p.instance.seq == 1)
+        .expect("Bridge")
+        .clone();
+
+    if seq == bridge.instance.seq {
+        for p in participants
+            .iter()
+            .filter(|p| p.instance.seq != bridge.instance.seq)
+        {
+            if let Err(e) = discv5.add_enr(p.instance.enr.clone()) {
+                error!("Failed to add the ENR of #{}: {e}", p.instance.seq);
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Discover the other nodes via the bridge
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if seq != bridge.instance.seq {
+        // The bridge should return the ENRs of the nodes of both IP versions.
+        let expected = participants
+            .iter()
+            .filter(|p| p.instance.seq != bridge.instance.seq && p.instance.seq != seq)
+            .collect::<Vec<_>>();
+        let discovered = match rpc_stats
+            .find_node_designated_peer(
+                &discv5,
+                bridge.instance.enr.clone(),
+                (1..=MAX_DISTANCE).collect(),
+            )
+            .await
+        {
+            Ok(enrs) => enrs.iter().map(|enr| enr.node_id()).collect::<HashSet<_>>(),
+            Err(e) => {
+                error!("Failed to run FINDNODE request to the bridge: {e}");
+                failed = true;
+                HashSet::new()
+            }
+        };
+
+        for p in expected.iter() {
+            let found = discovered.contains(&p.instance.enr.node_id());
+            if !found {
+                error!(
+                    "#{} ({}) hasn't been discovered via the bridge.",
+                    p.instance.seq, p.ip_version
+                );
+                failed = true;
+            }
+
+            // A session can be established only with the nodes of the same IP version.
+            let same_version = p.ip_version == instance_info.ip_version;
+            let reachable = if same_version {
+                match discv5.send_ping(p.instance.enr.clone()).await {
+                    Ok(_) => true,
+                    Err(e) => {
+                        error!(
+                            "Failed to send PING to #{} ({}): {e}",
+                            p.instance.seq, p.ip_version
+                        );
+                        failed = true;
+                        false
+                    }
+                }
+            } else {
+                false
+            };
+            info!(
+                "#{} ({}): discovered: {found}, reachable: {reachable}",
+                p.instance.seq, p.ip_version
+            );
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("discovered", found)
+            .add_field("reachable", reachable)
+            .add_tag("instance_seq", seq)
+            .add_tag("target_seq", p.instance.seq)
+            .add_tag("ip_version", instance_info.ip_version.clone())
+            .add_tag("target_ip_version", p.ip_version.clone());
+            record_metric(&client, write_query).await;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_DISCOVER,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, seq).await;
+
+    teardown(&client, discv5, seq, run_parameters.test_instance_count).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
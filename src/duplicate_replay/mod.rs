@@ -0,0 +1,512 @@
+mod params;
+
+use crate::config::build_config;
+use crate::duplicate_replay::params::Params;
+use crate::events::EventRecorder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::{final_barrier, teardown};
+use crate::topology::{join_topology, Topology};
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, bootstrap_count, build_enr, check_enr_port,
+    collect_instance_info, enr_socket, ip_version, link_shape_with, listen_config,
+    reconfigure_network, record_metric, udp_port, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::packet::{Packet, PacketKind};
+use discv5::{DefaultProtocolId, Discv5, Event};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use testground::client::Client;
+use testground::network_conf::LinkShape;
+use testground::WriteQuery;
+use tokio::net::UdpSocket;
+use tokio::time::{timeout_at, Instant};
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_BASELINE: &str = "state_completed_baseline";
+const STATE_COMPLETED_DUPLICATED: &str = "state_completed_duplicated";
+
+// The largest packet discv5 accepts.
+const MAX_PACKET_SIZE: usize = 1280;
+// How long an attacker waits for the WHOAREYOU challenges after replaying a datagram.
+const CHALLENGE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// The lookups and the sessions in a phase of an honest node.
+#[derive(Default)]
+struct PhaseStats {
+    lookups: u64,
+    successful_lookups: u64,
+    // The ENRs found more than once in a single lookup, i.e. a NODES response processed twice.
+    duplicated_enrs: u64,
+    // The sessions established with the peers for the first time in the run.
+    established: u64,
+    // The sessions established with the peers which had one already, i.e. the previous session
+    // has been dropped, or a handshake has been processed twice.
+    reestablished: u64,
+}
+
+impl PhaseStats {
+    fn success_rate(&self) -> f64 {
+        if self.lookups == 0 {
+            1.0
+        } else {
+            self.successful_lookups as f64 / self.lookups as f64
+        }
+    }
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("duplicate-replay", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // NOTE: The `attackers` instances with the highest seqs are the attackers, if any. The others
+    // are honest, and construct a star topology with the bootstrap nodes at the center.
+    let honest_count = run_parameters
+        .test_instance_count
+        .saturating_sub(params.attackers);
+    if honest_count <= bootstrap_count(&client)? {
+        return Err("`attackers` must leave at least one non-bootstrap honest instance.".into());
+    }
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr.clone())?;
+    let honest_instances = collect_instance_info(&client, &instance_info)
+        .await?
+        .into_iter()
+        .filter(|i| i.seq <= honest_count)
+        .collect::<Vec<_>>();
+
+    if instance_info.seq > honest_count {
+        play_attacker(&client, &params, enr.node_id(), &honest_instances).await
+    } else {
+        // ////////////////////////
+        // Start discv5
+        // ////////////////////////
+        let listen_config = listen_config(&run_parameters)?;
+        check_enr_port(&enr, &listen_config)?;
+        let mut discv5: Discv5 =
+            Discv5::new(enr, enr_key, build_config(listen_config, &run_parameters)?)?;
+        discv5
+            .start()
+            .await
+            .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+        play_honest(&client, &params, discv5, &instance_info, &honest_instances).await
+    }
+}
+
+async fn play_honest(
+    client: &Client,
+    params: &Params,
+    discv5: Discv5,
+    instance_info: &InstanceInfo,
+    honest_instances: &[InstanceInfo],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+
+    // The peers each session is established with, in order, taken by each phase.
+    let (event_recorder, mut event_stream) =
+        EventRecorder::start_forwarding(client, &discv5).await?;
+    let sessions = Arc::new(Mutex::new(vec![]));
+    let task_sessions = sessions.clone();
+    let session_task = tokio::spawn(async move {
+        while let Some(event) = event_stream.recv().await {
+            if let Event::SessionEstablished(enr, _) = event {
+                task_sessions
+                    .lock()
+                    .expect("Lock the sessions")
+                    .push(enr.node_id());
+            }
+        }
+    });
+
+    // //////////////////////////////////////////////////////////////
+    // Star topology
+    // //////////////////////////////////////////////////////////////
+    let rpc_stats = RpcStats::default();
+    join_topology(
+        &discv5,
+        &rpc_stats,
+        Topology::Star,
+        instance_info,
+        honest_instances,
+    )
+    .await?;
+
+    barrier_with_timeout(
+        client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(client)?,
+    )
+    .await?;
+
+    // The sessions established while constructing the topology are the first ones.
+    let mut peers = HashSet::new();
+    let _ = take_sessions(&sessions, &mut peers);
+
+    // //////////////////////////////////////////////////////////////
+    // Run lookups on the clean links and then on the duplicating ones
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+    let mut stats = HashMap::new();
+
+    for (phase, state) in [
+        ("baseline", STATE_COMPLETED_BASELINE),
+        ("duplicated", STATE_COMPLETED_DUPLICATED),
+    ] {
+        if phase == "duplicated" {
+            reconfigure_network(
+                client,
+                link_shape_with(&run_parameters.test_instance_params, |link_shape| {
+                    LinkShape {
+                        duplicate: params.duplicate,
+                        duplicate_corr: params.duplicate_corr,
+                        ..link_shape
+                    }
+                })?,
+                "state_network_duplicated".to_owned(),
+            )
+            .await?;
+        }
+
+        let mut phase_stats =
+            run_lookups(client, params, &discv5, &rpc_stats, instance_info, phase).await;
+        (phase_stats.established, phase_stats.reestablished) = take_sessions(&sessions, &mut peers);
+        let connected_peers = discv5.connected_peers();
+        let active_sessions = discv5.metrics().active_sessions;
+        info!(
+            "phase: {phase}, success_rate: {:.3}, duplicated_enrs: {}, established: {}, reestablished: {}, connected_peers: {connected_peers}, active_sessions: {active_sessions}",
+            phase_stats.success_rate(),
+            phase_stats.duplicated_enrs,
+            phase_stats.established,
+            phase_stats.reestablished
+        );
+
+        if phase_stats.duplicated_enrs > 0 {
+            error!(
+                "{} ENRs have been found twice in a lookup in the {phase} phase.",
+                phase_stats.duplicated_enrs
+            );
+            failed = true;
+        }
+        // Every honest node has a session with each of the other honest nodes at most.
+        if active_sessions > honest_instances.len() {
+            error!(
+                "The active sessions {active_sessions} exceed the honest peers {} in the {phase} phase.",
+                honest_instances.len()
+            );
+            failed = true;
+        }
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("lookups", phase_stats.lookups)
+        .add_field("successful_lookups", phase_stats.successful_lookups)
+        .add_field("success_rate", phase_stats.success_rate())
+        .add_field("duplicated_enrs", phase_stats.duplicated_enrs)
+        .add_field("established", phase_stats.established)
+        .add_field("reestablished", phase_stats.reestablished)
+        .add_field("connected_peers", connected_peers as u64)
+        .add_field("active_sessions", active_sessions as u64)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("phase", phase)
+        .add_tag("source", "summary");
+        record_metric(client, write_query).await;
+
+        stats.insert(phase, phase_stats);
+
+        barrier_with_timeout(
+            client,
+            state,
+            run_parameters.test_instance_count,
+            barrier_timeout(client)?,
+        )
+        .await?;
+    }
+
+    session_task.abort();
+    event_recorder.finish(client).await;
+
+    let baseline = &stats["baseline"];
+    let duplicated = &stats["duplicated"];
+    client.record_message(format!(
+        "success_rate: {:.3} -> {:.3}, reestablished: {} -> {}",
+        baseline.success_rate(),
+        duplicated.success_rate(),
+        baseline.reestablished,
+        duplicated.reestablished
+    ));
+    if duplicated.success_rate() < params.min_success_rate {
+        error!(
+            "The success rate on the duplicating links {:.3} is below {}.",
+            duplicated.success_rate(),
+            params.min_success_rate
+        );
+        failed = true;
+    }
+    // The sessions re-established beyond the clean baseline are the anomalous session drops.
+    if duplicated.reestablished > baseline.reestablished {
+        error!(
+            "{} sessions have been re-established on the duplicating links, against {} in the baseline.",
+            duplicated.reestablished, baseline.reestablished
+        );
+        failed = true;
+    }
+
+    rpc_stats.finish(client, instance_info.seq).await;
+
+    teardown(
+        client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Run random lookups every `lookup_interval` for `phase_duration`.
+async fn run_lookups(
+    client: &Client,
+    params: &Params,
+    discv5: &Discv5,
+    rpc_stats: &RpcStats,
+    instance_info: &InstanceInfo,
+    phase: &str,
+) -> PhaseStats {
+    let run_parameters = client.run_parameters();
+    let deadline = Instant::now() + Duration::from_secs(params.phase_duration);
+    let mut interval = tokio::time::interval(Duration::from_millis(params.lookup_interval));
+    let mut stats = PhaseStats::default();
+
+    while Instant::now() < deadline {
+        interval.tick().await;
+
+        let started_at = Instant::now();
+        let (success, duplicated) = match rpc_stats.find_node(discv5, NodeId::random()).await {
+            Ok(enrs) => {
+                let found = enrs.iter().map(|enr| enr.node_id()).collect::<HashSet<_>>();
+                (!enrs.is_empty(), (enrs.len() - found.len()) as u64)
+            }
+            Err(e) => {
+                warn!("Failed to run FINDNODE query: {e}");
+                (false, 0)
+            }
+        };
+        stats.lookups += 1;
+        if success {
+            stats.successful_lookups += 1;
+        }
+        stats.duplicated_enrs += duplicated;
+
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("elapsed_ms", started_at.elapsed().as_millis() as u64)
+        .add_field("success", success)
+        .add_field("duplicated_enrs", duplicated)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("phase", phase.to_owned());
+        record_metric(client, write_query).await;
+    }
+
+    stats
+}
+
+// Take the sessions established since the last call. Returns the number of them with new peers,
+// which are added to `peers`, and with the peers in `peers` already.
+fn take_sessions(sessions: &Mutex<Vec<NodeId>>, peers: &mut HashSet<NodeId>) -> (u64, u64) {
+    let mut established = 0;
+    let mut reestablished = 0;
+    for node_id in sessions.lock().expect("Lock the sessions").drain(..) {
+        if peers.insert(node_id) {
+            established += 1;
+        } else {
+            reestablished += 1;
+        }
+    }
+    (established, reestablished)
+}
+
+async fn play_attacker(
+    client: &Client,
+    params: &Params,
+    local_id: NodeId,
+    honest_instances: &[InstanceInfo],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let socket = UdpSocket::bind(SocketAddr::new(
+        ip_version(&run_parameters.test_instance_params)?.unspecified(),
+        udp_port(&run_parameters.test_instance_params)?,
+    ))
+    .await?;
+
+    // Stay quiet while the honest nodes measure the baseline.
+    for state in [STATE_COMPLETED_TO_BUILD_TOPOLOGY, STATE_COMPLETED_BASELINE] {
+        barrier_with_timeout(
+            client,
+            state,
+            run_parameters.test_instance_count,
+            barrier_timeout(client)?,
+        )
+        .await?;
+    }
+
+    reconfigure_network(
+        client,
+        link_shape_with(&run_parameters.test_instance_params, |link_shape| {
+            LinkShape {
+                duplicate: params.duplicate,
+                duplicate_corr: params.duplicate_corr,
+                ..link_shape
+            }
+        })?,
+        "state_network_duplicated".to_owned(),
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Replay datagrams
+    // //////////////////////////////////////////////////////////////
+    // Each datagram is sent `replays` more times right after itself. The honest node should
+    // process it once, i.e. issue one WHOAREYOU challenge at most.
+    let mut failed = false;
+    let mut replayed = HashMap::new();
+    let deadline = Instant::now() + Duration::from_secs(params.phase_duration);
+    let mut n = 0_usize;
+
+    while Instant::now() < deadline && !honest_instances.is_empty() {
+        let target = &honest_instances[n % honest_instances.len()];
+        n += 1;
+        let socket_addr = enr_socket(&target.enr)?;
+
+        let datagram =
+            Packet::new_random(&local_id)?.encode::<DefaultProtocolId>(&target.enr.node_id());
+        for _ in 0..=params.replays {
+            if let Err(e) = socket.send_to(&datagram, socket_addr).await {
+                warn!("Failed to replay a datagram to #{}: {e}", target.seq);
+            }
+        }
+
+        let challenges = capture_challenges(&socket, local_id, socket_addr).await;
+        let (bursts, total) = replayed.entry(target.seq).or_insert((0_u64, 0_u64));
+        *bursts += 1;
+        *total += challenges;
+        if challenges > 1 {
+            error!(
+                "#{} has issued {challenges} challenges to a datagram replayed {} times.",
+                target.seq, params.replays
+            );
+            failed = true;
+        }
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    for (target_seq, (bursts, challenges)) in replayed {
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("bursts", bursts)
+        .add_field("replays", bursts * params.replays)
+        .add_field("challenges", challenges)
+        .add_tag("instance_seq", client.global_seq())
+        .add_tag("target_seq", target_seq)
+        .add_tag("source", "replay");
+        record_metric(client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        client,
+        STATE_COMPLETED_DUPLICATED,
+        run_parameters.test_instance_count,
+        barrier_timeout(client)?,
+    )
+    .await?;
+    final_barrier(client, run_parameters.test_instance_count).await?;
+
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// The number of distinct WHOAREYOU challenges received from `socket_addr` within
+// `CHALLENGE_TIMEOUT`. The challenges duplicated by the links are identical, so they are counted
+// once.
+async fn capture_challenges(socket: &UdpSocket, local_id: NodeId, socket_addr: SocketAddr) -> u64 {
+    let deadline = Instant::now() + CHALLENGE_TIMEOUT;
+    let mut challenges = HashSet::new();
+    let mut buffer = [0; MAX_PACKET_SIZE];
+
+    while let Ok(received) = timeout_at(deadline, socket.recv_from(&mut buffer)).await {
+        let (length, from) = match received {
+            Ok(received) => received,
+            Err(e) => {
+                warn!("Failed to receive a packet: {e}");
+                continue;
+            }
+        };
+        if from != socket_addr {
+            continue;
+        }
+        if let Ok((packet, _)) = Packet::decode::<DefaultProtocolId>(&local_id, &buffer[..length]) {
+            if matches!(packet.header.kind, PacketKind::WhoAreYou { .. }) {
+                challenges.insert(buffer[..length].to_vec());
+            }
+        }
+    }
+
+    challenges.len() as u64
+}
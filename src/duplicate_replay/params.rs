@@ -0,0 +1,28 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub attackers: u64,
+    pub replays: u64,
+    pub duplicate: f32,
+    pub duplicate_corr: f32,
+    pub phase_duration: u64,
+    pub lookup_interval: u64,
+    pub min_success_rate: f64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            attackers: get_param_or::<u64>("attackers", instance_params, 0)?,
+            replays: get_param_or::<u64>("replays", instance_params, 10)?,
+            duplicate: get_param_or::<f32>("duplicate", instance_params, 50.0)?,
+            duplicate_corr: get_param_or::<f32>("duplicate_corr", instance_params, 25.0)?,
+            phase_duration: get_param_or::<u64>("phase_duration", instance_params, 30)?,
+            lookup_interval: get_param_or::<u64>("lookup_interval", instance_params, 1000)?,
+            min_success_rate: get_param_or::<f64>("min_success_rate", instance_params, 0.0)?,
+        })
+    }
+}
@@ -0,0 +1,225 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::dynamic_subnet_filter::params::Params;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr_with_fields, check_enr_port, enr_addresses,
+    listen_config, publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::{Discv5, Enr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+
+// The ENR key of the attnets-like subnet bitfield.
+const SUBNETS_KEY: &str = "subnets";
+
+// The subnets this node is interested in. `table_filter` accepts a function pointer only, so the
+// filter reads the local subscriptions from here.
+static SUBSCRIBED_SUBNETS: AtomicU64 = AtomicU64::new(0);
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("dynamic-subnet-filter", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // NOTE: #1 is the node that updates its advertised subnets. It subscribes to all the subnets
+    // so that it accepts every peer, while the other nodes subscribe to a single subnet each,
+    // assigned in round-robin.
+    let is_updater = client.global_seq() == 1;
+    let local_subnets = if is_updater {
+        u64::MAX
+    } else {
+        1 << ((client.global_seq() - 2) % params.subnet_count)
+    };
+    SUBSCRIBED_SUBNETS.store(local_subnets, Ordering::Relaxed);
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    // The updater doesn't advertise any subnet initially.
+    let advertised_subnets = if is_updater { 0_u64 } else { local_subnets };
+    let (enr, enr_key) = build_enr_with_fields(&run_parameters, client.global_seq(), |builder| {
+        builder.add_value(SUBNETS_KEY, &advertised_subnets);
+    })?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+        .table_filter(subnet_filter)
+        .build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}, subnets: {:#b}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr),
+        local_subnets
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    let updater = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("updater")
+        .clone();
+    let peers = participants
+        .iter()
+        .filter(|&p| p.seq != updater.seq)
+        .collect::<Vec<_>>();
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // The updater adds all the other nodes, while the other nodes attempt to add the updater,
+    // which should be rejected as it doesn't advertise any subnet yet.
+    if is_updater {
+        for p in peers.iter() {
+            if let Err(e) = discv5.add_enr(p.enr.clone()) {
+                error!("Failed to add ENR: {e}");
+            }
+        }
+    } else if let Err(e) = discv5.add_enr(updater.enr.clone()) {
+        info!("Rejected the updater's ENR: {e}");
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Update the advertised subnets and observe the table membership
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    for round in 0..params.update_count {
+        let advertised_subnets = 1_u64 << (round % params.subnet_count);
+
+        if is_updater {
+            discv5
+                .enr_insert(SUBNETS_KEY, &advertised_subnets)
+                .map_err(|e| format!("Failed to update the local ENR: {e:?}"))?;
+            client.record_message(format!(
+                "round: {round}, advertised subnets: {advertised_subnets:#b}, seq: {}",
+                discv5.local_enr().seq()
+            ));
+
+            // Notify the peers of the new sequence number so that they request the updated ENR.
+            for p in peers.iter() {
+                if let Err(e) = discv5.send_ping(p.enr.clone()).await {
+                    error!("Failed to send PING: {e}");
+                }
+            }
+        }
+
+        barrier_with_timeout(
+            &client,
+            format!("state_completed_to_update_{round}"),
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        if !is_updater {
+            // Give the node time to request the updated ENR and re-evaluate it.
+            tokio::time::sleep(Duration::from_secs(params.wait_duration)).await;
+
+            let expected = advertised_subnets & local_subnets != 0;
+            let member = discv5.table_entries_id().contains(&updater.enr.node_id());
+            let observed_subnets = discv5
+                .find_enr(&updater.enr.node_id())
+                .and_then(|enr| subnets(&enr));
+
+            if member == expected {
+                info!("round: {round}, member: {member}, observed subnets: {observed_subnets:?}");
+            } else {
+                error!(
+                    "Unexpected table membership. round: {round}, expected: {expected}, actual: {member}, observed subnets: {observed_subnets:?}"
+                );
+                failed = true;
+            }
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("member", member)
+            .add_field("expected", expected)
+            .add_field("advertised_subnets", advertised_subnets)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("round", round);
+            record_metric(&client, write_query).await;
+        }
+
+        barrier_with_timeout(
+            &client,
+            format!("state_completed_round_{round}"),
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+    }
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+fn subnets(enr: &Enr) -> Option<u64> {
+    enr.get_decodable::<u64>(SUBNETS_KEY)
+        .and_then(|subnets| subnets.ok())
+}
+
+fn subnet_filter(enr: &Enr) -> bool {
+    matches!(subnets(enr), Some(subnets) if subnets & SUBSCRIBED_SUBNETS.load(Ordering::Relaxed) != 0)
+}
@@ -0,0 +1,25 @@
+use crate::utils::get_param;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub subnet_count: u64,
+    pub update_count: u64,
+    pub wait_duration: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        let subnet_count = get_param::<u64>("subnet_count", instance_params)?;
+        if subnet_count == 0 || subnet_count > 64 {
+            return Err("subnet_count must be between 1 and 64.".into());
+        }
+
+        Ok(Params {
+            subnet_count,
+            update_count: get_param::<u64>("update_count", instance_params)?,
+            wait_duration: get_param::<u64>("wait_duration", instance_params)?,
+        })
+    }
+}
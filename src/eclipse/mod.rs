@@ -1,9 +1,20 @@
-use crate::utils::publish_and_collect;
-use discv5::enr::k256::elliptic_curve::rand_core::RngCore;
-use discv5::enr::k256::elliptic_curve::rand_core::SeedableRng;
+mod nodes_flooding;
+
+pub(super) use nodes_flooding::NodesFlooding;
+
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr_with_key, check_enr_port, get_group_seq,
+    get_instance_seq, get_param, ip_version, key_from_rng_seed, listen_config, publish_and_collect,
+    spawn_metrics_poller, InstanceInfo, Role,
+};
 use discv5::enr::{CombinedKey, NodeId};
-use discv5::{Discv5, Enr, ListenConfig};
-use serde::{Deserialize, Serialize};
+use discv5::Discv5;
+use std::sync::Arc;
+use std::time::Duration;
 use std::u64;
 use testground::client::Client;
 use tokio::task;
@@ -14,28 +25,24 @@ const STATE_COMPLETED_TO_COLLECT_INSTANCE_INFORMATION: &str =
 const STATE_ATTACKERS_SENT_QUERY: &str = "STATE_ATTACKERS_SENT_QUERY";
 const STATE_DONE: &str = "STATE_DONE";
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-enum Role {
-    Victim,
-    Honest,
-    Attacker,
+// The role of the group, which must be one of `victim`, `honest` or `attackers`.
+fn group_role(test_group_id: &str) -> Result<Role, String> {
+    Role::from_supported_group_id(test_group_id, &[Role::Victim, Role::Honest, Role::Attacker])
 }
 
-impl From<&str> for Role {
-    fn from(test_group_id: &str) -> Self {
-        match test_group_id {
-            "victim" => Role::Victim,
-            "honest" => Role::Honest,
-            "attackers" => Role::Attacker,
-            _ => unreachable!(),
-        }
-    }
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-struct InstanceInfo {
-    enr: Enr,
-    role: Role,
+pub(super) fn register(registry: &mut Registry) {
+    registry.register(
+        "eclipse-attack-monopolizing-by-incoming-nodes",
+        |client: Client| async move { MonopolizingByIncomingNodes::new().run(client).await },
+    );
+    registry.register(
+        "eclipse-attack-nodes-flooding",
+        |client: Client| async move {
+            NodesFlooding::new(&client.run_parameters())?
+                .run(client)
+                .await
+        },
+    );
 }
 
 pub(super) struct MonopolizingByIncomingNodes {}
@@ -48,40 +55,49 @@ impl MonopolizingByIncomingNodes {
     pub(super) async fn run(&self, client: Client) -> Result<(), Box<dyn std::error::Error>> {
         let run_parameters = client.run_parameters();
         // Note: The seq starts from 1.
-        let role: Role = run_parameters.test_group_id.as_str().into();
+        let role = group_role(&run_parameters.test_group_id)?;
         client.record_message(format!(
             "role: {:?}, group_seq: {}",
             role,
-            client.group_seq()
+            get_group_seq(&client)
         ));
 
         // ////////////////////////
         // Construct a local Enr
         // ////////////////////////
-        let enr_key = Self::generate_deterministic_keypair(client.group_seq(), &role);
-        let enr = Enr::builder()
-            .ip(run_parameters
-                .data_network_ip()?
-                .expect("IP address for the data network"))
-            .udp4(9000)
-            .build(&enr_key)
-            .expect("Construct an Enr");
+        let enr_key = Self::generate_deterministic_keypair(get_group_seq(&client), &role)?;
+        let enr = build_enr_with_key(
+            &run_parameters,
+            client.global_seq(),
+            ip_version(&run_parameters.test_instance_params)?,
+            &enr_key,
+        )?;
 
         // //////////////////////////////////////////////////////////////
         // Start Discovery v5 server
         // //////////////////////////////////////////////////////////////
-        let discv5_config = discv5::ConfigBuilder::new(ListenConfig::default())
-            .incoming_bucket_limit(
-                run_parameters
-                    .test_instance_params
-                    .get("incoming_bucket_limit")
-                    .expect("incoming_bucket_limit")
-                    .parse::<usize>()
-                    .expect("Valid as usize"),
-            )
-            .build();
+        // NOTE: The `incoming_bucket_limit` param is applied by `config_builder`.
+        let listen_config = listen_config(&run_parameters)?;
+        check_enr_port(&enr, &listen_config)?;
+        let discv5_config =
+            config_builder(listen_config, &run_parameters.test_instance_params)?.build();
         let mut discv5 = Discv5::new(enr, enr_key, discv5_config)?;
-        discv5.start().await.expect("Start Discovery v5 server");
+        discv5
+            .start()
+            .await
+            .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+        let discv5 = Arc::new(discv5);
+
+        // Record the metrics of discv5 periodically until the end of this test.
+        let metrics_poller = spawn_metrics_poller(
+            &client,
+            discv5.clone(),
+            get_instance_seq(&client),
+            Duration::from_secs(get_param::<u64>(
+                "metrics_interval",
+                &run_parameters.test_instance_params,
+            )?),
+        )?;
 
         // Observe Discv5 events.
         let mut event_stream = discv5.event_stream().await.expect("Discv5Event");
@@ -94,37 +110,59 @@ impl MonopolizingByIncomingNodes {
         // //////////////////////////////////////////////////////////////
         // Collect information of all participants in the test case
         // //////////////////////////////////////////////////////////////
-        let instance_info = InstanceInfo {
-            enr: discv5.local_enr(),
-            role,
-        };
+        let instance_info = InstanceInfo::with_role(&client, discv5.local_enr(), role)?;
 
         let (victim, honest, attackers) =
             self.collect_instance_info(&client, &instance_info).await?;
 
-        client
-            .signal_and_wait(
-                STATE_COMPLETED_TO_COLLECT_INSTANCE_INFORMATION,
-                run_parameters.test_instance_count,
-            )
-            .await?;
+        barrier_with_timeout(
+            &client,
+            STATE_COMPLETED_TO_COLLECT_INSTANCE_INFORMATION,
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
 
         // //////////////////////////////////////////////////////////////
         // Play the role
         // //////////////////////////////////////////////////////////////
+        let rpc_stats = RpcStats::default();
         match instance_info.role {
             Role::Victim => {
-                self.play_victim(discv5, client, &honest, &attackers)
+                self.play_victim(discv5.clone(), client.clone(), &honest, &attackers)
                     .await?
             }
-            Role::Honest => self.play_honest(client).await?,
-            Role::Attacker => self.play_attacker(discv5, client, &victim).await?,
+            Role::Honest => self.play_honest(client.clone()).await?,
+            Role::Attacker => {
+                self.play_attacker(
+                    discv5.clone(),
+                    client.clone(),
+                    &rpc_stats,
+                    &victim,
+                    &attackers,
+                )
+                .await?
+            }
+            role => return Err(role.unsupported()),
         }
 
-        Ok(())
+        // The test has completed, so stop recording the metrics.
+        drop(metrics_poller);
+        rpc_stats.finish(&client, instance_info.seq).await;
+
+        teardown(
+            &client,
+            discv5,
+            instance_info.seq,
+            run_parameters.test_instance_count,
+        )
+        .await
     }
 
-    fn generate_deterministic_keypair(group_seq: u64, role: &Role) -> CombinedKey {
+    fn generate_deterministic_keypair(
+        group_seq: u64,
+        role: &Role,
+    ) -> Result<CombinedKey, Box<dyn std::error::Error>> {
         // Generate 20 key pairs. Distances between the first key pair and all other ones are the
         // same. So in the node with the first key pair, node ids given from the other ones will be
         // inserted into the same bucket.
@@ -146,8 +184,9 @@ impl MonopolizingByIncomingNodes {
             Role::Victim => group_seq,
             Role::Honest => group_seq + 1, // Take the number of victim into account
             Role::Attacker => group_seq + 2, // Take the number of victim + honest into account
+            role => return Err(role.unsupported()),
         } - 1; // The group_seq starts from 1, not from 0, so we should minus one here.
-        keypairs.remove(usize::try_from(index).expect("Valid as usize"))
+        Ok(keypairs.remove(usize::try_from(index)?))
     }
 
     async fn collect_instance_info(
@@ -164,6 +203,7 @@ impl MonopolizingByIncomingNodes {
                 Role::Victim => victim.push(i),
                 Role::Honest => honest.push(i),
                 Role::Attacker => attackers.push(i),
+                role => return Err(role.unsupported()),
             }
         }
 
@@ -174,18 +214,19 @@ impl MonopolizingByIncomingNodes {
 
     async fn play_victim(
         &self,
-        discv5: Discv5,
+        discv5: Arc<Discv5>,
         client: Client,
         honest: &InstanceInfo,
         attackers: &[InstanceInfo],
     ) -> Result<(), Box<dyn std::error::Error>> {
-        // Wait until the attacker has done its attack.
-        client
-            .barrier(
-                STATE_ATTACKERS_SENT_QUERY,
-                u64::try_from(attackers.len()).unwrap(),
-            )
-            .await?;
+        // Wait until the attackers have done their attack.
+        barrier_with_timeout(
+            &client,
+            STATE_ATTACKERS_SENT_QUERY,
+            u64::try_from(attackers.len())? + 1,
+            barrier_timeout(&client)?,
+        )
+        .await?;
 
         // For debugging, dump the routing table statistics.
         for (i, bucket) in discv5.kbuckets().buckets_iter().enumerate() {
@@ -202,9 +243,13 @@ impl MonopolizingByIncomingNodes {
         // error because the bucket is full of the attacker's node id.
         let result = discv5.add_enr(honest.enr.clone());
 
-        client
-            .signal_and_wait(STATE_DONE, client.run_parameters().test_instance_count)
-            .await?;
+        barrier_with_timeout(
+            &client,
+            STATE_DONE,
+            client.run_parameters().test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
 
         if let Err(msg) = result {
             client
@@ -218,9 +263,13 @@ impl MonopolizingByIncomingNodes {
 
     async fn play_honest(&self, client: Client) -> Result<(), Box<dyn std::error::Error>> {
         // Nothing to do, just wait until the simulation has been done.
-        client
-            .signal_and_wait(STATE_DONE, client.run_parameters().test_instance_count)
-            .await?;
+        barrier_with_timeout(
+            &client,
+            STATE_DONE,
+            client.run_parameters().test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
 
         client.record_success().await?;
         Ok(())
@@ -228,50 +277,47 @@ impl MonopolizingByIncomingNodes {
 
     async fn play_attacker(
         &self,
-        discv5: Discv5,
+        discv5: Arc<Discv5>,
         client: Client,
+        rpc_stats: &RpcStats,
         victim: &InstanceInfo,
+        attackers: &[InstanceInfo],
     ) -> Result<(), Box<dyn std::error::Error>> {
         // The victim's ENR is added to the attacker's routing table prior to sending a query. So
         // the FINDNODE query will be sent to the victim, and then, if the victim is vulnerable
         // to the eclipse attack, the attacker's ENR will be added to the victim's routing table
         // because of the handshake.
         discv5.add_enr(victim.enr.clone())?;
-        if let Err(e) = discv5.find_node(NodeId::random()).await {
+        if let Err(e) = rpc_stats.find_node(&discv5, NodeId::random()).await {
             client.record_message(format!("Failed to run query: {}", e));
         }
 
-        // Inform that sending query has been done.
-        client.signal(STATE_ATTACKERS_SENT_QUERY).await?;
+        // Inform that sending query has been done. The victim takes part in this barrier, hence the
+        // attackers plus one.
+        barrier_with_timeout(
+            &client,
+            STATE_ATTACKERS_SENT_QUERY,
+            u64::try_from(attackers.len())? + 1,
+            barrier_timeout(&client)?,
+        )
+        .await?;
 
         // Wait until checking on the victim has been done.
-        client
-            .signal_and_wait(STATE_DONE, client.run_parameters().test_instance_count)
-            .await?;
+        barrier_with_timeout(
+            &client,
+            STATE_DONE,
+            client.run_parameters().test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
 
         client.record_success().await?;
         Ok(())
     }
 }
 
-// This function is copied from https://github.com/sigp/discv5/blob/master/src/discv5/test.rs
 // Generate `n` deterministic keypairs from a given seed.
 fn generate_deterministic_keypair(n: usize, seed: u64) -> Vec<CombinedKey> {
-    let mut keypairs = Vec::new();
-    for i in 0..n {
-        let sk = {
-            let rng = &mut rand_xorshift::XorShiftRng::seed_from_u64(seed + i as u64);
-            let mut b = [0; 32];
-            loop {
-                // until a value is given within the curve order
-                rng.fill_bytes(&mut b);
-                if let Ok(k) = enr::k256::ecdsa::SigningKey::from_slice(&b) {
-                    break k;
-                }
-            }
-        };
-        let kp = CombinedKey::from(sk);
-        keypairs.push(kp);
-    }
-    keypairs
+    // NOTE: The seed is pre-computed for the keys derived from `seed + i`, see `key_from_rng_seed`.
+    (0..n as u64).map(|i| key_from_rng_seed(seed + i)).collect()
 }
@@ -0,0 +1,835 @@
+use super::group_role;
+use crate::assertions::{Assertions, Thresholds};
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::sybil::grind_keys;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr_on_port, build_enr_with_key, check_enr_port,
+    collect_from_stream, deterministic_key, get_group_seq, get_instance_seq, get_param,
+    get_param_or, ip_version, listen_config, parse_distances, publish_and_collect, record_metric,
+    udp_port, InstanceInfo, IpVersion, Role, WAIT_FOR_SHUTDOWN,
+};
+use chrono::Local;
+use discv5::enr::{CombinedKey, EnrKey, NodeId};
+use discv5::{Config, Discv5, Enr, Key};
+use rand::seq::SliceRandom;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::time::Duration;
+use testground::client::Client;
+use testground::{RunParameters, WriteQuery};
+use tracing::{info, warn};
+
+const STATE_COMPLETED_TO_COLLECT_INSTANCE_INFORMATION: &str =
+    "STATE_COMPLETED_TO_COLLECT_INSTANCE_INFORMATION";
+const STATE_ATTACKERS_STARTED_SYBILS: &str = "STATE_ATTACKERS_STARTED_SYBILS";
+const STATE_DONE: &str = "STATE_DONE";
+
+const TOPIC_SYBILS: &str = "TOPIC_SYBILS";
+
+// The number of leading bits which the node ids of the sybils share with the victim's one.
+const CLUSTER_PREFIX_BITS: u64 = 4;
+// The number of self-lookups the victim runs to fill its routing table.
+const VICTIM_LOOKUPS: usize = 3;
+
+// Whether the victim runs with the defenses against the eclipse attacks, i.e. the limit of the
+// nodes per IP address and the limit of the incoming nodes per bucket, given by the `defenses`
+// param.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Defenses {
+    // The victim runs with the config given by the shared params.
+    Off,
+    // The victim runs with both of the defenses enabled.
+    On,
+    // The victim runs the same measurement without the defenses, and then with them.
+    Compare,
+}
+
+impl FromStr for Defenses {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Defenses::Off),
+            "on" => Ok(Defenses::On),
+            "compare" => Ok(Defenses::Compare),
+            _ => Err(format!(
+                "Invalid defenses: {s}. It must be one of off, on or compare."
+            )),
+        }
+    }
+}
+
+impl Defenses {
+    // Whether the defenses are enabled in each phase of the victim.
+    fn phases(&self) -> &'static [bool] {
+        match self {
+            Defenses::Off => &[false],
+            Defenses::On => &[true],
+            Defenses::Compare => &[false, true],
+        }
+    }
+}
+
+// The result of a lookup of the victim, with the returned peers classified by the roles
+// exchanged at the start.
+struct Lookup {
+    // The honest node looked up, or `None` for a random target.
+    honest_target: Option<NodeId>,
+    returned: usize,
+    attacker_returned: usize,
+    // Whether the honest target is among the returned peers.
+    found_target: bool,
+    // Whether the lookup has failed entirely, i.e. it returned an error or no peers.
+    failed: bool,
+}
+
+impl Lookup {
+    fn attacker_hit_ratio(&self) -> f64 {
+        if self.returned == 0 {
+            0.0
+        } else {
+            self.attacker_returned as f64 / self.returned as f64
+        }
+    }
+}
+
+// The result of a measurement of the victim.
+struct Measurement {
+    defended: bool,
+    total_entries: usize,
+    attacker_entries: usize,
+    // The sums over the lookups for random targets.
+    returned: usize,
+    attacker_returned: usize,
+    lookups: Vec<Lookup>,
+}
+
+impl Measurement {
+    fn label(&self) -> &'static str {
+        if self.defended {
+            "on"
+        } else {
+            "off"
+        }
+    }
+
+    fn poisoning_ratio(&self) -> f64 {
+        if self.returned == 0 {
+            0.0
+        } else {
+            self.attacker_returned as f64 / self.returned as f64
+        }
+    }
+
+    fn failed_lookups(&self) -> usize {
+        self.lookups.iter().filter(|l| l.failed).count()
+    }
+
+    fn honest_targets_found(&self) -> usize {
+        self.lookups.iter().filter(|l| l.found_target).count()
+    }
+
+    fn attacker_ratio(&self) -> f64 {
+        if self.total_entries == 0 {
+            0.0
+        } else {
+            self.attacker_entries as f64 / self.total_entries as f64
+        }
+    }
+}
+
+pub(crate) struct NodesFlooding {
+    sybils_per_attacker: u64,
+    // If specified, the keys are derived from the seed so that the node ids are stable across runs.
+    // Defaults to the `seed` param.
+    key_seed: Option<u64>,
+    // If specified, the roles are assigned by seq instead of by group: #1 is the victim, and this
+    // fraction of the other instances, the ones with the highest seqs, are the attackers.
+    attacker_fraction: Option<f64>,
+    // The number of lookups for random targets the victim runs to measure the poisoning ratio.
+    measurement_lookups: u64,
+    // The number of lookups for honest nodes the victim runs to verify whether it can still reach
+    // them.
+    honest_lookups: u64,
+    // If specified, the attackers grind the keys of their sybils so that they land at these
+    // log2-distances from the victim, one sybil per distance, within `grind_budget`.
+    sybil_distances: Vec<u64>,
+    grind_budget: Duration,
+    defenses: Defenses,
+    // The limit of the incoming nodes per bucket of the victim with the defenses.
+    defended_incoming_bucket_limit: usize,
+}
+
+impl NodesFlooding {
+    pub(crate) fn new(run_parameters: &RunParameters) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(NodesFlooding {
+            sybils_per_attacker: get_param::<u64>(
+                "sybils_per_attacker",
+                &run_parameters.test_instance_params,
+            )?,
+            key_seed: if run_parameters.test_instance_params.contains_key("key_seed") {
+                Some(get_param::<u64>(
+                    "key_seed",
+                    &run_parameters.test_instance_params,
+                )?)
+            } else {
+                seed(run_parameters)?
+            },
+            attacker_fraction: if run_parameters
+                .test_instance_params
+                .contains_key("attacker_fraction")
+            {
+                let fraction =
+                    get_param::<f64>("attacker_fraction", &run_parameters.test_instance_params)?;
+                if !(0.0..=1.0).contains(&fraction) {
+                    return Err(format!(
+                        "attacker_fraction must be between 0 and 1, but got {fraction}."
+                    )
+                    .into());
+                }
+                Some(fraction)
+            } else {
+                None
+            },
+            measurement_lookups: get_param_or::<u64>(
+                "measurement_lookups",
+                &run_parameters.test_instance_params,
+                10,
+            )?,
+            honest_lookups: get_param_or::<u64>(
+                "honest_lookups",
+                &run_parameters.test_instance_params,
+                5,
+            )?,
+            sybil_distances: match get_param_or::<String>(
+                "sybil_distances",
+                &run_parameters.test_instance_params,
+                String::new(),
+            )? {
+                distances if distances.is_empty() => vec![],
+                distances => {
+                    let distances = parse_distances(&distances)?;
+                    // Distance 0 is the victim's own node id.
+                    if distances.contains(&0) {
+                        return Err("sybil_distances must not contain 0.".into());
+                    }
+                    distances
+                }
+            },
+            grind_budget: Duration::from_secs(get_param_or::<u64>(
+                "grind_budget_secs",
+                &run_parameters.test_instance_params,
+                30,
+            )?),
+            defenses: get_param_or::<String>(
+                "defenses",
+                &run_parameters.test_instance_params,
+                "off".to_owned(),
+            )?
+            .parse()?,
+            defended_incoming_bucket_limit: match get_param_or::<usize>(
+                "defended_incoming_bucket_limit",
+                &run_parameters.test_instance_params,
+                8,
+            )? {
+                0 => return Err("defended_incoming_bucket_limit must be non-zero.".into()),
+                limit => limit,
+            },
+        })
+    }
+
+    // The discv5 config, with the defenses enabled if `defended`.
+    fn config(
+        &self,
+        run_parameters: &RunParameters,
+        defended: bool,
+    ) -> Result<Config, Box<dyn std::error::Error>> {
+        let mut builder = config_builder(
+            listen_config(run_parameters)?,
+            &run_parameters.test_instance_params,
+        )?;
+        if defended {
+            builder.ip_limit();
+            builder.incoming_bucket_limit(self.defended_incoming_bucket_limit);
+        }
+        Ok(builder.build())
+    }
+
+    fn role(&self, client: &Client) -> Result<Role, String> {
+        let run_parameters = client.run_parameters();
+        let Some(fraction) = self.attacker_fraction else {
+            return group_role(&run_parameters.test_group_id);
+        };
+
+        let seq = client.global_seq();
+        let attackers = (fraction * (run_parameters.test_instance_count - 1) as f64).round() as u64;
+        Ok(if seq == 1 {
+            Role::Victim
+        } else if seq > run_parameters.test_instance_count - attackers {
+            Role::Attacker
+        } else {
+            Role::Honest
+        })
+    }
+
+    pub(crate) async fn run(&self, client: Client) -> Result<(), Box<dyn std::error::Error>> {
+        let run_parameters = client.run_parameters();
+        let role = self.role(&client)?;
+        client.record_message(format!(
+            "role: {:?}, group_seq: {}",
+            role,
+            get_group_seq(&client)
+        ));
+
+        // ////////////////////////
+        // Construct a local Enr
+        // ////////////////////////
+        let enr_key = self.generate_key(client.global_seq());
+        // The victim restarts with the same key in each phase.
+        let victim_key = copy_key(&enr_key);
+        let enr = build_enr_with_key(
+            &run_parameters,
+            client.global_seq(),
+            ip_version(&run_parameters.test_instance_params)?,
+            &enr_key,
+        )?;
+        check_enr_port(&enr, &listen_config(&run_parameters)?)?;
+
+        // //////////////////////////////////////////////////////////////
+        // Start Discovery v5 server
+        // //////////////////////////////////////////////////////////////
+        // NOTE: The defenses only apply to the victim, starting with those of its first phase.
+        let defended = role == Role::Victim && self.defenses.phases()[0];
+        let mut discv5 = Discv5::new(enr, enr_key, self.config(&run_parameters, defended)?)?;
+        discv5
+            .start()
+            .await
+            .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+        // //////////////////////////////////////////////////////////////
+        // Collect information of all participants in the test case
+        // //////////////////////////////////////////////////////////////
+        let instance_info = InstanceInfo::with_role(&client, discv5.local_enr(), role)?;
+
+        let mut victim = vec![];
+        let mut others = vec![];
+        for i in publish_and_collect(&client, instance_info.clone()).await? {
+            match i.role {
+                Role::Victim => victim.push(i),
+                _ => others.push(i),
+            }
+        }
+        assert_eq!(victim.len(), 1);
+        let victim = victim.remove(0);
+
+        barrier_with_timeout(
+            &client,
+            STATE_COMPLETED_TO_COLLECT_INSTANCE_INFORMATION,
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        // //////////////////////////////////////////////////////////////
+        // Play the role
+        // //////////////////////////////////////////////////////////////
+        match instance_info.role {
+            Role::Victim => self.play_victim(discv5, victim_key, client, &others).await,
+            Role::Honest => self.play_honest(discv5, client).await,
+            Role::Attacker => self.play_attacker(discv5, client, &victim, &others).await,
+            role => Err(role.unsupported()),
+        }
+    }
+
+    async fn play_victim(
+        &self,
+        mut discv5: Discv5,
+        enr_key: CombinedKey,
+        client: Client,
+        others: &[InstanceInfo],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let run_parameters = client.run_parameters();
+        let attackers = others
+            .iter()
+            .filter(|i| matches!(i.role, Role::Attacker))
+            .count();
+
+        // Collect the node ids controlled by the attackers.
+        let sybils: Vec<Vec<Enr>> = collect_from_stream(
+            &client,
+            client.subscribe(TOPIC_SYBILS, u16::MAX.into()).await,
+            u64::try_from(attackers)?,
+        )
+        .await?;
+        let attacker_ids = others
+            .iter()
+            .filter(|i| matches!(i.role, Role::Attacker))
+            .map(|i| i.enr.node_id())
+            .chain(sybils.into_iter().flatten().map(|enr| enr.node_id()))
+            .collect::<HashSet<_>>();
+
+        // Wait until the attackers have started their sybils.
+        barrier_with_timeout(
+            &client,
+            STATE_ATTACKERS_STARTED_SYBILS,
+            u64::try_from(attackers)? + 1,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        // //////////////////////////////////////////////////////////////
+        // Measure the poisoning ratio in each phase
+        // //////////////////////////////////////////////////////////////
+        // NOTE: The attackers are passive, so the same attack is measured in each phase by
+        // restarting the victim with the same key, which starts with an empty routing table.
+        let mut measurements = vec![];
+        let rpc_stats = RpcStats::default();
+        for (i, defended) in self.defenses.phases().iter().enumerate() {
+            if i > 0 {
+                let enr = discv5.local_enr();
+                discv5.shutdown();
+                drop(discv5);
+                tokio::time::sleep(WAIT_FOR_SHUTDOWN).await;
+
+                discv5 = Discv5::new(
+                    enr,
+                    copy_key(&enr_key),
+                    self.config(&run_parameters, *defended)?,
+                )?;
+                discv5
+                    .start()
+                    .await
+                    .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+            }
+
+            let measurement = self
+                .measure(&discv5, &rpc_stats, others, &attacker_ids, *defended)
+                .await;
+            client.record_message(format!(
+                "[defenses: {}] Attacker ENRs are {} of {} peers returned by {} lookups (poisoning ratio: {:.3}), and occupy {} of {} entries ({:.3}) in the victim's routing table.",
+                measurement.label(),
+                measurement.attacker_returned,
+                measurement.returned,
+                self.measurement_lookups,
+                measurement.poisoning_ratio(),
+                measurement.attacker_entries,
+                measurement.total_entries,
+                measurement.attacker_ratio(),
+            ));
+            client.record_message(format!(
+                "[defenses: {}] Found {} of {} honest targets, and {} of {} lookups have failed entirely.",
+                measurement.label(),
+                measurement.honest_targets_found(),
+                measurement
+                    .lookups
+                    .iter()
+                    .filter(|l| l.honest_target.is_some())
+                    .count(),
+                measurement.failed_lookups(),
+                measurement.lookups.len(),
+            ));
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("total_entries", measurement.total_entries as u64)
+            .add_field("attacker_entries", measurement.attacker_entries as u64)
+            .add_field("attacker_ratio", measurement.attacker_ratio())
+            .add_field("returned_peers", measurement.returned as u64)
+            .add_field(
+                "attacker_returned_peers",
+                measurement.attacker_returned as u64,
+            )
+            .add_field("poisoning_ratio", measurement.poisoning_ratio())
+            .add_field("failed_lookups", measurement.failed_lookups() as u64)
+            .add_field(
+                "honest_targets_found",
+                measurement.honest_targets_found() as u64,
+            )
+            .add_field("attackers", u64::try_from(attackers)?)
+            .add_tag("instance_seq", get_instance_seq(&client))
+            .add_tag("defenses", measurement.label());
+            record_metric(&client, write_query).await;
+
+            for (index, lookup) in measurement.lookups.iter().enumerate() {
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("index", index as u64)
+                .add_field("returned_peers", lookup.returned as u64)
+                .add_field("attacker_returned_peers", lookup.attacker_returned as u64)
+                .add_field("attacker_hit_ratio", lookup.attacker_hit_ratio())
+                .add_field("found_target", lookup.found_target)
+                .add_field("failed", lookup.failed)
+                .add_tag("instance_seq", get_instance_seq(&client))
+                .add_tag("defenses", measurement.label())
+                .add_tag(
+                    "target",
+                    if lookup.honest_target.is_some() {
+                        "honest"
+                    } else {
+                        "random"
+                    },
+                )
+                .add_tag("source", "lookup");
+                record_metric(&client, write_query).await;
+            }
+
+            measurements.push(measurement);
+        }
+
+        if let [undefended, defended] = measurements.as_slice() {
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("undefended_poisoning_ratio", undefended.poisoning_ratio())
+            .add_field("defended_poisoning_ratio", defended.poisoning_ratio())
+            .add_field(
+                "poisoning_ratio_reduction",
+                undefended.poisoning_ratio() - defended.poisoning_ratio(),
+            )
+            .add_field("undefended_attacker_ratio", undefended.attacker_ratio())
+            .add_field("defended_attacker_ratio", defended.attacker_ratio())
+            .add_tag("instance_seq", get_instance_seq(&client))
+            .add_tag("source", "comparison");
+            record_metric(&client, write_query).await;
+        }
+
+        barrier_with_timeout(
+            &client,
+            STATE_DONE,
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        rpc_stats.finish(&client, client.global_seq()).await;
+        teardown(
+            &client,
+            discv5,
+            client.global_seq(),
+            run_parameters.test_instance_count,
+        )
+        .await?;
+
+        // The threshold applies to the last phase, i.e. the defended one when compared.
+        let poisoning_ratio = measurements
+            .last()
+            .map(|m| m.poisoning_ratio())
+            .unwrap_or(0.0);
+        let thresholds = Thresholds::from_params(&run_parameters.test_instance_params)?;
+        let mut assertions = Assertions::new();
+        assertions.assert_at_most_opt(
+            "poisoning_ratio",
+            poisoning_ratio,
+            thresholds.max_poisoning_ratio,
+        );
+        assertions.record(&client).await
+    }
+
+    // Fill the routing table of the victim from scratch, and measure how much of it, and of the
+    // peers returned by its lookups, are controlled by the attackers.
+    async fn measure(
+        &self,
+        discv5: &Discv5,
+        rpc_stats: &RpcStats,
+        others: &[InstanceInfo],
+        attacker_ids: &HashSet<NodeId>,
+        defended: bool,
+    ) -> Measurement {
+        // The victim knows all the honest nodes and the attackers up front.
+        for i in others {
+            if let Err(e) = discv5.add_enr(i.enr.clone()) {
+                warn!("Failed to add the ENR of a {:?} node: {e}", i.role);
+            }
+        }
+
+        // Look up the victim's own node id, which the attacker-controlled ENRs are clustered near.
+        let local_node_id = discv5.local_enr().node_id();
+        for _ in 0..VICTIM_LOOKUPS {
+            if let Err(e) = rpc_stats.find_node(discv5, local_node_id).await {
+                warn!("Failed to run query: {e}");
+            }
+        }
+
+        // The fraction of the peers returned by the victim's lookups which are attacker-controlled,
+        // i.e. how much the attackers would influence the victim's view of the network.
+        let mut lookups = vec![];
+        for _ in 0..self.measurement_lookups {
+            lookups.push(lookup(discv5, rpc_stats, NodeId::random(), None, attacker_ids).await);
+        }
+        // Whether the victim can still reach the honest nodes, i.e. whether the eclipse actually
+        // affects its queries rather than just its routing table.
+        let honest = others
+            .iter()
+            .filter(|i| matches!(i.role, Role::Honest))
+            .collect::<Vec<_>>();
+        for _ in 0..self.honest_lookups {
+            let Some(target) = honest.choose(&mut rand::thread_rng()) else {
+                break;
+            };
+            let target = target.enr.node_id();
+            lookups.push(lookup(discv5, rpc_stats, target, Some(target), attacker_ids).await);
+        }
+
+        let random = lookups.iter().filter(|l| l.honest_target.is_none());
+        let entries = discv5.table_entries_id();
+        Measurement {
+            defended,
+            total_entries: entries.len(),
+            attacker_entries: entries
+                .iter()
+                .filter(|node_id| attacker_ids.contains(node_id))
+                .count(),
+            returned: random.clone().map(|l| l.returned).sum(),
+            attacker_returned: random.map(|l| l.attacker_returned).sum(),
+            lookups,
+        }
+    }
+
+    async fn play_honest(
+        &self,
+        discv5: Discv5,
+        client: Client,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Nothing to do, just wait until the simulation has been done.
+        barrier_with_timeout(
+            &client,
+            STATE_DONE,
+            client.run_parameters().test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        teardown(
+            &client,
+            discv5,
+            client.global_seq(),
+            client.run_parameters().test_instance_count,
+        )
+        .await?;
+
+        client.record_success().await?;
+        Ok(())
+    }
+
+    async fn play_attacker(
+        &self,
+        discv5: Discv5,
+        client: Client,
+        victim: &InstanceInfo,
+        others: &[InstanceInfo],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let attackers = others
+            .iter()
+            .filter(|i| matches!(i.role, Role::Attacker))
+            .count();
+        let run_parameters = client.run_parameters();
+        let ip_version = ip_version(&run_parameters.test_instance_params)?;
+        let udp_port = udp_port(&run_parameters.test_instance_params)?;
+
+        // Start the sybils, whose node ids are clustered near the victim's one, or land at
+        // `sybil_distances` from it, with their own ports.
+        // The candidate keys of the sybils are numbered after the instances so that they don't
+        // collide with the keys of any instance.
+        let mut candidates = (client.global_seq() << 32)..;
+        let mut generate_key = || self.generate_key(candidates.next().expect("Infinite range"));
+        let victim_id = victim.enr.node_id();
+        let keys = if self.sybil_distances.is_empty() {
+            (0..self.sybils_per_attacker)
+                .map(|_| cluster_key(&victim_id, &mut generate_key))
+                .collect::<Vec<_>>()
+        } else {
+            let keys = grind_keys(
+                &victim_id,
+                &self.sybil_distances,
+                self.grind_budget,
+                &mut generate_key,
+            );
+            let distances = keys.iter().map(|(d, _)| *d).collect::<Vec<_>>();
+            if keys.len() < self.sybil_distances.len() {
+                warn!(
+                    "Found keys for {} of {} distances within {:?}: {distances:?}",
+                    keys.len(),
+                    self.sybil_distances.len(),
+                    self.grind_budget
+                );
+            } else {
+                info!("Found keys for all the distances: {distances:?}");
+            }
+            keys.into_iter().map(|(_, key)| key).collect()
+        };
+
+        let mut sybils = vec![];
+        for (i, enr_key) in keys.into_iter().enumerate() {
+            let port = u16::try_from(i + 1)
+                .ok()
+                .and_then(|i| udp_port.checked_add(i))
+                .ok_or("udp_port overflows with the sybils.")?;
+            sybils.push(
+                start_sybil(
+                    &run_parameters,
+                    client.global_seq(),
+                    ip_version,
+                    port,
+                    enr_key,
+                )
+                .await?,
+            );
+        }
+        let sybil_enrs = sybils.iter().map(|s| s.local_enr()).collect::<Vec<_>>();
+        info!(
+            "Started {} sybils. group_seq: {}",
+            sybil_enrs.len(),
+            get_group_seq(&client)
+        );
+
+        // Make the attacker and the sybils respond to FINDNODE with the sybils' ENRs.
+        for enr in &sybil_enrs {
+            if let Err(e) = discv5.add_enr(enr.clone()) {
+                warn!("Failed to add a sybil's ENR: {e}");
+            }
+            for s in sybils
+                .iter()
+                .filter(|s| s.local_enr().node_id() != enr.node_id())
+            {
+                let _ = s.add_enr(enr.clone());
+            }
+        }
+
+        client
+            .publish(TOPIC_SYBILS, Cow::Owned(serde_json::to_value(&sybil_enrs)?))
+            .await?;
+
+        // Inform that the sybils have been started. The victim takes part in this barrier, hence
+        // the attackers plus one.
+        barrier_with_timeout(
+            &client,
+            STATE_ATTACKERS_STARTED_SYBILS,
+            u64::try_from(attackers)? + 1,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        // Wait until checking on the victim has been done.
+        barrier_with_timeout(
+            &client,
+            STATE_DONE,
+            client.run_parameters().test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+
+        teardown(
+            &client,
+            discv5,
+            client.global_seq(),
+            client.run_parameters().test_instance_count,
+        )
+        .await?;
+
+        client.record_success().await?;
+        Ok(())
+    }
+
+    fn generate_key(&self, seq: u64) -> CombinedKey {
+        match self.key_seed {
+            Some(seed) => deterministic_key(seed, seq),
+            None => CombinedKey::generate_secp256k1(),
+        }
+    }
+}
+
+// A key whose node id shares `CLUSTER_PREFIX_BITS` leading bits with `target`. The candidate keys
+// are taken from `generate_key` until one of them qualifies.
+fn cluster_key(target: &NodeId, mut generate_key: impl FnMut() -> CombinedKey) -> CombinedKey {
+    let target_key: Key<NodeId> = (*target).into();
+    loop {
+        let enr_key = generate_key();
+        let node_id = NodeId::from(enr_key.public());
+        if let Some(distance) = Key::from(node_id).log2_distance(&target_key) {
+            if distance <= 256 - CLUSTER_PREFIX_BITS {
+                break enr_key;
+            }
+        }
+    }
+}
+
+// Look up `target`, classifying the returned peers by whether they are in `attacker_ids`.
+async fn lookup(
+    discv5: &Discv5,
+    rpc_stats: &RpcStats,
+    target: NodeId,
+    honest_target: Option<NodeId>,
+    attacker_ids: &HashSet<NodeId>,
+) -> Lookup {
+    match rpc_stats.find_node(discv5, target).await {
+        Ok(enrs) => Lookup {
+            honest_target,
+            returned: enrs.len(),
+            attacker_returned: enrs
+                .iter()
+                .filter(|enr| attacker_ids.contains(&enr.node_id()))
+                .count(),
+            found_target: honest_target.map_or(false, |target| {
+                enrs.iter().any(|enr| enr.node_id() == target)
+            }),
+            failed: enrs.is_empty(),
+        },
+        Err(e) => {
+            warn!("Failed to run query: {e}");
+            Lookup {
+                honest_target,
+                returned: 0,
+                attacker_returned: 0,
+                found_target: false,
+                failed: true,
+            }
+        }
+    }
+}
+
+fn copy_key(enr_key: &CombinedKey) -> CombinedKey {
+    CombinedKey::secp256k1_from_bytes(&mut enr_key.encode()).expect("A secp256k1 key")
+}
+
+// Start a discv5 server of a sybil with `enr_key`, on `port` of the attacker's addresses.
+async fn start_sybil(
+    run_parameters: &RunParameters,
+    seq: u64,
+    ip_version: IpVersion,
+    port: u16,
+    enr_key: CombinedKey,
+) -> Result<Discv5, Box<dyn std::error::Error>> {
+    let enr = build_enr_on_port(run_parameters, seq, ip_version, &enr_key, port)?;
+    let listen_config = ip_version.listen_config(port);
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5 = Discv5::new(
+        enr,
+        enr_key,
+        discv5::ConfigBuilder::new(listen_config).build(),
+    )?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    Ok(discv5)
+}
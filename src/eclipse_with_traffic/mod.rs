@@ -0,0 +1,266 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::eclipse_with_traffic::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, listen_config,
+    publish_and_collect, record_metric, InstanceInfo, Role,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::Discv5;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_DONE: &str = "state_done";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("eclipse-with-traffic", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // NOTE: The roles are given by the groups if the composition has the `victim`, `honest` and
+    // `attackers` groups. Otherwise #1 is the victim, the next `honest_count` nodes are honest
+    // ones, and the rest are attackers.
+    let seq = client.global_seq();
+    let role = match Role::from_group_id(&run_parameters.test_group_id) {
+        Some(_) => Role::from_supported_group_id(
+            &run_parameters.test_group_id,
+            &[Role::Victim, Role::Honest, Role::Attacker],
+        )?,
+        None if seq == 1 => Role::Victim,
+        None if seq <= 1 + params.honest_count => Role::Honest,
+        None => Role::Attacker,
+    };
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::with_role(&client, enr, role)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}, role: {:?}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr),
+        instance_info.role,
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+    let victim = participants
+        .iter()
+        .find(|p| p.role == Role::Victim)
+        .expect("Victim")
+        .clone();
+    let honest = participants
+        .iter()
+        .filter(|p| p.role == Role::Honest)
+        .cloned()
+        .collect::<Vec<_>>();
+    let attackers = participants
+        .iter()
+        .filter(|p| p.role == Role::Attacker)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // The victim knows the honest nodes, and the honest nodes know only the victim, so the
+    // lookups of the honest nodes go through the victim.
+    match instance_info.role {
+        Role::Victim => {
+            for p in honest.iter() {
+                discv5.add_enr(p.enr.clone())?;
+            }
+        }
+        Role::Honest => {
+            discv5.add_enr(victim.enr.clone())?;
+        }
+        Role::Attacker => {
+            // The attackers know each other so that they respond with other attackers.
+            for p in attackers.iter().filter(|p| p.seq != instance_info.seq) {
+                discv5.add_enr(p.enr.clone())?;
+            }
+        }
+        role => return Err(role.unsupported()),
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+    let started_at = Instant::now();
+
+    // //////////////////////////////////////////////////////////////
+    // Play the role
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+    let duration = Duration::from_secs(params.duration);
+
+    match instance_info.role {
+        Role::Victim => {
+            // Record the composition of the routing table over time.
+            let honest_ids = honest
+                .iter()
+                .map(|p| p.enr.node_id())
+                .collect::<HashSet<_>>();
+            let attacker_ids = attackers
+                .iter()
+                .map(|p| p.enr.node_id())
+                .collect::<HashSet<_>>();
+
+            while started_at.elapsed() < duration {
+                let entries = discv5.table_entries_id();
+                let honest_entries = entries.iter().filter(|id| honest_ids.contains(id)).count();
+                let attacker_entries = entries
+                    .iter()
+                    .filter(|id| attacker_ids.contains(id))
+                    .count();
+                info!("table composition: honest: {honest_entries}, attackers: {attacker_entries}");
+
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("elapsed_ms", started_at.elapsed().as_millis() as u64)
+                .add_field("honest_entries", honest_entries as u64)
+                .add_field("attacker_entries", attacker_entries as u64)
+                .add_tag("instance_seq", instance_info.seq)
+                .add_tag("role", "victim");
+                record_metric(&client, write_query).await;
+
+                tokio::time::sleep(Duration::from_millis(params.lookup_interval)).await;
+            }
+        }
+        Role::Honest => {
+            // Continuously look up the other honest nodes, and record the success rate of each
+            // round.
+            let targets = honest
+                .iter()
+                .filter(|p| p.seq != instance_info.seq)
+                .map(|p| p.enr.node_id())
+                .collect::<Vec<NodeId>>();
+
+            while started_at.elapsed() < duration && !targets.is_empty() {
+                let mut succeeded = 0_u64;
+                for target in targets.iter() {
+                    match tokio::time::timeout(
+                        Duration::from_secs(params.lookup_timeout),
+                        rpc_stats.find_node(&discv5, *target),
+                    )
+                    .await
+                    {
+                        Ok(Ok(enrs)) => {
+                            if enrs.iter().any(|enr| enr.node_id() == *target) {
+                                succeeded += 1;
+                            }
+                        }
+                        Ok(Err(e)) => error!("Failed to run FINDNODE query: {e}"),
+                        Err(_) => error!(
+                            "The query hasn't completed within {} seconds.",
+                            params.lookup_timeout
+                        ),
+                    }
+                }
+                let success_rate = succeeded as f64 / targets.len() as f64;
+                info!("success rate: {success_rate}");
+
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("elapsed_ms", started_at.elapsed().as_millis() as u64)
+                .add_field("success_rate", success_rate)
+                .add_tag("instance_seq", instance_info.seq)
+                .add_tag("role", "honest");
+                record_metric(&client, write_query).await;
+
+                tokio::time::sleep(Duration::from_millis(params.lookup_interval)).await;
+            }
+        }
+        Role::Attacker => {
+            // The attackers join one by one so that the attack progresses gradually. The FINDNODE
+            // query makes the attacker's ENR added to the victim's routing table because of the
+            // handshake.
+            let index = attackers
+                .iter()
+                .position(|p| p.seq == instance_info.seq)
+                .expect("Self");
+            tokio::time::sleep(Duration::from_millis(params.attack_interval * index as u64)).await;
+
+            discv5.add_enr(victim.enr.clone())?;
+            if let Err(e) = rpc_stats.find_node(&discv5, NodeId::random()).await {
+                error!("Failed to run FINDNODE query: {e}");
+                failed = true;
+            }
+        }
+        role => return Err(role.unsupported()),
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_DONE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use crate::utils::get_param;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub honest_count: u64,
+    pub duration: u64,
+    pub attack_interval: u64,
+    pub lookup_interval: u64,
+    pub lookup_timeout: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            honest_count: get_param::<u64>("honest_count", instance_params)?,
+            duration: get_param::<u64>("duration", instance_params)?,
+            attack_interval: get_param::<u64>("attack_interval", instance_params)?,
+            lookup_interval: get_param::<u64>("lookup_interval", instance_params)?,
+            lookup_timeout: get_param::<u64>("lookup_timeout", instance_params)?,
+        })
+    }
+}
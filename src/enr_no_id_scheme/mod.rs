@@ -0,0 +1,201 @@
+use crate::config::config_builder;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, base64_url, build_enr, check_enr_port, enr_addresses,
+    enr_socket, listen_config, publish_and_collect, record_metric, rlp_list, rlp_string,
+    InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::{CombinedKey, EnrKey, EnrPublicKey};
+use discv5::{Discv5, Enr};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_VALIDATE: &str = "state_completed_to_validate";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Participant {
+    #[serde(flatten)]
+    instance: InstanceInfo,
+    // The crafted ENRs in text form. Only the attacker publishes them.
+    crafted: Option<CraftedEnrs>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CraftedEnrs {
+    // A well-formed ENR crafted in the same way, to make sure the crafting itself is correct.
+    control: String,
+    // An ENR lacking the `id` field.
+    without_id: String,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("enr-no-id-scheme", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the attacker which crafts the malformed ENR.
+    let seq = client.global_seq();
+    let crafted = if seq == 1 {
+        let crafting_key = CombinedKey::generate_secp256k1();
+        let socket = enr_socket(&enr)?;
+        Some(CraftedEnrs {
+            control: craft_enr(&crafting_key, socket, true)?,
+            without_id: craft_enr(&crafting_key, socket, false)?,
+        })
+    } else {
+        None
+    };
+    let instance_info = Participant {
+        instance: InstanceInfo::new(&client, enr)?,
+        crafted,
+    };
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.instance.seq,
+        instance_info.instance.enr.node_id(),
+        enr_addresses(&instance_info.instance.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Validate the crafted ENRs
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if let Some(attacker) = participants.iter().find(|p| p.crafted.is_some()) {
+        if instance_info.instance.seq != attacker.instance.seq {
+            let crafted = attacker.crafted.as_ref().expect("Crafted ENRs");
+
+            if let Err(e) = crafted.control.parse::<Enr>() {
+                error!("Failed to decode the control ENR. The crafting is broken: {e}");
+                failed = true;
+            }
+
+            let (rejected_at_decode, inserted) = match crafted.without_id.parse::<Enr>() {
+                Ok(enr) => {
+                    error!("The ENR lacking the id scheme has been decoded: {enr}");
+                    failed = true;
+                    let inserted = discv5.add_enr(enr.clone()).is_ok()
+                        && discv5
+                            .table_entries_id()
+                            .iter()
+                            .any(|node_id| *node_id == enr.node_id());
+                    if inserted {
+                        error!("The ENR lacking the id scheme has been inserted into the table.");
+                    }
+                    (false, inserted)
+                }
+                Err(e) => {
+                    info!("The ENR lacking the id scheme has been rejected: {e}");
+                    (true, false)
+                }
+            };
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("rejected_at_decode", rejected_at_decode)
+            .add_field("inserted", inserted)
+            .add_tag("instance_seq", instance_info.instance.seq);
+            record_metric(&client, write_query).await;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_VALIDATE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    teardown(&client, discv5, seq, run_parameters.test_instance_count).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Crafts an ENR in text form by hand, since `EnrBuilder` always inserts the `id` field.
+fn craft_enr(
+    key: &CombinedKey,
+    socket: SocketAddr,
+    with_id: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (ip_key, udp_key, ip): (&[u8], &[u8], _) = match socket.ip() {
+        IpAddr::V4(ip) => (b"ip", b"udp", ip.octets().to_vec()),
+        IpAddr::V6(ip) => (b"ip6", b"udp6", ip.octets().to_vec()),
+    };
+    let udp = socket
+        .port()
+        .to_be_bytes()
+        .into_iter()
+        .skip_while(|b| *b == 0)
+        .collect::<Vec<_>>();
+
+    // The key/value pairs must be sorted by key.
+    let mut content = vec![rlp_string(&[1])];
+    if with_id {
+        content.push(rlp_string(b"id"));
+        content.push(rlp_string(b"v4"));
+    }
+    content.push(rlp_string(ip_key));
+    content.push(rlp_string(&ip));
+    content.push(rlp_string(b"secp256k1"));
+    content.push(rlp_string(&key.public().encode()));
+    content.push(rlp_string(udp_key));
+    content.push(rlp_string(&udp));
+
+    let signature = key
+        .sign_v4(&rlp_list(&content))
+        .map_err(|e| format!("Failed to sign the ENR: {e:?}"))?;
+
+    let mut record = vec![rlp_string(&signature)];
+    record.extend(content);
+
+    Ok(format!("enr:{}", base64_url(&rlp_list(&record))))
+}
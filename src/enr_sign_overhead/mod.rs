@@ -0,0 +1,114 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::enr_sign_overhead::params::Params;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{build_enr, check_enr_port, listen_config, record_metric};
+use chrono::Local;
+use discv5::Discv5;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+// The key of the ENR field that is updated in order to force re-signing.
+const ENR_KEY_BENCH: &str = "sign_bench";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("enr-sign-overhead", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr, enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    // //////////////////////////////////////////////////////////////
+    // Update the local ENR in a tight loop
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+    let initial_seq = discv5.local_enr().seq();
+    let duration = Duration::from_secs(params.duration);
+    let mut signings = 0_u64;
+
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        if let Err(e) = discv5.enr_insert(ENR_KEY_BENCH, &signings) {
+            error!("Failed to update the local ENR: {e:?}");
+            failed = true;
+            break;
+        }
+        signings += 1;
+    }
+    let elapsed = start.elapsed();
+
+    let signings_per_second = signings as f64 / elapsed.as_secs_f64();
+    info!("signings: {signings}, elapsed: {elapsed:?}, signings_per_second: {signings_per_second}");
+    client.record_message(format!(
+        "signings: {signings}, signings_per_second: {signings_per_second:.2}"
+    ));
+
+    // Every update is expected to bump the sequence number.
+    let final_seq = discv5.local_enr().seq();
+    if final_seq != initial_seq + signings {
+        error!(
+            "Unexpected ENR sequence number. expected: {}, actual: {final_seq}",
+            initial_seq + signings
+        );
+        failed = true;
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("signings", signings)
+    .add_field("elapsed_millis", elapsed.as_millis() as u64)
+    .add_field("signings_per_second", signings_per_second)
+    .add_tag("instance_seq", client.global_seq());
+    record_metric(&client, write_query).await;
+
+    teardown(
+        &client,
+        discv5,
+        client.global_seq(),
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
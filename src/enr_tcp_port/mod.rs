@@ -0,0 +1,185 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr_with_fields, check_enr_port, enr_addresses,
+    get_param, ip_version, listen_config, publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Enr};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("enr-tcp-port", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    // NOTE: `0` means the tcp port is not advertised.
+    let tcp_port = match get_param::<u16>("tcp_port", &run_parameters.test_instance_params)? {
+        0 => None,
+        port => Some(port),
+    };
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    // The tcp port is advertised for each IP version the udp port is advertised for.
+    let ip_version = ip_version(&run_parameters.test_instance_params)?;
+    let (enr, enr_key) = build_enr_with_fields(&run_parameters, client.global_seq(), |builder| {
+        if let Some(port) = tcp_port {
+            if ip_version.has_ipv4() {
+                builder.tcp4(port);
+            }
+            if ip_version.has_ipv6() {
+                builder.tcp6(port);
+            }
+        }
+    })?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}, tcp: {:?}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr),
+        tcp(&instance_info.enr),
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Star topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the bootstrap node.
+    let bootstrap_node = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Bootstrap node")
+        .clone();
+
+    if instance_info.seq == bootstrap_node.seq {
+        for p in participants.iter().filter(|p| p.seq != bootstrap_node.seq) {
+            discv5.add_enr(p.enr.clone())?;
+        }
+    } else {
+        discv5.add_enr(bootstrap_node.enr.clone())?;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run FINDNODE query and check the tcp port of discovered ENRs
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if instance_info.seq != bootstrap_node.seq {
+        match rpc_stats.find_node(&discv5, NodeId::random()).await {
+            Ok(enrs) => {
+                let mut observed = 0_u64;
+                let mut mismatched = 0_u64;
+
+                for enr in enrs.iter() {
+                    info!("node_id: {}, tcp: {:?}", enr.node_id(), tcp(enr));
+                    if tcp(enr).is_some() {
+                        observed += 1;
+                    }
+                    if tcp(enr) != tcp_port {
+                        error!(
+                            "Unexpected tcp port. node_id: {}, expected: {:?}, actual: {:?}",
+                            enr.node_id(),
+                            tcp_port,
+                            tcp(enr)
+                        );
+                        mismatched += 1;
+                        failed = true;
+                    }
+                }
+
+                // //////////////////////////////////////////////////////////////
+                // Record metrics
+                // //////////////////////////////////////////////////////////////
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("discovered", enrs.len() as u64)
+                .add_field("observed_tcp_ports", observed)
+                .add_field("mismatched_tcp_ports", mismatched)
+                .add_tag("instance_seq", instance_info.seq);
+                record_metric(&client, write_query).await;
+            }
+            Err(e) => {
+                error!("Failed to run FINDNODE query: {e}");
+                failed = true;
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// The advertised tcp port, preferring the IPv4 one.
+fn tcp(enr: &Enr) -> Option<u16> {
+    enr.tcp4().or_else(|| enr.tcp6())
+}
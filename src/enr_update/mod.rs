@@ -1,45 +1,82 @@
 mod params;
+mod unreachable;
 
+use crate::config::build_config;
 use crate::enr_update::params::Params;
-use crate::utils::publish_and_collect;
+use crate::events::EventRecorder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    add_bucket_histogram, assert_routing_table, await_event, barrier_timeout, barrier_with_timeout,
+    bucket_histogram, build_enr, check_enr_port, enr_addresses, listen_config, publish_and_collect,
+    record_metric, report_topology, InstanceInfo, MAX_ENTRIES_PER_BUCKET,
+};
 use chrono::Local;
-use discv5::enr::CombinedKey;
-use discv5::{Discv5, Enr, ListenConfig};
-use serde::{Deserialize, Serialize};
+use discv5::{Discv5, Enr};
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
 use std::time::Duration;
 use testground::client::Client;
-use tokio::{sync, task};
-use tracing::{debug, error, info};
+use testground::WriteQuery;
+use tokio::sync::watch;
+use tokio::task;
+use tracing::{debug, error, info, warn};
 
 const STATE_COMPLETED_ESTABLISH_CONNECTIONS: &str = "state_completed_establish_connections";
 const STATE_COMPLETED: &str = "state_completed";
+const STATE_ENR_MUTATED: &str = "state_enr_mutated";
+const STATE_COMPLETED_TO_OBSERVE_MUTATION: &str = "state_completed_to_observe_mutation";
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-struct InstanceInfo {
-    // The sequence number of this test instance within the test.
-    seq: u64,
-    enr: Enr,
+// The custom ENR field the mutating node inserts to bump its sequence number.
+const MUTATION_KEY: &str = "enr_update";
+
+// The interval between checks of the ENRs of the other nodes.
+const ENR_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// The backoff before the first retry, which is doubled on every retry up to `MAX_RETRY_BACKOFF`.
+const RETRY_BACKOFF_BASE: Duration = Duration::from_millis(500);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("enr-update", run);
+    // IPv4-only, since the NAT of the unreachable node is emulated by dropping the packets to its
+    // IPv4 address on the data network.
+    registry.register_ipv4_only("enr-update-unreachable", unreachable::run);
 }
 
 pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
     let run_parameters = client.run_parameters();
     let params = Params::new(&run_parameters.test_instance_params)?;
 
+    // The bootstrap nodes cut off the queries at half of the barrier timeout, so that they reach
+    // STATE_COMPLETED_ESTABLISH_CONNECTIONS before the other nodes, which have been waiting there
+    // since about the same time, give up on it. The backoff of `max_retries` must fit in it.
+    let establish_deadline = barrier_timeout(&client)? / 2;
+    let max_backoff = (0..params.max_retries).map(retry_backoff).sum::<Duration>();
+    if max_backoff >= establish_deadline {
+        return Err(format!(
+            "The backoff of max_retries ({}) takes {}s, which doesn't fit in the {}s the queries are given, i.e. half of barrier_timeout_secs.",
+            params.max_retries,
+            max_backoff.as_secs(),
+            establish_deadline.as_secs()
+        )
+        .into());
+    }
+
     // ////////////////////////
     // Construct a local Enr
     // ////////////////////////
-    let enr_key = CombinedKey::generate_secp256k1();
-
-    let enr = if client.global_seq() == 1 {
-        Enr::builder().build(&enr_key).expect("Construct an Enr")
-    } else {
-        Enr::builder()
-            .ip(run_parameters
-                .data_network_ip()?
-                .expect("IP address for the data network"))
-            .udp4(9000)
-            .build(&enr_key)
-            .expect("Construct an Enr")
+    // Failures on startup are recorded so that they show up as the result of the instance rather
+    // than as a panic in the logs.
+    let (enr, enr_key) = match build_enr(&run_parameters, client.global_seq()) {
+        Ok(built) => built,
+        Err(e) => {
+            client
+                .record_failure(format!("Failed to construct the local ENR: {e}"))
+                .await?;
+            return Ok(());
+        }
     };
 
     info!("ENR: {:?}", enr);
@@ -48,68 +85,131 @@ pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
     // //////////////////////////////////////////////////////////////
     // Start Discovery v5 server
     // //////////////////////////////////////////////////////////////
-    let mut discv5: Discv5 = Discv5::new(
-        enr,
-        enr_key,
-        discv5::ConfigBuilder::new(ListenConfig::default())
-            .ping_interval(Duration::from_secs(params.ping_interval))
-            .build(),
-    )?;
-    discv5.start().await.expect("Start Discovery v5 server");
+    let listen_config = listen_config(&run_parameters)?;
+    if let Err(e) = check_enr_port(&enr, &listen_config) {
+        client.record_failure(e).await?;
+        return Ok(());
+    }
+    let config = build_config(listen_config, &run_parameters)?;
+    let mut discv5: Discv5 = match Discv5::new(enr, enr_key, config) {
+        Ok(discv5) => discv5,
+        Err(e) => {
+            client
+                .record_failure(format!("Failed to construct the Discovery v5 server: {e}"))
+                .await?;
+            return Ok(());
+        }
+    };
+    if let Err(e) = discv5.start().await {
+        client
+            .record_failure(format!("Failed to start the Discovery v5 server: {e:?}"))
+            .await?;
+        return Ok(());
+    }
     let started_up_at = Local::now();
 
     // //////////////////////////////////////////////////////////////
     // Collect information of all participants in the test case
     // //////////////////////////////////////////////////////////////
-    let instance_info = InstanceInfo {
-        seq: client.global_seq(),
-        enr: discv5.local_enr(),
-    };
+    let instance_info = InstanceInfo::new(&client, discv5.local_enr())?;
     debug!("instance_info: {:?}", instance_info);
 
     let participants = publish_and_collect(&client, instance_info.clone()).await?;
+    let rpc_stats = RpcStats::default();
 
-    let maybe_receiver = if instance_info.seq == 1 {
-        let (sender, receiver) = sync::oneshot::channel();
-        let mut event_stream = discv5.event_stream().await.expect("Discv5Event");
-
-        task::spawn(async move {
-            while let Some(event) = event_stream.recv().await {
-                if let discv5::Event::SocketUpdated(socket_addr) = event {
-                    sender.send(socket_addr).unwrap();
-                    break;
-                }
+    // Wait for the socket to be updated in parallel with establishing connections so that the
+    // event stream is drained while the connections are being established.
+    let (event_recorder, mut event_stream) =
+        match EventRecorder::start_forwarding(&client, &discv5).await {
+            Ok(started) => started,
+            Err(e) => {
+                client.record_failure(e.to_string()).await?;
+                return Ok(());
             }
-        });
+        };
+    let maybe_handle = if instance_info.is_bootstrap_node {
+        let timeout = Duration::from_secs(params.socket_update_timeout);
 
-        Some(receiver)
+        Some(task::spawn(async move {
+            await_event(
+                &mut event_stream,
+                |event| matches!(event, discv5::Event::SocketUpdated(_)),
+                timeout,
+            )
+            .await
+        }))
     } else {
+        // Only the bootstrap nodes wait for the events.
+        drop(event_stream);
         None
     };
 
     // //////////////////////////////////////////////////////////////
     // Establish connections
     // //////////////////////////////////////////////////////////////
-    if instance_info.seq == 1 {
-        for p in participants
-            .iter()
-            .filter(|&p| p.seq != client.global_seq())
-        {
-            if let Err(e) = discv5
-                .find_node_designated_peer(p.enr.clone(), vec![0])
-                .await
-            {
-                error!("Failed to run FIND_NODE query: {e}");
+    if instance_info.is_bootstrap_node {
+        // The retries pending backoff are cancelled once the queries haven't completed by the
+        // establish deadline, so that the bootstrap node still reaches the barrier below in time.
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let canceller = task::spawn(async move {
+            tokio::time::sleep(establish_deadline).await;
+            warn!("The FIND_NODE queries haven't completed in time, cancelling the retries.");
+            let _ = shutdown_tx.send(true);
+        });
+
+        // Run the queries concurrently, up to `query_concurrency` at a time. A failed query doesn't
+        // abort the others.
+        let max_retries = params.max_retries;
+        let results = stream::iter(
+            participants
+                .iter()
+                .filter(|&p| p.seq != client.global_seq()),
+        )
+        .map(|p| {
+            let discv5 = &discv5;
+            let rpc_stats = &rpc_stats;
+            let mut shutdown_rx = shutdown_rx.clone();
+            async move {
+                let (result, retries) =
+                    find_node_with_retry(discv5, rpc_stats, &p.enr, max_retries, &mut shutdown_rx)
+                        .await;
+                (p, result, retries)
             }
+        })
+        .buffer_unordered(params.query_concurrency)
+        .collect::<Vec<_>>()
+        .await;
+        canceller.abort();
+
+        for (p, result, retries) in results {
+            if let Err(e) = result {
+                error!(
+                    "Failed to run FIND_NODE query to #{} after {retries} retries: {e}",
+                    p.seq
+                );
+            }
+
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("find_node_retries", retries as u64)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("target_seq", p.seq);
+            record_metric(&client, write_query).await;
         }
     }
 
-    client
-        .signal_and_wait(
-            STATE_COMPLETED_ESTABLISH_CONNECTIONS,
-            run_parameters.test_instance_count,
-        )
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_ESTABLISH_CONNECTIONS,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
 
     client.record_message(format!(
         "peers: {:?}",
@@ -117,32 +217,325 @@ pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
             .kbuckets()
             .iter()
             .map(|b| (
-                b.node.value.ip4().unwrap(),
+                enr_addresses(&b.node.value),
                 b.status.direction,
                 b.status.state
             ))
             .collect::<Vec<_>>()
     ));
 
-    if let Some(receiver) = maybe_receiver {
-        match receiver.await {
-            Ok(socket_addr) => {
+    // Awaiting the task drops the event stream held by it, so no event is delivered to it while
+    // discv5 is shutting down.
+    if let Some(handle) = maybe_handle {
+        match handle.await? {
+            Ok(discv5::Event::SocketUpdated(socket_addr)) => {
                 info!("Discv5Event::SocketUpdated {socket_addr}");
                 client.record_message(format!(
                     "The socket has been updated {} seconds after startup.",
                     (Local::now() - started_up_at).num_seconds()
                 ));
             }
+            Ok(_) => unreachable!(),
             Err(e) => {
-                error!("RecvError: {e}");
+                error!("Failed to wait for Discv5Event::SocketUpdated: {e}");
             }
         }
     }
 
-    client
-        .signal_and_wait(STATE_COMPLETED, run_parameters.test_instance_count)
-        .await?;
+    // //////////////////////////////////////////////////////////////
+    // Observe the propagation of the updated ENRs
+    // //////////////////////////////////////////////////////////////
+    // The other nodes poll their view of the ENRs of the nodes which have started without any
+    // socket address, until the sequence numbers are bumped or the completion barrier is reached.
+    let targets = if instance_info.is_bootstrap_node {
+        vec![]
+    } else {
+        participants
+            .iter()
+            .filter(|p| p.seq != instance_info.seq)
+            .filter(|p| p.enr.udp4_socket().is_none() && p.enr.udp6_socket().is_none())
+            .collect::<Vec<_>>()
+    };
+    let mut propagated_at = HashMap::new();
+
+    let barrier = barrier_with_timeout(
+        &client,
+        STATE_COMPLETED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    );
+    tokio::pin!(barrier);
 
-    client.record_success().await?;
+    let poll = async {
+        let mut interval = tokio::time::interval(ENR_POLL_INTERVAL);
+        while propagated_at.len() < targets.len() {
+            interval.tick().await;
+            for t in targets.iter() {
+                if propagated_at.contains_key(&t.seq) {
+                    continue;
+                }
+                if let Some(enr) = discv5.find_enr(&t.enr.node_id()) {
+                    if enr.seq() > t.enr.seq() {
+                        propagated_at.insert(t.seq, Local::now() - started_up_at);
+                    }
+                }
+            }
+        }
+    };
+
+    let mut barrier_result = None;
+    tokio::select! {
+        result = &mut barrier => barrier_result = Some(result),
+        _ = poll => {}
+    }
+    match barrier_result {
+        Some(result) => result?,
+        None => barrier.await?,
+    };
+
+    for t in targets {
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("target_seq", t.seq);
+
+        let write_query = match propagated_at.get(&t.seq) {
+            Some(elapsed) => {
+                client.record_message(format!(
+                    "The updated ENR of #{} has been observed {} milliseconds after startup.",
+                    t.seq,
+                    elapsed.num_milliseconds()
+                ));
+                write_query
+                    .add_field("propagated", true)
+                    .add_field("propagation_ms", elapsed.num_milliseconds())
+            }
+            None => {
+                client.record_message(format!(
+                    "The updated ENR of #{} has never propagated before the completion barrier.",
+                    t.seq
+                ));
+                write_query.add_field("propagated", false)
+            }
+        };
+        record_metric(&client, write_query).await;
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Mutate an ENR and observe the propagation of it
+    // //////////////////////////////////////////////////////////////
+    // NOTE: `mutate_seq` (default: the last instance) inserts a custom field into its ENR, and
+    // sends PING to its peers so that they learn the bumped sequence number via PONG. The other
+    // nodes which know the mutating node poll their view of its ENR for `propagation_timeout`.
+    let mutate_seq = params
+        .mutate_seq
+        .unwrap_or(run_parameters.test_instance_count);
+    let mutator = participants
+        .iter()
+        .find(|p| p.seq == mutate_seq)
+        .ok_or(format!("No instance with `mutate_seq`: {mutate_seq}"))?;
+    let known_seq = discv5.find_enr(&mutator.enr.node_id()).map(|enr| enr.seq());
+
+    if instance_info.seq == mutator.seq {
+        if let Err(e) = discv5.enr_insert(MUTATION_KEY, &1_u64) {
+            error!("Failed to insert the custom field into the ENR: {e:?}");
+        }
+        client.record_message(format!(
+            "The ENR has been mutated to seq {}.",
+            discv5.local_enr().seq()
+        ));
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_ENR_MUTATED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+    let mutated_at = Local::now();
+
+    let mut mutation_failed = false;
+    if instance_info.seq == mutator.seq {
+        let peers = discv5.table_entries_enr();
+        stream::iter(peers)
+            .map(|peer| {
+                let discv5 = &discv5;
+                async move {
+                    if let Err(e) = discv5.send_ping(peer).await {
+                        warn!("Failed to send PING after mutating the ENR: {e}");
+                    }
+                }
+            })
+            .buffer_unordered(params.query_concurrency)
+            .collect::<Vec<_>>()
+            .await;
+    } else if let Some(known_seq) = known_seq {
+        let node_id = mutator.enr.node_id();
+        let timeout = Duration::from_secs(params.propagation_timeout);
+        let mut interval = tokio::time::interval(ENR_POLL_INTERVAL);
+        let mut propagation = None;
+        while (Local::now() - mutated_at).to_std().unwrap_or_default() < timeout {
+            interval.tick().await;
+            if discv5
+                .find_enr(&node_id)
+                .map_or(false, |enr| enr.seq() > known_seq)
+            {
+                propagation = Some(Local::now() - mutated_at);
+                break;
+            }
+        }
+
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("target_seq", mutator.seq)
+        .add_tag("source", "mutation");
+
+        let write_query = match propagation {
+            Some(elapsed) => write_query
+                .add_field("propagated", true)
+                .add_field("propagation_ms", elapsed.num_milliseconds()),
+            None => {
+                // Ask the mutating node directly, to tell the lack of propagation from the loss of
+                // the mutating node.
+                let designated_seq = rpc_stats
+                    .find_node_designated_peer(&discv5, mutator.enr.clone(), vec![0])
+                    .await
+                    .ok()
+                    .and_then(|enrs| enrs.into_iter().find(|enr| enr.node_id() == node_id))
+                    .map(|enr| enr.seq());
+                error!(
+                    "The mutated ENR of #{} hasn't propagated within {}s. known seq: {known_seq}, designated seq: {designated_seq:?}",
+                    mutator.seq, params.propagation_timeout
+                );
+                mutation_failed = true;
+                write_query.add_field("propagated", false)
+            }
+        };
+        record_metric(&client, write_query).await;
+    } else {
+        info!(
+            "#{} isn't known, so the mutation can't be observed.",
+            mutator.seq
+        );
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_OBSERVE_MUTATION,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // Record how the routing table is filled by distance.
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_tag("instance_seq", instance_info.seq)
+    .add_tag("source", "bucket_histogram");
+    record_metric(
+        &client,
+        add_bucket_histogram(write_query, &bucket_histogram(&discv5)),
+    )
+    .await;
+
+    // Report the resulting overlay graph.
+    report_topology(&client, &discv5, run_parameters.test_instance_count).await?;
+
+    // The bootstrap nodes have connected to all the other nodes. On the other hand, the other
+    // nodes may not have inserted the bootstrap nodes into their routing table as the ENRs of the
+    // bootstrap nodes start without any socket address.
+    let min_peers = if instance_info.is_bootstrap_node {
+        run_parameters.test_instance_count - 1
+    } else {
+        0
+    };
+    let result = assert_routing_table(
+        &discv5,
+        usize::try_from(min_peers)?.min(MAX_ENTRIES_PER_BUCKET),
+    );
+
+    event_recorder.finish(&client).await;
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    // Shut down discv5 explicitly rather than leaving its background tasks and socket to be torn
+    // down abruptly on exit, which occasionally produces spurious error logs.
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    if let Err(e) = result {
+        client.record_failure(e).await?;
+    } else if mutation_failed {
+        client
+            .record_failure(
+                "The mutated ENR hasn't propagated, please check error logs for details.",
+            )
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
     Ok(())
 }
+
+/// Run FIND_NODE toward `enr`, retrying up to `max_retries` times with exponential backoff.
+/// Returns the result of the last attempt with the number of retries made. The backoff is
+/// cancelled once `shutdown` is notified.
+async fn find_node_with_retry(
+    discv5: &Discv5,
+    rpc_stats: &RpcStats,
+    enr: &Enr,
+    max_retries: u32,
+    shutdown: &mut watch::Receiver<bool>,
+) -> (Result<Vec<Enr>, String>, u32) {
+    let mut retries = 0;
+    loop {
+        let result = rpc_stats
+            .find_node_designated_peer(discv5, enr.clone(), vec![0])
+            .await
+            .map_err(|e| e.to_string());
+        // The receivers cloned after the cancellation don't see it as a change.
+        if result.is_ok() || retries >= max_retries || *shutdown.borrow() {
+            return (result, retries);
+        }
+
+        if let Err(e) = &result {
+            warn!("FIND_NODE query failed, retrying: {e}");
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(retry_backoff(retries)) => {}
+            _ = shutdown.changed() => return (result, retries),
+        }
+        retries += 1;
+    }
+}
+
+// The backoff before the retry which follows `retries` retries.
+fn retry_backoff(retries: u32) -> Duration {
+    2_u32
+        .checked_pow(retries)
+        .map_or(MAX_RETRY_BACKOFF, |factor| {
+            RETRY_BACKOFF_BASE.saturating_mul(factor)
+        })
+        .min(MAX_RETRY_BACKOFF)
+}
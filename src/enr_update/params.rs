@@ -1,16 +1,33 @@
-use crate::utils::get_param;
+use crate::utils::{get_param, get_param_or};
 use std::collections::HashMap;
 
 pub(crate) struct Params {
-    pub ping_interval: u64,
+    pub socket_update_timeout: u64,
+    pub max_retries: u32,
+    pub query_concurrency: usize,
+    pub mutate_seq: Option<u64>,
+    pub propagation_timeout: u64,
 }
 
 impl Params {
     pub(crate) fn new(
         instance_params: &HashMap<String, String>,
     ) -> Result<Params, Box<dyn std::error::Error>> {
+        let query_concurrency = get_param_or::<usize>("query_concurrency", instance_params, 16)?;
+        if query_concurrency == 0 {
+            return Err("query_concurrency must be non-zero.".into());
+        }
+
         Ok(Params {
-            ping_interval: get_param::<u64>("ping_interval", instance_params)?,
+            socket_update_timeout: get_param::<u64>("socket_update_timeout", instance_params)?,
+            max_retries: get_param_or::<u32>("max_retries", instance_params, 3)?,
+            query_concurrency,
+            mutate_seq: if instance_params.contains_key("mutate_seq") {
+                Some(get_param::<u64>("mutate_seq", instance_params)?)
+            } else {
+                None
+            },
+            propagation_timeout: get_param_or::<u64>("propagation_timeout", instance_params, 30)?,
         })
     }
 }
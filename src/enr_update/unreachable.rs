@@ -0,0 +1,307 @@
+use crate::config::build_config;
+use crate::enr_update::params::Params;
+use crate::events::EventRecorder;
+use crate::rpc_stats::RpcStats;
+use crate::teardown::teardown;
+use crate::utils::{
+    await_event, barrier_timeout, barrier_with_timeout, build_enr_with_key, check_enr_port,
+    drop_rules, instance_key, ip_version, link_shape, listen_config, publish_and_collect,
+    publish_and_collect_on, reconfigure_network_with_rules, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::{Discv5, Enr, Event};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_VOTE: &str = "state_completed_to_vote";
+const STATE_COMPLETED: &str = "state_completed";
+const TOPIC_UPDATED_ENRS: &str = "updated_enrs";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Participant {
+    #[serde(flatten)]
+    instance: InstanceInfo,
+    // The ENRs of the nodes without any socket address don't tell their IP addresses, which the
+    // network rules are applied to.
+    ip: IpAddr,
+}
+
+// The roles of the participants, which are assigned by seq.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+    // Starts without any socket address in its ENR, and is reachable by everyone.
+    Reachable,
+    // Starts without any socket address in its ENR, and is unreachable except by the peers it has
+    // contacted, i.e. as if it were behind a NAT.
+    Unreachable,
+    // Contacted by both of the nodes above, so that they are reported their sockets via PONG.
+    Contacted,
+    // Never contacted by the nodes above. They try to reach the nodes above once those advertise
+    // their sockets.
+    Outside,
+}
+
+fn role_of(seq: u64) -> Role {
+    match seq {
+        1 => Role::Reachable,
+        2 => Role::Unreachable,
+        seq if seq % 2 == 1 => Role::Contacted,
+        _ => Role::Outside,
+    }
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+    let ip = run_parameters
+        .data_network_ip()?
+        .ok_or("No IP address for the data network.")?;
+    let seq = client.global_seq();
+    let role = role_of(seq);
+    let without_socket = matches!(role, Role::Reachable | Role::Unreachable);
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let enr_key = instance_key(&run_parameters, seq)?;
+    let listen_config = listen_config(&run_parameters)?;
+    let enr = if without_socket {
+        Enr::builder()
+            .build(&enr_key)
+            .map_err(|e| format!("Failed to construct an Enr: {e:?}"))?
+    } else {
+        build_enr_with_key(
+            &run_parameters,
+            seq,
+            ip_version(&run_parameters.test_instance_params)?,
+            &enr_key,
+        )?
+    };
+    check_enr_port(&enr, &listen_config)?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let config = build_config(listen_config, &run_parameters)?;
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+    let (event_recorder, mut event_stream) =
+        EventRecorder::start_forwarding(&client, &discv5).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = Participant {
+        instance: InstanceInfo::new(&client, enr)?,
+        ip,
+    };
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}, role: {:?}",
+        instance_info.instance.seq,
+        instance_info.instance.enr.node_id(),
+        ip,
+        role
+    ));
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Make the unreachable node unreachable
+    // //////////////////////////////////////////////////////////////
+    // NOTE: The network rules are stateless and apply to the outgoing traffic, so the NAT of the
+    // unreachable node is emulated by the peers it never contacts, which drop the packets to it.
+    // The contacted peers only talk to it over the sessions it has initiated, as the NAT allows.
+    let unreachable_node = participants
+        .iter()
+        .find(|p| role_of(p.instance.seq) == Role::Unreachable)
+        .expect("Unreachable node")
+        .clone();
+    let rules = if role == Role::Outside {
+        Some(drop_rules(
+            &run_parameters.test_instance_params,
+            &[unreachable_node.ip],
+        )?)
+    } else {
+        None
+    };
+    reconfigure_network_with_rules(
+        &client,
+        link_shape(&run_parameters.test_instance_params)?,
+        rules,
+        "state_unreachable_configured".to_owned(),
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Contact the peers and wait for the majority vote
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if without_socket {
+        let timeout = Duration::from_secs(params.socket_update_timeout);
+        let started_at = Instant::now();
+        let wait = tokio::spawn(async move {
+            await_event(
+                &mut event_stream,
+                |event| matches!(event, Event::SocketUpdated(_)),
+                timeout,
+            )
+            .await
+        });
+
+        let contacted = participants
+            .iter()
+            .filter(|p| role_of(p.instance.seq) == Role::Contacted)
+            .collect::<Vec<_>>();
+        let results = futures::future::join_all(contacted.iter().map(|p| {
+            rpc_stats.find_node_designated_peer(&discv5, p.instance.enr.clone(), vec![0])
+        }))
+        .await;
+        let established = results.iter().filter(|r| r.is_ok()).count();
+        info!(
+            "Established sessions with {established} of {} contacted peers.",
+            contacted.len()
+        );
+
+        let vote = match wait.await? {
+            Ok(Event::SocketUpdated(socket)) => {
+                let elapsed = started_at.elapsed();
+                client.record_message(format!(
+                    "The socket has been updated to {socket} {}ms after contacting the peers.",
+                    elapsed.as_millis()
+                ));
+                Some(elapsed)
+            }
+            Ok(_) => unreachable!(),
+            Err(e) => {
+                client.record_message(format!("The socket hasn't been updated: {e}"));
+                None
+            }
+        };
+        // The reachable node is the baseline, whose socket should be updated when enough peers
+        // are contacted.
+        if role == Role::Reachable && vote.is_none() {
+            error!("The socket of the reachable node hasn't been updated.");
+            failed = true;
+        }
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("contacted", contacted.len() as u64)
+        .add_field("established", established as u64)
+        .add_field("socket_updated", vote.is_some())
+        .add_tag("instance_seq", instance_info.instance.seq)
+        .add_tag("role", format!("{role:?}"))
+        .add_tag("source", "vote");
+        let write_query = match vote {
+            Some(elapsed) => write_query.add_field("vote_ms", elapsed.as_millis() as u64),
+            None => write_query,
+        };
+        record_metric(&client, write_query).await;
+    } else {
+        // The peers just respond, so they don't take the events.
+        drop(event_stream);
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_VOTE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Reach the updated ENRs from outside
+    // //////////////////////////////////////////////////////////////
+    // The outside peers try to reach the nodes which have learned their sockets, which should
+    // succeed for the reachable node only.
+    let updated_enrs =
+        publish_and_collect_on(&client, TOPIC_UPDATED_ENRS, (seq, discv5.local_enr())).await?;
+
+    if role == Role::Outside {
+        for (target_seq, target_enr) in updated_enrs
+            .into_iter()
+            .filter(|(seq, _)| matches!(role_of(*seq), Role::Reachable | Role::Unreachable))
+        {
+            let target_role = role_of(target_seq);
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_tag("instance_seq", instance_info.instance.seq)
+            .add_tag("target_seq", target_seq)
+            .add_tag("target_role", format!("{target_role:?}"))
+            .add_tag("source", "outside");
+
+            let write_query = if target_enr.udp4_socket().is_none() {
+                info!("#{target_seq} doesn't advertise any socket, so it can't be reached.");
+                write_query.add_field("advertised", false)
+            } else {
+                let reached = match discv5.send_ping(target_enr).await {
+                    Ok(_) => true,
+                    Err(e) => {
+                        info!("Failed to reach #{target_seq}: {e}");
+                        false
+                    }
+                };
+                if target_role == Role::Unreachable && reached {
+                    error!("The unreachable node #{target_seq} has been reached from outside.");
+                    failed = true;
+                }
+                if target_role == Role::Reachable && !reached {
+                    warn!("The reachable node #{target_seq} hasn't been reached from outside.");
+                }
+                write_query
+                    .add_field("advertised", true)
+                    .add_field("reached", reached)
+            };
+            record_metric(&client, write_query).await;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    event_recorder.finish(&client).await;
+
+    rpc_stats.finish(&client, seq).await;
+
+    teardown(&client, discv5, seq, run_parameters.test_instance_count).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
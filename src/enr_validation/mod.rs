@@ -0,0 +1,318 @@
+use crate::adversary::{self, Attack};
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::{final_barrier, teardown};
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_socket, listen_config,
+    publish_and_collect, publish_and_collect_on, record_metric, InstanceInfo, Role,
+};
+use chrono::Local;
+use discv5::enr::{CombinedKey, NodeId};
+use discv5::{Discv5, Enr, Key};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_ATTACKERS_STARTED: &str = "state_attackers_started";
+const STATE_COMPLETED_TO_VALIDATE: &str = "state_completed_to_validate";
+const TOPIC_CRAFTED: &str = "crafted";
+
+// The roles of this test case, which are given by the `victims` and `attackers` groups.
+const ROLES: [Role; 2] = [Role::Victim, Role::Attacker];
+
+// The attackers play the attacks in turn by their group seq.
+fn attack_of(group_seq: u64) -> Attack {
+    match group_seq % 3 {
+        1 => Attack::Forged,
+        2 => Attack::SpoofedIp,
+        _ => Attack::Oversized,
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Participant {
+    #[serde(flatten)]
+    instance: InstanceInfo,
+    // The attack this instance plays. Only the attackers have one.
+    attack: Option<Attack>,
+    // The node ids, in text form, claimed by the crafted ENRs. Only the attackers publish them.
+    forged_node_ids: Vec<String>,
+    // The log2-distances from the attacker to the node ids claimed by the crafted ENRs.
+    forged_distances: Vec<u64>,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("enr-validation", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let role = Role::from_supported_group_id(&run_parameters.test_group_id, &ROLES)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+        .request_timeout(Duration::from_secs(3))
+        .build();
+
+    match role {
+        Role::Victim => play_victim(client, enr, enr_key, config).await,
+        Role::Attacker => play_attacker(client, enr, enr_key, config).await,
+        role => Err(role.unsupported()),
+    }
+}
+
+async fn play_victim(
+    client: Client,
+    enr: Enr,
+    enr_key: CombinedKey,
+    config: discv5::Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    let instance_info = Participant {
+        instance: InstanceInfo::new(&client, enr)?,
+        attack: None,
+        forged_node_ids: vec![],
+        forged_distances: vec![],
+    };
+    publish_and_collect(&client, instance_info.clone()).await?;
+    // The attackers publish the crafted node ids once their adversaries have started.
+    let attackers = publish_and_collect_on(&client, TOPIC_CRAFTED, instance_info)
+        .await?
+        .into_iter()
+        .filter(|i| i.instance.role == Role::Attacker)
+        .collect::<Vec<_>>();
+
+    barrier_with_timeout(
+        &client,
+        STATE_ATTACKERS_STARTED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Query the attackers
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+    for attacker in attackers.iter() {
+        let attack = attacker.attack.expect("Attack of the attacker");
+        // Request the distances the crafted ENRs claim to be at, so that they aren't discarded
+        // just because of the distance.
+        let distances = attacker.forged_distances.clone();
+
+        let returned = match rpc_stats
+            .find_node_designated_peer(&discv5, attacker.instance.enr.clone(), distances)
+            .await
+        {
+            Ok(enrs) => {
+                info!(
+                    "The attacker ({attack:?}) has responded with {} ENRs.",
+                    enrs.len()
+                );
+                enrs.iter()
+                    .filter(|enr| {
+                        attacker
+                            .forged_node_ids
+                            .contains(&enr.node_id().to_string())
+                    })
+                    .count()
+            }
+            // The NODES response carrying the crafted ENRs is expected to be discarded.
+            Err(e) => {
+                info!("The query to the attacker ({attack:?}) has failed: {e}");
+                0
+            }
+        };
+
+        // The spoofed ENR is validly signed, so it may be returned by the query. It must never be
+        // inserted into the routing table though, which is validated below.
+        if returned > 0 && !matches!(attack, Attack::SpoofedIp) {
+            error!("{returned} crafted ENRs ({attack:?}) have been accepted.");
+            failed = true;
+        }
+
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("returned_crafted", returned as u64)
+        .add_tag("instance_seq", client.global_seq())
+        .add_tag("attack", format!("{attack:?}"));
+        record_metric(&client, write_query).await;
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Validate the routing table
+    // //////////////////////////////////////////////////////////////
+    // None of the crafted ENRs can be inserted: the forged ones are invalid, no session can be
+    // established with the spoofed one as the node at the IP address has another node id, and
+    // the oversized response is dropped.
+    let forged_in_table = discv5
+        .table_entries_id()
+        .iter()
+        .filter(|node_id| {
+            attackers
+                .iter()
+                .any(|a| a.forged_node_ids.contains(&node_id.to_string()))
+        })
+        .count();
+    if forged_in_table > 0 {
+        error!("{forged_in_table} crafted ENRs have been inserted into the routing table.");
+        failed = true;
+    }
+
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("forged_in_table", forged_in_table as u64)
+    .add_tag("instance_seq", client.global_seq());
+    record_metric(&client, write_query).await;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_VALIDATE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, client.global_seq()).await;
+
+    teardown(
+        &client,
+        discv5,
+        client.global_seq(),
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+async fn play_attacker(
+    client: Client,
+    enr: Enr,
+    enr_key: CombinedKey,
+    config: discv5::Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let attack = attack_of(client.group_seq());
+
+    // The IP address of a victim is needed to spoof it, so the instance information is exchanged
+    // before crafting the ENRs.
+    let instance_info = Participant {
+        instance: InstanceInfo::new(&client, enr.clone())?,
+        attack: Some(attack),
+        forged_node_ids: vec![],
+        forged_distances: vec![],
+    };
+    let victims = publish_and_collect(&client, instance_info.clone())
+        .await?
+        .into_iter()
+        .filter(|i| i.instance.role == Role::Victim)
+        .collect::<Vec<_>>();
+
+    // ////////////////////////
+    // Craft ENRs
+    // ////////////////////////
+    let claimed_socket = match attack {
+        Attack::SpoofedIp => enr_socket(
+            &victims
+                .first()
+                .ok_or("No victim found to spoof the IP address of.")?
+                .instance
+                .enr,
+        )?,
+        _ => enr_socket(&enr)?,
+    };
+    let crafted = adversary::craft(attack, claimed_socket)?;
+
+    let local_key: Key<NodeId> = enr.node_id().into();
+    let mut forged_distances = crafted
+        .node_ids
+        .iter()
+        .filter_map(|node_id| local_key.log2_distance(&Key::from(*node_id)))
+        .collect::<Vec<_>>();
+    forged_distances.sort_unstable();
+    forged_distances.dedup();
+    let forged_node_ids = crafted
+        .node_ids
+        .iter()
+        .map(|node_id| node_id.to_string())
+        .collect::<Vec<_>>();
+    info!("Crafted ENRs ({attack:?}) claiming node ids: {forged_node_ids:?}");
+
+    // ////////////////////////
+    // Start adversary
+    // ////////////////////////
+    let _adversary = adversary::start(enr, enr_key, config, crafted).await;
+
+    publish_and_collect_on(
+        &client,
+        TOPIC_CRAFTED,
+        Participant {
+            forged_node_ids,
+            forged_distances,
+            ..instance_info
+        },
+    )
+    .await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_ATTACKERS_STARTED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_VALIDATE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+    final_barrier(&client, run_parameters.test_instance_count).await?;
+
+    client.record_success().await?;
+    Ok(())
+}
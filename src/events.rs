@@ -0,0 +1,144 @@
+use crate::utils::{get_instance_seq, record_metric};
+use chrono::Local;
+use discv5::{Discv5, Event};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use testground::client::Client;
+use testground::WriteQuery;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+// The file name of the timeline in the outputs directory of the instance.
+const EVENTS_FILE: &str = "events.ndjson";
+// The capacity of the channel the events are forwarded to the test case through.
+const FORWARD_CAPACITY: usize = 64;
+
+/// A background task which records every event of a discv5 server for the whole run. Each event is
+/// timestamped and written to `events.ndjson` in the outputs directory, and the number of events
+/// per kind is recorded as a metric by `finish`. The task is cancelled when this is dropped.
+///
+/// NOTE: discv5 delivers the events to the last event stream obtained only, so the test cases
+/// which wait for events must take them from the recorder, see `start_forwarding`.
+pub(crate) struct EventRecorder {
+    handle: JoinHandle<()>,
+    counts: Arc<Mutex<BTreeMap<String, u64>>>,
+}
+
+impl Drop for EventRecorder {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl EventRecorder {
+    pub(crate) async fn start(
+        client: &Client,
+        discv5: &Discv5,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::spawn(client, discv5, None).await
+    }
+
+    /// Same as `start`, but also forwards the events to the returned receiver, as long as it is
+    /// alive.
+    pub(crate) async fn start_forwarding(
+        client: &Client,
+        discv5: &Discv5,
+    ) -> Result<(Self, mpsc::Receiver<Event>), Box<dyn std::error::Error>> {
+        let (tx, rx) = mpsc::channel(FORWARD_CAPACITY);
+        Ok((Self::spawn(client, discv5, Some(tx)).await?, rx))
+    }
+
+    async fn spawn(
+        client: &Client,
+        discv5: &Discv5,
+        mut forward: Option<mpsc::Sender<Event>>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut event_stream = discv5
+            .event_stream()
+            .await
+            .map_err(|e| format!("Failed to obtain the event stream: {e:?}"))?;
+
+        let path = Path::new(&client.run_parameters().test_outputs_path).join(EVENTS_FILE);
+        let mut file = match File::create(&path) {
+            Ok(file) => Some(LineWriter::new(file)),
+            Err(e) => {
+                warn!("Failed to create {}: {e}", path.display());
+                None
+            }
+        };
+
+        let seq = get_instance_seq(client);
+        let counts = Arc::new(Mutex::new(BTreeMap::new()));
+        let task_counts = counts.clone();
+        let handle = tokio::spawn(async move {
+            while let Some(event) = event_stream.recv().await {
+                debug!("Discv5Event: {:?}", event);
+                let detail = format!("{event:?}");
+                let kind = kind_of(&detail).to_owned();
+
+                if let Some(f) = file.as_mut() {
+                    let line = json!({
+                        "timestamp": Local::now().to_rfc3339(),
+                        "seq": seq,
+                        "event": &kind,
+                        "detail": detail,
+                    });
+                    if let Err(e) = writeln!(f, "{line}") {
+                        warn!("Failed to write the event timeline: {e}");
+                        file = None;
+                    }
+                }
+                *task_counts
+                    .lock()
+                    .expect("Lock the event counts")
+                    .entry(kind)
+                    .or_insert(0) += 1;
+
+                if let Some(tx) = forward.as_ref() {
+                    // The test case has stopped taking the events.
+                    if tx.send(event).await.is_err() {
+                        forward = None;
+                    }
+                }
+            }
+        });
+
+        Ok(EventRecorder { handle, counts })
+    }
+
+    /// Stop recording, and record the number of events per kind.
+    pub(crate) async fn finish(self, client: &Client) {
+        self.handle.abort();
+        let counts = self.counts.lock().expect("Lock the event counts").clone();
+        client.record_message(format!("Discv5 events: {counts:?}"));
+
+        let run_parameters = client.run_parameters();
+        let mut write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("events", counts.values().sum::<u64>())
+        .add_tag("instance_seq", get_instance_seq(client))
+        .add_tag("source", "events");
+        for (kind, count) in counts {
+            write_query = write_query.add_field(kind, count);
+        }
+        record_metric(client, write_query).await;
+    }
+}
+
+// The kind of the event, i.e. the name of the variant, e.g. `SessionEstablished`.
+fn kind_of(detail: &str) -> &str {
+    detail
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or(detail)
+}
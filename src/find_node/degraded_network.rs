@@ -0,0 +1,211 @@
+use crate::config::build_config;
+use crate::network::{Scheduler, INITIAL_PHASE};
+use crate::rpc_stats::RpcStats;
+use crate::teardown::teardown;
+use crate::topology::{join_topology, Topology};
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, collect_instance_info,
+    get_param_or, link_shape, listen_config, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::Discv5;
+use rand::seq::SliceRandom;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use testground::client::Client;
+use testground::network_conf::LinkShape;
+use testground::WriteQuery;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_OBSERVE: &str = "state_completed_to_observe";
+
+struct Params {
+    degrade_at: u64,
+    restore_at: u64,
+    latency_factor: u64,
+    observation_duration: u64,
+    lookup_interval: u64,
+}
+
+impl Params {
+    fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        let params = Params {
+            degrade_at: get_param_or::<u64>("degrade_at", instance_params, 60)?,
+            restore_at: get_param_or::<u64>("restore_at", instance_params, 120)?,
+            latency_factor: get_param_or::<u64>("latency_factor", instance_params, 10)?,
+            observation_duration: get_param_or::<u64>(
+                "observation_duration",
+                instance_params,
+                180,
+            )?,
+            lookup_interval: get_param_or::<u64>("lookup_interval", instance_params, 1000)?,
+        };
+        if params.restore_at > params.observation_duration {
+            return Err(format!(
+                "restore_at ({}) must not be later than observation_duration ({}).",
+                params.restore_at, params.observation_duration
+            )
+            .into());
+        }
+        Ok(params)
+    }
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let instance_params = &run_parameters.test_instance_params;
+    let params = Params::new(instance_params)?;
+
+    // ////////////////////////
+    // Construct a local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // //////////////////////////////////////////////////////////////
+    // Start Discovery v5 server
+    // //////////////////////////////////////////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5: Discv5 =
+        Discv5::new(enr, enr_key, build_config(listen_config, &run_parameters)?)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, discv5.local_enr())?;
+    let other_instances = collect_instance_info(&client, &instance_info).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Star topology
+    // //////////////////////////////////////////////////////////////
+    let rpc_stats = RpcStats::default();
+    join_topology(
+        &discv5,
+        &rpc_stats,
+        Topology::Star,
+        &instance_info,
+        &other_instances,
+    )
+    .await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Degrade and restore the network while running lookups
+    // //////////////////////////////////////////////////////////////
+    let default_latency = link_shape(instance_params)?.latency;
+    let scheduler = Scheduler::new()
+        .phase(
+            Duration::from_secs(params.degrade_at),
+            "degraded",
+            LinkShape {
+                latency: default_latency * params.latency_factor,
+                ..link_shape(instance_params)?
+            },
+        )?
+        .phase(
+            Duration::from_secs(params.restore_at),
+            "restored",
+            link_shape(instance_params)?,
+        )?;
+    let timeline = scheduler.timeline();
+    let started_at = Instant::now();
+
+    let targets = other_instances
+        .iter()
+        .filter(|i| !i.is_bootstrap_node)
+        .collect::<Vec<_>>();
+    let lookups = async {
+        // The latencies of the lookups by phase.
+        let mut latencies: BTreeMap<String, Vec<u128>> = BTreeMap::new();
+        if instance_info.is_bootstrap_node || targets.is_empty() {
+            return latencies;
+        }
+
+        while started_at.elapsed() < Duration::from_secs(params.observation_duration) {
+            let target = targets
+                .choose(&mut rand::thread_rng())
+                .expect("Lookup target");
+            let phase = timeline.phase_at(started_at.elapsed()).to_owned();
+            let lookup_started_at = Instant::now();
+            let found = match rpc_stats.find_node(&discv5, target.enr.node_id()).await {
+                Ok(enrs) => enrs.iter().any(|enr| enr.node_id() == target.enr.node_id()),
+                Err(e) => {
+                    warn!("Failed to run FINDNODE query for #{}: {e}", target.seq);
+                    false
+                }
+            };
+            let elapsed = lookup_started_at.elapsed();
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("elapsed_ms", elapsed.as_millis() as u64)
+            .add_field("found", found)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("target_seq", target.seq)
+            .add_tag("phase", phase.clone());
+            record_metric(&client, write_query).await;
+
+            latencies
+                .entry(phase)
+                .or_default()
+                .push(elapsed.as_millis());
+            tokio::time::sleep(Duration::from_millis(params.lookup_interval)).await;
+        }
+        latencies
+    };
+    let (schedule, latencies) = tokio::join!(scheduler.run(&client, started_at), lookups);
+    schedule?;
+
+    for phase in [INITIAL_PHASE, "degraded", "restored"] {
+        if let Some(latencies) = latencies.get(phase) {
+            let mean = latencies.iter().sum::<u128>() / latencies.len() as u128;
+            info!(
+                "{phase}: {} lookups, mean latency: {mean}ms",
+                latencies.len()
+            );
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_OBSERVE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    client.record_success().await?;
+    Ok(())
+}
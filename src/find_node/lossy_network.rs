@@ -0,0 +1,195 @@
+use crate::config::build_config;
+use crate::rpc_stats::RpcStats;
+use crate::teardown::teardown;
+use crate::topology::{join_topology, Topology};
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, collect_instance_info,
+    get_param_or, listen_config, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::Discv5;
+use std::time::Instant;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_COLLECT_INSTANCE_INFORMATION: &str =
+    "state_completed_to_collect_instance_information";
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let instance_params = &run_parameters.test_instance_params;
+    // The queries are expected to fail under packet loss, so this test case fails only if the
+    // success rate falls below the optional `min_success_rate`.
+    let min_success_rate = get_param_or::<f64>("min_success_rate", instance_params, 0.0)?;
+    let loss = get_param_or::<f32>("loss", instance_params, 0.0)?;
+
+    // ////////////////////////
+    // Construct a local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // //////////////////////////////////////////////////////////////
+    // Start Discovery v5 server
+    // //////////////////////////////////////////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5: Discv5 =
+        Discv5::new(enr, enr_key, build_config(listen_config, &run_parameters)?)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, discv5.local_enr())?;
+    let other_instances = collect_instance_info(&client, &instance_info).await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_COLLECT_INSTANCE_INFORMATION,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Star topology
+    // //////////////////////////////////////////////////////////////
+    let rpc_stats = RpcStats::default();
+    join_topology(
+        &discv5,
+        &rpc_stats,
+        Topology::Star,
+        &instance_info,
+        &other_instances,
+    )
+    .await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run FINDNODE query
+    // //////////////////////////////////////////////////////////////
+    // Unlike `find-node`, a failed query is counted rather than failing the test. The lost packets
+    // are retransmitted by discv5 after the request timeout, which is visible in the latency.
+    let mut failed = false;
+
+    if !instance_info.is_bootstrap_node {
+        let targets = other_instances
+            .iter()
+            .filter(|i| !i.is_bootstrap_node)
+            .collect::<Vec<_>>();
+        let mut successful_queries = 0_u64;
+
+        for target in targets.iter() {
+            let target_id = target.enr.node_id();
+            let started_at = Instant::now();
+            let result = rpc_stats.find_node(&discv5, target_id).await;
+            let elapsed = started_at.elapsed();
+
+            let success = match result {
+                Ok(enrs) => enrs.iter().any(|enr| enr.node_id() == target_id),
+                Err(e) => {
+                    warn!("Failed to run FINDNODE query for #{}: {e}", target.seq);
+                    false
+                }
+            };
+            if success {
+                successful_queries += 1;
+            }
+            info!(
+                "target: #{}, success: {success}, elapsed: {}ms",
+                target.seq,
+                elapsed.as_millis()
+            );
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("elapsed_ms", elapsed.as_millis() as u64)
+            .add_field("success", success)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("target_seq", target.seq)
+            .add_tag("loss", loss.to_string());
+            record_metric(&client, write_query).await;
+        }
+
+        let success_rate = if targets.is_empty() {
+            1.0
+        } else {
+            successful_queries as f64 / targets.len() as f64
+        };
+        client.record_message(format!(
+            "{successful_queries} of {} queries have succeeded ({success_rate:.3}) under {loss}% packet loss.",
+            targets.len()
+        ));
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("queries", targets.len() as u64)
+        .add_field("successful_queries", successful_queries)
+        .add_field("success_rate", success_rate)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("loss", loss.to_string())
+        .add_tag("source", "summary");
+        record_metric(&client, write_query).await;
+
+        if success_rate < min_success_rate {
+            error!("The success rate {success_rate:.3} is below {min_success_rate}.");
+            failed = true;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
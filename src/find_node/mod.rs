@@ -1,54 +1,57 @@
-use crate::utils::publish_and_collect;
+mod degraded_network;
+mod lossy_network;
+
+use crate::assertions::{Assertions, Thresholds};
+use crate::client_emulation::spawn_client_emulation;
+use crate::config::config_builder;
+use crate::events::EventRecorder;
+use crate::report::{write_report, Report};
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::topology::{join_topology, Topology};
+use crate::traffic::TrafficMeter;
+use crate::utils::{
+    add_bucket_histogram, assert_routing_table, barrier_timeout, barrier_with_timeout,
+    bucket_histogram, build_enr, check_enr_port, collect_instance_info, distance_summary,
+    enr_key_type, get_param, get_param_or, listen_config, populated_bucket_count, record_metric,
+    report_topology, spawn_metrics_poller, spawn_table_sampler, InstanceInfo, Role,
+    MAX_ENTRIES_PER_BUCKET,
+};
 use chrono::Local;
-use discv5::enr::{CombinedKey, NodeId};
-use discv5::{Discv5, Enr, Key, ListenConfig};
-use serde::{Deserialize, Serialize};
+use discv5::enr::NodeId;
+use discv5::{Discv5, Key};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use testground::client::Client;
 use testground::WriteQuery;
-use tokio::task;
-use tracing::{debug, error, info};
+use tracing::{debug, info};
 
 const STATE_COMPLETED_TO_COLLECT_INSTANCE_INFORMATION: &str =
     "state_completed_to_collect_instance_information";
 const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
 const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-struct InstanceInfo {
-    // The sequence number of this test instance within the test.
-    seq: u64,
-    enr: Enr,
-    is_bootstrap_node: bool,
-}
-
-impl InstanceInfo {
-    async fn new(client: &Client, enr: Enr) -> Result<Self, Box<dyn std::error::Error>> {
-        let seq = client.global_seq();
-
-        // NOTE: For now, #1 is bootstrap node.
-        let is_bootstrap_node = seq == 1;
-
-        Ok(InstanceInfo {
-            seq,
-            enr,
-            is_bootstrap_node,
-        })
-    }
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("find-node", run);
+    registry.register("lossy-network", lossy_network::run);
+    registry.register("degraded-network", degraded_network::run);
 }
 
 pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
     let run_parameters = client.run_parameters();
+    let topology = Topology::from_params(&run_parameters.test_instance_params)?;
+    let external_peers = get_param_or::<bool>(
+        "external_peers",
+        &run_parameters.test_instance_params,
+        false,
+    )?;
+
     // ////////////////////////
     // Construct a local Enr
     // ////////////////////////
-    let enr_key = CombinedKey::generate_secp256k1();
-    let enr = Enr::builder()
-        .ip(run_parameters
-            .data_network_ip()?
-            .expect("IP address for the data network"))
-        .udp4(9000)
-        .build(&enr_key)
-        .expect("Construct an Enr");
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
 
     info!("ENR: {:?}", enr);
     info!("NodeId: {}", enr.node_id());
@@ -56,70 +59,113 @@ pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
     // //////////////////////////////////////////////////////////////
     // Start Discovery v5 server
     // //////////////////////////////////////////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
     let mut discv5: Discv5 = Discv5::new(
         enr,
         enr_key,
-        discv5::ConfigBuilder::new(ListenConfig::default()).build(),
+        config_builder(listen_config, &run_parameters.test_instance_params)?.build(),
     )?;
-    discv5.start().await.expect("Start Discovery v5 server");
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let discv5 = Arc::new(discv5);
 
-    // Observe Discv5 events.
-    let mut event_stream = discv5.event_stream().await.expect("Discv5Event");
-    task::spawn(async move {
-        while let Some(event) = event_stream.recv().await {
-            info!("Discv5Event: {:?}", event);
-        }
-    });
+    // Record the timeline of Discv5 events until the end of this test.
+    let event_recorder = EventRecorder::start(&client, &discv5).await?;
 
     // //////////////////////////////////////////////////////////////
     // Collect information of all participants in the test case
     // //////////////////////////////////////////////////////////////
-    let instance_info = InstanceInfo::new(&client, discv5.local_enr()).await?;
+    let instance_info = InstanceInfo::new(&client, discv5.local_enr())?;
     debug!("instance_info: {:?}", instance_info);
 
-    let other_instances = collect_other_instance_info(&client, &instance_info).await?;
+    // Record the metrics of discv5 periodically until the end of this test.
+    let metrics_poller = spawn_metrics_poller(
+        &client,
+        discv5.clone(),
+        instance_info.seq,
+        Duration::from_secs(get_param::<u64>(
+            "metrics_interval",
+            &run_parameters.test_instance_params,
+        )?),
+    )?;
+
+    // Record the routing table periodically if opted in with `table_sample_interval`.
+    let table_sampler = spawn_table_sampler(&client, discv5.clone(), instance_info.seq)?;
+
+    // The outbound operations of the test case and the client emulation are accounted together.
+    let rpc_stats = RpcStats::default();
+
+    // Run the usage pattern of a consensus client underneath if opted in with
+    // `client_emulation_duration`.
+    let client_emulation = spawn_client_emulation(
+        &client,
+        discv5.clone(),
+        rpc_stats.clone(),
+        instance_info.seq,
+    )?;
+
+    // Measure the traffic, in order to report the overhead per lookup and per session.
+    let traffic_meter = TrafficMeter::start(&client, discv5.clone(), instance_info.seq)?;
+
+    let other_instances = collect_instance_info(&client, &instance_info).await?;
     debug!("other_instances: {:?}", other_instances);
 
-    client
-        .signal_and_wait(
-            STATE_COMPLETED_TO_COLLECT_INSTANCE_INFORMATION,
-            run_parameters.test_instance_count,
+    // In the external-peers mode, the instances of the other implementations publish their
+    // information only, so the barriers wait for the instances of this implementation.
+    let externals = other_instances.iter().filter(|i| i.is_external()).count() as u64;
+    if externals > 0 && !external_peers {
+        return Err(format!(
+            "{externals} instances of the other implementations have been found. Set `external_peers` to run with them."
         )
-        .await?;
+        .into());
+    }
+    let barrier_count = run_parameters.test_instance_count - externals;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_COLLECT_INSTANCE_INFORMATION,
+        barrier_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // Record the keyspace layout of this run, in order to interpret the results.
+    if instance_info.role == Role::Bootnode {
+        let mut node_ids = other_instances
+            .iter()
+            .map(|i| i.enr.node_id())
+            .collect::<Vec<_>>();
+        node_ids.push(instance_info.enr.node_id());
+        if let Some(summary) = distance_summary(&node_ids) {
+            client.record_message(format!("Pairwise distances: {summary}"));
+        }
+    }
 
     // //////////////////////////////////////////////////////////////
-    // Star topology
+    // Initial topology
     // //////////////////////////////////////////////////////////////
-    // NOTE: Assumes only 1 bootstrap node.
     let key: Key<NodeId> = discv5.local_enr().node_id().into();
-    if instance_info.is_bootstrap_node {
-        for i in other_instances.iter() {
-            discv5.add_enr(i.enr.clone())?;
-        }
-    } else {
-        let bootstrap_node = other_instances
-            .iter()
-            .find(|&i| i.is_bootstrap_node)
-            .expect("Bootstrap node");
-
-        // Emit distance to the bootstrap node.
-        let bootstrap_key: Key<NodeId> = bootstrap_node.enr.node_id().into();
-        info!(
-            "Distance between `self` and `bootstrap`: {}",
-            key.log2_distance(&bootstrap_key).expect("Distance")
-        );
+    join_topology(
+        &discv5,
+        &rpc_stats,
+        topology,
+        &instance_info,
+        &other_instances,
+    )
+    .await?;
 
-        discv5.add_enr(bootstrap_node.enr.clone())?;
-    }
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        barrier_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
 
-    client
-        .signal_and_wait(
-            STATE_COMPLETED_TO_BUILD_TOPOLOGY,
-            run_parameters.test_instance_count,
-        )
-        .await?;
-
-    if instance_info.is_bootstrap_node {
+    if instance_info.role == Role::Bootnode {
         let buckets = discv5.kbuckets();
         for b in buckets.buckets_iter() {
             for n in b.iter() {
@@ -131,23 +177,31 @@ pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
     // //////////////////////////////////////////////////////////////
     // Run FINDNODE query
     // //////////////////////////////////////////////////////////////
-    let mut failed = false;
+    let thresholds = Thresholds::from_params(&run_parameters.test_instance_params)?;
+    let mut assertions = Assertions::new();
+    let mut report = Report::new(&client);
+    // The lookups and the found targets per implementation of the targets.
+    let mut interop: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    // The bootstrap nodes are given by either the `bootnodes` group or the `bootstrap_count` param.
+    let bootstrap_count = other_instances
+        .iter()
+        .chain([&instance_info])
+        .filter(|i| i.role == Role::Bootnode)
+        .count();
 
-    if instance_info.is_bootstrap_node {
-        println!("Skipped to run FINDNODE query because this is the bootstrap node.");
+    if instance_info.role == Role::Bootnode {
+        println!("Skipped to run FINDNODE query because this is a bootstrap node.");
     } else {
         for target in other_instances {
-            if target.is_bootstrap_node {
+            if target.role == Role::Bootnode {
                 continue;
             }
 
             // Emit distance to the target.
             let target_key: Key<NodeId> = target.enr.node_id().into();
+            let distance = key.log2_distance(&target_key).expect("Distance");
             info!("target: {}", target.enr.node_id());
-            info!(
-                "Distance between `self` and `target`: {}",
-                key.log2_distance(&target_key).expect("Distance")
-            );
+            info!("Distance between `self` and `target`: {distance}");
 
             if let Some(enr) = discv5.find_enr(&target.enr.node_id()) {
                 info!(
@@ -155,26 +209,52 @@ pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
                     enr
                 );
             } else {
-                let enrs = discv5
-                    .find_node(target.enr.node_id())
+                let started_at = Instant::now();
+                let enrs = rpc_stats
+                    .find_node(&discv5, target.enr.node_id())
                     .await
                     .expect("FINDNODE query");
+                let elapsed = started_at.elapsed();
+                info!("The query took {}ms.", elapsed.as_millis());
+                report.record_lookup(Some(elapsed));
+                let (lookups, found) = interop.entry(target.implementation.clone()).or_default();
+                *lookups += 1;
+
+                // NOTE: discv5 doesn't expose the number of lookup rounds in the query result, so
+                // only the latency and the number of returned ENRs are recorded.
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("elapsed_ms", elapsed.as_millis() as u64)
+                .add_field("found_enrs", enrs.len() as u64)
+                .add_tag("instance_seq", instance_info.seq)
+                .add_tag("distance", distance)
+                .add_tag("group", instance_info.group_id.clone())
+                .add_tag("target_group", target.group_id.clone())
+                .add_tag("key_type", enr_key_type(&instance_info.enr))
+                .add_tag("target_key_type", enr_key_type(&target.enr))
+                .add_tag("target_implementation", target.implementation.clone());
+                record_metric(&client, write_query).await;
 
                 if enrs.is_empty() {
-                    error!("Found no ENRs");
-                    failed = true;
+                    assertions.fail(format!("Found no ENRs for #{}", target.seq));
                 } else {
                     info!("Found ENRs: {:?}", enrs);
 
                     // The target node should be found because the bootstrap node knows all the nodes in our star topology.
                     if enrs.iter().any(|enr| enr.node_id() == target.enr.node_id()) {
                         info!("Found the target");
+                        *found += 1;
                     } else {
-                        error!(
-                            "Couldn't find the target. node_id: {}",
+                        assertions.fail(format!(
+                            "Couldn't find the target #{}. node_id: {}",
+                            target.seq,
                             target.enr.node_id()
-                        );
-                        failed = true;
+                        ));
                     }
                 }
             }
@@ -198,40 +278,124 @@ pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
             .add_field("bytes_sent", metrics.bytes_sent as u64)
             .add_field("bytes_recv", metrics.bytes_recv as u64)
             .add_tag("instance_seq", instance_info.seq);
-            client.record_metric(write_query).await?;
+            record_metric(&client, write_query).await;
         }
     }
 
-    client
-        .signal_and_wait(
-            STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
-            run_parameters.test_instance_count,
-        )
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        barrier_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
 
-    // //////////////////////////////////////////////////////////////
-    // Record result of this test
-    // //////////////////////////////////////////////////////////////
-    if failed {
-        client
-            .record_failure("Failures have happened, please check error logs for details.")
-            .await?;
-    } else {
-        client.record_success().await?;
+    traffic_meter.finish(&client, report.lookups).await;
+
+    // Break down the results by the implementation of the targets, so that the interoperability
+    // with each of the other implementations shows up.
+    if external_peers {
+        for (implementation, (lookups, found)) in interop {
+            info!("implementation: {implementation}, found: {found}/{lookups}");
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("lookups", lookups)
+            .add_field("found_targets", found)
+            .add_field("success_rate", found as f64 / lookups as f64)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("target_implementation", implementation)
+            .add_tag("source", "interop");
+            record_metric(&client, write_query).await;
+        }
     }
 
-    Ok(())
-}
+    // Record how many buckets are actually populated after the queries.
+    let populated_buckets = populated_bucket_count(&discv5);
+    info!("Populated buckets: {populated_buckets}");
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("populated_buckets", populated_buckets as u64)
+    .add_tag("instance_seq", instance_info.seq)
+    .add_tag("group", instance_info.group_id.clone());
+    record_metric(&client, write_query).await;
 
-async fn collect_other_instance_info(
-    client: &Client,
-    instance_info: &InstanceInfo,
-) -> Result<Vec<InstanceInfo>, Box<dyn std::error::Error>> {
-    let mut info = publish_and_collect(client, instance_info.clone()).await?;
+    // Record how the routing table is filled by distance.
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_tag("instance_seq", instance_info.seq)
+    .add_tag("source", "bucket_histogram");
+    record_metric(
+        &client,
+        add_bucket_histogram(write_query, &bucket_histogram(&discv5)),
+    )
+    .await;
 
-    if let Some(pos) = info.iter().position(|i| i.seq == instance_info.seq) {
-        info.remove(pos);
+    // Report the resulting overlay graph.
+    report_topology(&client, &discv5, barrier_count).await?;
+
+    // The test has completed, so stop recording the metrics.
+    drop(metrics_poller);
+    drop(table_sampler);
+    drop(client_emulation);
+    event_recorder.finish(&client).await;
+    report.rpc_stats = rpc_stats.finish(&client, instance_info.seq).await;
+
+    // A single bootstrap node is contacted by all the other nodes, whereas with multiple bootstrap
+    // nodes each of them may not be chosen by anyone. The other nodes are connected to their
+    // bootstrap node at least.
+    let min_peers = if instance_info.role != Role::Bootnode {
+        1
+    } else if bootstrap_count == 1 {
+        usize::try_from(run_parameters.test_instance_count - 1)?.min(MAX_ENTRIES_PER_BUCKET)
+    } else {
+        0
+    };
+    if let Err(e) = assert_routing_table(&discv5, min_peers) {
+        assertions.fail(e);
     }
 
-    Ok(info)
+    report.peers_discovered = discv5.connected_peers();
+    if instance_info.role != Role::Bootnode {
+        assertions.assert_at_least_opt(
+            "peers_discovered",
+            report.peers_discovered,
+            thresholds.min_peers_discovered,
+        );
+    }
+    if let Some(max_latency) = report.lookup_latencies_ms.iter().max() {
+        assertions.assert_at_most_opt(
+            "max_lookup_latency_ms",
+            *max_latency,
+            thresholds.max_lookup_latency_ms,
+        );
+    }
+    report.failures = assertions.failures().to_vec();
+    // The instance #1 fails the test if the summary of the reports has regressed from the baseline.
+    for regression in write_report(&client, report, barrier_count).await? {
+        assertions.fail(regression);
+    }
+
+    teardown(&client, discv5, instance_info.seq, barrier_count).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    assertions.record(&client).await?;
+
+    Ok(())
 }
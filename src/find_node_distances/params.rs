@@ -0,0 +1,37 @@
+use crate::utils::{get_param_or, parse_distances};
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    // The distance vectors, each of which is sent in a FINDNODE request.
+    pub distance_sets: Vec<Vec<u64>>,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        let distances = get_param_or::<String>(
+            "distances",
+            instance_params,
+            "0,1,128,254,255,256".to_owned(),
+        )?;
+        let distance_sets =
+            get_param_or::<String>("distance_sets", instance_params, "".to_owned())?;
+
+        // The `distance_sets` param, if not empty, overrides requesting each of `distances` one by
+        // one.
+        let distance_sets = if distance_sets.trim().is_empty() {
+            parse_distances(&distances)?
+                .into_iter()
+                .map(|d| vec![d])
+                .collect()
+        } else {
+            distance_sets
+                .split(';')
+                .map(parse_distances)
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        Ok(Params { distance_sets })
+    }
+}
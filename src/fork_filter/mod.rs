@@ -0,0 +1,184 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr_with_fields, check_enr_port, enr_addresses,
+    get_param, listen_config, publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Enr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+// The ENR key of the fork-digest-like field.
+const FORK_KEY: &str = "fork";
+
+// The fork of this node. `table_filter` accepts a function pointer only, so the filter reads the
+// local fork from here.
+static LOCAL_FORK: AtomicU64 = AtomicU64::new(0);
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("fork-filter", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let fork_count = get_param::<u64>("fork_count", &run_parameters.test_instance_params)?;
+    if fork_count == 0 {
+        return Err("fork_count must be greater than zero.".into());
+    }
+
+    // Assign the forks to the participants in round-robin.
+    let local_fork = (client.global_seq() - 1) % fork_count;
+    LOCAL_FORK.store(local_fork, Ordering::Relaxed);
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr_with_fields(&run_parameters, client.global_seq(), |builder| {
+        builder.add_value(FORK_KEY, &local_fork);
+    })?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+        .table_filter(fork_filter)
+        .build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}, fork: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr),
+        local_fork
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // Attempt to add all the other participants. The ENRs on a different fork should be rejected
+    // by the table filter.
+    let mut rejected = 0;
+    for p in participants.iter().filter(|&p| p.seq != instance_info.seq) {
+        if let Err(e) = discv5.add_enr(p.enr.clone()) {
+            info!(
+                "Rejected ENR. node_id: {}, fork: {:?}, reason: {e}",
+                p.enr.node_id(),
+                fork(&p.enr)
+            );
+            rejected += 1;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // Run a lookup so that the nodes are also discovered through the other participants.
+    if let Err(e) = rpc_stats.find_node(&discv5, NodeId::random()).await {
+        error!("Failed to run FINDNODE query: {e}");
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Check the routing table
+    // //////////////////////////////////////////////////////////////
+    let (same_fork, different_fork): (Vec<Enr>, Vec<Enr>) = discv5
+        .table_entries_enr()
+        .into_iter()
+        .partition(|enr| fork(enr) == Some(local_fork));
+
+    client.record_message(format!(
+        "same_fork: {}, different_fork: {}, rejected: {}",
+        same_fork.len(),
+        different_fork.len(),
+        rejected
+    ));
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("same_fork", same_fork.len() as u64)
+    .add_field("different_fork", different_fork.len() as u64)
+    .add_field("rejected", rejected as u64)
+    .add_tag("instance_seq", instance_info.seq)
+    .add_tag("fork", local_fork);
+    record_metric(&client, write_query).await;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if different_fork.is_empty() {
+        client.record_success().await?;
+    } else {
+        client
+            .record_failure(format!(
+                "The routing table contains nodes on a different fork: {:?}",
+                different_fork
+                    .iter()
+                    .map(|enr| enr.node_id())
+                    .collect::<Vec<_>>()
+            ))
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn fork(enr: &Enr) -> Option<u64> {
+    enr.get_decodable::<u64>(FORK_KEY)
+        .and_then(|fork| fork.ok())
+}
+
+fn fork_filter(enr: &Enr) -> bool {
+    fork(enr) == Some(LOCAL_FORK.load(Ordering::Relaxed))
+}
@@ -0,0 +1,20 @@
+use crate::link_sweep::{self, LinkSweep};
+use crate::scenario::Registry;
+use testground::client::Client;
+use testground::network_conf::LinkShape;
+
+const SWEEP: LinkSweep = LinkSweep {
+    levels_param: "latency_levels",
+    field: "latency_ms",
+    unit: "ms",
+    apply: |link_shape, latency| LinkShape {
+        latency: (latency * 1_000_000.0) as u64, // Translate from millisecond to nanosecond
+        ..link_shape
+    },
+};
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("handshake-latency-limit", |client: Client| {
+        link_sweep::run(client, &SWEEP)
+    });
+}
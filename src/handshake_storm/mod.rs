@@ -0,0 +1,296 @@
+mod params;
+
+use crate::config::build_config;
+use crate::handshake_storm::params::Params;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, listen_config, percentile,
+    publish_and_collect, publish_and_collect_on, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::{Discv5, Enr};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_ESTABLISH_EXISTING_SESSIONS: &str =
+    "state_completed_to_establish_existing_sessions";
+const STATE_STORM: &str = "state_storm";
+const STATE_COMPLETED_STORM: &str = "state_completed_storm";
+const TOPIC_STORM_RESULTS: &str = "storm_results";
+
+// The roles of the participants, assigned by seq.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum Role {
+    // The node the others start their handshakes with.
+    Target,
+    // Starts a handshake with the target all at once with the other initiators.
+    Initiator,
+    // Has established a session with the target before the storm.
+    Existing,
+}
+
+impl Role {
+    fn of(seq: u64, storm_size: u64) -> Role {
+        match seq {
+            1 => Role::Target,
+            seq if seq <= 1 + storm_size => Role::Initiator,
+            _ => Role::Existing,
+        }
+    }
+}
+
+// The result of the storm which each instance shares with the target.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct StormResult {
+    seq: u64,
+    role: Role,
+    // Whether the session with the target has been established (initiators), or is still usable
+    // after the storm (existing nodes).
+    completed: bool,
+    elapsed_ms: u64,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("handshake-storm", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(
+        &run_parameters.test_instance_params,
+        run_parameters.test_instance_count,
+    )?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5: Discv5 =
+        Discv5::new(enr, enr_key, build_config(listen_config, &run_parameters)?)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, discv5.local_enr())?;
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // NOTE: #1 is the target. The next `storm_size` instances initiate sessions to the target
+    // simultaneously, and the rest have established sessions with the target before the storm.
+    let target = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Target")
+        .enr
+        .clone();
+    let role = Role::of(instance_info.seq, params.storm_size);
+    info!("role: {role:?}");
+
+    // //////////////////////////////////////////////////////////////
+    // Establish the existing sessions
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if role == Role::Existing {
+        match ping(&discv5, &target).await {
+            Ok(elapsed) => record_ping(&client, instance_info.seq, "before", elapsed).await,
+            Err(e) => {
+                error!("Failed to establish a session with the target: {e}");
+                failed = true;
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_ESTABLISH_EXISTING_SESSIONS,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    let sessions_before = discv5.metrics().active_sessions;
+
+    // //////////////////////////////////////////////////////////////
+    // Handshake storm
+    // //////////////////////////////////////////////////////////////
+    // All the initiators are released by the same barrier.
+    barrier_with_timeout(
+        &client,
+        STATE_STORM,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    let mut result = StormResult {
+        seq: instance_info.seq,
+        role,
+        completed: false,
+        elapsed_ms: 0,
+    };
+
+    if role == Role::Initiator {
+        match ping(&discv5, &target).await {
+            Ok(elapsed) => {
+                result.completed = true;
+                result.elapsed_ms = elapsed.as_millis() as u64;
+            }
+            Err(e) => warn!("Failed to establish a session with the target: {e}"),
+        }
+        info!(
+            "completed: {}, elapsed: {}ms",
+            result.completed, result.elapsed_ms
+        );
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_STORM,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // The existing sessions are expected to stay usable after the storm. If the target has evicted
+    // a session, e.g. due to the `session_cache_capacity`, the PING costs another handshake, which
+    // shows up as the latency.
+    if role == Role::Existing {
+        match ping(&discv5, &target).await {
+            Ok(elapsed) => {
+                result.completed = true;
+                result.elapsed_ms = elapsed.as_millis() as u64;
+                record_ping(&client, instance_info.seq, "after", elapsed).await;
+            }
+            Err(e) => {
+                error!("Failed to send PING to the target after the storm: {e}");
+                failed = true;
+            }
+        }
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Aggregate the results on the target
+    // //////////////////////////////////////////////////////////////
+    let results = publish_and_collect_on(&client, TOPIC_STORM_RESULTS, result).await?;
+
+    if role == Role::Target {
+        let initiators = results
+            .iter()
+            .filter(|r| r.role == Role::Initiator)
+            .collect::<Vec<_>>();
+        let mut latencies = initiators
+            .iter()
+            .filter(|r| r.completed)
+            .map(|r| r.elapsed_ms)
+            .collect::<Vec<_>>();
+        latencies.sort_unstable();
+        let completion_rate = latencies.len() as f64 / initiators.len() as f64;
+        let survived = results
+            .iter()
+            .filter(|r| r.role == Role::Existing && r.completed)
+            .count();
+        let sessions_after = discv5.metrics().active_sessions;
+
+        client.record_message(format!(
+            "{} of {} handshakes have completed ({completion_rate:.3}), p50: {:?}ms, p99: {:?}ms, active sessions: {sessions_before} -> {sessions_after}",
+            latencies.len(),
+            initiators.len(),
+            percentile(&latencies, 50),
+            percentile(&latencies, 99)
+        ));
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let mut write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("initiators", initiators.len() as u64)
+        .add_field("completed_handshakes", latencies.len() as u64)
+        .add_field("completion_rate", completion_rate)
+        .add_field("survived_sessions", survived as u64)
+        .add_field("active_sessions_before", sessions_before as u64)
+        .add_field("active_sessions_after", sessions_after as u64)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("storm_size", params.storm_size)
+        .add_tag("source", "summary");
+        for p in [50, 90, 99] {
+            if let Some(latency) = percentile(&latencies, p) {
+                write_query = write_query.add_field(format!("p{p}_ms"), latency);
+            }
+        }
+        record_metric(&client, write_query).await;
+
+        if completion_rate < params.min_completion_rate {
+            error!(
+                "The completion rate {completion_rate:.3} is below {}.",
+                params.min_completion_rate
+            );
+            failed = true;
+        }
+    }
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Send a PING to the target, which establishes a session with it first if there is none.
+async fn ping(discv5: &Discv5, target: &Enr) -> Result<Duration, String> {
+    let started_at = Instant::now();
+    discv5
+        .send_ping(target.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(started_at.elapsed())
+}
+
+async fn record_ping(client: &Client, seq: u64, storm: &str, elapsed: Duration) {
+    let run_parameters = client.run_parameters();
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("ping_ms", elapsed.as_millis() as u64)
+    .add_tag("instance_seq", seq)
+    .add_tag("storm", storm.to_owned());
+    record_metric(client, write_query).await;
+}
@@ -0,0 +1,30 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub storm_size: u64,
+    pub min_completion_rate: f64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+        instance_count: u64,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        let params = Params {
+            storm_size: get_param_or::<u64>("storm_size", instance_params, 8)?,
+            min_completion_rate: get_param_or::<f64>("min_completion_rate", instance_params, 0.0)?,
+        };
+
+        // #1 is the target, so it can't be an initiator.
+        if params.storm_size == 0 || params.storm_size >= instance_count {
+            return Err(format!(
+                "`storm_size` must be between 1 and {}, the number of instances except the target.",
+                instance_count - 1
+            )
+            .into());
+        }
+
+        Ok(params)
+    }
+}
@@ -0,0 +1,251 @@
+use crate::config::build_config;
+use crate::rpc_stats::RpcStats;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, get_param,
+    link_shape_with, listen_config, publish_and_collect, reconfigure_network, record_metric,
+    InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Key};
+use std::collections::HashSet;
+use testground::client::Client;
+use testground::network_conf::LinkShape;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+/// An impairment of the links, e.g. the duplication of the packets, under which the others send a
+/// FINDNODE request to node #1 for all the distances it knows, so that the NODES response is
+/// split into multiple packets. Node #1 knows all the other participants.
+///
+/// The requesters must receive the full set of nodes without duplicates, and the table and the
+/// number of sessions of node #1 must show no signs of double-processing.
+pub(crate) struct Impairment {
+    /// The params of the rate and the correlation of the impairment, e.g. `duplicate` and
+    /// `duplicate_corr`.
+    pub(crate) params: (&'static str, &'static str),
+    /// The state signalled once the links have been impaired.
+    pub(crate) state: &'static str,
+    /// Apply the rate and the correlation to the default link shape.
+    pub(crate) apply: fn(LinkShape, f32, f32) -> LinkShape,
+}
+
+pub(crate) async fn run(
+    client: Client,
+    impairment: &Impairment,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let instance_params = &run_parameters.test_instance_params;
+    let rate = get_param::<f32>(impairment.params.0, instance_params)?;
+    let corr = get_param::<f32>(impairment.params.1, instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5: Discv5 = Discv5::new(
+        enr.clone(),
+        enr_key,
+        build_config(listen_config, &run_parameters)?,
+    )?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the responder. It knows all the other participants, so that its NODES response
+    // is large enough to be split into multiple packets.
+    let responder = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .ok_or("No participant with seq 1 found.")?
+        .clone();
+
+    if instance_info.seq == responder.seq {
+        for p in participants.iter().filter(|p| p.seq != responder.seq) {
+            discv5.add_enr(p.enr.clone())?;
+        }
+    }
+
+    // Impair the links.
+    reconfigure_network(
+        &client,
+        link_shape_with(instance_params, |link_shape| {
+            (impairment.apply)(link_shape, rate, corr)
+        })?,
+        impairment.state.to_owned(),
+    )
+    .await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run FINDNODE request for all the distances the responder knows
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if instance_info.seq != responder.seq {
+        let responder_key: Key<NodeId> = responder.enr.node_id().into();
+
+        // The requester itself is excluded since the responder never returns the requester's own
+        // ENR.
+        let expected = participants
+            .iter()
+            .filter(|p| p.seq != responder.seq && p.seq != instance_info.seq)
+            .map(|p| p.enr.node_id())
+            .collect::<HashSet<_>>();
+        let mut distances = expected
+            .iter()
+            .filter_map(|node_id| responder_key.log2_distance(&Key::from(*node_id)))
+            .collect::<Vec<_>>();
+        distances.sort_unstable();
+        distances.dedup();
+
+        match rpc_stats
+            .find_node_designated_peer(&discv5, responder.enr.clone(), distances)
+            .await
+        {
+            Ok(enrs) => {
+                // If a NODES packet were processed twice, the same ENR would appear more than once
+                // in the response.
+                let received = enrs.iter().map(|enr| enr.node_id()).collect::<HashSet<_>>();
+                let duplicated = enrs.len() - received.len();
+                if duplicated > 0 {
+                    error!("The response contains {duplicated} duplicated ENRs.");
+                    failed = true;
+                }
+                let complete = received == expected;
+                if complete {
+                    info!(
+                        "Reconstructed the full set of nodes. nodes: {}",
+                        received.len()
+                    );
+                } else {
+                    error!(
+                        "Failed to reconstruct the full set of nodes. missing: {:?}, unexpected: {:?}",
+                        expected.difference(&received).collect::<Vec<_>>(),
+                        received.difference(&expected).collect::<Vec<_>>()
+                    );
+                    failed = true;
+                }
+
+                // //////////////////////////////////////////////////////////////
+                // Record metrics
+                // //////////////////////////////////////////////////////////////
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("expected", expected.len() as u64)
+                .add_field("received", enrs.len() as u64)
+                .add_field("duplicated", duplicated as u64)
+                .add_field("complete", complete)
+                .add_tag("instance_seq", instance_info.seq);
+                record_metric(&client, write_query).await;
+            }
+            Err(e) => {
+                error!("Failed to run FINDNODE request: {e}");
+                failed = true;
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Check the state of the responder
+    // //////////////////////////////////////////////////////////////
+    if instance_info.seq == responder.seq {
+        // Each requester should be in the table exactly once and have exactly one session.
+        let peers = participants.len() - 1;
+        let table_entries = discv5.table_entries_id().len();
+        let active_sessions = discv5.metrics().active_sessions;
+        info!("table_entries: {table_entries}, active_sessions: {active_sessions}");
+
+        if table_entries != peers {
+            error!("Unexpected table entries. expected: {peers}, actual: {table_entries}");
+            failed = true;
+        }
+        if active_sessions > peers {
+            error!("Unexpected active sessions. expected: {peers}, actual: {active_sessions}");
+            failed = true;
+        }
+
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("table_entries", table_entries as u64)
+        .add_field("active_sessions", active_sessions as u64)
+        .add_tag("instance_seq", instance_info.seq);
+        record_metric(&client, write_query).await;
+    }
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
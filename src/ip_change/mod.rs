@@ -1,70 +1,65 @@
 mod params;
 
+use crate::config::config_builder;
 use crate::ip_change::params::Params;
-use crate::utils::publish_and_collect;
-use discv5::enr::CombinedKey;
-use discv5::{Discv5, Enr, ListenConfig};
-use serde::{Deserialize, Serialize};
-use std::net::{IpAddr, Ipv4Addr};
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, data_network, enr_addresses,
+    link_shape, listen_config, publish_and_collect, InstanceInfo,
+};
+use discv5::Discv5;
+use std::net::IpAddr;
 use std::time::Duration;
 use testground::client::Client;
-use testground::network_conf::{
-    FilterAction, LinkShape, NetworkConfiguration, RoutingPolicyType, DEFAULT_DATA_NETWORK,
-};
+use testground::network_conf::{NetworkConfiguration, RoutingPolicyType};
 
 const STATE_COMPLETED_TO_CONNECT: &str = "state_completed_to_connect";
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-struct InstanceInfo {
-    // The sequence number of this test instance within the test.
-    seq: u64,
-    enr: Enr,
+pub(super) fn register(registry: &mut Registry) {
+    // IPv4-only, since the new IP address is an unused IPv4 address of the test subnet.
+    registry.register_ipv4_only("ip-change", run);
 }
 
 pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
     let run_parameters = client.run_parameters();
     let ip = run_parameters
         .data_network_ip()?
-        .expect("IP address for the data network");
+        .ok_or("No IP address for the data network.")?;
     let params = Params::new(&run_parameters.test_instance_params)?;
 
     // ////////////////////////
     // Construct local Enr
     // ////////////////////////
-    let enr_key = CombinedKey::generate_secp256k1();
-    let enr = Enr::builder()
-        .ip(ip)
-        .udp4(9000)
-        .build(&enr_key)
-        .expect("enr");
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
 
     // ////////////////////////
     // Start discv5
     // ////////////////////////
-    let listen_config = ListenConfig::Ipv4 {
-        ip: Ipv4Addr::UNSPECIFIED,
-        port: 9000,
-    };
-    let config = discv5::ConfigBuilder::new(listen_config)
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
         .vote_duration(Duration::from_secs(params.vote_duration))
         .ping_interval(Duration::from_secs(params.ping_interval))
         .enr_peer_update_min(run_parameters.test_instance_count as usize - 1)
         .build();
     let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
-    discv5.start().await.expect("Start Discovery v5 server");
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
 
     // //////////////////////////////////////////////////////////////
     // Collect information of all participants in the test case
     // //////////////////////////////////////////////////////////////
-    let instance_info = InstanceInfo {
-        seq: client.global_seq(),
-        enr,
-    };
+    let instance_info = InstanceInfo::new(&client, enr)?;
     client.record_message(format!(
         "seq: {}, node_id: {}, ip: {}",
         instance_info.seq,
         instance_info.enr.node_id(),
-        ip
+        enr_addresses(&instance_info.enr)
     ));
 
     let participants = publish_and_collect(&client, instance_info.clone()).await?;
@@ -78,18 +73,19 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
             .iter()
             .filter(|&p| p.seq != client.global_seq())
         {
-            let _ = discv5
-                .find_node_designated_peer(p.enr.clone(), vec![0])
+            let _ = rpc_stats
+                .find_node_designated_peer(&discv5, p.enr.clone(), vec![0])
                 .await;
         }
     }
 
-    client
-        .signal_and_wait(
-            STATE_COMPLETED_TO_CONNECT,
-            run_parameters.test_instance_count,
-        )
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_CONNECT,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
 
     client.record_message(format!(
         "peers: {:?}",
@@ -97,7 +93,7 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
             .kbuckets()
             .iter()
             .map(|b| (
-                b.node.value.ip4().unwrap(),
+                enr_addresses(&b.node.value),
                 b.status.direction,
                 b.status.state
             ))
@@ -123,6 +119,15 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
         println!("debug: {:?}", discv5.table_entries());
     }
 
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
     client.record_success().await?;
     Ok(())
 }
@@ -133,7 +138,7 @@ async fn change_ip(
 ) -> Result<IpAddr, Box<dyn std::error::Error>> {
     let participants_ip4 = participants
         .iter()
-        .map(|p| p.enr.ip4().expect("ip4"))
+        .filter_map(|p| p.enr.ip4())
         .collect::<Vec<_>>();
 
     let subnet = client.run_parameters().test_subnet;
@@ -156,29 +161,11 @@ async fn change_ip(
 
     client
         .configure_network(NetworkConfiguration {
-            network: DEFAULT_DATA_NETWORK.to_owned(),
+            network: data_network(&client.run_parameters().test_instance_params)?,
             ipv4: Some(format!("{}/{}", new_ip, subnet.prefix()).parse().unwrap()),
             ipv6: None,
             enable: true,
-            default: LinkShape {
-                latency: client
-                    .run_parameters()
-                    .test_instance_params
-                    .get("latency")
-                    .ok_or("latency is not specified")?
-                    .parse::<u64>()?
-                    * 1_000_000, // Translate from millisecond to nanosecond
-                jitter: 0,
-                bandwidth: 1048576, // 1Mib
-                filter: FilterAction::Accept,
-                loss: 0.0,
-                corrupt: 0.0,
-                corrupt_corr: 0.0,
-                reorder: 0.0,
-                reorder_corr: 0.0,
-                duplicate: 0.0,
-                duplicate_corr: 0.0,
-            },
+            default: link_shape(&client.run_parameters().test_instance_params)?,
             rules: None,
             callback_state: "ip_change".to_owned(),
             callback_target: Some(1),
@@ -0,0 +1,235 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::join_storm::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, listen_config,
+    publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::Discv5;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_JOIN_IN_ADVANCE: &str = "state_completed_to_join_in_advance";
+const STATE_STORM: &str = "state_storm";
+const STATE_COMPLETED_TO_OBSERVE: &str = "state_completed_to_observe";
+
+// The request rate of the bootstrap node regarded as settled.
+const SETTLED_REQUESTS_PER_SECOND: f64 = 1.0;
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("join-storm", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Construct discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // NOTE: #1 is the bootstrap node. The first `storm_fraction` of the other nodes join
+    // simultaneously, and the rest join in advance.
+    let bootstrap_node = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Bootstrap node")
+        .clone();
+    let storm_joiners =
+        ((run_parameters.test_instance_count - 1) as f64 * params.storm_fraction).ceil() as u64;
+    let is_storm_joiner =
+        instance_info.seq != bootstrap_node.seq && instance_info.seq <= 1 + storm_joiners;
+    info!("storm joiners: {storm_joiners}, is_storm_joiner: {is_storm_joiner}");
+
+    // //////////////////////////////////////////////////////////////
+    // Join in advance
+    // //////////////////////////////////////////////////////////////
+    if !is_storm_joiner {
+        discv5
+            .start()
+            .await
+            .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+        let rpc_stats = RpcStats::default();
+        if instance_info.seq != bootstrap_node.seq {
+            discv5.add_enr(bootstrap_node.enr.clone())?;
+            if let Err(e) = rpc_stats.find_node(&discv5, NodeId::random()).await {
+                error!("Failed to run FINDNODE query: {e}");
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_JOIN_IN_ADVANCE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Join storm
+    // //////////////////////////////////////////////////////////////
+    // All the storm joiners are released by the same barrier.
+    barrier_with_timeout(
+        &client,
+        STATE_STORM,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+    let storm_started_at = Instant::now();
+
+    let mut failed = false;
+
+    if is_storm_joiner {
+        discv5
+            .start()
+            .await
+            .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+        discv5.add_enr(bootstrap_node.enr.clone())?;
+
+        let (joined, discovered) = match rpc_stats.find_node(&discv5, NodeId::random()).await {
+            Ok(enrs) => (true, enrs.len()),
+            Err(e) => {
+                error!("Failed to run FINDNODE query: {e}");
+                (false, 0)
+            }
+        };
+        let elapsed = storm_started_at.elapsed();
+        info!(
+            "joined: {joined}, discovered: {discovered}, elapsed: {}ms",
+            elapsed.as_millis()
+        );
+        if !joined {
+            failed = true;
+        }
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("joined", joined)
+        .add_field("discovered", discovered as u64)
+        .add_field("join_latency_ms", elapsed.as_millis() as u64)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("role", "storm_joiner");
+        record_metric(&client, write_query).await;
+    } else if instance_info.seq == bootstrap_node.seq {
+        // Observe the requests served by the bootstrap node until the storm settles.
+        let mut served_requests = 0_f64;
+        let mut settled_at = Duration::ZERO;
+        let mut peak_rate = 0_f64;
+        let sample_interval = Duration::from_millis(500);
+
+        while storm_started_at.elapsed() < Duration::from_secs(params.observation_duration) {
+            tokio::time::sleep(sample_interval).await;
+            let metrics = discv5.metrics();
+            let rate = metrics.unsolicited_requests_per_second;
+            served_requests += rate * sample_interval.as_secs_f64();
+            peak_rate = peak_rate.max(rate);
+            if rate >= SETTLED_REQUESTS_PER_SECOND {
+                settled_at = storm_started_at.elapsed();
+            }
+
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("elapsed_ms", storm_started_at.elapsed().as_millis() as u64)
+            .add_field("requests_per_second", rate)
+            .add_field("active_sessions", metrics.active_sessions as u64)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("role", "bootstrap");
+            record_metric(&client, write_query).await;
+        }
+
+        client.record_message(format!(
+            "served requests (estimated): {}, peak rate: {peak_rate}/s, settled after: {}ms",
+            served_requests.round(),
+            settled_at.as_millis()
+        ));
+
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("served_requests", served_requests.round() as u64)
+        .add_field("peak_requests_per_second", peak_rate)
+        .add_field("settle_time_ms", settled_at.as_millis() as u64)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("role", "bootstrap");
+        record_metric(&client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_OBSERVE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
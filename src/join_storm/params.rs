@@ -0,0 +1,18 @@
+use crate::utils::get_param;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub storm_fraction: f64,
+    pub observation_duration: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            storm_fraction: get_param::<f64>("storm_fraction", instance_params)?,
+            observation_duration: get_param::<u64>("observation_duration", instance_params)?,
+        })
+    }
+}
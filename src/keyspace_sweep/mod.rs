@@ -0,0 +1,217 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::keyspace_sweep::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, listen_config,
+    publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Key};
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_POPULATE_TABLES: &str = "state_completed_to_populate_tables";
+const STATE_COMPLETED_TO_SWEEP: &str = "state_completed_to_sweep";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("keyspace-sweep", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let mut participants = publish_and_collect(&client, instance_info.clone()).await?;
+    participants.sort_by_key(|p| p.seq);
+
+    // //////////////////////////////////////////////////////////////
+    // Ring topology
+    // //////////////////////////////////////////////////////////////
+    // Each node knows the next `neighbors` nodes in order of the seq, and then runs a query to
+    // populate its routing table.
+    let position = participants
+        .iter()
+        .position(|p| p.seq == instance_info.seq)
+        .expect("Self");
+    for i in 1..=params.neighbors.min(participants.len() - 1) {
+        let neighbor = &participants[(position + i) % participants.len()];
+        discv5.add_enr(neighbor.enr.clone())?;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    if let Err(e) = rpc_stats
+        .find_node(&discv5, instance_info.enr.node_id())
+        .await
+    {
+        error!("Failed to run FINDNODE query: {e}");
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_POPULATE_TABLES,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Sweep the keyspace
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the only node which runs the lookups.
+    let mut failed = false;
+
+    if instance_info.seq == 1 {
+        let others = participants
+            .iter()
+            .filter(|p| p.seq != instance_info.seq)
+            .map(|p| p.enr.node_id())
+            .collect::<Vec<_>>();
+        let mut succeeded = 0;
+
+        for region in 0..params.regions {
+            let target = region_target(region, params.regions);
+            let target_key: Key<NodeId> = target.into();
+
+            // The lookup succeeds if it finds the participant closest to the target.
+            let closest = others
+                .iter()
+                .min_by_key(|node_id| target_key.distance(&Key::from(**node_id)))
+                .expect("Other participants");
+
+            let started_at = Instant::now();
+            let result = tokio::time::timeout(
+                Duration::from_secs(params.lookup_timeout),
+                rpc_stats.find_node(&discv5, target),
+            )
+            .await;
+            let elapsed = started_at.elapsed();
+
+            let success = match result {
+                Ok(Ok(enrs)) => enrs.iter().any(|enr| enr.node_id() == *closest),
+                Ok(Err(e)) => {
+                    error!("Failed to run FINDNODE query: {e}");
+                    false
+                }
+                Err(_) => {
+                    error!(
+                        "The query hasn't completed within {} seconds.",
+                        params.lookup_timeout
+                    );
+                    false
+                }
+            };
+            if success {
+                succeeded += 1;
+            }
+            info!(
+                "region: {region}, target: {target}, success: {success}, elapsed: {}ms",
+                elapsed.as_millis()
+            );
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("success", success)
+            .add_field("elapsed_ms", elapsed.as_millis() as u64)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("region", format!("{:02x}", target.raw()[0]));
+            record_metric(&client, write_query).await;
+        }
+
+        info!("succeeded: {succeeded}/{}", params.regions);
+        if succeeded == 0 {
+            error!("No lookup has succeeded.");
+            failed = true;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_SWEEP,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Returns a target whose high bits are at the start of the `region`-th of `regions` evenly spread
+// regions of the keyspace. The rest of the bits are random.
+fn region_target(region: u64, regions: u64) -> NodeId {
+    let high_bits = (u64::MAX / regions.max(1)).saturating_mul(region);
+    let mut raw = NodeId::random().raw();
+    raw[..8].copy_from_slice(&high_bits.to_be_bytes());
+    NodeId::new(&raw)
+}
@@ -0,0 +1,20 @@
+use crate::utils::get_param;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub regions: u64,
+    pub neighbors: usize,
+    pub lookup_timeout: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            regions: get_param::<u64>("regions", instance_params)?,
+            neighbors: get_param::<usize>("neighbors", instance_params)?,
+            lookup_timeout: get_param::<u64>("lookup_timeout", instance_params)?,
+        })
+    }
+}
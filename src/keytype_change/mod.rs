@@ -0,0 +1,217 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr_with_key, check_enr_port, enr_addresses,
+    enr_key_type, ip_version, listen_config, publish_and_collect, record_metric, seed,
+    InstanceInfo, KeyType,
+};
+use chrono::Local;
+use discv5::enr::{CombinedKey, CombinedPublicKey};
+use discv5::Discv5;
+use std::time::Duration;
+use testground::client::Client;
+use testground::{RunParameters, WriteQuery};
+use tracing::error;
+
+const STATE_COMPLETED_TO_CONNECT: &str = "state_completed_to_connect";
+const STATE_COMPLETED_TO_CHANGE_KEY_TYPE: &str = "state_completed_to_change_key_type";
+const STATE_FINISHED: &str = "state_finished";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("keytype-change", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    // NOTE: The ENR starts as secp256k1 regardless of the `key_type` param, since the key type is
+    // changed to ed25519 later.
+    let mut discv5 = start_discv5(
+        &run_parameters,
+        client.global_seq(),
+        KeyType::Secp256k1.generate(seed(&run_parameters)?, client.global_seq()),
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, discv5.local_enr())?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+    let rpc_stats = RpcStats::default();
+
+    // NOTE: #1 is the node that changes its key type.
+    let changer = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("changer")
+        .clone();
+
+    // //////////////////////////////////////////////////////////////
+    // Establish sessions with the secp256k1 ENR
+    // //////////////////////////////////////////////////////////////
+    if instance_info.seq == changer.seq {
+        connect(&discv5, &rpc_stats, &participants, instance_info.seq).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_CONNECT,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Restart with an ed25519 ENR on the same socket address
+    // //////////////////////////////////////////////////////////////
+    if instance_info.seq == changer.seq {
+        discv5.shutdown();
+        // Give the previous server time to release the socket.
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        discv5 = start_discv5(
+            &run_parameters,
+            instance_info.seq,
+            CombinedKey::generate_ed25519(),
+        )
+        .await?;
+        client.record_message(format!(
+            "Restarted with an ed25519 ENR. node_id: {}",
+            discv5.local_enr().node_id()
+        ));
+        connect(&discv5, &rpc_stats, &participants, instance_info.seq).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_CHANGE_KEY_TYPE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Check how the peers see the changer
+    // //////////////////////////////////////////////////////////////
+    if instance_info.seq != changer.seq {
+        let entries = discv5
+            .table_entries_enr()
+            .into_iter()
+            .filter(|enr| enr.udp4_socket() == changer.enr.udp4_socket())
+            .collect::<Vec<_>>();
+        let old_entry = entries
+            .iter()
+            .any(|enr| enr.node_id() == changer.enr.node_id());
+        let new_entry = entries
+            .iter()
+            .any(|enr| matches!(enr.public_key(), CombinedPublicKey::Ed25519(_)));
+
+        let outcome = match (old_entry, new_entry) {
+            (false, true) => "replaced",
+            (true, false) => "rejected",
+            (true, true) => "both",
+            (false, false) => "none",
+        };
+        client.record_message(format!(
+            "outcome: {outcome}, entries at the changer's socket: {:?}",
+            entries
+                .iter()
+                .map(|enr| (enr.node_id(), enr_key_type(enr)))
+                .collect::<Vec<_>>()
+        ));
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("old_entry", old_entry)
+        .add_field("new_entry", new_entry)
+        .add_field("active_sessions", discv5.metrics().active_sessions as u64)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("outcome", outcome);
+        record_metric(&client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_FINISHED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    client.record_success().await?;
+    Ok(())
+}
+
+async fn start_discv5(
+    run_parameters: &RunParameters,
+    seq: u64,
+    enr_key: CombinedKey,
+) -> Result<Discv5, Box<dyn std::error::Error>> {
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let enr = build_enr_with_key(
+        run_parameters,
+        seq,
+        ip_version(&run_parameters.test_instance_params)?,
+        &enr_key,
+    )?;
+
+    let listen_config = listen_config(run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr, enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    Ok(discv5)
+}
+
+// Run FINDNODE query to connect to other participants.
+async fn connect(
+    discv5: &Discv5,
+    rpc_stats: &RpcStats,
+    participants: &[InstanceInfo],
+    local_seq: u64,
+) {
+    for p in participants.iter().filter(|&p| p.seq != local_seq) {
+        if let Err(e) = rpc_stats
+            .find_node_designated_peer(discv5, p.enr.clone(), vec![0])
+            .await
+        {
+            error!("Failed to run FIND_NODE query: {e}");
+        }
+    }
+}
@@ -0,0 +1,291 @@
+mod params;
+
+use crate::large_scale::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::topology::{join_topology, Topology};
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, percentile, publish_and_collect_on, record_metric,
+    InstanceInfo,
+};
+use crate::virtual_nodes::{collect_virtual_nodes, start_virtual_nodes, VirtualNode};
+use chrono::Local;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tokio::time::timeout;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_LOOKUPS: &str = "state_completed_to_run_lookups";
+const TOPIC_NODE_RESULTS: &str = "node_results";
+
+// The result of a virtual node, which #1 aggregates across all the nodes in the test.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct NodeResult {
+    // The virtual seq of the node.
+    seq: u64,
+    lookups: u64,
+    found: u64,
+    connected_peers: u64,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("large-scale", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Start the virtual nodes
+    // ////////////////////////
+    let nodes = start_virtual_nodes(&client).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all the nodes in the test case
+    // //////////////////////////////////////////////////////////////
+    let all_nodes = collect_virtual_nodes(&client, &nodes).await?;
+    client.record_message(format!(
+        "seq: {}, nodes: {:?}, all nodes: {}",
+        client.global_seq(),
+        nodes.iter().map(|n| n.info.seq).collect::<Vec<_>>(),
+        all_nodes.len()
+    ));
+    let others = nodes
+        .iter()
+        .map(|n| {
+            all_nodes
+                .iter()
+                .filter(|i| i.seq != n.info.seq)
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // //////////////////////////////////////////////////////////////
+    // Star topology
+    // //////////////////////////////////////////////////////////////
+    // Each virtual node keeps its own stats, so they are recorded under the seq of the node.
+    let rpc_stats = nodes
+        .iter()
+        .map(|_| RpcStats::default())
+        .collect::<Vec<_>>();
+    let joins =
+        nodes
+            .iter()
+            .zip(others.iter())
+            .zip(rpc_stats.iter())
+            .map(|((n, others), rpc_stats)| {
+                join_topology(&n.discv5, rpc_stats, Topology::Star, &n.info, others)
+            });
+    for result in futures::future::join_all(joins).await {
+        result?;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run lookups on every virtual node
+    // //////////////////////////////////////////////////////////////
+    let results =
+        futures::future::join_all(
+            nodes.iter().zip(rpc_stats.iter()).zip(others.iter()).map(
+                |((n, rpc_stats), others)| run_lookups(&client, &params, n, rpc_stats, others),
+            ),
+        )
+        .await;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_LOOKUPS,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Aggregate the results of all the nodes
+    // //////////////////////////////////////////////////////////////
+    let all_results = publish_and_collect_on(&client, TOPIC_NODE_RESULTS, results)
+        .await?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    let mut failed = false;
+
+    // NOTE: #1 is the designated instance which aggregates the results.
+    if client.global_seq() == 1 {
+        let lookups = all_results.iter().map(|r| r.lookups).sum::<u64>();
+        let found = all_results.iter().map(|r| r.found).sum::<u64>();
+        let success_rate = if lookups == 0 {
+            0.0
+        } else {
+            found as f64 / lookups as f64
+        };
+        let mut connected_peers = all_results
+            .iter()
+            .map(|r| r.connected_peers)
+            .collect::<Vec<_>>();
+        connected_peers.sort_unstable();
+        client.record_message(format!(
+            "nodes: {}, found: {found}/{lookups} ({success_rate:.3}), connected peers: min: {:?}, p50: {:?}",
+            all_results.len(),
+            connected_peers.first(),
+            percentile(&connected_peers, 50),
+        ));
+
+        let mut write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("nodes", all_results.len() as u64)
+        .add_field("lookups", lookups)
+        .add_field("found", found)
+        .add_field("success_rate", success_rate)
+        .add_tag("instance_seq", client.global_seq())
+        .add_tag("source", "aggregate");
+        if let (Some(min), Some(median)) =
+            (connected_peers.first(), percentile(&connected_peers, 50))
+        {
+            write_query = write_query
+                .add_field("connected_peers_min", *min)
+                .add_field("connected_peers_p50", median);
+        }
+        record_metric(&client, write_query).await;
+
+        if success_rate < params.min_success_rate {
+            error!(
+                "The success rate {success_rate:.3} is below {}.",
+                params.min_success_rate
+            );
+            failed = true;
+        }
+    }
+
+    for (n, rpc_stats) in nodes.iter().zip(rpc_stats.iter()) {
+        rpc_stats.finish(&client, n.info.seq).await;
+    }
+
+    // The snapshot is taken of the first virtual node, as there's a single one in the outputs of the
+    // instance. The other nodes are shut down as they are.
+    let mut nodes = nodes.into_iter();
+    let first = nodes.next().expect("at least one virtual node");
+    for mut n in nodes {
+        n.discv5.shutdown();
+    }
+    teardown(
+        &client,
+        first.discv5,
+        first.info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Look up `lookups` nodes chosen at random out of `others`, and record the metrics of `node`.
+async fn run_lookups(
+    client: &Client,
+    params: &Params,
+    node: &VirtualNode,
+    rpc_stats: &RpcStats,
+    others: &[InstanceInfo],
+) -> NodeResult {
+    let run_parameters = client.run_parameters();
+    let metric = |source: &str| {
+        WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_tag("instance_seq", client.global_seq())
+        .add_tag("node_seq", node.info.seq)
+        .add_tag("source", source.to_owned())
+    };
+
+    let mut found = 0;
+    for _ in 0..params.lookups {
+        let Some(target) = others.choose(&mut rand::thread_rng()) else {
+            break;
+        };
+        let started_at = Instant::now();
+        let result = match timeout(
+            Duration::from_secs(params.lookup_timeout),
+            rpc_stats.find_node(&node.discv5, target.enr.node_id()),
+        )
+        .await
+        {
+            Ok(Ok(enrs)) => enrs.iter().any(|enr| enr.node_id() == target.enr.node_id()),
+            Ok(Err(e)) => {
+                warn!(
+                    "#{}: Failed to run FINDNODE query for #{}: {e}",
+                    node.info.seq, target.seq
+                );
+                false
+            }
+            Err(_) => {
+                warn!(
+                    "#{}: The FINDNODE query for #{} hasn't completed within {} seconds.",
+                    node.info.seq, target.seq, params.lookup_timeout
+                );
+                false
+            }
+        };
+        if result {
+            found += 1;
+        }
+
+        let write_query = metric("lookup")
+            .add_field("found", result)
+            .add_field("elapsed_ms", started_at.elapsed().as_millis() as u64)
+            .add_tag("target_seq", target.seq);
+        record_metric(client, write_query).await;
+    }
+
+    let connected_peers = node.discv5.connected_peers() as u64;
+    info!(
+        "#{}: found: {found}/{}, connected peers: {connected_peers}",
+        node.info.seq, params.lookups
+    );
+    let write_query = metric("node")
+        .add_field("lookups", params.lookups)
+        .add_field("found", found)
+        .add_field("connected_peers", connected_peers);
+    record_metric(client, write_query).await;
+
+    NodeResult {
+        seq: node.info.seq,
+        lookups: params.lookups,
+        found,
+        connected_peers,
+    }
+}
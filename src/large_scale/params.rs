@@ -0,0 +1,20 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub lookups: u64,
+    pub lookup_timeout: u64,
+    pub min_success_rate: f64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            lookups: get_param_or::<u64>("lookups", instance_params, 5)?,
+            lookup_timeout: get_param_or::<u64>("lookup_timeout", instance_params, 30)?,
+            min_success_rate: get_param_or::<f64>("min_success_rate", instance_params, 0.9)?,
+        })
+    }
+}
@@ -0,0 +1,326 @@
+mod params;
+
+use crate::config::build_config;
+use crate::latency_mix::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, link_shape, listen_config,
+    publish_and_collect, reconfigure_network, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::kbucket::ConnectionDirection;
+use discv5::Discv5;
+use rand::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_LOOKUPS: &str = "state_completed_to_run_lookups";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Participant {
+    // The testground group of the instance is the latency class it belongs to.
+    #[serde(flatten)]
+    instance: InstanceInfo,
+    // The latency of the links of this test instance, in milliseconds.
+    latency: u64,
+}
+
+// The peers of a lookup, broken down by the latency class.
+#[derive(Default)]
+struct Breakdown {
+    peers: BTreeMap<String, u64>,
+    latency_sum: u64,
+}
+
+impl Breakdown {
+    fn of<'a>(
+        node_ids: impl Iterator<Item = &'a NodeId>,
+        peers: &HashMap<NodeId, Participant>,
+    ) -> Self {
+        let mut breakdown = Breakdown::default();
+        for peer in node_ids.filter_map(|node_id| peers.get(node_id)) {
+            *breakdown
+                .peers
+                .entry(peer.instance.group_id.clone())
+                .or_default() += 1;
+            breakdown.latency_sum += peer.latency;
+        }
+        breakdown
+    }
+
+    fn count(&self) -> u64 {
+        self.peers.values().sum()
+    }
+
+    // The mean latency of the peers, or `None` if there is no peer.
+    fn mean_latency(&self) -> Option<f64> {
+        let count = self.count();
+        (count > 0).then(|| self.latency_sum as f64 / count as f64)
+    }
+
+    fn add_fields(&self, write_query: WriteQuery, prefix: &str) -> WriteQuery {
+        let mut write_query = write_query.add_field(prefix.to_owned(), self.count());
+        for (group_id, count) in self.peers.iter() {
+            write_query = write_query.add_field(format!("{prefix}_{group_id}"), *count);
+        }
+        if let Some(mean_latency) = self.mean_latency() {
+            write_query = write_query.add_field(format!("{prefix}_latency_ms"), mean_latency);
+        }
+        write_query
+    }
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("latency-mix", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5: Discv5 = Discv5::new(
+        enr.clone(),
+        enr_key,
+        build_config(listen_config, &run_parameters)?,
+    )?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = Participant {
+        instance: InstanceInfo::new(&client, enr)?,
+        latency: params.latency,
+    };
+    client.record_message(format!(
+        "seq: {}, node_id: {}, group: {}, latency: {}ms",
+        instance_info.instance.seq,
+        instance_info.instance.enr.node_id(),
+        instance_info.instance.group_id,
+        instance_info.latency
+    ));
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+    let peers = participants
+        .iter()
+        .map(|p| (p.instance.enr.node_id(), p.clone()))
+        .collect::<HashMap<_, _>>();
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the bootstrap node, which knows all the other nodes, and the other nodes know
+    // only the bootstrap node. The links of each node are shaped with the latency of its group.
+    let bootstrap_node = participants
+        .iter()
+        .find(|p| p.instance.seq == 1)
+        .expect("Bootstrap node")
+        .clone();
+
+    if instance_info.instance.seq == bootstrap_node.instance.seq {
+        for p in participants
+            .iter()
+            .filter(|p| p.instance.seq != bootstrap_node.instance.seq)
+        {
+            discv5.add_enr(p.instance.enr.clone())?;
+        }
+    } else {
+        discv5.add_enr(bootstrap_node.instance.enr.clone())?;
+    }
+
+    reconfigure_network(
+        &client,
+        link_shape(&run_parameters.test_instance_params)?,
+        "state_network_configured".to_owned(),
+    )
+    .await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run the same lookups on every node
+    // //////////////////////////////////////////////////////////////
+    let mut succeeded = 0;
+    // The elapsed time and the mean latency of the returned peers of each successful lookup.
+    let mut samples = vec![];
+
+    for i in 0..params.lookups {
+        let target = random_target(params.target_seed.wrapping_add(i));
+        let queried_before = queried_peers(&discv5);
+
+        let started_at = Instant::now();
+        let result = timeout(
+            Duration::from_secs(params.lookup_timeout),
+            rpc_stats.find_node(&discv5, target),
+        )
+        .await;
+        let elapsed = started_at.elapsed();
+
+        let returned = match result {
+            Ok(Ok(enrs)) => Some(enrs.iter().map(|enr| enr.node_id()).collect::<Vec<_>>()),
+            Ok(Err(e)) => {
+                warn!("Failed to run FINDNODE query: {e}");
+                None
+            }
+            Err(_) => {
+                warn!(
+                    "The query hasn't completed within {} seconds.",
+                    params.lookup_timeout
+                );
+                None
+            }
+        };
+
+        // discv5 doesn't expose the peers a query has contacted, so the peers connected in the
+        // outgoing direction during the query are taken as the ones it has queried.
+        let queried = Breakdown::of(queried_peers(&discv5).difference(&queried_before), &peers);
+        let returned = returned.map(|returned| Breakdown::of(returned.iter(), &peers));
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("elapsed_ms", elapsed.as_millis() as u64)
+        .add_field("succeeded", returned.is_some())
+        .add_tag("instance_seq", instance_info.instance.seq)
+        .add_tag("group", instance_info.instance.group_id.clone())
+        .add_tag("target", target.to_string())
+        .add_tag("source", "lookup");
+        let mut write_query = queried.add_fields(write_query, "queried");
+        if let Some(returned) = returned {
+            succeeded += 1;
+            if let Some(mean_latency) = returned.mean_latency() {
+                samples.push((elapsed.as_millis() as f64, mean_latency));
+            }
+            write_query = returned.add_fields(write_query, "returned");
+        }
+        record_metric(&client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_LOOKUPS,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record the summary of the lookups
+    // //////////////////////////////////////////////////////////////
+    let queried = Breakdown::of(queried_peers(&discv5).iter(), &peers);
+    let correlation = correlation(&samples);
+    info!(
+        "succeeded: {succeeded}/{}, queried: {:?}, correlation: {correlation:?}",
+        params.lookups, queried.peers
+    );
+
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("lookups", params.lookups)
+    .add_field("succeeded", succeeded)
+    .add_tag("instance_seq", instance_info.instance.seq)
+    .add_tag("group", instance_info.instance.group_id.clone())
+    .add_tag("source", "summary");
+    let mut write_query = queried.add_fields(write_query, "queried");
+    // The correlation between the elapsed time of a lookup and the mean latency of the peers it
+    // has returned.
+    if let Some(correlation) = correlation {
+        write_query = write_query.add_field("latency_correlation", correlation);
+    }
+    record_metric(&client, write_query).await;
+
+    rpc_stats.finish(&client, instance_info.instance.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.instance.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // This test case measures the lookups, and doesn't fail on them.
+    client.record_success().await?;
+
+    Ok(())
+}
+
+// The peers connected in the outgoing direction, i.e. the ones this node has contacted.
+fn queried_peers(discv5: &Discv5) -> HashSet<NodeId> {
+    discv5
+        .kbuckets()
+        .iter()
+        .filter(|entry| {
+            entry.status.is_connected() && entry.status.direction == ConnectionDirection::Outgoing
+        })
+        .map(|entry| *entry.node.key.preimage())
+        .collect()
+}
+
+// The Pearson correlation coefficient of the samples, or `None` if it's undefined.
+fn correlation(samples: &[(f64, f64)]) -> Option<f64> {
+    if samples.len() < 2 {
+        return None;
+    }
+    let n = samples.len() as f64;
+    let (mean_x, mean_y) = samples
+        .iter()
+        .fold((0.0, 0.0), |(x, y), (sx, sy)| (x + sx / n, y + sy / n));
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in samples {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(cov / (var_x * var_y).sqrt())
+}
+
+// Generate a random node id from the given seed.
+fn random_target(seed: u64) -> NodeId {
+    let rng = &mut rand_xorshift::XorShiftRng::seed_from_u64(seed);
+    let mut raw = [0; 32];
+    rng.fill_bytes(&mut raw);
+    NodeId::new(&raw)
+}
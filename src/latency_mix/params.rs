@@ -0,0 +1,22 @@
+use crate::utils::{get_param, get_param_or};
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub latency: u64,
+    pub lookups: u64,
+    pub target_seed: u64,
+    pub lookup_timeout: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            latency: get_param::<u64>("latency", instance_params)?,
+            lookups: get_param_or::<u64>("lookups", instance_params, 10)?,
+            target_seed: get_param_or::<u64>("target_seed", instance_params, 0)?,
+            lookup_timeout: get_param_or::<u64>("lookup_timeout", instance_params, 30)?,
+        })
+    }
+}
@@ -0,0 +1,231 @@
+use crate::config::build_config;
+use crate::rpc_stats::RpcStats;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr_on_port, check_enr_port, get_param,
+    instance_key, ip_version, link_shape_with, publish_and_collect, reconfigure_network,
+    record_metric, udp_port, InstanceInfo, IpVersion,
+};
+use chrono::Local;
+use discv5::enr::CombinedKey;
+use discv5::{Discv5, Enr};
+use std::time::Duration;
+use testground::client::Client;
+use testground::network_conf::LinkShape;
+use testground::{RunParameters, WriteQuery};
+use tracing::{error, info};
+
+/// A sweep of a dimension of the link shape, e.g. the packet loss. For each level given by
+/// `levels_param`, node #1 restarts with a fresh key and tries to establish sessions with all the
+/// other nodes within the `connect_timeout` param, and the success rate is recorded per level.
+pub(crate) struct LinkSweep {
+    /// The param listing the comma-separated levels to sweep, e.g. `loss_levels`.
+    pub(crate) levels_param: &'static str,
+    /// The field the level is recorded as, e.g. `loss`.
+    pub(crate) field: &'static str,
+    /// The unit of the levels, for the messages.
+    pub(crate) unit: &'static str,
+    /// Apply a level to the default link shape.
+    pub(crate) apply: fn(LinkShape, f64) -> LinkShape,
+}
+
+struct Params {
+    levels: Vec<f64>,
+    connect_timeout: u64,
+}
+
+impl Params {
+    fn new(sweep: &LinkSweep, run_parameters: &RunParameters) -> Result<Params, String> {
+        let instance_params = &run_parameters.test_instance_params;
+        let levels = get_param::<String>(sweep.levels_param, instance_params)?
+            .split(',')
+            .map(|level| {
+                level
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("Failed to parse the level of {}: {level}", sweep.field))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Params {
+            levels,
+            connect_timeout: get_param::<u64>("connect_timeout", instance_params)?,
+        })
+    }
+}
+
+pub(crate) async fn run(
+    client: Client,
+    sweep: &LinkSweep,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(sweep, &run_parameters)?;
+    let ip_version = ip_version(&run_parameters.test_instance_params)?;
+    let port = udp_port(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let (mut discv5, enr) = start_discv5(
+        &run_parameters,
+        client.global_seq(),
+        ip_version,
+        port,
+        instance_key(&run_parameters, client.global_seq())?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Establish sessions at each level
+    // //////////////////////////////////////////////////////////////
+    let mut results = vec![];
+    let rpc_stats = RpcStats::default();
+
+    for (i, level) in params.levels.iter().enumerate() {
+        reconfigure_network(
+            &client,
+            link_shape_with(&run_parameters.test_instance_params, |link_shape| {
+                (sweep.apply)(link_shape, *level)
+            })?,
+            format!("state_network_configured_{i}"),
+        )
+        .await?;
+
+        // Node #1 tries to establish sessions with all the other participants.
+        if instance_info.seq == 1 {
+            if i > 0 {
+                // Restart discv5 with a fresh key so that no session established at the previous
+                // level is reused. A different port is used to avoid waiting for the previous
+                // socket to be released.
+                let port = u16::try_from(i)
+                    .ok()
+                    .and_then(|i| port.checked_add(i))
+                    .ok_or("udp_port overflows with the levels to sweep.")?;
+                discv5.shutdown();
+                (discv5, _) = start_discv5(
+                    &run_parameters,
+                    instance_info.seq,
+                    ip_version,
+                    port,
+                    CombinedKey::generate_secp256k1(),
+                )
+                .await?;
+            }
+
+            let mut handles = vec![];
+            for p in participants.iter().filter(|&p| p.seq != instance_info.seq) {
+                let fut = tokio::time::timeout(
+                    Duration::from_secs(params.connect_timeout),
+                    rpc_stats.find_node_designated_peer(&discv5, p.enr.clone(), vec![0]),
+                );
+                handles.push(tokio::spawn(fut));
+            }
+
+            let intended = handles.len();
+            let mut established = 0;
+            for h in handles {
+                match h.await? {
+                    Ok(Ok(_)) => established += 1,
+                    Ok(Err(e)) => error!("Failed to run FIND_NODE query: {e}"),
+                    Err(_) => error!("FIND_NODE query timed out."),
+                }
+            }
+
+            let success_rate = if intended == 0 {
+                0.0
+            } else {
+                established as f64 / intended as f64
+            };
+            info!(
+                "{}: {level}{}, established: {established}/{intended}",
+                sweep.field, sweep.unit
+            );
+            results.push((*level, success_rate));
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field(sweep.field, *level)
+            .add_field("connections_intended", intended as u64)
+            .add_field("connections_established", established as u64)
+            .add_field("success_rate", success_rate)
+            .add_tag("instance_seq", instance_info.seq);
+            record_metric(&client, write_query).await;
+        }
+
+        barrier_with_timeout(
+            &client,
+            format!("state_completed_level_{i}"),
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+    }
+
+    if instance_info.seq == 1 {
+        client.record_message(format!("({}, success_rate): {:?}", sweep.field, results));
+
+        // The approximate limit is the first level at which some sessions fail to be established.
+        match results.iter().find(|(_, success_rate)| *success_rate < 1.0) {
+            Some((level, success_rate)) => client.record_message(format!(
+                "The success rate dropped off at {level}{}. success_rate: {success_rate}",
+                sweep.unit
+            )),
+            None => client.record_message("All the sessions have been established at every level."),
+        }
+    }
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    client.record_success().await?;
+    Ok(())
+}
+
+async fn start_discv5(
+    run_parameters: &RunParameters,
+    seq: u64,
+    ip_version: IpVersion,
+    port: u16,
+    enr_key: CombinedKey,
+) -> Result<(Discv5, Enr), Box<dyn std::error::Error>> {
+    let enr = build_enr_on_port(run_parameters, seq, ip_version, &enr_key, port)?;
+    let listen_config = ip_version.listen_config(port);
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5 = Discv5::new(
+        enr.clone(),
+        enr_key,
+        build_config(listen_config, run_parameters)?,
+    )?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server on port {port}: {e:?}"))?;
+
+    Ok((discv5, enr))
+}
@@ -0,0 +1,296 @@
+mod params;
+
+use crate::config::build_config;
+use crate::load::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::traffic::TrafficMeter;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, collect_instance_info,
+    listen_config, percentile, publish_and_collect_on, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::Discv5;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tokio::sync::Semaphore;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, warn};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_CONVERGED: &str = "state_converged";
+const TOPIC_LOAD_STATS: &str = "load_stats";
+
+// The results of the load by an instance, which are aggregated by the leader.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct LoadStats {
+    seq: u64,
+    // The lookups scheduled by the target QPS.
+    offered: u64,
+    // The lookups skipped as `max_concurrency` lookups were already in flight.
+    dropped: u64,
+    errors: u64,
+    // The latencies of the lookups which have completed without errors.
+    latencies_ms: Vec<u64>,
+}
+
+impl LoadStats {
+    fn completed(&self) -> u64 {
+        self.latencies_ms.len() as u64
+    }
+
+    fn error_rate(&self) -> f64 {
+        let issued = self.completed() + self.errors;
+        if issued == 0 {
+            0.0
+        } else {
+            self.errors as f64 / issued as f64
+        }
+    }
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("load", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = build_config(listen_config, &run_parameters)?;
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+    let discv5 = Arc::new(discv5);
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    let other_instances = collect_instance_info(&client, &instance_info).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // The bootstrap nodes know all the other nodes, and the other nodes know the bootstrap nodes.
+    for i in other_instances
+        .iter()
+        .filter(|i| instance_info.is_bootstrap_node || i.is_bootstrap_node)
+    {
+        if let Err(e) = discv5.add_enr(i.enr.clone()) {
+            error!("Failed to add the ENR of #{}: {e}", i.seq);
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // Converge by the self-lookup, so that the load is put on a populated network.
+    if !instance_info.is_bootstrap_node {
+        if let Err(e) = rpc_stats
+            .find_node(&discv5, instance_info.enr.node_id())
+            .await
+        {
+            warn!("Failed to run the self-lookup: {e}");
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_CONVERGED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run lookups at the target QPS
+    // //////////////////////////////////////////////////////////////
+    // A lookup is started for a random target every `1 / qps` seconds, unless `max_concurrency`
+    // lookups are already in flight, in which case it's dropped. So the achieved QPS falls below
+    // the target once the lookups take longer than the node can keep up with.
+    let mut stats = LoadStats {
+        seq: instance_info.seq,
+        ..Default::default()
+    };
+    let semaphore = Arc::new(Semaphore::new(params.max_concurrency));
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / params.qps));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut lookups = vec![];
+
+    // The traffic under the load, i.e. excluding the convergence.
+    let traffic_meter = TrafficMeter::start(&client, discv5.clone(), instance_info.seq)?;
+    let started_at = Instant::now();
+    loop {
+        ticker.tick().await;
+        if started_at.elapsed() >= params.duration {
+            break;
+        }
+        stats.offered += 1;
+
+        let Ok(permit) = semaphore.clone().try_acquire_owned() else {
+            stats.dropped += 1;
+            continue;
+        };
+        let discv5 = discv5.clone();
+        let rpc_stats = rpc_stats.clone();
+        lookups.push(tokio::spawn(async move {
+            let lookup_started_at = Instant::now();
+            let result = rpc_stats.find_node(&discv5, NodeId::random()).await;
+            drop(permit);
+            result
+                .map(|_| lookup_started_at.elapsed())
+                .map_err(|e| e.to_string())
+        }));
+    }
+
+    // The lookups in flight at the end also count.
+    for lookup in lookups {
+        match lookup.await? {
+            Ok(elapsed) => stats.latencies_ms.push(elapsed.as_millis() as u64),
+            Err(e) => {
+                warn!("Failed to run FINDNODE query: {e}");
+                stats.errors += 1;
+            }
+        }
+    }
+    let elapsed = started_at.elapsed();
+    stats.latencies_ms.sort_unstable();
+    traffic_meter
+        .finish(&client, stats.offered - stats.dropped)
+        .await;
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    let write_query = add_load_fields(
+        WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("source", "instance_summary"),
+        &stats,
+        elapsed,
+    );
+    record_metric(&client, write_query).await;
+
+    // //////////////////////////////////////////////////////////////
+    // Aggregate the results of all the instances
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the leader which records the summary of the whole network.
+    let all_stats = publish_and_collect_on(&client, TOPIC_LOAD_STATS, stats).await?;
+    let mut failed = false;
+
+    if instance_info.seq == 1 {
+        let mut total = LoadStats::default();
+        for s in all_stats.iter() {
+            total.offered += s.offered;
+            total.dropped += s.dropped;
+            total.errors += s.errors;
+            total.latencies_ms.extend(s.latencies_ms.iter().copied());
+        }
+        total.latencies_ms.sort_unstable();
+
+        client.record_message(format!(
+            "offered: {}, completed: {}, dropped: {}, errors: {}, achieved QPS: {:.2}, lookup latency (ms): p50: {:?}, p90: {:?}, p99: {:?}",
+            total.offered,
+            total.completed(),
+            total.dropped,
+            total.errors,
+            total.completed() as f64 / elapsed.as_secs_f64(),
+            percentile(&total.latencies_ms, 50),
+            percentile(&total.latencies_ms, 90),
+            percentile(&total.latencies_ms, 99),
+        ));
+
+        let write_query = add_load_fields(
+            WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("source", "summary"),
+            &total,
+            elapsed,
+        );
+        record_metric(&client, write_query).await;
+
+        if total.completed() == 0 {
+            error!("None of the lookups has completed.");
+            failed = true;
+        }
+    }
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Add the counts, the achieved QPS over `elapsed`, the error rate and the p50/p90/p99 latencies of
+// `stats` to `write_query`.
+fn add_load_fields(write_query: WriteQuery, stats: &LoadStats, elapsed: Duration) -> WriteQuery {
+    let mut write_query = write_query
+        .add_field("offered", stats.offered)
+        .add_field("completed", stats.completed())
+        .add_field("dropped", stats.dropped)
+        .add_field("errors", stats.errors)
+        .add_field(
+            "achieved_qps",
+            stats.completed() as f64 / elapsed.as_secs_f64(),
+        )
+        .add_field("error_rate", stats.error_rate());
+    for p in [50, 90, 99] {
+        if let Some(v) = percentile(&stats.latencies_ms, p) {
+            write_query = write_query.add_field(format!("p{p}_ms"), v);
+        }
+    }
+    write_query
+}
@@ -0,0 +1,34 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub(crate) struct Params {
+    pub qps: f64,
+    pub duration: Duration,
+    pub max_concurrency: usize,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        let qps = get_param_or::<f64>("qps", instance_params, 10.0)?;
+        if qps.is_nan() || qps <= 0.0 {
+            return Err(format!("qps must be positive, but got {qps}.").into());
+        }
+        let duration = get_param_or::<u64>("load_duration", instance_params, 60)?;
+        if duration == 0 {
+            return Err("load_duration must be non-zero.".into());
+        }
+        let max_concurrency = get_param_or::<usize>("max_concurrency", instance_params, 16)?;
+        if max_concurrency == 0 {
+            return Err("max_concurrency must be non-zero.".into());
+        }
+
+        Ok(Params {
+            qps,
+            duration: Duration::from_secs(duration),
+            max_concurrency,
+        })
+    }
+}
@@ -0,0 +1,201 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, get_param,
+    listen_config, publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::kbucket::ConnectionDirection;
+use discv5::Discv5;
+use std::collections::HashSet;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+// The roles of the participants, assigned by seq.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+    // Looks up the target, an attacker.
+    Searcher,
+    Honest,
+    Attacker,
+}
+
+impl Role {
+    // NOTE: #1 is the searcher, the next `honest_count` nodes are honest ones, and the rest are
+    // attackers.
+    fn of(seq: u64, honest_count: u64) -> Role {
+        if seq == 1 {
+            Role::Searcher
+        } else if seq <= 1 + honest_count {
+            Role::Honest
+        } else {
+            Role::Attacker
+        }
+    }
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("lookup-attacker-target", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let honest_count = get_param::<u64>("honest_count", &run_parameters.test_instance_params)?;
+    let role = Role::of(client.global_seq(), honest_count);
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}, role: {:?}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr),
+        role,
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // The searcher and the honest nodes know everyone, whereas the attackers know only the other
+    // attackers so that they respond with attackers only.
+    for p in participants.iter().filter(|p| p.seq != instance_info.seq) {
+        if role != Role::Attacker || Role::of(p.seq, honest_count) == Role::Attacker {
+            discv5.add_enr(p.enr.clone())?;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Look up an attacker's node id
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if role == Role::Searcher {
+        let honest_ids = participants
+            .iter()
+            .filter(|p| Role::of(p.seq, honest_count) == Role::Honest)
+            .map(|p| p.enr.node_id())
+            .collect::<HashSet<_>>();
+        let target = participants
+            .iter()
+            .find(|p| Role::of(p.seq, honest_count) == Role::Attacker)
+            .expect("Attacker");
+        let target_id: NodeId = target.enr.node_id();
+
+        match rpc_stats.find_node(&discv5, target_id).await {
+            Ok(enrs) => {
+                // The peers we have connected to in outgoing direction are the ones contacted
+                // during the query.
+                let contacted = discv5
+                    .kbuckets()
+                    .iter()
+                    .filter(|entry| {
+                        entry.status.is_connected()
+                            && entry.status.direction == ConnectionDirection::Outgoing
+                    })
+                    .map(|entry| *entry.node.key.preimage())
+                    .collect::<Vec<_>>();
+                let honest_contacted = contacted
+                    .iter()
+                    .filter(|node_id| honest_ids.contains(node_id))
+                    .count();
+                let honest_ratio = if contacted.is_empty() {
+                    0.0
+                } else {
+                    honest_contacted as f64 / contacted.len() as f64
+                };
+                info!(
+                    "target: {target_id}, found: {}, contacted: {:?}, honest: {honest_contacted}",
+                    enrs.iter().any(|enr| enr.node_id() == target_id),
+                    contacted
+                );
+
+                // //////////////////////////////////////////////////////////////
+                // Record metrics
+                // //////////////////////////////////////////////////////////////
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("contacted", contacted.len() as u64)
+                .add_field("honest_contacted", honest_contacted as u64)
+                .add_field("honest_ratio", honest_ratio)
+                .add_tag("instance_seq", instance_info.seq);
+                record_metric(&client, write_query).await;
+            }
+            Err(e) => {
+                error!("Failed to run FINDNODE query: {e}");
+                failed = true;
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
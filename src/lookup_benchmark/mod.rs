@@ -0,0 +1,255 @@
+mod params;
+
+use crate::config::build_config;
+use crate::lookup_benchmark::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, collect_instance_info,
+    listen_config, percentile, publish_and_collect_on, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::Discv5;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_CONVERGED: &str = "state_converged";
+const TOPIC_LOOKUP_STATS: &str = "lookup_stats";
+
+// The results of the lookups by an instance, which are aggregated by the leader.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct LookupStats {
+    seq: u64,
+    durations_ms: Vec<u64>,
+    found_enrs: Vec<u64>,
+    failures: u64,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("lookup-benchmark", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = build_config(listen_config, &run_parameters)?;
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    let other_instances = collect_instance_info(&client, &instance_info).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // The bootstrap nodes know all the other nodes, and the other nodes know the bootstrap nodes.
+    for i in other_instances
+        .iter()
+        .filter(|i| instance_info.is_bootstrap_node || i.is_bootstrap_node)
+    {
+        if let Err(e) = discv5.add_enr(i.enr.clone()) {
+            error!("Failed to add the ENR of #{}: {e}", i.seq);
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // Converge by the self-lookup, so that the benchmark measures lookups on a populated network.
+    if !instance_info.is_bootstrap_node {
+        if let Err(e) = rpc_stats
+            .find_node(&discv5, instance_info.enr.node_id())
+            .await
+        {
+            warn!("Failed to run the self-lookup: {e}");
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_CONVERGED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run lookups for random targets
+    // //////////////////////////////////////////////////////////////
+    let mut stats = LookupStats {
+        seq: instance_info.seq,
+        durations_ms: vec![],
+        found_enrs: vec![],
+        failures: 0,
+    };
+    for _ in 0..params.lookups {
+        let started_at = Instant::now();
+        let result = rpc_stats.find_node(&discv5, NodeId::random()).await;
+        let elapsed = started_at.elapsed().as_millis() as u64;
+
+        let found = match result {
+            Ok(enrs) => enrs.len() as u64,
+            Err(e) => {
+                warn!("Failed to run FINDNODE query: {e}");
+                stats.failures += 1;
+                continue;
+            }
+        };
+        stats.durations_ms.push(elapsed);
+        stats.found_enrs.push(found);
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("elapsed_ms", elapsed)
+        .add_field("found_enrs", found)
+        .add_tag("instance_seq", instance_info.seq);
+        record_metric(&client, write_query).await;
+    }
+
+    let write_query = add_percentiles(
+        WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("source", "instance_summary"),
+        &stats.durations_ms,
+        stats.failures,
+    );
+    record_metric(&client, write_query).await;
+
+    // //////////////////////////////////////////////////////////////
+    // Aggregate the results of all the instances
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the leader which records the summary of the whole network.
+    let all_stats = publish_and_collect_on(&client, TOPIC_LOOKUP_STATS, stats).await?;
+    let mut failed = false;
+
+    if instance_info.seq == 1 {
+        let mut durations = all_stats
+            .iter()
+            .flat_map(|s| s.durations_ms.iter().copied())
+            .collect::<Vec<_>>();
+        durations.sort_unstable();
+        let found_enrs = all_stats
+            .iter()
+            .flat_map(|s| s.found_enrs.iter().copied())
+            .collect::<Vec<_>>();
+        let failures = all_stats.iter().map(|s| s.failures).sum::<u64>();
+
+        client.record_message(format!(
+            "lookup latency (ms): p50: {:?}, p90: {:?}, p99: {:?}, max: {:?}, lookups: {}, failures: {failures}",
+            percentile(&durations, 50),
+            percentile(&durations, 90),
+            percentile(&durations, 99),
+            durations.last(),
+            durations.len(),
+        ));
+
+        let mut write_query = add_percentiles(
+            WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("source", "summary"),
+            &durations,
+            failures,
+        );
+        if !found_enrs.is_empty() {
+            write_query = write_query.add_field(
+                "mean_found_enrs",
+                found_enrs.iter().sum::<u64>() as f64 / found_enrs.len() as f64,
+            );
+        }
+        record_metric(&client, write_query).await;
+
+        if durations.is_empty() && params.lookups > 0 {
+            error!("None of the lookups has succeeded.");
+            failed = true;
+        }
+    }
+    info!("Completed {} lookups.", params.lookups);
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Add the p50/p90/p99 of `durations`, and the numbers of the successful and failed lookups to
+// `write_query`.
+fn add_percentiles(write_query: WriteQuery, durations: &[u64], failures: u64) -> WriteQuery {
+    let mut sorted = durations.to_vec();
+    sorted.sort_unstable();
+
+    let mut write_query = write_query
+        .add_field("lookups", sorted.len() as u64)
+        .add_field("failures", failures);
+    for p in [50, 90, 99] {
+        if let Some(v) = percentile(&sorted, p) {
+            write_query = write_query.add_field(format!("p{p}_ms"), v);
+        }
+    }
+    write_query
+}
@@ -0,0 +1,186 @@
+use crate::scenario::Registry;
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::process::Command;
+
+// The usage of the loopback runner.
+const USAGE: &str = "Usage: loopback <test_case> [--instances <n>] [--port-stride <n>] [--test-param <key>=<value>]...";
+// The env var which tells the instances spawned by the loopback runner their offset of the UDP
+// port.
+const PORT_OFFSET_ENV: &str = "DISCV5_TESTGROUND_PORT_OFFSET";
+// The group all the instances spawned by the loopback runner belong to, as in
+// `testground run single`.
+const GROUP_ID: &str = "single";
+// The default number of UDP ports reserved for each instance, counted up from `udp_port`.
+const DEFAULT_PORT_STRIDE: u16 = 100;
+
+/// A run of a test case with its instances spawned as child processes on the loopback address,
+/// invoked as described by `USAGE`.
+///
+/// This replaces the Testground runner and the sidecar only. Each instance is given the
+/// environment Testground would give it and still uses the Testground client, so it requires a
+/// sync service listening on `SYNC_SERVICE_HOST` (default: 127.0.0.1), and sends its metrics to
+/// the InfluxDB at `INFLUXDB_URL` (default: http://127.0.0.1:8086). The network configuration isn't
+/// applied without the sidecar.
+///
+/// The instances share the loopback address, so the instance #`i` (0-based) listens on the ports
+/// counted up from `udp_port + i * port_stride`, which `udp_port` returns. A test case which listens
+/// on more ports than `port_stride` per instance, e.g. `large-scale` with a large
+/// `nodes_per_instance`, fails to bind the ports shared with the next instance.
+pub(crate) struct LoopbackRun {
+    test_case: String,
+    instances: u64,
+    port_stride: u16,
+    params: BTreeMap<String, String>,
+}
+
+impl LoopbackRun {
+    /// Parse the command line arguments, excluding the binary name. Returns `None` if this binary
+    /// isn't invoked as the loopback runner, i.e. it's an instance of a test case.
+    pub(crate) fn from_args(
+        mut args: impl Iterator<Item = String>,
+    ) -> Result<Option<Self>, String> {
+        if args.next().as_deref() != Some("loopback") {
+            return Ok(None);
+        }
+        let test_case = args.next().ok_or(USAGE)?;
+
+        let mut instances = 1;
+        let mut port_stride = DEFAULT_PORT_STRIDE;
+        let mut params = BTreeMap::new();
+        while let Some(arg) = args.next() {
+            let value = args.next().ok_or(format!("{arg} requires a value."))?;
+            match arg.as_str() {
+                "--instances" => {
+                    instances = match value.parse::<u64>() {
+                        Ok(0) | Err(_) => {
+                            return Err(format!("--instances must be a positive integer: {value}"))
+                        }
+                        Ok(n) => n,
+                    }
+                }
+                "--port-stride" => {
+                    port_stride = match value.parse::<u16>() {
+                        Ok(0) | Err(_) => {
+                            return Err(format!(
+                                "--port-stride must be a positive integer: {value}"
+                            ))
+                        }
+                        Ok(n) => n,
+                    }
+                }
+                "--test-param" => {
+                    let (k, v) = value
+                        .split_once('=')
+                        .ok_or(format!("--test-param must be <key>=<value>: {value}"))?;
+                    params.insert(k.to_owned(), v.to_owned());
+                }
+                _ => return Err(format!("Unknown argument: {arg}. {USAGE}")),
+            }
+        }
+
+        Ok(Some(LoopbackRun {
+            test_case,
+            instances,
+            port_stride,
+            params,
+        }))
+    }
+
+    /// Spawn the instances and wait for all of them to exit.
+    pub(crate) fn run(mut self, registry: &Registry) -> Result<(), Box<dyn std::error::Error>> {
+        if registry.get(&self.test_case).is_none() {
+            return Err(format!("Unknown test case: {}", self.test_case).into());
+        }
+
+        // NOTE: The defaults in the manifest aren't merged as Testground does, so only `latency`,
+        // which every instance requires, is given here. The link shape isn't applied without the
+        // sidecar anyway.
+        self.params
+            .entry("latency".to_owned())
+            .or_insert_with(|| "0".to_owned());
+        let instance_params = self
+            .params
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("|");
+
+        let started_at = Utc::now();
+        let test_run = format!("loopback-{}", started_at.format("%Y%m%d%H%M%S"));
+        let outputs = std::env::temp_dir()
+            .join("discv5-testground")
+            .join(&test_run);
+        let sync_service_host =
+            std::env::var("SYNC_SERVICE_HOST").unwrap_or_else(|_| "127.0.0.1".to_owned());
+        let influxdb_url =
+            std::env::var("INFLUXDB_URL").unwrap_or_else(|_| "http://127.0.0.1:8086".to_owned());
+        let exe = std::env::current_exe()?;
+
+        println!(
+            "Running {} with {} instances. run: {test_run}, params: {instance_params}",
+            self.test_case, self.instances
+        );
+
+        let mut children = vec![];
+        for i in 0..self.instances {
+            let port_offset = u16::try_from(i)
+                .ok()
+                .and_then(|i| i.checked_mul(self.port_stride))
+                .ok_or("The port offsets overflow with the instances and --port-stride.")?;
+            let outputs_path = outputs.join(i.to_string());
+            std::fs::create_dir_all(&outputs_path)?;
+
+            let child = Command::new(&exe)
+                .env("TEST_PLAN", "discv5-testground")
+                .env("TEST_CASE", &self.test_case)
+                .env("TEST_RUN", &test_run)
+                .env("TEST_REPO", "")
+                .env("TEST_COMMIT", "")
+                .env("TEST_BRANCH", "")
+                .env("TEST_TAG", "")
+                .env("TEST_OUTPUTS_PATH", &outputs_path)
+                .env("TEST_TEMP_PATH", &outputs_path)
+                .env("TEST_INSTANCE_COUNT", self.instances.to_string())
+                .env("TEST_INSTANCE_ROLE", "")
+                .env("TEST_INSTANCE_PARAMS", &instance_params)
+                .env("TEST_GROUP_ID", GROUP_ID)
+                .env("TEST_GROUP_INSTANCE_COUNT", self.instances.to_string())
+                .env("TEST_SIDECAR", "false")
+                .env("TEST_SUBNET", "127.0.0.0/8")
+                .env("TEST_START_TIME", started_at.to_rfc3339())
+                .env("TEST_CAPTURE_PROFILES", "")
+                .env("SYNC_SERVICE_HOST", &sync_service_host)
+                .env("INFLUXDB_URL", &influxdb_url)
+                .env(PORT_OFFSET_ENV, port_offset.to_string())
+                .spawn()?;
+            children.push(child);
+        }
+
+        let mut failures = 0;
+        for (i, mut child) in children.into_iter().enumerate() {
+            let status = child.wait()?;
+            if !status.success() {
+                eprintln!("The instance {i} has exited with {status}.");
+                failures += 1;
+            }
+        }
+        println!("The outputs of the instances are in {}", outputs.display());
+
+        if failures > 0 {
+            return Err(format!("{failures} of {} instances have failed.", self.instances).into());
+        }
+        Ok(())
+    }
+}
+
+/// The offset added to the `udp_port` param, so that the instances spawned by the loopback runner,
+/// which share the loopback address, listen on their own ports.
+pub(crate) fn port_offset() -> Result<u16, String> {
+    match std::env::var(PORT_OFFSET_ENV) {
+        Ok(offset) => offset
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid {PORT_OFFSET_ENV}: {offset}")),
+        Err(_) => Ok(0),
+    }
+}
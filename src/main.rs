@@ -1,20 +1,121 @@
+mod adversary;
+mod aggressive_ping;
+mod all_distances;
+mod alpha_benchmark;
+mod assertions;
+mod ban_list;
+mod baseline;
+mod blackhole;
+mod bootstrap_flag_exchange;
+mod churn;
+mod client_emulation;
+mod collect_single;
 mod concurrent_requests;
+mod config;
+mod config_matrix;
+mod connect_under_loss;
+mod convergence_cdf;
+mod designated_vs_recursive;
+mod discovery_coverage;
+mod dual_stack;
+mod duplicate_replay;
+mod dynamic_subnet_filter;
 mod eclipse;
+mod eclipse_with_traffic;
+mod enr_no_id_scheme;
+mod enr_sign_overhead;
+mod enr_tcp_port;
 mod enr_update;
+mod enr_validation;
+mod events;
 mod find_node;
+mod find_node_distances;
+mod fork_filter;
+mod handshake_latency_limit;
+mod handshake_storm;
+mod impaired_nodes;
 mod ip_change;
+mod join_storm;
+mod keyspace_sweep;
+mod keytype_change;
+mod large_scale;
+mod latency_mix;
+mod link_sweep;
+mod load;
+mod lookup_attacker_target;
+mod lookup_benchmark;
+#[cfg(feature = "loopback")]
+mod loopback;
+mod malformed_packets;
+mod max_distance_lookup;
+mod metrics_backend_down;
 mod mock;
+// Not registered until the network can model a NAT, see the module.
+#[allow(dead_code)]
+mod nat_traversal;
+mod network;
+mod network_flapping;
+mod nodes_cap;
+mod nodes_reorder;
+mod overloaded_responder;
+mod oversized_enr;
+mod packet_duplication;
+mod parallelism_overshoot;
+mod partition_enr_conflict;
+mod partition_heal;
+mod ping_enr_seq_mismatch;
+mod post_heal_lookup;
+mod private_ip_enr;
+mod query_before_start;
+mod random_target_lookup;
+mod rate_limiter;
+mod report;
+mod rpc_stats;
 mod sandbox;
+mod scenario;
+mod session_cache_eviction;
+mod session_teardown;
+mod slow_path_lookup;
+mod socket_update_validation;
+mod socket_update_vs_ping;
+mod start_stop_loop;
+mod steady_state;
+mod subscribe_early_close;
+mod sybil;
+mod table_filter;
 mod talk;
+mod teardown;
+#[cfg(feature = "topics")]
+mod topics;
+mod topology;
+mod traffic;
+mod unreachable_in_results;
+mod update_lookup_race;
 mod utils;
+mod virtual_nodes;
 
-use testground::client::Client;
-use testground::network_conf::{
-    FilterAction, LinkShape, NetworkConfiguration, RoutingPolicyType, DEFAULT_DATA_NETWORK,
+use crate::scenario::Registry;
+use crate::utils::{
+    data_network, data_network_ipv6, extra_data_network, ip_version, link_shape, record_run_config,
+    IpVersion,
 };
+use futures::FutureExt;
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+use testground::client::Client;
+use testground::network_conf::{NetworkConfiguration, RoutingPolicyType};
+
+const STATE_NETWORK_CONFIGURED: &str = "state_network_configured";
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // This binary can spawn the instances of a test case by itself in place of the Testground
+    // runner, see `LoopbackRun`.
+    #[cfg(feature = "loopback")]
+    if let Some(loopback_run) = loopback::LoopbackRun::from_args(std::env::args().skip(1))? {
+        return loopback_run.run(&registry());
+    }
+
     let client = Client::new_and_init().await?;
 
     // Enable tracing.
@@ -25,64 +126,208 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with_env_filter(env_filter)
         .try_init();
 
+    // Record the configuration before any fallible setup, so that even early failures report
+    // the configuration they were meant to run with. The instance params have been merged with
+    // the defaults in the manifest by Testground.
+    let run_parameters = client.run_parameters();
+    record_run_config(
+        &client,
+        &run_parameters,
+        &run_parameters.test_instance_params,
+    )
+    .await;
+
     // ////////////////////////
     // Configure network
     // ////////////////////////
+    let instance_params = client.run_parameters().test_instance_params;
+    configure_network(
+        &client,
+        data_network(&instance_params)?,
+        STATE_NETWORK_CONFIGURED.to_owned(),
+        None,
+    )
+    .await?;
+
+    // A group can opt in to an extra data network. Only some of the instances configure it, so
+    // each of them waits for its own configuration, and `STATE_NETWORK_CONFIGURED` above still
+    // accounts for every instance.
+    if let Some(network) = extra_data_network(&instance_params)? {
+        configure_network(
+            &client,
+            network,
+            format!("state_extra_network_configured_{}", client.global_seq()),
+            Some(1),
+        )
+        .await?;
+    }
+
+    // The scenarios advertise and listen on the IP version(s) given by the `ip-mode` param, so
+    // fail early if the data network doesn't provide the addresses for them.
+    let ip_version = ip_version(&instance_params)?;
+    if ip_version.has_ipv6() && data_network_ipv6(&run_parameters)?.is_none() {
+        let message =
+            format!("ip-mode is {ip_version:?}, but the data network has no IPv6 address.");
+        client.record_failure(message.clone()).await?;
+        return Err(message.into());
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Run test case
+    // //////////////////////////////////////////////////////////////
+    let registry = registry();
+
+    let test_case = client.run_parameters().test_case;
+    let Some(scenario) = registry.get(&test_case) else {
+        let message = format!("Unknown test case: {test_case}");
+        client.record_failure(message.clone()).await?;
+        return Err(message.into());
+    };
+    if ip_version != IpVersion::V4 && registry.is_ipv4_only(&test_case) {
+        let message = format!(
+            "The test case `{test_case}` supports IPv4 only, but ip-mode is {ip_version:?}."
+        );
+        client.record_failure(message.clone()).await?;
+        return Err(message.into());
+    }
+
+    // Record a failure with the context if the test case returns an error or panics, instead of
+    // exiting without any result. The failure may have been recorded already, e.g. by
+    // `barrier_with_timeout`, in which case this adds the context to it.
+    let message = match AssertUnwindSafe(scenario.run(client.clone()))
+        .catch_unwind()
+        .await
+    {
+        Ok(Ok(())) => return Ok(()),
+        Ok(Err(e)) => format!("The test case `{test_case}` has failed: {e}"),
+        Err(panic) => format!(
+            "The test case `{test_case}` has panicked: {}",
+            panic_message(&*panic)
+        ),
+    };
+    client.record_failure(message.clone()).await?;
+    Err(message.into())
+}
+
+// The message given to `panic!`, which is either a `&str` or a `String`.
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown")
+}
+
+/// The registry of all the test cases.
+fn registry() -> Registry {
+    let mut registry = Registry::new();
+    find_node::register(&mut registry);
+    concurrent_requests::register(&mut registry);
+    eclipse::register(&mut registry);
+    enr_update::register(&mut registry);
+    ip_change::register(&mut registry);
+    max_distance_lookup::register(&mut registry);
+    connect_under_loss::register(&mut registry);
+    fork_filter::register(&mut registry);
+    keytype_change::register(&mut registry);
+    nodes_reorder::register(&mut registry);
+    parallelism_overshoot::register(&mut registry);
+    start_stop_loop::register(&mut registry);
+    nodes_cap::register(&mut registry);
+    metrics_backend_down::register(&mut registry);
+    ping_enr_seq_mismatch::register(&mut registry);
+    designated_vs_recursive::register(&mut registry);
+    enr_tcp_port::register(&mut registry);
+    post_heal_lookup::register(&mut registry);
+    enr_no_id_scheme::register(&mut registry);
+    eclipse_with_traffic::register(&mut registry);
+    aggressive_ping::register(&mut registry);
+    socket_update_validation::register(&mut registry);
+    lookup_attacker_target::register(&mut registry);
+    packet_duplication::register(&mut registry);
+    keyspace_sweep::register(&mut registry);
+    config_matrix::register(&mut registry);
+    private_ip_enr::register(&mut registry);
+    convergence_cdf::register(&mut registry);
+    network_flapping::register(&mut registry);
+    bootstrap_flag_exchange::register(&mut registry);
+    subscribe_early_close::register(&mut registry);
+    slow_path_lookup::register(&mut registry);
+    collect_single::register(&mut registry);
+    socket_update_vs_ping::register(&mut registry);
+    join_storm::register(&mut registry);
+    query_before_start::register(&mut registry);
+    enr_sign_overhead::register(&mut registry);
+    session_teardown::register(&mut registry);
+    dynamic_subnet_filter::register(&mut registry);
+    overloaded_responder::register(&mut registry);
+    unreachable_in_results::register(&mut registry);
+    update_lookup_race::register(&mut registry);
+    all_distances::register(&mut registry);
+    handshake_latency_limit::register(&mut registry);
+    partition_enr_conflict::register(&mut registry);
+    random_target_lookup::register(&mut registry);
+    talk::register(&mut registry);
+    enr_validation::register(&mut registry);
+    churn::register(&mut registry);
+    find_node_distances::register(&mut registry);
+    dual_stack::register(&mut registry);
+    partition_heal::register(&mut registry);
+    lookup_benchmark::register(&mut registry);
+    discovery_coverage::register(&mut registry);
+    malformed_packets::register(&mut registry);
+    handshake_storm::register(&mut registry);
+    rate_limiter::register(&mut registry);
+    load::register(&mut registry);
+    alpha_benchmark::register(&mut registry);
+    session_cache_eviction::register(&mut registry);
+    ban_list::register(&mut registry);
+    table_filter::register(&mut registry);
+    oversized_enr::register(&mut registry);
+    duplicate_replay::register(&mut registry);
+    latency_mix::register(&mut registry);
+    #[cfg(feature = "topics")]
+    topics::register(&mut registry);
+    blackhole::register(&mut registry);
+    large_scale::register(&mut registry);
+    steady_state::register(&mut registry);
+    sandbox::register(&mut registry);
+
+    registry
+}
+
+async fn configure_network(
+    client: &Client,
+    network: String,
+    callback_state: String,
+    callback_target: Option<u64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // The link shape is built from the params of this instance's group, so each group in a
+    // composition can have its own, e.g. a "slow" group and a "fast" group.
+    let run_parameters = client.run_parameters();
+    let link_shape = link_shape(&run_parameters.test_instance_params)?;
+    client.record_message(format!(
+        "Configuring {network} for group `{}`. latency: {}ms, jitter: {}ms, bandwidth: {}bit/s, loss: {}%",
+        run_parameters.test_group_id,
+        link_shape.latency / 1_000_000,
+        link_shape.jitter / 1_000_000,
+        link_shape.bandwidth,
+        link_shape.loss
+    ));
+
     client
         .configure_network(NetworkConfiguration {
-            network: DEFAULT_DATA_NETWORK.to_owned(),
+            network,
             ipv4: None,
             ipv6: None,
             enable: true,
-            default: LinkShape {
-                latency: client
-                    .run_parameters()
-                    .test_instance_params
-                    .get("latency")
-                    .ok_or("latency is not specified")?
-                    .parse::<u64>()?
-                    * 1_000_000, // Translate from millisecond to nanosecond
-                jitter: 0,
-                bandwidth: 1048576, // 1Mib
-                filter: FilterAction::Accept,
-                loss: 0.0,
-                corrupt: 0.0,
-                corrupt_corr: 0.0,
-                reorder: 0.0,
-                reorder_corr: 0.0,
-                duplicate: 0.0,
-                duplicate_corr: 0.0,
-            },
+            default: link_shape,
             rules: None,
-            callback_state: "state_network_configured".to_owned(),
-            callback_target: None,
+            callback_state,
+            callback_target,
             routing_policy: RoutingPolicyType::DenyAll,
         })
         .await?;
 
-    // //////////////////////////////////////////////////////////////
-    // Run test case
-    // //////////////////////////////////////////////////////////////
-    match client.run_parameters().test_case.clone().as_str() {
-        "find-node" => find_node::run(client.clone()).await?,
-        "concurrent-requests" => concurrent_requests::run(client).await?,
-        "concurrent-requests_whoareyou-timeout" => {
-            concurrent_requests::whoareyou_timeout::run(client).await?
-        }
-        "concurrent-requests_before-establishing-session" => {
-            concurrent_requests::before_establishing_session::run(client).await?
-        }
-        "eclipse-attack-monopolizing-by-incoming-nodes" => {
-            eclipse::MonopolizingByIncomingNodes::new()
-                .run(client.clone())
-                .await?
-        }
-        "enr-update" => enr_update::run(client.clone()).await?,
-        "ip-change" => ip_change::run(client).await?,
-        "sandbox" => sandbox::run(client).await?,
-        "talk" => talk::run(client).await?,
-        _ => unreachable!(),
-    };
-
     Ok(())
 }
@@ -0,0 +1,458 @@
+mod params;
+
+use crate::config::build_config;
+use crate::malformed_packets::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::{final_barrier, teardown};
+use crate::topology::{join_topology, Topology};
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, bootstrap_count, build_enr, check_enr_port,
+    collect_instance_info, enr_socket, ip_version, listen_config, record_metric, udp_port,
+    InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::packet::{Packet, PacketKind};
+use discv5::{DefaultProtocolId, Discv5};
+use rand::{Rng, RngCore};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tokio::net::UdpSocket;
+use tokio::time::{timeout, Instant};
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_BASELINE: &str = "state_completed_baseline";
+const STATE_COMPLETED_TO_ATTACK: &str = "state_completed_to_attack";
+
+// The masking IV and the static header, which lead every discv5 packet.
+const IV_LENGTH: usize = 16;
+const STATIC_HEADER_LENGTH: usize = 23;
+// The smallest and the largest packets discv5 accepts.
+const MIN_PACKET_SIZE: usize = 63;
+const MAX_PACKET_SIZE: usize = 1280;
+// How long an attacker waits for the WHOAREYOU challenge to replay.
+const CHALLENGE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// The malformed packets the attackers send in turn.
+#[derive(Clone, Copy, Debug)]
+enum Malformation {
+    // A packet cut off in the middle of the header.
+    TruncatedHeader,
+    // A packet whose protocol id, after unmasking, isn't `discv5`.
+    BadMagic,
+    // Random bytes of an acceptable packet size.
+    RandomBytes,
+    // A WHOAREYOU challenge the honest node has issued to the attacker, sent back to it.
+    ReplayedWhoAreYou,
+}
+
+const MALFORMATIONS: [Malformation; 4] = [
+    Malformation::TruncatedHeader,
+    Malformation::BadMagic,
+    Malformation::RandomBytes,
+    Malformation::ReplayedWhoAreYou,
+];
+
+// An honest node the attacker sends the malformed packets to.
+struct Target {
+    node_id: NodeId,
+    socket_addr: SocketAddr,
+    whoareyou: Option<Packet>,
+}
+
+// The lookups in a phase of an honest node.
+#[derive(Default)]
+struct PhaseStats {
+    lookups: u64,
+    successful_lookups: u64,
+}
+
+impl PhaseStats {
+    fn success_rate(&self) -> f64 {
+        if self.lookups == 0 {
+            1.0
+        } else {
+            self.successful_lookups as f64 / self.lookups as f64
+        }
+    }
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("malformed-packets", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // NOTE: The `attackers` instances with the highest seqs are the attackers. The others are
+    // honest, and construct a star topology with the bootstrap nodes at the center.
+    let honest_count = run_parameters
+        .test_instance_count
+        .saturating_sub(params.attackers);
+    if honest_count <= bootstrap_count(&client)? {
+        return Err("`attackers` must leave at least one non-bootstrap honest instance.".into());
+    }
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr.clone())?;
+    let honest_instances = collect_instance_info(&client, &instance_info)
+        .await?
+        .into_iter()
+        .filter(|i| i.seq <= honest_count)
+        .collect::<Vec<_>>();
+
+    if instance_info.seq > honest_count {
+        play_attacker(&client, &params, enr.node_id(), &honest_instances).await
+    } else {
+        // ////////////////////////
+        // Start discv5
+        // ////////////////////////
+        let listen_config = listen_config(&run_parameters)?;
+        check_enr_port(&enr, &listen_config)?;
+        let mut discv5: Discv5 =
+            Discv5::new(enr, enr_key, build_config(listen_config, &run_parameters)?)?;
+        discv5
+            .start()
+            .await
+            .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+        play_honest(&client, &params, discv5, &instance_info, &honest_instances).await
+    }
+}
+
+async fn play_honest(
+    client: &Client,
+    params: &Params,
+    discv5: Discv5,
+    instance_info: &InstanceInfo,
+    honest_instances: &[InstanceInfo],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+
+    // //////////////////////////////////////////////////////////////
+    // Star topology
+    // //////////////////////////////////////////////////////////////
+    let rpc_stats = RpcStats::default();
+    join_topology(
+        &discv5,
+        &rpc_stats,
+        Topology::Star,
+        instance_info,
+        honest_instances,
+    )
+    .await?;
+
+    barrier_with_timeout(
+        client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run lookups without and then under the garbage traffic
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+    let mut stats = HashMap::new();
+
+    for (phase, state) in [
+        ("baseline", STATE_COMPLETED_BASELINE),
+        ("attack", STATE_COMPLETED_TO_ATTACK),
+    ] {
+        let phase_stats =
+            run_lookups(client, params, &discv5, &rpc_stats, instance_info, phase).await;
+        let connected_peers = discv5.connected_peers();
+        info!(
+            "phase: {phase}, success_rate: {:.3}, connected_peers: {connected_peers}",
+            phase_stats.success_rate()
+        );
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("lookups", phase_stats.lookups)
+        .add_field("successful_lookups", phase_stats.successful_lookups)
+        .add_field("success_rate", phase_stats.success_rate())
+        .add_field("connected_peers", connected_peers as u64)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("phase", phase)
+        .add_tag("source", "summary");
+        record_metric(client, write_query).await;
+
+        stats.insert(phase, (phase_stats.success_rate(), connected_peers));
+
+        barrier_with_timeout(
+            client,
+            state,
+            run_parameters.test_instance_count,
+            barrier_timeout(client)?,
+        )
+        .await?;
+    }
+
+    let (baseline_rate, baseline_peers) = stats["baseline"];
+    let (attack_rate, attack_peers) = stats["attack"];
+    client.record_message(format!(
+        "success_rate: {baseline_rate:.3} -> {attack_rate:.3}, connected_peers: {baseline_peers} -> {attack_peers}"
+    ));
+    if attack_rate < params.min_success_rate {
+        error!(
+            "The success rate under the garbage traffic {attack_rate:.3} is below {}.",
+            params.min_success_rate
+        );
+        failed = true;
+    }
+
+    rpc_stats.finish(client, instance_info.seq).await;
+
+    teardown(
+        client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Run random lookups every `lookup_interval` for `phase_duration`.
+async fn run_lookups(
+    client: &Client,
+    params: &Params,
+    discv5: &Discv5,
+    rpc_stats: &RpcStats,
+    instance_info: &InstanceInfo,
+    phase: &str,
+) -> PhaseStats {
+    let run_parameters = client.run_parameters();
+    let deadline = Instant::now() + Duration::from_secs(params.phase_duration);
+    let mut interval = tokio::time::interval(Duration::from_millis(params.lookup_interval));
+    let mut stats = PhaseStats::default();
+
+    while Instant::now() < deadline {
+        interval.tick().await;
+
+        let started_at = Instant::now();
+        let success = match rpc_stats.find_node(discv5, NodeId::random()).await {
+            Ok(enrs) => !enrs.is_empty(),
+            Err(e) => {
+                warn!("Failed to run FINDNODE query: {e}");
+                false
+            }
+        };
+        stats.lookups += 1;
+        if success {
+            stats.successful_lookups += 1;
+        }
+
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("elapsed_ms", started_at.elapsed().as_millis() as u64)
+        .add_field("success", success)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("phase", phase.to_owned());
+        record_metric(client, write_query).await;
+    }
+
+    stats
+}
+
+async fn play_attacker(
+    client: &Client,
+    params: &Params,
+    local_id: NodeId,
+    honest_instances: &[InstanceInfo],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let socket = UdpSocket::bind(SocketAddr::new(
+        ip_version(&run_parameters.test_instance_params)?.unspecified(),
+        udp_port(&run_parameters.test_instance_params)?,
+    ))
+    .await?;
+
+    barrier_with_timeout(
+        client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(client)?,
+    )
+    .await?;
+
+    // Stay quiet while the honest nodes measure the baseline, and capture the WHOAREYOU
+    // challenges to replay meanwhile.
+    let mut targets = vec![];
+    for honest in honest_instances.iter() {
+        let socket_addr = enr_socket(&honest.enr)?;
+        let whoareyou = capture_whoareyou(&socket, local_id, honest.enr.node_id(), socket_addr)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Failed to capture WHOAREYOU from #{}: {e}", honest.seq);
+                None
+            });
+        targets.push(Target {
+            node_id: honest.enr.node_id(),
+            socket_addr,
+            whoareyou,
+        });
+    }
+
+    barrier_with_timeout(
+        client,
+        STATE_COMPLETED_BASELINE,
+        run_parameters.test_instance_count,
+        barrier_timeout(client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Send malformed packets
+    // //////////////////////////////////////////////////////////////
+    let deadline = Instant::now() + Duration::from_secs(params.phase_duration);
+    let mut interval = tokio::time::interval(Duration::from_secs(1) / params.packet_rate as u32);
+    let mut sent: HashMap<String, u64> = HashMap::new();
+    let mut n = 0_usize;
+
+    while Instant::now() < deadline {
+        interval.tick().await;
+
+        let target = &targets[n % targets.len()];
+        let malformation = MALFORMATIONS[(n / targets.len()) % MALFORMATIONS.len()];
+        n += 1;
+
+        let Some(packet) = malformed_packet(malformation, local_id, target)? else {
+            continue;
+        };
+        if let Err(e) = socket.send_to(&packet, target.socket_addr).await {
+            warn!("Failed to send a malformed packet: {e}");
+            continue;
+        }
+        *sent.entry(format!("{malformation:?}")).or_default() += 1;
+    }
+    info!("Sent malformed packets: {sent:?}");
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    for (malformation, count) in sent {
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("sent_packets", count)
+        .add_tag("instance_seq", client.global_seq())
+        .add_tag("malformation", malformation);
+        record_metric(client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        client,
+        STATE_COMPLETED_TO_ATTACK,
+        run_parameters.test_instance_count,
+        barrier_timeout(client)?,
+    )
+    .await?;
+    final_barrier(client, run_parameters.test_instance_count).await?;
+
+    client.record_success().await?;
+    Ok(())
+}
+
+// Send a random packet, to which the honest node responds with a WHOAREYOU challenge.
+async fn capture_whoareyou(
+    socket: &UdpSocket,
+    local_id: NodeId,
+    node_id: NodeId,
+    socket_addr: SocketAddr,
+) -> Result<Option<Packet>, Box<dyn std::error::Error>> {
+    let random_packet = Packet::new_random(&local_id)?;
+    socket
+        .send_to(
+            &random_packet.encode::<DefaultProtocolId>(&node_id),
+            socket_addr,
+        )
+        .await?;
+
+    let mut buffer = [0; MAX_PACKET_SIZE];
+    let (length, _) = timeout(CHALLENGE_TIMEOUT, socket.recv_from(&mut buffer)).await??;
+    let (packet, _) = Packet::decode::<DefaultProtocolId>(&local_id, &buffer[..length])
+        .map_err(|e| format!("Failed to decode the packet: {e:?}"))?;
+
+    Ok(matches!(packet.header.kind, PacketKind::WhoAreYou { .. }).then_some(packet))
+}
+
+// Build a packet of `malformation` to `target`, or None if there is no WHOAREYOU to replay.
+fn malformed_packet(
+    malformation: Malformation,
+    local_id: NodeId,
+    target: &Target,
+) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    let mut rng = rand::thread_rng();
+
+    let packet = match malformation {
+        Malformation::TruncatedHeader => {
+            let mut packet =
+                Packet::new_random(&local_id)?.encode::<DefaultProtocolId>(&target.node_id);
+            packet.truncate(rng.gen_range(1..IV_LENGTH + STATIC_HEADER_LENGTH));
+            packet
+        }
+        Malformation::BadMagic => {
+            let mut packet =
+                Packet::new_random(&local_id)?.encode::<DefaultProtocolId>(&target.node_id);
+            // The protocol id follows the masking IV.
+            packet[IV_LENGTH] ^= 0xff;
+            packet
+        }
+        Malformation::RandomBytes => {
+            let mut packet = vec![0; rng.gen_range(MIN_PACKET_SIZE..=MAX_PACKET_SIZE)];
+            rng.fill_bytes(&mut packet);
+            packet
+        }
+        Malformation::ReplayedWhoAreYou => match target.whoareyou.clone() {
+            Some(whoareyou) => whoareyou.encode::<DefaultProtocolId>(&target.node_id),
+            None => return Ok(None),
+        },
+    };
+
+    Ok(Some(packet))
+}
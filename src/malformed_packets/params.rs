@@ -0,0 +1,30 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub attackers: u64,
+    pub packet_rate: u64,
+    pub phase_duration: u64,
+    pub lookup_interval: u64,
+    pub min_success_rate: f64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        let params = Params {
+            attackers: get_param_or::<u64>("attackers", instance_params, 1)?,
+            packet_rate: get_param_or::<u64>("packet_rate", instance_params, 100)?,
+            phase_duration: get_param_or::<u64>("phase_duration", instance_params, 30)?,
+            lookup_interval: get_param_or::<u64>("lookup_interval", instance_params, 1000)?,
+            min_success_rate: get_param_or::<f64>("min_success_rate", instance_params, 0.0)?,
+        };
+
+        if params.packet_rate == 0 {
+            return Err("`packet_rate` must be greater than 0.".into());
+        }
+
+        Ok(params)
+    }
+}
@@ -0,0 +1,207 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, listen_config,
+    publish_and_collect, record_metric, InstanceInfo, MAX_DISTANCE,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Key};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+// The maximum number of nodes the responder returns in a NODES response.
+const MAX_NODES_RESPONSE: usize = 16;
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("max-distance-lookup", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+        .max_nodes_response(MAX_NODES_RESPONSE)
+        .build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the responder. It knows all the other participants, so the content of its
+    // buckets can be derived from the collected information.
+    let responder = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("responder")
+        .clone();
+
+    if instance_info.seq == responder.seq {
+        for p in participants.iter().filter(|p| p.seq != responder.seq) {
+            if let Err(e) = discv5.add_enr(p.enr.clone()) {
+                error!("Failed to add ENR: {e}");
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run FINDNODE request with the maximum distance
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if instance_info.seq != responder.seq {
+        let responder_key: Key<NodeId> = responder.enr.node_id().into();
+
+        // The nodes that the responder knows at the maximum distance. The requester itself is
+        // excluded since the responder never returns the requester's own ENR.
+        let expected = participants
+            .iter()
+            .filter(|p| p.seq != responder.seq && p.seq != instance_info.seq)
+            .map(|p| p.enr.node_id())
+            .filter(|node_id| {
+                responder_key.log2_distance(&Key::from(*node_id)) == Some(MAX_DISTANCE)
+            })
+            .collect::<Vec<_>>();
+        info!(
+            "The responder knows {} nodes at distance {MAX_DISTANCE}.",
+            expected.len()
+        );
+
+        match rpc_stats
+            .find_node_designated_peer(&discv5, responder.enr.clone(), vec![MAX_DISTANCE])
+            .await
+        {
+            Ok(enrs) => {
+                info!("Found ENRs: {:?}", enrs);
+
+                for enr in enrs.iter() {
+                    let distance = responder_key.log2_distance(&Key::from(enr.node_id()));
+                    if distance != Some(MAX_DISTANCE) {
+                        error!(
+                            "The response contains a node at an unexpected distance. node_id: {}, distance: {:?}",
+                            enr.node_id(),
+                            distance
+                        );
+                        failed = true;
+                    } else if !expected.contains(&enr.node_id()) {
+                        error!(
+                            "The response contains an unknown node. node_id: {}",
+                            enr.node_id()
+                        );
+                        failed = true;
+                    }
+                }
+
+                if enrs.len() > MAX_NODES_RESPONSE {
+                    error!(
+                        "The response exceeds the limit. limit: {MAX_NODES_RESPONSE}, actual: {}",
+                        enrs.len()
+                    );
+                    failed = true;
+                }
+
+                if expected.is_empty() {
+                    info!("No nodes at distance {MAX_DISTANCE}, so the response should be empty.");
+                } else if enrs.is_empty() {
+                    error!("Found no ENRs although the responder knows nodes at distance {MAX_DISTANCE}.");
+                    failed = true;
+                }
+
+                // //////////////////////////////////////////////////////////////
+                // Record metrics
+                // //////////////////////////////////////////////////////////////
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("response_size", enrs.len() as u64)
+                .add_field(
+                    "expected_size",
+                    expected.len().min(MAX_NODES_RESPONSE) as u64,
+                )
+                .add_tag("instance_seq", instance_info.seq);
+                record_metric(&client, write_query).await;
+            }
+            Err(e) => {
+                error!("Failed to run FINDNODE request: {e}");
+                failed = true;
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
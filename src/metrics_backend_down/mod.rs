@@ -0,0 +1,63 @@
+use crate::scenario::Registry;
+use crate::utils::{barrier_timeout, barrier_with_timeout, metric_failures, record_metric};
+use chrono::Local;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::info;
+
+const STATE_FINISHED: &str = "state_finished";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("metrics-backend-down", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let measurement = format!(
+        "discv5-testground_{}_{}",
+        run_parameters.test_case, run_parameters.test_run
+    );
+
+    // A well-formed metric.
+    record_metric(
+        &client,
+        WriteQuery::new(Local::now().into(), measurement.clone())
+            .add_field("well_formed", true)
+            .add_tag("instance_seq", client.global_seq()),
+    )
+    .await;
+
+    // A metric the backend never accepts, in order to simulate the metrics backend being down.
+    // InfluxDB rejects a point without any field.
+    let failures_before = metric_failures();
+    record_metric(
+        &client,
+        WriteQuery::new(Local::now().into(), measurement)
+            .add_tag("instance_seq", client.global_seq()),
+    )
+    .await;
+    let failures = metric_failures() - failures_before;
+    info!("Failed to record {failures} metric(s).");
+
+    client.record_message(format!(
+        "The test has continued after failing to record {failures} metric(s)."
+    ));
+
+    barrier_with_timeout(
+        &client,
+        STATE_FINISHED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // The degradation path is only verified if the bad metric has actually been rejected.
+    if failures == 0 {
+        client
+            .record_failure("The metric without any field has been recorded without a failure.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+    Ok(())
+}
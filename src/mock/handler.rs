@@ -2,6 +2,7 @@ use crate::mock;
 use crate::mock::session::Session;
 use crate::mock::socket::Socket;
 use crate::mock::{Action, Behaviours, CustomResponse, CustomResponseId, Expect, Request};
+use crate::utils::{enr_socket, rlp_list, rlp_string};
 use discv5::enr::{CombinedKey, NodeId};
 use discv5::handler::{NodeAddress, NodeContact};
 use discv5::packet::{ChallengeData, IdNonce, Packet, PacketKind};
@@ -9,12 +10,14 @@ use discv5::rpc::{Message, RequestBody};
 use discv5::socket::{InboundPacket, OutboundPacket};
 use discv5::{DefaultProtocolId, Enr};
 use std::collections::HashMap;
-use std::net::IpAddr;
 use std::num::NonZeroU16;
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::{Receiver, UnboundedSender};
 use tracing::{info, warn};
 
+// The message type of NODES in the discv5 wire protocol.
+const NODES_MESSAGE_TYPE: u8 = 0x04;
+
 #[derive(Debug)]
 /// A Challenge (WHOAREYOU) object used to handle and send WHOAREYOU requests.
 pub struct Challenge {
@@ -321,6 +324,9 @@ impl Handler {
                             mock::Response::Custom(responses) => {
                                 self.send_custom_responses(node_address, responses).await
                             }
+                            mock::Response::RawNodes(enrs) => {
+                                self.send_raw_nodes(node_address, request, enrs).await
+                            }
                         }
                     } else {
                         panic!("Session does not exist.")
@@ -399,14 +405,15 @@ impl Handler {
     ) {
         match request.body {
             RequestBody::Ping { .. } => {
+                let socket = enr_socket(&self.enr).unwrap();
                 self.send_response(
                     node_address,
                     discv5::rpc::Response {
                         id: request.id,
                         body: discv5::rpc::ResponseBody::Pong {
                             enr_seq: self.enr.seq(),
-                            ip: IpAddr::from(self.enr.ip4().unwrap()),
-                            port: NonZeroU16::new(self.enr.udp4().unwrap()).unwrap(),
+                            ip: socket.ip(),
+                            port: NonZeroU16::new(socket.port()).unwrap(),
                         },
                     },
                 )
@@ -436,6 +443,40 @@ impl Handler {
         }
     }
 
+    async fn send_raw_nodes(
+        &mut self,
+        node_address: NodeAddress,
+        request: discv5::rpc::Request,
+        enrs: Vec<Vec<u8>>,
+    ) {
+        if !matches!(request.body, RequestBody::FindNode { .. }) {
+            return self.send_default_response(node_address, request).await;
+        }
+
+        // The NODES message is encoded by hand, as `discv5::rpc::Response` only takes valid ENRs.
+        // message = message-type || rlp_list(request-id, total, rlp_list(ENRs))
+        let mut message = vec![NODES_MESSAGE_TYPE];
+        message.extend(rlp_list(&[
+            rlp_string(request.id.as_bytes()),
+            rlp_string(&[1]),
+            rlp_list(&enrs),
+        ]));
+
+        let packet = if let Some(session) = self.sessions.get_mut(&node_address) {
+            session.encrypt_message(self.node_id, &message)
+        } else {
+            return warn!(
+                "Session is not established. Dropping raw NODES response for node: {}",
+                node_address.node_id
+            );
+        };
+
+        match packet {
+            Ok(packet) => self.send(node_address, packet).await,
+            Err(e) => warn!("Could not encrypt response: {:?}", e),
+        }
+    }
+
     async fn establish_session(
         &mut self,
         node_address: NodeAddress,
@@ -58,6 +58,10 @@ pub enum Action {
 pub enum Response {
     Default,
     Custom(Vec<CustomResponse>),
+    /// Respond to FINDNODE with a NODES response carrying the given RLP-encoded ENRs as is, which
+    /// allows sending ENRs that `Enr` can't represent, e.g. ones with an invalid signature. Other
+    /// requests are responded to as `Default`.
+    RawNodes(Vec<Vec<u8>>),
 }
 
 #[derive(Clone)]
@@ -22,17 +22,32 @@ impl Socket {
         node_id: NodeId,
         listen_config: ListenConfig,
     ) -> Self {
-        let socket = match listen_config {
-            ListenConfig::Ipv4 { ip, port } => {
-                let socket_addr: SocketAddr = (ip, port).into();
-                Arc::new(UdpSocket::bind(socket_addr).await.unwrap())
-            }
-            ListenConfig::Ipv6 { .. } => unreachable!(),
-            ListenConfig::DualStack { .. } => unreachable!(),
+        let socket_addrs: Vec<SocketAddr> = match listen_config {
+            ListenConfig::Ipv4 { ip, port } => vec![(ip, port).into()],
+            ListenConfig::Ipv6 { ip, port } => vec![(ip, port).into()],
+            ListenConfig::DualStack {
+                ipv4,
+                ipv4_port,
+                ipv6,
+                ipv6_port,
+            } => vec![(ipv4, ipv4_port).into(), (ipv6, ipv6_port).into()],
         };
+        let mut sockets = vec![];
+        for socket_addr in socket_addrs {
+            sockets.push(Arc::new(UdpSocket::bind(socket_addr).await.unwrap()));
+        }
 
-        let from_recv_handler = RecvHandler::spawn(executor.clone(), node_id, socket.clone());
-        let to_send_handler = SendHandler::spawn(executor, socket);
+        // The packets received on any of the sockets are sent to the handler via the same channel.
+        let (handler_send, from_recv_handler) = mpsc::channel(30);
+        for socket in sockets.iter() {
+            RecvHandler::spawn(
+                executor.clone(),
+                node_id,
+                socket.clone(),
+                handler_send.clone(),
+            );
+        }
+        let to_send_handler = SendHandler::spawn(executor, sockets);
 
         Socket {
             recv: from_recv_handler,
@@ -52,10 +67,8 @@ impl RecvHandler {
         executor: Box<dyn Executor>,
         node_id: NodeId,
         socket: Arc<UdpSocket>,
-    ) -> Receiver<InboundPacket> {
-        // create the channel to send decoded packets to the handler
-        let (handler_send, handler_recv) = mpsc::channel(30);
-
+        handler_send: mpsc::Sender<InboundPacket>,
+    ) {
         let receive_handler = RecvHandler {
             node_id,
             socket,
@@ -65,8 +78,6 @@ impl RecvHandler {
         executor.spawn(Box::pin(async move {
             receive_handler.start().await;
         }));
-
-        handler_recv
     }
 
     async fn start(&self) {
@@ -103,19 +114,20 @@ impl RecvHandler {
 
 struct SendHandler {
     from_handler: Receiver<OutboundPacket>,
-    socket: Arc<UdpSocket>,
+    // One socket per IP version to listen on.
+    sockets: Vec<Arc<UdpSocket>>,
 }
 
 impl SendHandler {
     pub(crate) fn spawn(
         executor: Box<dyn Executor>,
-        socket: Arc<UdpSocket>,
+        sockets: Vec<Arc<UdpSocket>>,
     ) -> Sender<OutboundPacket> {
         let (to_send_handler, from_handler) = mpsc::channel(30);
 
         let mut send_handler = SendHandler {
             from_handler,
-            socket,
+            sockets,
         };
 
         executor.spawn(Box::pin(async move {
@@ -132,7 +144,16 @@ impl SendHandler {
                     .packet
                     .encode::<DefaultProtocolId>(&outbound_packet.node_address.node_id);
                 let dest = &outbound_packet.node_address.socket_addr;
-                let _ = self.socket.send_to(&encoded_packet, dest).await.unwrap();
+                // Send from the socket of the same IP version as the destination.
+                let Some(socket) = self.sockets.iter().find(|socket| {
+                    socket
+                        .local_addr()
+                        .map_or(false, |addr| addr.is_ipv4() == dest.is_ipv4())
+                }) else {
+                    warn!("No socket to send a packet to {dest}");
+                    continue;
+                };
+                let _ = socket.send_to(&encoded_packet, dest).await.unwrap();
             }
         }
     }
@@ -0,0 +1,21 @@
+use crate::scenario::Registry;
+use testground::client::Client;
+use tracing::warn;
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("nat-traversal", run);
+}
+
+// NOTE: A NAT drops the unsolicited inbound packets, but lets through the replies to the outbound
+// ones. The rules of the Testground network are stateless filters on the egress traffic of an
+// instance by subnet, so they can only drop the traffic between two NATed nodes in both
+// directions, and the sessions between them would fail by construction rather than because of
+// discv5. Until the network can model a NAT, this test case isn't registered nor listed in the
+// manifest, and it records a failure if it's run anyway, rather than passing without measuring
+// anything.
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let message = "Unsupported: the Testground network rules are stateless egress filters, which can't model a NAT that admits the replies to outbound traffic only.";
+    warn!("{message}");
+    client.record_failure(message).await?;
+    Ok(())
+}
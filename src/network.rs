@@ -0,0 +1,90 @@
+use crate::utils::reconfigure_network;
+use std::time::Duration;
+use testground::client::Client;
+use testground::network_conf::LinkShape;
+use tokio::time::Instant;
+use tracing::info;
+
+/// The name of the phase before the first scheduled change of the network conditions.
+pub(crate) const INITIAL_PHASE: &str = "initial";
+
+/// Changes the network conditions at defined phases of a test, e.g. degrade the latency at T+60s
+/// and restore it at T+120s. Every instance has to run the same schedule, as each change waits
+/// until all the instances have applied it.
+pub(crate) struct Scheduler {
+    phases: Vec<(Duration, String, LinkShape)>,
+}
+
+impl Scheduler {
+    pub(crate) fn new() -> Self {
+        Scheduler { phases: vec![] }
+    }
+
+    /// Apply `link_shape` at `at` from the start of the schedule. The phases must be added in
+    /// chronological order.
+    pub(crate) fn phase(
+        mut self,
+        at: Duration,
+        name: &str,
+        link_shape: LinkShape,
+    ) -> Result<Self, String> {
+        if let Some((last, last_name, _)) = self.phases.last() {
+            if at <= *last {
+                return Err(format!(
+                    "The phase `{name}` must start after the phase `{last_name}`."
+                ));
+            }
+        }
+        self.phases.push((at, name.to_owned(), link_shape));
+        Ok(self)
+    }
+
+    /// The start time and the name of each phase, which tells the phase at a given time while the
+    /// schedule is running.
+    pub(crate) fn timeline(&self) -> Timeline {
+        Timeline {
+            phases: self
+                .phases
+                .iter()
+                .map(|(at, name, _)| (*at, name.clone()))
+                .collect(),
+        }
+    }
+
+    /// Apply each phase on schedule, counting from `started_at`.
+    pub(crate) async fn run(
+        self,
+        client: &Client,
+        started_at: Instant,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for (i, (at, name, link_shape)) in self.phases.into_iter().enumerate() {
+            tokio::time::sleep_until(started_at + at).await;
+            // The callback states have to be distinct, so that each change is a barrier of its own.
+            reconfigure_network(
+                client,
+                link_shape,
+                format!("state_network_phase_{i}_{name}"),
+            )
+            .await?;
+            info!("Entered the network phase `{name}`.");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Timeline {
+    phases: Vec<(Duration, String)>,
+}
+
+impl Timeline {
+    /// The name of the phase at `elapsed` from the start of the schedule.
+    pub(crate) fn phase_at(&self, elapsed: Duration) -> &str {
+        self.phases
+            .iter()
+            .rev()
+            .find(|(at, _)| *at <= elapsed)
+            .map(|(_, name)| name.as_str())
+            .unwrap_or(INITIAL_PHASE)
+    }
+}
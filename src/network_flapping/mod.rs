@@ -0,0 +1,229 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::network_flapping::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, link_shape,
+    listen_config, publish_and_collect, reconfigure_network, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::Discv5;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::network_conf::LinkShape;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FINAL_QUERY: &str = "state_completed_to_run_final_query";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("network-flapping", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Star topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the bootstrap node.
+    let bootstrap_node = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Bootstrap node")
+        .clone();
+
+    if instance_info.seq == bootstrap_node.seq {
+        for p in participants.iter().filter(|p| p.seq != bootstrap_node.seq) {
+            discv5.add_enr(p.enr.clone())?;
+        }
+    } else {
+        discv5.add_enr(bootstrap_node.enr.clone())?;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // The target of the lookups is the next non-bootstrap node.
+    let non_bootstrap_nodes = participants
+        .iter()
+        .filter(|p| p.seq != bootstrap_node.seq)
+        .collect::<Vec<_>>();
+    let target = non_bootstrap_nodes
+        .iter()
+        .position(|p| p.seq == instance_info.seq)
+        .map(|position| {
+            non_bootstrap_nodes[(position + 1) % non_bootstrap_nodes.len()]
+                .enr
+                .node_id()
+        });
+
+    // //////////////////////////////////////////////////////////////
+    // Run lookups while the network is flapping
+    // //////////////////////////////////////////////////////////////
+    let default_latency = link_shape(&run_parameters.test_instance_params)?.latency;
+
+    for round in 0..params.reconfig_count {
+        // Alternate the latency between the flapping one and the default one.
+        let latency = if round % 2 == 0 {
+            params.flap_latency * 1_000_000 // Translate from millisecond to nanosecond
+        } else {
+            default_latency
+        };
+        reconfigure_network(
+            &client,
+            LinkShape {
+                latency,
+                ..link_shape(&run_parameters.test_instance_params)?
+            },
+            format!("state_network_flapped_{round}"),
+        )
+        .await?;
+
+        let Some(target) = target else {
+            tokio::time::sleep(Duration::from_secs(params.reconfig_interval_secs)).await;
+            continue;
+        };
+
+        let round_started_at = Instant::now();
+        let mut attempts = 0_u64;
+        let mut succeeded = 0_u64;
+        while round_started_at.elapsed() < Duration::from_secs(params.reconfig_interval_secs) {
+            attempts += 1;
+            if lookup(&discv5, &rpc_stats, target, params.lookup_timeout).await {
+                succeeded += 1;
+            }
+        }
+        info!(
+            "round: {round}, latency: {}ms, succeeded: {succeeded}/{attempts}",
+            latency / 1_000_000
+        );
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("latency_ms", latency / 1_000_000)
+        .add_field("attempts", attempts)
+        .add_field("succeeded", succeeded)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("round", round);
+        record_metric(&client, write_query).await;
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Check lookups work after the flapping
+    // //////////////////////////////////////////////////////////////
+    reconfigure_network(
+        &client,
+        link_shape(&run_parameters.test_instance_params)?,
+        "state_network_restored".to_owned(),
+    )
+    .await?;
+
+    let mut failed = false;
+    if let Some(target) = target {
+        if !lookup(&discv5, &rpc_stats, target, params.lookup_timeout).await {
+            error!("The lookup has failed after the flapping.");
+            failed = true;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FINAL_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Looks up `target` and returns whether it has been found within `timeout` seconds.
+async fn lookup(discv5: &Discv5, rpc_stats: &RpcStats, target: NodeId, timeout: u64) -> bool {
+    match tokio::time::timeout(
+        Duration::from_secs(timeout),
+        rpc_stats.find_node(discv5, target),
+    )
+    .await
+    {
+        Ok(Ok(enrs)) => enrs.iter().any(|enr| enr.node_id() == target),
+        Ok(Err(e)) => {
+            error!("Failed to run FINDNODE query: {e}");
+            false
+        }
+        Err(_) => {
+            error!("The query hasn't completed within {timeout} seconds.");
+            false
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::utils::get_param;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub flap_latency: u64,
+    pub reconfig_interval_secs: u64,
+    pub reconfig_count: u64,
+    pub lookup_timeout: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            flap_latency: get_param::<u64>("flap_latency", instance_params)?,
+            reconfig_interval_secs: get_param::<u64>("reconfig_interval_secs", instance_params)?,
+            reconfig_count: get_param::<u64>("reconfig_count", instance_params)?,
+            lookup_timeout: get_param::<u64>("lookup_timeout", instance_params)?,
+        })
+    }
+}
@@ -0,0 +1,181 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, get_param,
+    listen_config, publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Key};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("nodes-cap", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let max_nodes_response =
+        get_param::<usize>("max_nodes_response", &run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+        .max_nodes_response(max_nodes_response)
+        .build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the responder. It knows all the other participants.
+    let responder = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("responder")
+        .clone();
+
+    if instance_info.seq == responder.seq {
+        for p in participants.iter().filter(|p| p.seq != responder.seq) {
+            discv5.add_enr(p.enr.clone())?;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run FINDNODE request
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if instance_info.seq != responder.seq {
+        let responder_key: Key<NodeId> = responder.enr.node_id().into();
+        let distance_to_self = responder_key.log2_distance(&Key::from(instance_info.enr.node_id()));
+
+        // The responder applies the cap before excluding the requester's own ENR from the
+        // response. So the distance of the requester is not requested, in order to make the
+        // expected response size predictable.
+        let matching = participants
+            .iter()
+            .filter(|p| p.seq != responder.seq && p.seq != instance_info.seq)
+            .filter_map(|p| responder_key.log2_distance(&Key::from(p.enr.node_id())))
+            .filter(|distance| Some(*distance) != distance_to_self)
+            .collect::<Vec<_>>();
+        let mut distances = matching.clone();
+        distances.sort_unstable();
+        distances.dedup();
+
+        let expected = matching.len().min(max_nodes_response);
+        info!(
+            "matching: {}, max_nodes_response: {}, expected: {}",
+            matching.len(),
+            max_nodes_response,
+            expected
+        );
+
+        match rpc_stats
+            .find_node_designated_peer(&discv5, responder.enr.clone(), distances)
+            .await
+        {
+            Ok(enrs) => {
+                if enrs.len() != expected {
+                    error!(
+                        "Unexpected response size. expected: {expected}, actual: {}",
+                        enrs.len()
+                    );
+                    failed = true;
+                }
+
+                // //////////////////////////////////////////////////////////////
+                // Record metrics
+                // //////////////////////////////////////////////////////////////
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("max_nodes_response", max_nodes_response as u64)
+                .add_field("matching", matching.len() as u64)
+                .add_field("response_size", enrs.len() as u64)
+                .add_tag("instance_seq", instance_info.seq);
+                record_metric(&client, write_query).await;
+            }
+            Err(e) => {
+                error!("Failed to run FINDNODE request: {e}");
+                failed = true;
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,24 @@
+mod params;
+mod resilience;
+
+use crate::impaired_nodes::{self, Impairment};
+use crate::scenario::Registry;
+use testground::client::Client;
+use testground::network_conf::LinkShape;
+
+const REORDERING: Impairment = Impairment {
+    params: ("reorder", "reorder_corr"),
+    state: "state_network_reordered",
+    apply: |link_shape, reorder, reorder_corr| LinkShape {
+        reorder,
+        reorder_corr,
+        ..link_shape
+    },
+};
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("nodes-reorder", |client: Client| {
+        impaired_nodes::run(client, &REORDERING)
+    });
+    registry.register("reorder-resilience", resilience::run);
+}
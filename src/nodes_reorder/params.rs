@@ -0,0 +1,22 @@
+use crate::utils::{get_param, get_param_or};
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub reorder: f32,
+    pub reorder_corr: f32,
+    pub lookups: u64,
+    pub lookup_timeout: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            reorder: get_param::<f32>("reorder", instance_params)?,
+            reorder_corr: get_param::<f32>("reorder_corr", instance_params)?,
+            lookups: get_param_or::<u64>("lookups", instance_params, 5)?,
+            lookup_timeout: get_param_or::<u64>("lookup_timeout", instance_params, 10)?,
+        })
+    }
+}
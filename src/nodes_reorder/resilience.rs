@@ -0,0 +1,271 @@
+use crate::config::build_config;
+use crate::nodes_reorder::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, link_shape_with,
+    listen_config, publish_and_collect, reconfigure_network, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Key};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::network_conf::LinkShape;
+use testground::WriteQuery;
+use tokio::time::timeout;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+    let lookup_timeout = Duration::from_secs(params.lookup_timeout);
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5: Discv5 = Discv5::new(
+        enr.clone(),
+        enr_key,
+        build_config(listen_config, &run_parameters)?,
+    )?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the responder. It knows all the other participants, so that its NODES response
+    // is split into multiple packets, and the others know #1 only. No session is established
+    // until the links are reordered.
+    let responder = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("responder")
+        .clone();
+
+    if instance_info.seq == responder.seq {
+        for p in participants.iter().filter(|p| p.seq != responder.seq) {
+            discv5.add_enr(p.enr.clone())?;
+        }
+    } else {
+        discv5.add_enr(responder.enr.clone())?;
+    }
+
+    reconfigure_network(
+        &client,
+        link_shape_with(&run_parameters.test_instance_params, |link_shape| {
+            LinkShape {
+                reorder: params.reorder,
+                reorder_corr: params.reorder_corr,
+                ..link_shape
+            }
+        })?,
+        "state_network_reordered".to_owned(),
+    )
+    .await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    let mut failed = false;
+
+    if instance_info.seq != responder.seq {
+        let metric = |source: &str| {
+            WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("source", source.to_owned())
+        };
+
+        // //////////////////////////////////////////////////////////////
+        // Establish a session with the responder
+        // //////////////////////////////////////////////////////////////
+        let started_at = Instant::now();
+        let established =
+            match timeout(lookup_timeout, discv5.send_ping(responder.enr.clone())).await {
+                Ok(Ok(_)) => true,
+                Ok(Err(e)) => {
+                    error!("Failed to establish a session with the responder: {e}");
+                    false
+                }
+                Err(_) => {
+                    error!(
+                        "No session has been established with the responder within {} seconds.",
+                        params.lookup_timeout
+                    );
+                    false
+                }
+            };
+        failed |= !established;
+        record_metric(
+            &client,
+            metric("session")
+                .add_field("established", established)
+                .add_field("elapsed_ms", started_at.elapsed().as_millis() as u64),
+        )
+        .await;
+
+        // //////////////////////////////////////////////////////////////
+        // Run FINDNODE request for all the distances the responder knows
+        // //////////////////////////////////////////////////////////////
+        let responder_key: Key<NodeId> = responder.enr.node_id().into();
+        let expected = participants
+            .iter()
+            .filter(|p| p.seq != responder.seq && p.seq != instance_info.seq)
+            .map(|p| p.enr.node_id())
+            .collect::<HashSet<_>>();
+        let mut distances = expected
+            .iter()
+            .filter_map(|node_id| responder_key.log2_distance(&Key::from(*node_id)))
+            .collect::<Vec<_>>();
+        distances.sort_unstable();
+        distances.dedup();
+
+        let started_at = Instant::now();
+        let received = match timeout(
+            lookup_timeout,
+            rpc_stats.find_node_designated_peer(&discv5, responder.enr.clone(), distances),
+        )
+        .await
+        {
+            Ok(Ok(enrs)) => enrs.iter().map(|enr| enr.node_id()).collect::<HashSet<_>>(),
+            Ok(Err(e)) => {
+                error!("Failed to run FINDNODE request: {e}");
+                HashSet::new()
+            }
+            Err(_) => {
+                error!(
+                    "The FINDNODE request hasn't completed within {} seconds.",
+                    params.lookup_timeout
+                );
+                HashSet::new()
+            }
+        };
+        let complete = received == expected;
+        if !complete {
+            error!(
+                "Failed to reconstruct the full set of nodes. missing: {}, unexpected: {}",
+                expected.difference(&received).count(),
+                received.difference(&expected).count()
+            );
+            failed = true;
+        }
+        record_metric(
+            &client,
+            metric("nodes")
+                .add_field("expected", expected.len() as u64)
+                .add_field("received", received.len() as u64)
+                .add_field("complete", complete)
+                .add_field("elapsed_ms", started_at.elapsed().as_millis() as u64),
+        )
+        .await;
+
+        // //////////////////////////////////////////////////////////////
+        // Run lookups, each establishing sessions with new peers
+        // //////////////////////////////////////////////////////////////
+        let mut completed = 0;
+        for _ in 0..params.lookups {
+            let started_at = Instant::now();
+            let found = match timeout(
+                lookup_timeout,
+                rpc_stats.find_node(&discv5, NodeId::random()),
+            )
+            .await
+            {
+                Ok(Ok(enrs)) => Some(enrs.len()),
+                Ok(Err(e)) => {
+                    error!("Failed to run FINDNODE query: {e}");
+                    None
+                }
+                Err(_) => {
+                    error!(
+                        "The FINDNODE query hasn't completed within {} seconds.",
+                        params.lookup_timeout
+                    );
+                    None
+                }
+            };
+            if found.is_some() {
+                completed += 1;
+            }
+            let write_query = metric("lookup")
+                .add_field("completed", found.is_some())
+                .add_field("elapsed_ms", started_at.elapsed().as_millis() as u64);
+            let write_query = match found {
+                Some(found) => write_query.add_field("found", found as u64),
+                None => write_query,
+            };
+            record_metric(&client, write_query).await;
+        }
+        info!(
+            "established: {established}, complete: {complete}, completed_lookups: {completed}/{}, connected_peers: {}",
+            params.lookups,
+            discv5.connected_peers()
+        );
+        if completed < params.lookups {
+            failed = true;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::overloaded_responder::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, link_shape,
+    listen_config, publish_and_collect, reconfigure_network, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::Discv5;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::network_conf::LinkShape;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("overloaded-responder", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the overloaded responder, which knows all the other nodes. The other nodes are
+    // the requesters, which know only the responder, so that their lookups must query it.
+    let responder = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Responder")
+        .clone();
+    let requesters = participants
+        .iter()
+        .filter(|p| p.seq != responder.seq)
+        .collect::<Vec<_>>();
+
+    if instance_info.seq == responder.seq {
+        for p in requesters.iter() {
+            discv5.add_enr(p.enr.clone())?;
+        }
+    } else {
+        discv5.add_enr(responder.enr.clone())?;
+    }
+
+    // Emulate the processing delay of the responder by delaying all the packets it sends, so
+    // every response arrives `responder_delay_ms` later than it would otherwise.
+    let default_link_shape = link_shape(&run_parameters.test_instance_params)?;
+    let local_link_shape = if instance_info.seq == responder.seq {
+        LinkShape {
+            latency: default_link_shape.latency + params.responder_delay_ms * 1_000_000, // Translate from millisecond to nanosecond
+            ..default_link_shape
+        }
+    } else {
+        default_link_shape
+    };
+    reconfigure_network(
+        &client,
+        local_link_shape,
+        "state_responder_delayed".to_owned(),
+    )
+    .await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run FINDNODE queries through the responder
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    // Each requester looks up the next requester, which it can only learn of from the responder.
+    let target = requesters
+        .iter()
+        .position(|p| p.seq == instance_info.seq)
+        .filter(|_| requesters.len() > 1)
+        .map(|position| requesters[(position + 1) % requesters.len()]);
+
+    if let Some(target) = target {
+        let target_id = target.enr.node_id();
+        let started_at = Instant::now();
+        let result = tokio::time::timeout(
+            Duration::from_secs(params.lookup_timeout),
+            rpc_stats.find_node(&discv5, target_id),
+        )
+        .await;
+        let elapsed = started_at.elapsed();
+
+        let (completed, found) = match result {
+            Ok(Ok(enrs)) => (true, enrs.iter().any(|enr| enr.node_id() == target_id)),
+            Ok(Err(e)) => {
+                info!("The query has failed in discv5: {e}");
+                (true, false)
+            }
+            Err(_) => {
+                error!(
+                    "The query hasn't completed within {} seconds.",
+                    params.lookup_timeout
+                );
+                failed = true;
+                (false, false)
+            }
+        };
+        client.record_message(format!(
+            "target: #{}, found: {found}, elapsed: {}ms",
+            target.seq,
+            elapsed.as_millis()
+        ));
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("responder_delay_ms", params.responder_delay_ms)
+        .add_field("elapsed_ms", elapsed.as_millis() as u64)
+        .add_field("completed", completed)
+        .add_field("found", found)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("target_seq", target.seq);
+        record_metric(&client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
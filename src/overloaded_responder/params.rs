@@ -0,0 +1,18 @@
+use crate::utils::get_param;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub responder_delay_ms: u64,
+    pub lookup_timeout: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            responder_delay_ms: get_param::<u64>("responder_delay_ms", instance_params)?,
+            lookup_timeout: get_param::<u64>("lookup_timeout", instance_params)?,
+        })
+    }
+}
@@ -0,0 +1,355 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, base64_url, build_enr, build_enr_with_fields,
+    check_enr_port, enr_addresses, enr_ip, listen_config, publish_and_collect, record_metric,
+    rlp_list, rlp_string, udp_port, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::{CombinedKey, EnrKey, EnrPublicKey, NodeId};
+use discv5::{Discv5, Enr, Key};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::net::IpAddr;
+use testground::client::Client;
+use testground::{RunParameters, WriteQuery};
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_VALIDATE: &str = "state_completed_to_validate";
+
+// The maximum size of an encoded ENR, as defined in EIP-778.
+const MAX_ENR_SIZE: usize = 300;
+// The ENR key of the custom field the ENRs are padded with.
+const PAD_KEY: &str = "pad";
+// The maximum number of nodes the responder returns in a NODES response, i.e. the discv5 default.
+const MAX_NODES_RESPONSE: usize = 16;
+// The distances requested to the responder at once, i.e. the largest buckets, so that the NODES
+// response spans multiple packets.
+const REQUESTED_DISTANCES: [u64; 3] = [256, 255, 254];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Participant {
+    #[serde(flatten)]
+    instance: InstanceInfo,
+    // An ENR over the size limit in text form, which can't be used by discv5 itself.
+    over_limit: Option<String>,
+}
+
+// The kinds of the participants, assigned by seq. #1 is always the responder with a plain ENR.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Plain,
+    // Runs with an ENR padded up to the size limit.
+    NearLimit,
+    // Runs with a plain ENR, and publishes a crafted ENR over the size limit.
+    OverLimit,
+}
+
+impl Kind {
+    fn of(seq: u64) -> Kind {
+        if seq == 1 {
+            return Kind::Plain;
+        }
+        match seq % 3 {
+            0 => Kind::NearLimit,
+            2 => Kind::OverLimit,
+            _ => Kind::Plain,
+        }
+    }
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("oversized-enr", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let seq = client.global_seq();
+    let kind = Kind::of(seq);
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = if kind == Kind::NearLimit {
+        near_limit_enr(&run_parameters, seq)?
+    } else {
+        build_enr(&run_parameters, seq)?
+    };
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+        .max_nodes_response(MAX_NODES_RESPONSE)
+        .build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let over_limit = if kind == Kind::OverLimit {
+        Some(over_limit_enr(
+            &CombinedKey::generate_secp256k1(),
+            enr_ip(&enr)?,
+            udp_port(&run_parameters.test_instance_params)?,
+        )?)
+    } else {
+        None
+    };
+    let instance_info = Participant {
+        instance: InstanceInfo::new(&client, enr)?,
+        over_limit,
+    };
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}, kind: {:?}, enr_size: {}",
+        instance_info.instance.seq,
+        instance_info.instance.enr.node_id(),
+        enr_addresses(&instance_info.instance.enr),
+        kind,
+        instance_info.instance.enr.size()
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the responder. It knows all the other participants, so the content of each of
+    // its buckets can be derived from the collected information.
+    let responder = participants
+        .iter()
+        .find(|p| p.instance.seq == 1)
+        .expect("responder")
+        .clone();
+
+    if instance_info.instance.seq == responder.instance.seq {
+        for p in participants
+            .iter()
+            .filter(|p| p.instance.seq != responder.instance.seq)
+        {
+            if let Err(e) = discv5.add_enr(p.instance.enr.clone()) {
+                error!("Failed to add the ENR of #{}: {e}", p.instance.seq);
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Validate the oversized ENRs
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if instance_info.instance.seq != responder.instance.seq {
+        // The ENRs over the size limit should be rejected when decoding them.
+        let mut over_limit_rejected = 0_u64;
+        let mut over_limit_accepted = 0_u64;
+        for (p, text) in participants
+            .iter()
+            .filter_map(|p| p.over_limit.as_ref().map(|text| (p, text)))
+        {
+            match text.parse::<Enr>() {
+                Ok(enr) => {
+                    error!(
+                        "The ENR of {} bytes by #{} has been decoded.",
+                        enr.size(),
+                        p.instance.seq
+                    );
+                    over_limit_accepted += 1;
+                }
+                Err(e) => {
+                    info!(
+                        "The ENR over the limit by #{} has been rejected: {e}",
+                        p.instance.seq
+                    );
+                    over_limit_rejected += 1;
+                }
+            }
+        }
+        if over_limit_accepted > 0 {
+            failed = true;
+        }
+
+        // The NODES response carrying the ENRs near the limit should be split into multiple
+        // packets, and all the ENRs should arrive intact.
+        let responder_key: Key<NodeId> = responder.instance.enr.node_id().into();
+        let expected = participants
+            .iter()
+            .filter(|p| {
+                p.instance.seq != instance_info.instance.seq
+                    && p.instance.seq != responder.instance.seq
+            })
+            .filter(|p| {
+                REQUESTED_DISTANCES.contains(
+                    &responder_key
+                        .log2_distance(&Key::from(p.instance.enr.node_id()))
+                        .unwrap_or(0),
+                )
+            })
+            .map(|p| p.instance.enr.node_id())
+            .collect::<HashSet<_>>();
+        let expected_size = expected.len().min(MAX_NODES_RESPONSE);
+        let near_limit = participants
+            .iter()
+            .filter(|p| Kind::of(p.instance.seq) == Kind::NearLimit)
+            .map(|p| p.instance.enr.node_id())
+            .collect::<HashSet<_>>();
+
+        let enrs = match rpc_stats
+            .find_node_designated_peer(
+                &discv5,
+                responder.instance.enr.clone(),
+                REQUESTED_DISTANCES.to_vec(),
+            )
+            .await
+        {
+            Ok(enrs) => enrs,
+            Err(e) => {
+                error!("Failed to run FINDNODE request: {e}");
+                failed = true;
+                vec![]
+            }
+        };
+        let near_limit_accepted = enrs
+            .iter()
+            .filter(|enr| near_limit.contains(&enr.node_id()))
+            .count();
+        let unexpected = enrs
+            .iter()
+            .filter(|enr| !expected.contains(&enr.node_id()))
+            .count();
+        if enrs.len() != expected_size || unexpected > 0 {
+            error!(
+                "Unexpected NODES response. expected: {expected_size}, actual: {}, unexpected: {unexpected}",
+                enrs.len()
+            );
+            failed = true;
+        }
+        if enrs.iter().any(|enr| enr.size() > MAX_ENR_SIZE) {
+            error!("The NODES response contains ENRs over the size limit.");
+            failed = true;
+        }
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("found_enrs", enrs.len() as u64)
+        .add_field("expected_enrs", expected_size as u64)
+        .add_field("near_limit_accepted", near_limit_accepted as u64)
+        .add_field("over_limit_rejected", over_limit_rejected)
+        .add_field("over_limit_accepted", over_limit_accepted)
+        .add_tag("instance_seq", instance_info.instance.seq)
+        .add_tag("kind", format!("{kind:?}"));
+        record_metric(&client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_VALIDATE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, seq).await;
+
+    teardown(&client, discv5, seq, run_parameters.test_instance_count).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// The ENR padded with the custom field as long as it fits within the size limit.
+fn near_limit_enr(
+    run_parameters: &RunParameters,
+    seq: u64,
+) -> Result<(Enr, CombinedKey), Box<dyn std::error::Error>> {
+    let mut enr = None;
+    for len in 0..MAX_ENR_SIZE {
+        let padding = "x".repeat(len);
+        match build_enr_with_fields(run_parameters, seq, |builder| {
+            builder.add_value(PAD_KEY, &padding);
+        }) {
+            Ok(padded) => enr = Some(padded),
+            // The size limit is exceeded.
+            Err(_) => break,
+        }
+    }
+    enr.ok_or_else(|| "Failed to build the padded ENR.".into())
+}
+
+// Crafts an ENR by hand just over the size limit, since `EnrBuilder` refuses to build it.
+fn over_limit_enr(
+    key: &CombinedKey,
+    ip: IpAddr,
+    port: u16,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (ip_key, udp_key, ip): (&[u8], &[u8], _) = match ip {
+        IpAddr::V4(ip) => (b"ip", b"udp", ip.octets().to_vec()),
+        IpAddr::V6(ip) => (b"ip6", b"udp6", ip.octets().to_vec()),
+    };
+    let udp = port.to_be_bytes();
+
+    let mut len = 0;
+    loop {
+        // The key/value pairs must be sorted by key.
+        let content = vec![
+            rlp_string(&[1]),
+            rlp_string(b"id"),
+            rlp_string(b"v4"),
+            rlp_string(ip_key),
+            rlp_string(&ip),
+            rlp_string(PAD_KEY.as_bytes()),
+            rlp_string(&vec![b'x'; len]),
+            rlp_string(b"secp256k1"),
+            rlp_string(&key.public().encode()),
+            rlp_string(udp_key),
+            rlp_string(&udp),
+        ];
+
+        let signature = key
+            .sign_v4(&rlp_list(&content))
+            .map_err(|e| format!("Failed to sign the ENR: {e:?}"))?;
+        let mut record = vec![rlp_string(&signature)];
+        record.extend(content);
+        let encoded = rlp_list(&record);
+
+        if encoded.len() > MAX_ENR_SIZE {
+            return Ok(format!("enr:{}", base64_url(&encoded)));
+        }
+        len += 1;
+    }
+}
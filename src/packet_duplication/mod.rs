@@ -0,0 +1,20 @@
+use crate::impaired_nodes::{self, Impairment};
+use crate::scenario::Registry;
+use testground::client::Client;
+use testground::network_conf::LinkShape;
+
+const DUPLICATION: Impairment = Impairment {
+    params: ("duplicate", "duplicate_corr"),
+    state: "state_network_duplicated",
+    apply: |link_shape, duplicate, duplicate_corr| LinkShape {
+        duplicate,
+        duplicate_corr,
+        ..link_shape
+    },
+};
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("packet-duplication", |client: Client| {
+        impaired_nodes::run(client, &DUPLICATION)
+    });
+}
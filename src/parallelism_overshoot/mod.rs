@@ -0,0 +1,194 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::parallelism_overshoot::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, listen_config,
+    publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::kbucket::ConnectionDirection;
+use discv5::Discv5;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("parallelism-overshoot", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+        .query_parallelism(params.query_parallelism)
+        .build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the bootstrap node. The routing table of the other nodes is seeded with the
+    // bootstrap node only, so the query parallelism exceeds the number of known peers.
+    if instance_info.seq == 1 {
+        for p in participants.iter().filter(|p| p.seq != instance_info.seq) {
+            discv5.add_enr(p.enr.clone())?;
+        }
+    } else {
+        let bootstrap_node = participants
+            .iter()
+            .find(|p| p.seq == 1)
+            .expect("Bootstrap node");
+        discv5.add_enr(bootstrap_node.enr.clone())?;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run FINDNODE query
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if instance_info.seq != 1 {
+        let known_peers = discv5.table_entries_id().len();
+        let started_at = Instant::now();
+        let result = tokio::time::timeout(
+            Duration::from_secs(params.lookup_timeout),
+            rpc_stats.find_node(&discv5, NodeId::random()),
+        )
+        .await;
+        let elapsed = started_at.elapsed();
+
+        let (completed, found) = match result {
+            Ok(Ok(enrs)) => {
+                info!("The query has completed. Found ENRs: {:?}", enrs);
+                (true, enrs.len())
+            }
+            Ok(Err(e)) => {
+                error!("Failed to run FINDNODE query: {e}");
+                failed = true;
+                (false, 0)
+            }
+            Err(_) => {
+                error!(
+                    "The query hasn't completed within {} seconds.",
+                    params.lookup_timeout
+                );
+                failed = true;
+                (false, 0)
+            }
+        };
+
+        // The peers we have connected to in outgoing direction are the ones contacted during the
+        // query.
+        let contacted = discv5
+            .kbuckets()
+            .iter()
+            .filter(|entry| {
+                entry.status.is_connected()
+                    && entry.status.direction == ConnectionDirection::Outgoing
+            })
+            .count();
+
+        client.record_message(format!(
+            "query_parallelism: {}, known_peers: {}, contacted: {}, completed: {}, elapsed: {}ms",
+            params.query_parallelism,
+            known_peers,
+            contacted,
+            completed,
+            elapsed.as_millis()
+        ));
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("query_parallelism", params.query_parallelism as u64)
+        .add_field("known_peers", known_peers as u64)
+        .add_field("contacted", contacted as u64)
+        .add_field("found", found as u64)
+        .add_field("completed", completed)
+        .add_field("elapsed_ms", elapsed.as_millis() as u64)
+        .add_tag("instance_seq", instance_info.seq);
+        record_metric(&client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
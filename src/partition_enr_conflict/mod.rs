@@ -0,0 +1,307 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::partition_enr_conflict::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, enr_ips,
+    listen_config, publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::{Discv5, Enr};
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_PARTITIONED: &str = "state_partitioned";
+const STATE_COMPLETED_TO_UPDATE: &str = "state_completed_to_update";
+const STATE_HEALED: &str = "state_healed";
+const STATE_COMPLETED_TO_OBSERVE: &str = "state_completed_to_observe";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("partition-enr-conflict", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: The participants are split into two sides. Each side constructs a star topology
+    // around its first node, and the two centers know each other so that the sides are
+    // connected.
+    let half = run_parameters.test_instance_count / 2;
+    let side = |seq: u64| seq <= half;
+    let center_a = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Center of side A")
+        .clone();
+    let center_b = participants
+        .iter()
+        .find(|p| p.seq == half + 1)
+        .expect("Center of side B")
+        .clone();
+    let own_center = if side(instance_info.seq) {
+        &center_a
+    } else {
+        &center_b
+    };
+
+    if instance_info.seq == own_center.seq {
+        for p in participants
+            .iter()
+            .filter(|p| p.seq != instance_info.seq && side(p.seq) == side(instance_info.seq))
+        {
+            discv5.add_enr(p.enr.clone())?;
+        }
+        let other_center = if side(instance_info.seq) {
+            &center_b
+        } else {
+            &center_a
+        };
+        discv5.add_enr(other_center.enr.clone())?;
+    } else {
+        discv5.add_enr(own_center.enr.clone())?;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Partition
+    // //////////////////////////////////////////////////////////////
+    // Packets from the other side are dropped by banning their IPs.
+    let other_side_ips = participants
+        .iter()
+        .filter(|p| side(p.seq) != side(instance_info.seq))
+        .flat_map(|p| enr_ips(&p.enr))
+        .collect::<Vec<_>>();
+    for ip in other_side_ips.iter() {
+        discv5.ban_ip(*ip, None);
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_PARTITIONED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Update the ENR within side A
+    // //////////////////////////////////////////////////////////////
+    // NOTE: The center of side A updates its ENR while partitioned, so only side A can learn the
+    // new sequence number.
+    let updated_seq = center_a.enr.seq() + 1;
+    if instance_info.seq == center_a.seq {
+        discv5
+            .enr_insert("partition", &1_u64)
+            .map_err(|e| format!("Failed to update the local ENR: {e:?}"))?;
+        client.record_message(format!(
+            "Updated the local ENR. seq: {} -> {}",
+            center_a.enr.seq(),
+            discv5.local_enr().seq()
+        ));
+
+        // Notify the nodes on side A of the new sequence number.
+        for p in participants
+            .iter()
+            .filter(|p| p.seq != instance_info.seq && side(p.seq))
+        {
+            if let Err(e) = discv5.send_ping(p.enr.clone()).await {
+                error!("Failed to send PING: {e}");
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_UPDATE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    tokio::time::sleep(Duration::from_secs(params.partition_duration)).await;
+
+    let seq_at_heal = observed_seq(&discv5, &center_a.enr);
+    client.record_message(format!(
+        "The sequence number of #1 at heal: {seq_at_heal:?}"
+    ));
+
+    // //////////////////////////////////////////////////////////////
+    // Heal
+    // //////////////////////////////////////////////////////////////
+    for ip in other_side_ips.iter() {
+        discv5.ban_ip_remove(ip);
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_HEALED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+    let healed_at = Instant::now();
+
+    // //////////////////////////////////////////////////////////////
+    // Reconcile the ENR of the updater
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+    if instance_info.seq != center_a.seq {
+        let target_id = center_a.enr.node_id();
+        let mut seq = seq_at_heal;
+        let mut converged_at = None;
+
+        while healed_at.elapsed() < Duration::from_secs(params.observation_duration) {
+            match tokio::time::timeout(
+                Duration::from_secs(params.lookup_timeout),
+                rpc_stats.find_node(&discv5, target_id),
+            )
+            .await
+            {
+                Ok(Ok(enrs)) => {
+                    // Take the highest sequence number seen so far in the local view and the lookup
+                    // results.
+                    seq = enrs
+                        .iter()
+                        .filter(|enr| enr.node_id() == target_id)
+                        .map(|enr| enr.seq())
+                        .chain(observed_seq(&discv5, &center_a.enr))
+                        .chain(seq)
+                        .max();
+                }
+                Ok(Err(e)) => error!("Failed to run FINDNODE query: {e}"),
+                Err(_) => error!(
+                    "The query hasn't completed within {} seconds.",
+                    params.lookup_timeout
+                ),
+            }
+
+            if seq == Some(updated_seq) {
+                converged_at = Some(healed_at.elapsed());
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(params.lookup_interval)).await;
+        }
+
+        let side_name = if side(instance_info.seq) { "A" } else { "B" };
+        match converged_at {
+            Some(elapsed) => info!(
+                "side: {side_name}, converged to seq {updated_seq} in {}ms after the heal.",
+                elapsed.as_millis()
+            ),
+            None => {
+                error!(
+                    "side: {side_name}, hasn't converged to seq {updated_seq} within {} seconds after the heal. observed: {seq:?}",
+                    params.observation_duration
+                );
+                failed = true;
+            }
+        }
+        client.record_message(format!(
+            "side: {side_name}, seq at heal: {seq_at_heal:?}, converged seq: {seq:?}"
+        ));
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let mut write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("seq_at_heal", seq_at_heal.unwrap_or_default())
+        .add_field("converged_seq", seq.unwrap_or_default())
+        .add_field("converged", converged_at.is_some())
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("side", side_name);
+        if let Some(elapsed) = converged_at {
+            write_query = write_query.add_field("time_to_converge_ms", elapsed.as_millis() as u64);
+        }
+        record_metric(&client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_OBSERVE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// The sequence number of `enr` in the local view, i.e. the routing table or the sessions.
+fn observed_seq(discv5: &Discv5, enr: &Enr) -> Option<u64> {
+    discv5.find_enr(&enr.node_id()).map(|enr| enr.seq())
+}
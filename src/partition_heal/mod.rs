@@ -0,0 +1,245 @@
+mod params;
+
+use crate::config::build_config;
+use crate::partition_heal::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_ip, heal_network,
+    listen_config, partition_network, publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::Discv5;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const STATE_CONVERGED: &str = "state_converged";
+const STATE_PARTITIONED: &str = "state_partitioned";
+const STATE_HEALED: &str = "state_healed";
+const STATE_COMPLETED_TO_OBSERVE: &str = "state_completed_to_observe";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("partition-heal", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = build_config(listen_config, &run_parameters)?;
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Converge
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 knows all the other participants, and the others look themselves up via #1, so
+    // that the routing tables contain the nodes of both sides before the partition.
+    if instance_info.seq == 1 {
+        for p in participants.iter().filter(|p| p.seq != 1) {
+            if let Err(e) = discv5.add_enr(p.enr.clone()) {
+                error!("Failed to add the ENR of #{}: {e}", p.seq);
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_CONVERGED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    if instance_info.seq != 1 {
+        if let Err(e) = rpc_stats
+            .find_node(&discv5, instance_info.enr.node_id())
+            .await
+        {
+            warn!("Failed to run the self-lookup: {e}");
+        }
+    }
+
+    // The participants are split into two sides, by the first half of the seqs and the rest.
+    let half = run_parameters.test_instance_count / 2;
+    let side = |seq: u64| seq <= half;
+    let other_side = participants
+        .iter()
+        .filter(|p| side(p.seq) != side(instance_info.seq))
+        .collect::<Vec<_>>();
+    let other_side_node_ids = other_side
+        .iter()
+        .map(|p| p.enr.node_id())
+        .collect::<HashSet<_>>();
+    let before_partition = cross_entries(&discv5, &other_side_node_ids);
+
+    // //////////////////////////////////////////////////////////////
+    // Partition
+    // //////////////////////////////////////////////////////////////
+    // The packets to the other side are dropped by the network, rather than by discv5, so the
+    // routing tables diverge as the entries of the other side fail to respond.
+    let other_side_ips = other_side
+        .iter()
+        .map(|p| enr_ip(&p.enr))
+        .collect::<Result<Vec<_>, _>>()?;
+    partition_network(&client, &other_side_ips, STATE_PARTITIONED.to_owned()).await?;
+
+    tokio::time::sleep(Duration::from_secs(params.partition_duration)).await;
+    let at_heal = cross_entries(&discv5, &other_side_node_ids);
+    info!("Connected entries of the other side. before partition: {before_partition}, at heal: {at_heal}");
+
+    // //////////////////////////////////////////////////////////////
+    // Heal
+    // //////////////////////////////////////////////////////////////
+    heal_network(&client, STATE_HEALED.to_owned()).await?;
+    let healed_at = Instant::now();
+
+    // //////////////////////////////////////////////////////////////
+    // Run cross-partition lookups
+    // //////////////////////////////////////////////////////////////
+    // The target is the node on the other side with the same position.
+    let target_seq = if side(instance_info.seq) {
+        instance_info.seq + half
+    } else {
+        instance_info.seq - half
+    };
+    // NOTE: If the number of instances is odd, the last node has no counterpart.
+    let target = other_side.iter().find(|p| p.seq == target_seq);
+
+    let mut failed = false;
+    if let Some(target) = target {
+        let target_id = target.enr.node_id();
+        let mut recovered_after = None;
+
+        while healed_at.elapsed() < Duration::from_secs(params.observation_duration) {
+            let result = tokio::time::timeout(
+                Duration::from_secs(params.lookup_timeout),
+                rpc_stats.find_node(&discv5, target_id),
+            )
+            .await;
+
+            let found = match result {
+                Ok(Ok(enrs)) => enrs.iter().any(|enr| enr.node_id() == target_id),
+                Ok(Err(e)) => {
+                    warn!("Failed to run FINDNODE query: {e}");
+                    false
+                }
+                Err(_) => {
+                    warn!(
+                        "The query hasn't completed within {} seconds.",
+                        params.lookup_timeout
+                    );
+                    false
+                }
+            };
+            if found {
+                recovered_after = Some(healed_at.elapsed());
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(params.lookup_interval)).await;
+        }
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("cross_entries_before_partition", before_partition as u64)
+        .add_field("cross_entries_at_heal", at_heal as u64)
+        .add_field("recovered", recovered_after.is_some())
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("target_seq", target.seq);
+        let write_query = match recovered_after {
+            Some(elapsed) => write_query.add_field("recovery_ms", elapsed.as_millis() as u64),
+            None => write_query,
+        };
+        record_metric(&client, write_query).await;
+
+        match recovered_after {
+            Some(elapsed) => client.record_message(format!(
+                "Found #{} {}ms after the partition healed.",
+                target.seq,
+                elapsed.as_millis()
+            )),
+            None => {
+                error!(
+                    "#{} hasn't been found within {} seconds after the partition healed.",
+                    target.seq, params.observation_duration
+                );
+                failed = true;
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_OBSERVE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// The number of the connected entries in the routing table which are on the other side.
+fn cross_entries(discv5: &Discv5, other_side: &HashSet<NodeId>) -> usize {
+    discv5
+        .kbuckets()
+        .iter()
+        .filter(|entry| {
+            entry.status.is_connected() && other_side.contains(entry.node.key.preimage())
+        })
+        .count()
+}
@@ -0,0 +1,22 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub partition_duration: u64,
+    pub observation_duration: u64,
+    pub lookup_interval: u64,
+    pub lookup_timeout: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            partition_duration: get_param_or::<u64>("partition_duration", instance_params, 30)?,
+            observation_duration: get_param_or::<u64>("observation_duration", instance_params, 60)?,
+            lookup_interval: get_param_or::<u64>("lookup_interval", instance_params, 500)?,
+            lookup_timeout: get_param_or::<u64>("lookup_timeout", instance_params, 5)?,
+        })
+    }
+}
@@ -0,0 +1,191 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, get_param,
+    listen_config, publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::Discv5;
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_CONNECT: &str = "state_completed_to_connect";
+const STATE_COMPLETED_TO_SEND_PING: &str = "state_completed_to_send_ping";
+const STATE_FINISHED: &str = "state_finished";
+
+// Long enough that no PING is sent other than the one sent explicitly in this test, so the ENR
+// can only be updated in response to that PING.
+const PING_INTERVAL: Duration = Duration::from_secs(3600);
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("ping-enr-seq-mismatch", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let wait_duration = get_param::<u64>("wait_duration", &run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+        .ping_interval(PING_INTERVAL)
+        .build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // NOTE: #1 is the node that updates its ENR.
+    let updater = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("updater")
+        .clone();
+
+    // //////////////////////////////////////////////////////////////
+    // Establish sessions
+    // //////////////////////////////////////////////////////////////
+    if instance_info.seq == updater.seq {
+        for p in participants.iter().filter(|&p| p.seq != instance_info.seq) {
+            if let Err(e) = rpc_stats
+                .find_node_designated_peer(&discv5, p.enr.clone(), vec![0])
+                .await
+            {
+                error!("Failed to run FIND_NODE query: {e}");
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_CONNECT,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Update the ENR and send PING with the new sequence number
+    // //////////////////////////////////////////////////////////////
+    if instance_info.seq == updater.seq {
+        discv5
+            .enr_insert("ping_test", &1_u64)
+            .map_err(|e| format!("Failed to update the local ENR: {e:?}"))?;
+        client.record_message(format!(
+            "Updated the local ENR. seq: {} -> {}",
+            updater.enr.seq(),
+            discv5.local_enr().seq()
+        ));
+
+        for p in participants.iter().filter(|&p| p.seq != instance_info.seq) {
+            if let Err(e) = discv5.send_ping(p.enr.clone()).await {
+                error!("Failed to send PING: {e}");
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_SEND_PING,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Check whether the peers have fetched the new ENR
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if instance_info.seq != updater.seq {
+        // Give the peers time to request the updated ENR.
+        tokio::time::sleep(Duration::from_secs(wait_duration)).await;
+
+        let observed_seq = discv5.find_enr(&updater.enr.node_id()).map(|enr| enr.seq());
+        let fetched = matches!(observed_seq, Some(seq) if seq > updater.enr.seq());
+        if fetched {
+            info!("Fetched the updated ENR. seq: {:?}", observed_seq);
+        } else {
+            error!(
+                "The updated ENR hasn't been fetched. initial seq: {}, observed seq: {:?}",
+                updater.enr.seq(),
+                observed_seq
+            );
+            failed = true;
+        }
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("initial_seq", updater.enr.seq())
+        .add_field("observed_seq", observed_seq.unwrap_or_default())
+        .add_field("fetched", fetched)
+        .add_tag("instance_seq", instance_info.seq);
+        record_metric(&client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_FINISHED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,257 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::post_heal_lookup::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, enr_ips,
+    listen_config, publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::Discv5;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_PARTITIONED: &str = "state_partitioned";
+const STATE_HEALED: &str = "state_healed";
+const STATE_COMPLETED_TO_OBSERVE: &str = "state_completed_to_observe";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("post-heal-lookup", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: The participants are split into two sides. Each side constructs a star topology
+    // around its first node, and the two centers know each other so that the sides are
+    // connected.
+    let half = run_parameters.test_instance_count / 2;
+    let side = |seq: u64| seq <= half;
+    let center_a = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Center of side A")
+        .clone();
+    let center_b = participants
+        .iter()
+        .find(|p| p.seq == half + 1)
+        .expect("Center of side B")
+        .clone();
+    let own_center = if side(instance_info.seq) {
+        &center_a
+    } else {
+        &center_b
+    };
+
+    if instance_info.seq == own_center.seq {
+        for p in participants
+            .iter()
+            .filter(|p| p.seq != instance_info.seq && side(p.seq) == side(instance_info.seq))
+        {
+            discv5.add_enr(p.enr.clone())?;
+        }
+        let other_center = if side(instance_info.seq) {
+            &center_b
+        } else {
+            &center_a
+        };
+        discv5.add_enr(other_center.enr.clone())?;
+    } else {
+        discv5.add_enr(own_center.enr.clone())?;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Partition
+    // //////////////////////////////////////////////////////////////
+    // Packets from the other side are dropped by banning their IPs.
+    let other_side_ips = participants
+        .iter()
+        .filter(|p| side(p.seq) != side(instance_info.seq))
+        .flat_map(|p| enr_ips(&p.enr))
+        .collect::<Vec<_>>();
+    for ip in other_side_ips.iter() {
+        discv5.ban_ip(*ip, None);
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_PARTITIONED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    tokio::time::sleep(Duration::from_secs(params.partition_duration)).await;
+
+    // //////////////////////////////////////////////////////////////
+    // Heal
+    // //////////////////////////////////////////////////////////////
+    for ip in other_side_ips.iter() {
+        discv5.ban_ip_remove(ip);
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_HEALED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+    let healed_at = Instant::now();
+
+    // //////////////////////////////////////////////////////////////
+    // Run cross-partition lookups
+    // //////////////////////////////////////////////////////////////
+    // The target is the node on the other side with the same position.
+    let target_seq = if side(instance_info.seq) {
+        instance_info.seq + half
+    } else {
+        instance_info.seq - half
+    };
+    // NOTE: If the number of instances is odd, the last node has no counterpart.
+    let target = participants
+        .iter()
+        .find(|p| p.seq == target_seq && side(p.seq) != side(instance_info.seq));
+
+    let mut failed = false;
+    if let Some(target) = target {
+        let target_id = target.enr.node_id();
+        let mut found = false;
+
+        while healed_at.elapsed() < Duration::from_secs(params.observation_duration) {
+            let since_heal = healed_at.elapsed();
+            let started_at = Instant::now();
+            let result = tokio::time::timeout(
+                Duration::from_secs(params.lookup_timeout),
+                rpc_stats.find_node(&discv5, target_id),
+            )
+            .await;
+            let elapsed = started_at.elapsed();
+
+            found = match result {
+                Ok(Ok(enrs)) => enrs.iter().any(|enr| enr.node_id() == target_id),
+                Ok(Err(e)) => {
+                    error!("Failed to run FINDNODE query: {e}");
+                    false
+                }
+                Err(_) => {
+                    error!(
+                        "The query hasn't completed within {} seconds.",
+                        params.lookup_timeout
+                    );
+                    false
+                }
+            };
+            info!(
+                "since_heal: {}ms, elapsed: {}ms, found: {found}",
+                since_heal.as_millis(),
+                elapsed.as_millis()
+            );
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("since_heal_ms", since_heal.as_millis() as u64)
+            .add_field("elapsed_ms", elapsed.as_millis() as u64)
+            .add_field("found", found)
+            .add_tag("instance_seq", instance_info.seq);
+            record_metric(&client, write_query).await;
+
+            tokio::time::sleep(Duration::from_millis(params.lookup_interval)).await;
+        }
+
+        if !found {
+            error!(
+                "The target hasn't been found within {} seconds after the partition healed.",
+                params.observation_duration
+            );
+            failed = true;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_OBSERVE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
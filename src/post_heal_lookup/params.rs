@@ -0,0 +1,22 @@
+use crate::utils::get_param;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub partition_duration: u64,
+    pub observation_duration: u64,
+    pub lookup_interval: u64,
+    pub lookup_timeout: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            partition_duration: get_param::<u64>("partition_duration", instance_params)?,
+            observation_duration: get_param::<u64>("observation_duration", instance_params)?,
+            lookup_interval: get_param::<u64>("lookup_interval", instance_params)?,
+            lookup_timeout: get_param::<u64>("lookup_timeout", instance_params)?,
+        })
+    }
+}
@@ -0,0 +1,156 @@
+use crate::config::config_builder;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, check_enr_port, instance_key, listen_config,
+    publish_and_collect, record_metric, udp_port, InstanceInfo,
+};
+use chrono::Local;
+use discv5::{Discv5, Enr};
+use std::net::{IpAddr, Ipv4Addr};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_PROBE: &str = "state_completed_to_probe";
+
+// A private address which no instance is reachable at.
+const UNREACHABLE_PRIVATE_IP: Ipv4Addr = Ipv4Addr::new(10, 255, 255, 254);
+
+pub(super) fn register(registry: &mut Registry) {
+    // IPv4-only, since #2 advertises the private IPv4 address `UNREACHABLE_PRIVATE_IP`.
+    registry.register_ipv4_only("private-ip-enr", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let ip = run_parameters
+        .data_network_ip()?
+        .ok_or("No IP address for the data network.")?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    // NOTE: #1 advertises its data network IP, whereas #2 advertises a private IP which it isn't
+    // reachable at. The other nodes are probers.
+    let advertised_ip = if client.global_seq() == 2 {
+        IpAddr::V4(UNREACHABLE_PRIVATE_IP)
+    } else {
+        ip
+    };
+    let enr_key = instance_key(&run_parameters, client.global_seq())?;
+    let enr = Enr::builder()
+        .ip(advertised_ip)
+        .udp4(udp_port(&run_parameters.test_instance_params)?)
+        .build(&enr_key)
+        .expect("enr");
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}, advertised_ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        ip,
+        advertised_ip,
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Probe the reachability
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if instance_info.seq > 2 {
+        for p in participants.iter().filter(|p| p.seq <= 2) {
+            let Some(ip) = p.enr.ip4() else {
+                error!(
+                    "#{}'s ENR has no IPv4 address. node_id: {}",
+                    p.seq,
+                    p.enr.node_id()
+                );
+                failed = true;
+                continue;
+            };
+            let reachable = match discv5.send_ping(p.enr.clone()).await {
+                Ok(_) => true,
+                Err(e) => {
+                    warn!("Failed to send PING to #{}: {e}", p.seq);
+                    false
+                }
+            };
+            info!(
+                "#{}: ip: {ip}, private: {}, reachable: {reachable}",
+                p.seq,
+                ip.is_private()
+            );
+
+            // The node advertising its data network IP should be reachable, regardless of
+            // whether the IP is in a private range.
+            if p.seq == 1 && !reachable {
+                error!("#1 is unreachable at its data network IP {ip}.");
+                failed = true;
+            }
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("private", ip.is_private())
+            .add_field("reachable", reachable)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("target_seq", p.seq);
+            record_metric(&client, write_query).await;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_PROBE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{build_enr, check_enr_port, listen_config, record_metric};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::Discv5;
+use std::sync::Arc;
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+// Duration to wait for the query, regarded as hanging if exceeded.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("query-before-start", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Construct discv5, but don't start it
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let discv5 = Arc::new(Discv5::new(enr, enr_key, config)?);
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Run FINDNODE query before starting discv5
+    // //////////////////////////////////////////////////////////////
+    // The query runs in a separate task so that a panic can be observed.
+    let handle = tokio::spawn({
+        let discv5 = discv5.clone();
+        let rpc_stats = rpc_stats.clone();
+        async move {
+            tokio::time::timeout(
+                QUERY_TIMEOUT,
+                rpc_stats.find_node(&discv5, NodeId::random()),
+            )
+            .await
+        }
+    });
+
+    let mut failed = false;
+    let outcome = match handle.await {
+        Ok(Ok(Ok(enrs))) => {
+            error!("The query has succeeded before starting discv5: {:?}", enrs);
+            failed = true;
+            "succeeded".to_owned()
+        }
+        Ok(Ok(Err(e))) => {
+            info!("The query has returned an error: {e}");
+            format!("error: {e}")
+        }
+        Ok(Err(_)) => {
+            error!(
+                "The query has hung for {} seconds.",
+                QUERY_TIMEOUT.as_secs()
+            );
+            failed = true;
+            "hung".to_owned()
+        }
+        Err(e) => {
+            error!("The query has panicked: {e}");
+            failed = true;
+            "panicked".to_owned()
+        }
+    };
+    client.record_message(format!("outcome: {outcome}"));
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("outcome", outcome)
+    .add_tag("instance_seq", client.global_seq());
+    record_metric(&client, write_query).await;
+
+    rpc_stats.finish(&client, client.global_seq()).await;
+    teardown(
+        &client,
+        discv5,
+        client.global_seq(),
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
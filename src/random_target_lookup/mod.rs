@@ -0,0 +1,263 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::random_target_lookup::params::Params;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, collect_from_stream,
+    enr_addresses, listen_config, publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::kbucket::ConnectionDirection;
+use discv5::Discv5;
+use rand::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_AGGREGATE: &str = "state_completed_to_aggregate";
+const TOPIC_LOOKUP_RESULT: &str = "lookup_result";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct LookupResult {
+    seq: u64,
+    target: String,
+    elapsed_millis: u64,
+    contacted: u64,
+    succeeded: bool,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("random-target-lookup", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the bootstrap node, which knows all the other nodes, and the other nodes know
+    // only the bootstrap node.
+    let bootstrap_node = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Bootstrap node")
+        .clone();
+
+    if instance_info.seq == bootstrap_node.seq {
+        for p in participants.iter().filter(|p| p.seq != bootstrap_node.seq) {
+            discv5.add_enr(p.enr.clone())?;
+        }
+    } else {
+        discv5.add_enr(bootstrap_node.enr.clone())?;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run a lookup for a fresh random target
+    // //////////////////////////////////////////////////////////////
+    // The target is derived from the seed and the instance sequence number, so that a run can be
+    // reproduced with the same seed.
+    let target = random_target(params.seed + instance_info.seq);
+    client.record_message(format!("target: {target}"));
+
+    let started_at = Instant::now();
+    let result = tokio::time::timeout(
+        Duration::from_secs(params.lookup_timeout),
+        rpc_stats.find_node(&discv5, target),
+    )
+    .await;
+    let elapsed = started_at.elapsed();
+
+    let succeeded = match result {
+        Ok(Ok(enrs)) => !enrs.is_empty(),
+        Ok(Err(e)) => {
+            error!("Failed to run FINDNODE query: {e}");
+            false
+        }
+        Err(_) => {
+            error!(
+                "The query hasn't completed within {} seconds.",
+                params.lookup_timeout
+            );
+            false
+        }
+    };
+
+    // discv5 doesn't expose the number of hops of a query, so the peers we have connected to in
+    // outgoing direction are taken as the ones contacted during the query.
+    let contacted = discv5
+        .kbuckets()
+        .iter()
+        .filter(|entry| {
+            entry.status.is_connected() && entry.status.direction == ConnectionDirection::Outgoing
+        })
+        .count() as u64;
+    info!(
+        "target: {target}, elapsed: {}ms, contacted: {contacted}, succeeded: {succeeded}",
+        elapsed.as_millis()
+    );
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("elapsed_ms", elapsed.as_millis() as u64)
+    .add_field("contacted", contacted)
+    .add_field("succeeded", succeeded)
+    .add_tag("instance_seq", instance_info.seq)
+    .add_tag("target", target.to_string());
+    record_metric(&client, write_query).await;
+
+    client
+        .publish(
+            TOPIC_LOOKUP_RESULT,
+            Cow::Owned(serde_json::to_value(LookupResult {
+                seq: instance_info.seq,
+                target: target.to_string(),
+                elapsed_millis: elapsed.as_millis() as u64,
+                contacted,
+                succeeded,
+            })?),
+        )
+        .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Aggregate the results across the fleet
+    // //////////////////////////////////////////////////////////////
+    // NOTE: The bootstrap node is the designated node which aggregates the results of all the
+    // nodes.
+    let mut failed = !succeeded;
+
+    if instance_info.seq == bootstrap_node.seq {
+        let results: Vec<LookupResult> = collect_from_stream(
+            &client,
+            client.subscribe(TOPIC_LOOKUP_RESULT, u16::MAX.into()).await,
+            run_parameters.test_instance_count,
+        )
+        .await?;
+
+        let count = results.len() as f64;
+        let succeeded_count = results.iter().filter(|r| r.succeeded).count();
+        let mean_elapsed = results.iter().map(|r| r.elapsed_millis).sum::<u64>() as f64 / count;
+        let mean_contacted = results.iter().map(|r| r.contacted).sum::<u64>() as f64 / count;
+        client.record_message(format!(
+            "succeeded: {succeeded_count}/{}, mean elapsed: {mean_elapsed:.2}ms, mean contacted: {mean_contacted:.2}",
+            results.len()
+        ));
+
+        for r in results.iter().filter(|r| !r.succeeded) {
+            error!(
+                "The lookup has failed. seq: {}, target: {}",
+                r.seq, r.target
+            );
+            failed = true;
+        }
+
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("lookups", results.len() as u64)
+        .add_field("succeeded", succeeded_count as u64)
+        .add_field("mean_elapsed_ms", mean_elapsed)
+        .add_field("mean_contacted", mean_contacted)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("aggregate", true);
+        record_metric(&client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_AGGREGATE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Generate a random node id from the given seed.
+fn random_target(seed: u64) -> NodeId {
+    let rng = &mut rand_xorshift::XorShiftRng::seed_from_u64(seed);
+    let mut raw = [0; 32];
+    rng.fill_bytes(&mut raw);
+    NodeId::new(&raw)
+}
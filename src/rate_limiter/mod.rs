@@ -0,0 +1,288 @@
+mod params;
+
+use crate::config::build_config;
+use crate::rate_limiter::params::Params;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, get_param, listen_config,
+    publish_and_collect, publish_and_collect_on, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::Discv5;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_ESTABLISH_SESSIONS: &str = "state_completed_to_establish_sessions";
+const STATE_ABUSE: &str = "state_abuse";
+const STATE_COMPLETED_TO_ABUSE: &str = "state_completed_to_abuse";
+const TOPIC_RESULTS: &str = "rate_limiter_results";
+
+// The seconds allowed on top of `abuse_duration`, for the burst of the rate limiter and the
+// requests retried after the abuse.
+const SLACK_SECS: u64 = 3;
+// The interval between the PINGs of the well-behaved node.
+const WELL_BEHAVED_INTERVAL: Duration = Duration::from_secs(1);
+
+// The roles of the participants, which are given by the groups. See
+// `compositions/rate-limiter.toml`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum Role {
+    // The node the others send their PINGs to.
+    Target,
+    // Floods the target with PINGs.
+    Abuser,
+    // Sends PINGs to the target at a modest rate.
+    WellBehaved,
+}
+
+impl Role {
+    fn from_group_id(test_group_id: &str) -> Result<Role, String> {
+        match test_group_id {
+            "target" => Ok(Role::Target),
+            "abusers" => Ok(Role::Abuser),
+            "well-behaved" => Ok(Role::WellBehaved),
+            _ => Err(format!(
+                "The group `{test_group_id}` isn't named after any of the roles of this test case."
+            )),
+        }
+    }
+}
+
+// The PINGs each instance has sent to the target, which are shared with the target.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PingResult {
+    seq: u64,
+    role: Role,
+    attempted: u64,
+    served: u64,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("rate-limiter", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+    let role = Role::from_group_id(&run_parameters.test_group_id)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    // NOTE: The packet filter of the target is configured by the `filter_ip_limit` and
+    // `filter_total_limit` params of the `target` group. See `compositions/rate-limiter.toml`.
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5: Discv5 =
+        Discv5::new(enr, enr_key, build_config(listen_config, &run_parameters)?)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, discv5.local_enr())?;
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+    let target = participants
+        .iter()
+        .find(|p| Role::from_group_id(&p.group_id) == Ok(Role::Target))
+        .ok_or("No target found.")?
+        .enr
+        .clone();
+
+    // //////////////////////////////////////////////////////////////
+    // Establish sessions with the target
+    // //////////////////////////////////////////////////////////////
+    // The handshakes are done in advance, so that the PINGs in the abuse are the only packets.
+    if role != Role::Target {
+        if let Err(e) = discv5.send_ping(target.clone()).await {
+            warn!("Failed to establish a session with the target: {e}");
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_ESTABLISH_SESSIONS,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Abuse the target
+    // //////////////////////////////////////////////////////////////
+    barrier_with_timeout(
+        &client,
+        STATE_ABUSE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    let (attempted, served) = match role {
+        Role::Target => (0, 0),
+        // The abusers send PINGs at `abuse_rate` regardless of the responses.
+        Role::Abuser => {
+            let attempts = params.abuse_rate * params.abuse_duration;
+            let interval = Duration::from_secs(1) / params.abuse_rate as u32;
+            let served = stream::iter(0..attempts)
+                .map(|i| {
+                    let discv5 = &discv5;
+                    let target = target.clone();
+                    async move {
+                        tokio::time::sleep(interval * i as u32).await;
+                        discv5.send_ping(target).await.is_ok()
+                    }
+                })
+                .buffer_unordered(attempts as usize)
+                .filter(|ok| futures::future::ready(*ok))
+                .count()
+                .await as u64;
+            (attempts, served)
+        }
+        Role::WellBehaved => {
+            let attempts = params.abuse_duration;
+            let mut served = 0;
+            for _ in 0..attempts {
+                let started_at = tokio::time::Instant::now();
+                match discv5.send_ping(target.clone()).await {
+                    Ok(_) => served += 1,
+                    Err(e) => warn!("Failed to send PING to the target: {e}"),
+                }
+                tokio::time::sleep_until(started_at + WELL_BEHAVED_INTERVAL).await;
+            }
+            (attempts, served)
+        }
+    };
+    info!("role: {role:?}, attempted: {attempted}, served: {served}");
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_ABUSE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    if role != Role::Target {
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("attempted", attempted)
+        .add_field("served", served)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("role", format!("{role:?}"));
+        record_metric(&client, write_query).await;
+    }
+
+    let result = PingResult {
+        seq: instance_info.seq,
+        role,
+        attempted,
+        served,
+    };
+    let results = publish_and_collect_on(&client, TOPIC_RESULTS, result).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Verify the rate limits on the target
+    // //////////////////////////////////////////////////////////////
+    let failed = role == Role::Target && !verify(&client, &params, &results)?;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Check that each abuser has been served within `filter_ip_limit`, that all of them have been
+// served within `filter_total_limit`, and that the well-behaved nodes haven't been affected.
+fn verify(
+    client: &Client,
+    params: &Params,
+    results: &[PingResult],
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let instance_params = &client.run_parameters().test_instance_params;
+    let window = params.abuse_duration + SLACK_SECS;
+    let mut ok = true;
+
+    if instance_params.contains_key("filter_ip_limit") {
+        let allowed = get_param::<u64>("filter_ip_limit", instance_params)? * window;
+        for r in results.iter().filter(|r| r.role == Role::Abuser) {
+            if r.served > allowed {
+                error!(
+                    "#{} has been served {} of {} PINGs, more than {allowed} allowed by `filter_ip_limit`.",
+                    r.seq, r.served, r.attempted
+                );
+                ok = false;
+            }
+        }
+    }
+
+    let served = results.iter().map(|r| r.served).sum::<u64>();
+    if instance_params.contains_key("filter_total_limit") {
+        let allowed = get_param::<u64>("filter_total_limit", instance_params)? * window;
+        if served > allowed {
+            error!("{served} PINGs have been served, more than {allowed} allowed by `filter_total_limit`.");
+            ok = false;
+        }
+    }
+
+    for r in results.iter().filter(|r| r.role == Role::WellBehaved) {
+        let success_rate = if r.attempted == 0 {
+            1.0
+        } else {
+            r.served as f64 / r.attempted as f64
+        };
+        if success_rate < params.min_well_behaved_success_rate {
+            error!(
+                "The well-behaved #{} has been served only {} of {} PINGs.",
+                r.seq, r.served, r.attempted
+            );
+            ok = false;
+        }
+    }
+
+    let abusers = results.iter().filter(|r| r.role == Role::Abuser);
+    client.record_message(format!(
+        "served {served} PINGs in total, {} of {} PINGs of the abusers",
+        abusers.clone().map(|r| r.served).sum::<u64>(),
+        abusers.map(|r| r.attempted).sum::<u64>()
+    ));
+
+    Ok(ok)
+}
@@ -0,0 +1,30 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub abuse_rate: u64,
+    pub abuse_duration: u64,
+    pub min_well_behaved_success_rate: f64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        let params = Params {
+            abuse_rate: get_param_or::<u64>("abuse_rate", instance_params, 50)?,
+            abuse_duration: get_param_or::<u64>("abuse_duration", instance_params, 10)?,
+            min_well_behaved_success_rate: get_param_or::<f64>(
+                "min_well_behaved_success_rate",
+                instance_params,
+                0.9,
+            )?,
+        };
+
+        if params.abuse_rate == 0 {
+            return Err("`abuse_rate` must be greater than 0.".into());
+        }
+
+        Ok(params)
+    }
+}
@@ -0,0 +1,141 @@
+use crate::baseline::regressions;
+use crate::rpc_stats::PeerRpcStats;
+use crate::utils::{percentile, Exchange};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use testground::client::Client;
+use tracing::warn;
+
+const TOPIC_REPORTS: &str = "reports";
+// The file names of the artifacts in the outputs directory of the instances.
+const REPORT_FILE: &str = "report.json";
+const SUMMARY_FILE: &str = "summary.json";
+
+/// The result of a test instance, which the test cases fill while running. It is written to
+/// `report.json` in the outputs directory of the instance, so that the results can be
+/// post-processed without parsing the messages.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Report {
+    pub seq: u64,
+    pub group_id: String,
+    // The number of the connected peers in the routing table at the end of the test.
+    pub peers_discovered: usize,
+    pub lookups: u64,
+    // The latencies of the lookups which have completed.
+    pub lookup_latencies_ms: Vec<u64>,
+    // The outcomes of the outbound operations per peer, see `RpcStats`.
+    pub rpc_stats: Vec<PeerRpcStats>,
+    pub failures: Vec<String>,
+}
+
+impl Report {
+    pub(crate) fn new(client: &Client) -> Self {
+        Report {
+            seq: client.global_seq(),
+            group_id: client.run_parameters().test_group_id,
+            ..Default::default()
+        }
+    }
+
+    /// Record a lookup, which has completed if `elapsed` is given.
+    pub(crate) fn record_lookup(&mut self, elapsed: Option<Duration>) {
+        self.lookups += 1;
+        if let Some(elapsed) = elapsed {
+            self.lookup_latencies_ms.push(elapsed.as_millis() as u64);
+        }
+    }
+}
+
+/// The summary of the reports of all the instances, which is written to `summary.json` in the
+/// outputs directory of the instance #1.
+#[derive(Debug, Serialize)]
+struct Summary {
+    instances: usize,
+    min_peers_discovered: Option<usize>,
+    mean_peers_discovered: Option<f64>,
+    lookups: u64,
+    completed_lookups: usize,
+    p50_lookup_latency_ms: Option<u64>,
+    p90_lookup_latency_ms: Option<u64>,
+    p99_lookup_latency_ms: Option<u64>,
+    failures: usize,
+    // The seqs of the instances which have reported any failure.
+    failed_instances: Vec<u64>,
+}
+
+impl Summary {
+    fn new(reports: &[Report]) -> Self {
+        let mut latencies = reports
+            .iter()
+            .flat_map(|r| r.lookup_latencies_ms.iter().copied())
+            .collect::<Vec<_>>();
+        latencies.sort_unstable();
+        let peers = reports.iter().map(|r| r.peers_discovered);
+
+        Summary {
+            instances: reports.len(),
+            min_peers_discovered: peers.clone().min(),
+            mean_peers_discovered: if reports.is_empty() {
+                None
+            } else {
+                Some(peers.sum::<usize>() as f64 / reports.len() as f64)
+            },
+            lookups: reports.iter().map(|r| r.lookups).sum(),
+            completed_lookups: latencies.len(),
+            p50_lookup_latency_ms: percentile(&latencies, 50),
+            p90_lookup_latency_ms: percentile(&latencies, 90),
+            p99_lookup_latency_ms: percentile(&latencies, 99),
+            failures: reports.iter().map(|r| r.failures.len()).sum(),
+            failed_instances: reports
+                .iter()
+                .filter(|r| !r.failures.is_empty())
+                .map(|r| r.seq)
+                .collect(),
+        }
+    }
+}
+
+/// Write the report of this instance to the outputs directory, and share it with the other
+/// instances. The instance #1 then writes the summary of all the reports, and returns the
+/// regressions of the summary against the baseline, see `baseline::regressions`. All the `count`
+/// instances taking part, e.g. all the instances but the external peers, must call this function,
+/// as it waits for the reports of all of them.
+pub(crate) async fn write_report(
+    client: &Client,
+    report: Report,
+    count: u64,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let outputs = Path::new(&run_parameters.test_outputs_path);
+    write_json(&outputs.join(REPORT_FILE), &report);
+
+    let seq = report.seq;
+    let mut reports = Exchange::new(client, TOPIC_REPORTS)?
+        .count(count)
+        .publish_and_collect(client, report)
+        .await?;
+    if seq != 1 {
+        return Ok(vec![]);
+    }
+
+    reports.sort_by_key(|r| r.seq);
+    let summary = Summary::new(&reports);
+    client.record_message(format!("Summary: {}", serde_json::to_string(&summary)?));
+    write_json(&outputs.join(SUMMARY_FILE), &summary);
+
+    regressions(
+        &run_parameters.test_instance_params,
+        &serde_json::to_value(&summary)?,
+    )
+}
+
+// The artifacts are supplementary to the metrics, so a failure to write them doesn't fail the test.
+pub(crate) fn write_json<T: Serialize>(path: &Path, value: &T) {
+    let result = serde_json::to_vec_pretty(value)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("Failed to write {}: {e}", path.display());
+    }
+}
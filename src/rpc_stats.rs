@@ -0,0 +1,206 @@
+use crate::utils::record_metric;
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Enr, QueryError, RequestError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+
+/// The outbound operations whose outcomes are accounted per peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Operation {
+    FindNode,
+    FindNodeDesignatedPeer,
+    TalkReq,
+}
+
+impl Operation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Operation::FindNode => "find_node",
+            Operation::FindNodeDesignatedPeer => "find_node_designated_peer",
+            Operation::TalkReq => "talk_req",
+        }
+    }
+}
+
+enum Outcome {
+    Success,
+    Error,
+    Timeout,
+}
+
+/// The outcomes of an operation to a peer, or of all the FINDNODE queries, which have no peer. The
+/// durations are the ones of all the outcomes.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct PeerRpcStats {
+    pub node_id: Option<String>,
+    pub operation: String,
+    pub successes: u64,
+    pub errors: u64,
+    pub timeouts: u64,
+    pub total_ms: u64,
+    pub max_ms: u64,
+}
+
+/// A registry of the outcomes of the outbound operations per peer, shared by cloning it. The
+/// operations are run through the methods below in place of the ones of `Discv5`, so that the
+/// flaky peers can be told apart instead of the errors being logged only.
+///
+/// NOTE: A FINDNODE query contacts many peers and discv5 doesn't expose them, and its target is
+/// usually a random node id rather than a peer, so the queries are accounted all together without
+/// a peer. discv5 completes a query which has timed out with the peers found so far, so the queries
+/// have no timeouts.
+#[derive(Clone, Default)]
+pub(crate) struct RpcStats {
+    peers: Arc<Mutex<HashMap<(Option<NodeId>, Operation), PeerRpcStats>>>,
+}
+
+impl RpcStats {
+    /// Like the other operations, the query doesn't borrow `discv5`, so that it can be spawned as
+    /// the one of `Discv5`.
+    pub(crate) fn find_node(
+        &self,
+        discv5: &Discv5,
+        target: NodeId,
+    ) -> impl Future<Output = Result<Vec<Enr>, QueryError>> + 'static {
+        let rpc_stats = self.clone();
+        let query = discv5.find_node(target);
+        async move {
+            let started_at = Instant::now();
+            let result = query.await;
+            let outcome = match result {
+                Ok(_) => Outcome::Success,
+                Err(_) => Outcome::Error,
+            };
+            rpc_stats.record(None, Operation::FindNode, outcome, started_at.elapsed());
+            result
+        }
+    }
+
+    pub(crate) fn find_node_designated_peer(
+        &self,
+        discv5: &Discv5,
+        enr: Enr,
+        distances: Vec<u64>,
+    ) -> impl Future<Output = Result<Vec<Enr>, RequestError>> + 'static {
+        let rpc_stats = self.clone();
+        let node_id = enr.node_id();
+        let request = discv5.find_node_designated_peer(enr, distances);
+        async move {
+            let started_at = Instant::now();
+            let result = request.await;
+            rpc_stats.record(
+                Some(node_id),
+                Operation::FindNodeDesignatedPeer,
+                request_outcome(&result),
+                started_at.elapsed(),
+            );
+            result
+        }
+    }
+
+    pub(crate) fn talk_req(
+        &self,
+        discv5: &Discv5,
+        enr: Enr,
+        protocol: Vec<u8>,
+        request: Vec<u8>,
+    ) -> impl Future<Output = Result<Vec<u8>, RequestError>> + 'static {
+        let rpc_stats = self.clone();
+        let node_id = enr.node_id();
+        let request = discv5.talk_req(enr, protocol, request);
+        async move {
+            let started_at = Instant::now();
+            let result = request.await;
+            rpc_stats.record(
+                Some(node_id),
+                Operation::TalkReq,
+                request_outcome(&result),
+                started_at.elapsed(),
+            );
+            result
+        }
+    }
+
+    fn record(
+        &self,
+        node_id: Option<NodeId>,
+        operation: Operation,
+        outcome: Outcome,
+        elapsed: Duration,
+    ) {
+        let mut peers = self.peers.lock().expect("lock peers");
+        let stats = peers
+            .entry((node_id, operation))
+            .or_insert_with(|| PeerRpcStats {
+                node_id: node_id.map(|node_id| node_id.to_string()),
+                operation: operation.as_str().to_owned(),
+                ..Default::default()
+            });
+        match outcome {
+            Outcome::Success => stats.successes += 1,
+            Outcome::Error => stats.errors += 1,
+            Outcome::Timeout => stats.timeouts += 1,
+        }
+        let elapsed_ms = elapsed.as_millis() as u64;
+        stats.total_ms += elapsed_ms;
+        stats.max_ms = stats.max_ms.max(elapsed_ms);
+    }
+
+    /// The outcomes so far, ordered by peer and operation.
+    pub(crate) fn snapshot(&self) -> Vec<PeerRpcStats> {
+        let mut stats = self
+            .peers
+            .lock()
+            .expect("lock peers")
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| (&a.node_id, &a.operation).cmp(&(&b.node_id, &b.operation)));
+        stats
+    }
+
+    /// Record the outcomes per peer and operation with `source = rpc_stats`, and return them so
+    /// that they can be included in the report. The FINDNODE queries are recorded without the
+    /// `peer` tag.
+    pub(crate) async fn finish(&self, client: &Client, instance_seq: u64) -> Vec<PeerRpcStats> {
+        let run_parameters = client.run_parameters();
+        let stats = self.snapshot();
+        for s in &stats {
+            let calls = s.successes + s.errors + s.timeouts;
+            let mut write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("successes", s.successes)
+            .add_field("errors", s.errors)
+            .add_field("timeouts", s.timeouts)
+            .add_field("mean_ms", s.total_ms as f64 / calls.max(1) as f64)
+            .add_field("max_ms", s.max_ms)
+            .add_tag("instance_seq", instance_seq)
+            .add_tag("operation", s.operation.clone())
+            .add_tag("source", "rpc_stats");
+            if let Some(node_id) = &s.node_id {
+                write_query = write_query.add_tag("peer", node_id.clone());
+            }
+            record_metric(client, write_query).await;
+        }
+        stats
+    }
+}
+
+fn request_outcome<T>(result: &Result<T, RequestError>) -> Outcome {
+    match result {
+        Ok(_) => Outcome::Success,
+        Err(RequestError::Timeout) => Outcome::Timeout,
+        Err(_) => Outcome::Error,
+    }
+}
@@ -1,16 +1,19 @@
+use crate::config::config_builder;
 use crate::mock::{
     Action, Behaviour, Behaviours, CustomResponse, CustomResponseId, DeclarativeBehaviour, Expect,
     Mock, Request, Response,
 };
-use crate::utils::publish_and_collect;
+use crate::scenario::Registry;
+use crate::teardown::{final_barrier, teardown};
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr_with_key, check_enr_port, enr_addresses,
+    enr_socket, ip_version, key_from_rng_seed, listen_config, publish_and_collect, unsupported_seq,
+    InstanceInfo,
+};
 use discv5::enr::{CombinedKey, NodeId};
 use discv5::rpc::ResponseBody;
-use discv5::{Discv5, Enr, ListenConfig};
-use enr::k256;
-use rand::{RngCore, SeedableRng};
-use serde::{Deserialize, Serialize};
+use discv5::{Discv5, Enr};
 use std::collections::VecDeque;
-use std::net::{IpAddr, Ipv4Addr};
 use std::num::NonZeroU16;
 use std::time::Duration;
 use std::vec;
@@ -20,18 +23,12 @@ use tracing::{error, info};
 const STATE_DISCV5_STARTED: &str = "state_discv5_started";
 const STATE_FINISHED: &str = "state_finished";
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-struct InstanceInfo {
-    // The sequence number of this test instance within the test.
-    seq: u64,
-    enr: Enr,
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("sandbox", run);
 }
 
 pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
     let run_parameters = client.run_parameters();
-    let ip = run_parameters
-        .data_network_ip()?
-        .expect("IP address for the data network");
 
     // Seed is chosen such that all nodes are in the 256th bucket of bootstrap
     let seed = 1652;
@@ -46,24 +43,22 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
     // Construct local Enr
     // ////////////////////////
     let enr_key = keypairs.remove(client.global_seq() as usize - 1);
-    let enr = Enr::builder()
-        .ip(ip)
-        .udp4(9000)
-        .build(&enr_key)
-        .expect("enr");
+    let enr = build_enr_with_key(
+        &run_parameters,
+        client.global_seq(),
+        ip_version(&run_parameters.test_instance_params)?,
+        &enr_key,
+    )?;
 
     // //////////////////////////////////////////////////////////////
     // Collect information of all participants in the test case
     // //////////////////////////////////////////////////////////////
-    let instance_info = InstanceInfo {
-        seq: client.global_seq(),
-        enr: enr.clone(),
-    };
+    let instance_info = InstanceInfo::new(&client, enr.clone())?;
     client.record_message(format!(
         "seq: {}, node_id: {}, ip: {}",
         instance_info.seq,
         instance_info.enr.node_id(),
-        ip
+        enr_addresses(&instance_info.enr)
     ));
 
     let participants = publish_and_collect(&client, instance_info).await?;
@@ -86,11 +81,9 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
     // ////////////////////////
     // Discv5 config
     // ////////////////////////
-    let listen_config = ListenConfig::Ipv4 {
-        ip: Ipv4Addr::UNSPECIFIED,
-        port: 9000,
-    };
-    let config = discv5::ConfigBuilder::new(listen_config)
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
         .vote_duration(Duration::from_secs(3))
         .enr_peer_update_min(2)
         .request_timeout(Duration::from_secs(3))
@@ -109,7 +102,7 @@ pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
             .await?
         }
         2 | 3 => run_mock(client, enr, enr_key, config, participants, target_enr).await?,
-        _ => unreachable!(),
+        seq => return Err(unsupported_seq(seq)),
     }
 
     Ok(())
@@ -130,14 +123,18 @@ async fn run_discv5(
     for peer in participants.iter().filter(|p| p.seq != client.global_seq()) {
         discv5.add_enr(peer.enr.clone()).unwrap();
     }
-    discv5.start().await.expect("Start Discovery v5 server");
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
 
-    client
-        .signal_and_wait(
-            STATE_DISCV5_STARTED,
-            client.run_parameters().test_instance_count,
-        )
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_DISCV5_STARTED,
+        client.run_parameters().test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
 
     let mut handles = vec![];
     for peer in participants.iter().filter(|p| p.seq != client.global_seq()) {
@@ -161,9 +158,21 @@ async fn run_discv5(
 
     tokio::time::sleep(Duration::from_secs(10)).await;
 
-    client
-        .signal_and_wait(STATE_FINISHED, client.run_parameters().test_instance_count)
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_FINISHED,
+        client.run_parameters().test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    teardown(
+        &client,
+        discv5,
+        client.global_seq(),
+        client.run_parameters().test_instance_count,
+    )
+    .await?;
 
     if succeeded {
         client.record_success().await?;
@@ -182,6 +191,9 @@ async fn run_mock(
     _target_enr: Enr,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let discv5_node = participants.into_iter().find(|p| p.seq == 1).unwrap();
+    let discv5_socket = enr_socket(&discv5_node.enr)?;
+    let discv5_port =
+        NonZeroU16::new(discv5_socket.port()).ok_or("The UDP port of the discv5 node is zero.")?;
 
     // ////////////////////////
     // Start mock
@@ -234,8 +246,8 @@ async fn run_mock(
                 id: CustomResponseId::CapturedRequestId(0),
                 body: ResponseBody::Pong {
                     enr_seq: discv5_node.enr.seq(),
-                    ip: IpAddr::V4(discv5_node.enr.ip4().unwrap()),
-                    port: NonZeroU16::new(9000).unwrap(),
+                    ip: discv5_socket.ip(),
+                    port: discv5_port,
                 },
             }])),
         ],
@@ -248,8 +260,8 @@ async fn run_mock(
                 id: CustomResponseId::CapturedRequestId(1),
                 body: ResponseBody::Pong {
                     enr_seq: discv5_node.enr.seq(),
-                    ip: IpAddr::V4(discv5_node.enr.ip4().unwrap()),
-                    port: NonZeroU16::new(9000).unwrap(),
+                    ip: discv5_socket.ip(),
+                    port: discv5_port,
                 },
             }])),
         ],
@@ -265,16 +277,23 @@ async fn run_mock(
     let mut _mock = Mock::start(enr, enr_key, config, Behaviours::Sequential(behaviours)).await;
     // let mut _mock = Mock::start(enr, enr_key, config, Behaviours::Sequential(behaviours)).await;
 
-    client
-        .signal_and_wait(
-            STATE_DISCV5_STARTED,
-            client.run_parameters().test_instance_count,
-        )
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_DISCV5_STARTED,
+        client.run_parameters().test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_FINISHED,
+        client.run_parameters().test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
 
-    client
-        .signal_and_wait(STATE_FINISHED, client.run_parameters().test_instance_count)
-        .await?;
+    final_barrier(&client, client.run_parameters().test_instance_count).await?;
 
     client.record_success().await?;
     Ok(())
@@ -282,21 +301,6 @@ async fn run_mock(
 
 /// Generate `n` deterministic keypairs from a given seed.
 fn generate_deterministic_keypair(n: usize, seed: u64) -> Vec<CombinedKey> {
-    let mut keypairs = Vec::new();
-    for i in 0..n {
-        let sk = {
-            let rng = &mut rand_xorshift::XorShiftRng::seed_from_u64(seed + i as u64);
-            let mut b = [0; 32];
-            loop {
-                // until a value is given within the curve order
-                rng.fill_bytes(&mut b);
-                if let Ok(k) = k256::ecdsa::SigningKey::from_slice(&b) {
-                    break k;
-                }
-            }
-        };
-        let kp = CombinedKey::from(sk);
-        keypairs.push(kp);
-    }
-    keypairs
+    // NOTE: The seed is pre-computed for the keys derived from `seed + i`, see `key_from_rng_seed`.
+    (0..n as u64).map(|i| key_from_rng_seed(seed + i)).collect()
 }
@@ -0,0 +1,66 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use testground::client::Client;
+
+pub(crate) type ScenarioResult = Result<(), Box<dyn std::error::Error>>;
+
+/// A test case which can be run by its name.
+pub(crate) trait Scenario {
+    fn run(&self, client: Client) -> Pin<Box<dyn Future<Output = ScenarioResult>>>;
+}
+
+// Any async function, or closure, taking the client is a scenario, e.g. the `run` function of the
+// test case modules.
+impl<F, Fut> Scenario for F
+where
+    F: Fn(Client) -> Fut,
+    Fut: Future<Output = ScenarioResult> + 'static,
+{
+    fn run(&self, client: Client) -> Pin<Box<dyn Future<Output = ScenarioResult>>> {
+        Box::pin(self(client))
+    }
+}
+
+/// The test cases by name. Each test case module registers its test cases via its `register`
+/// function.
+pub(crate) struct Registry {
+    scenarios: HashMap<&'static str, Box<dyn Scenario>>,
+    // The test cases which build their ENRs and listen on IPv4 regardless of the `ip-mode` param.
+    ipv4_only: HashSet<&'static str>,
+}
+
+impl Registry {
+    pub(crate) fn new() -> Self {
+        Registry {
+            scenarios: HashMap::new(),
+            ipv4_only: HashSet::new(),
+        }
+    }
+
+    /// Register `scenario` as the test case `name`, which must be the name in the manifest.
+    pub(crate) fn register(&mut self, name: &'static str, scenario: impl Scenario + 'static) {
+        if self.scenarios.insert(name, Box::new(scenario)).is_some() {
+            panic!("The test case `{name}` has been registered twice.");
+        }
+    }
+
+    /// Same as `register`, but the test case runs on IPv4 only, so that it's rejected with any other
+    /// `ip-mode` instead of silently running on IPv4.
+    pub(crate) fn register_ipv4_only(
+        &mut self,
+        name: &'static str,
+        scenario: impl Scenario + 'static,
+    ) {
+        self.register(name, scenario);
+        self.ipv4_only.insert(name);
+    }
+
+    pub(crate) fn is_ipv4_only(&self, name: &str) -> bool {
+        self.ipv4_only.contains(name)
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&dyn Scenario> {
+        self.scenarios.get(name).map(|scenario| scenario.as_ref())
+    }
+}
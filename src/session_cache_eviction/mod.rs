@@ -0,0 +1,220 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::events::EventRecorder;
+use crate::scenario::Registry;
+use crate::session_cache_eviction::params::Params;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, listen_config,
+    publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::{Discv5, Event};
+use std::time::Instant;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_SEND_REQUESTS: &str = "state_completed_to_send_requests";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("session-cache-eviction", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // NOTE: #1 is the hub, whose session cache is deliberately smaller than the number of its
+    // peers, so that it keeps evicting the sessions.
+    let is_hub = client.global_seq() == 1;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut builder = config_builder(listen_config, &run_parameters.test_instance_params)?;
+    if is_hub {
+        builder.session_cache_capacity(params.hub_session_cache_capacity);
+    }
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, builder.build())?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    // The sessions established are taken from the events.
+    let (event_recorder, mut events) = EventRecorder::start_forwarding(&client, &discv5).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+    let hub = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("hub")
+        .clone();
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Send requests to the hub
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if is_hub {
+        // The hub just responds, so it doesn't take the events.
+        drop(events);
+    } else {
+        let hub_node_id = hub.enr.node_id();
+        let mut failures = 0_u64;
+        let mut rehandshakes = 0_u64;
+        let mut cached_latencies = vec![];
+        let mut rehandshake_latencies = vec![];
+
+        for i in 0..params.requests {
+            let started_at = Instant::now();
+            let result = discv5.send_ping(hub.enr.clone()).await;
+            let elapsed = started_at.elapsed().as_millis() as u64;
+            let success = match result {
+                Ok(_) => true,
+                Err(e) => {
+                    error!("Failed to send PING to the hub: {e}");
+                    failures += 1;
+                    false
+                }
+            };
+
+            // The events of this request have been forwarded by the time of the next request. A
+            // session established with the hub means that the hub has evicted the previous one,
+            // except for the first request, which establishes the initial session.
+            tokio::time::sleep(params.request_interval).await;
+            let mut established = false;
+            while let Ok(event) = events.try_recv() {
+                if let Event::SessionEstablished(enr, _) = event {
+                    established |= enr.node_id() == hub_node_id;
+                }
+            }
+            let rehandshake = established && i > 0;
+            if success && i > 0 {
+                if rehandshake {
+                    rehandshakes += 1;
+                    rehandshake_latencies.push(elapsed);
+                } else {
+                    cached_latencies.push(elapsed);
+                }
+            }
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("success", success)
+            .add_field("latency_ms", elapsed)
+            .add_field("rehandshake", rehandshake)
+            .add_field("initial", i == 0)
+            .add_tag("instance_seq", instance_info.seq);
+            record_metric(&client, write_query).await;
+        }
+
+        let mean = |latencies: &[u64]| {
+            if latencies.is_empty() {
+                None
+            } else {
+                Some(latencies.iter().sum::<u64>() as f64 / latencies.len() as f64)
+            }
+        };
+        let mean_cached = mean(&cached_latencies);
+        let mean_rehandshake = mean(&rehandshake_latencies);
+        client.record_message(format!(
+            "requests: {}, failures: {failures}, re-handshakes: {rehandshakes}, mean latency (ms): cached: {mean_cached:?}, re-handshake: {mean_rehandshake:?}",
+            params.requests
+        ));
+
+        let mut write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("requests", params.requests)
+        .add_field("failures", failures)
+        .add_field("rehandshakes", rehandshakes)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("source", "summary");
+        if let Some(v) = mean_cached {
+            write_query = write_query.add_field("mean_cached_latency_ms", v);
+        }
+        if let Some(v) = mean_rehandshake {
+            write_query = write_query.add_field("mean_rehandshake_latency_ms", v);
+        }
+        if let (Some(cached), Some(rehandshake)) = (mean_cached, mean_rehandshake) {
+            write_query = write_query.add_field("added_latency_ms", rehandshake - cached);
+        }
+        record_metric(&client, write_query).await;
+
+        // The evicted sessions should be re-established transparently.
+        if failures > 0 {
+            failed = true;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_SEND_REQUESTS,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    if is_hub {
+        info!(
+            "Active sessions at the end: {}",
+            discv5.metrics().active_sessions
+        );
+    }
+    event_recorder.finish(&client).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
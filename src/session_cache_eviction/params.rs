@@ -0,0 +1,31 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub(crate) struct Params {
+    pub hub_session_cache_capacity: usize,
+    pub requests: u64,
+    pub request_interval: Duration,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        let hub_session_cache_capacity =
+            get_param_or::<usize>("hub_session_cache_capacity", instance_params, 2)?;
+        if hub_session_cache_capacity == 0 {
+            return Err("hub_session_cache_capacity must be non-zero.".into());
+        }
+
+        Ok(Params {
+            hub_session_cache_capacity,
+            requests: get_param_or::<u64>("requests", instance_params, 20)?,
+            request_interval: Duration::from_millis(get_param_or::<u64>(
+                "request_interval",
+                instance_params,
+                500,
+            )?),
+        })
+    }
+}
@@ -0,0 +1,224 @@
+use crate::config::config_builder;
+use crate::events::EventRecorder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    await_event, barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses,
+    get_param, listen_config, publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::{Discv5, Event};
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_CONNECT: &str = "state_completed_to_connect";
+const STATE_FINISHED: &str = "state_finished";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("session-teardown", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let wait_duration = get_param::<u64>("wait_duration", &run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+    let (event_recorder, mut events) = EventRecorder::start_forwarding(&client, &discv5).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // NOTE: #1 is the node that removes its peers.
+    let remover = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("remover")
+        .clone();
+    let peers = participants
+        .iter()
+        .filter(|&p| p.seq != remover.seq)
+        .collect::<Vec<_>>();
+
+    // //////////////////////////////////////////////////////////////
+    // Establish sessions
+    // //////////////////////////////////////////////////////////////
+    if instance_info.seq == remover.seq {
+        for p in peers.iter() {
+            if let Err(e) = rpc_stats
+                .find_node_designated_peer(&discv5, p.enr.clone(), vec![0])
+                .await
+            {
+                error!("Failed to run FIND_NODE query: {e}");
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_CONNECT,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Remove the peers and check whether the sessions are torn down
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if instance_info.seq != remover.seq {
+        // The peers just respond, so they don't take the events.
+        drop(events);
+    } else {
+        // The sessions established so far are not the ones after the removal.
+        while events.try_recv().is_ok() {}
+
+        let sessions_before = discv5.metrics().active_sessions;
+        client.record_message(format!("active_sessions before removal: {sessions_before}"));
+
+        if sessions_before < peers.len() {
+            error!(
+                "Failed to establish sessions. expected: {}, actual: {sessions_before}",
+                peers.len()
+            );
+            failed = true;
+        }
+
+        let mut removed = 0_u64;
+        for p in peers.iter() {
+            if discv5.remove_node(&p.enr.node_id()) {
+                removed += 1;
+            } else {
+                error!(
+                    "The peer was not found in the routing table. node_id: {}",
+                    p.enr.node_id()
+                );
+                failed = true;
+            }
+        }
+
+        // Give the handler time to clean up the sessions.
+        tokio::time::sleep(Duration::from_secs(wait_duration)).await;
+
+        let sessions_after = discv5.metrics().active_sessions;
+        client.record_message(format!("active_sessions after removal: {sessions_after}"));
+
+        if sessions_after >= sessions_before {
+            error!(
+                "The sessions haven't been torn down. before: {sessions_before}, after: {sessions_after}"
+            );
+            failed = true;
+        } else {
+            info!("The sessions have been torn down. before: {sessions_before}, after: {sessions_after}");
+        }
+
+        // The count above doesn't tell which sessions have been torn down, so a request to each
+        // removed peer must start a new handshake rather than reuse the previous session.
+        let mut rehandshakes = 0_u64;
+        for p in peers.iter() {
+            let node_id = p.enr.node_id();
+            if let Err(e) = discv5.send_ping(p.enr.clone()).await {
+                error!("Failed to send PING to the removed peer. node_id: {node_id}, error: {e}");
+                failed = true;
+                continue;
+            }
+            match await_event(
+                &mut events,
+                |event| {
+                    matches!(event, Event::SessionEstablished(enr, _) if enr.node_id() == node_id)
+                },
+                Duration::from_secs(wait_duration),
+            )
+            .await
+            {
+                Ok(_) => rehandshakes += 1,
+                Err(e) => {
+                    error!("The session with the removed peer has been reused. node_id: {node_id}, error: {e}");
+                    failed = true;
+                }
+            }
+        }
+        client.record_message(format!(
+            "rehandshakes after removal: {rehandshakes}/{}",
+            peers.len()
+        ));
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("removed", removed)
+        .add_field("sessions_before", sessions_before as u64)
+        .add_field("sessions_after", sessions_after as u64)
+        .add_field("rehandshakes", rehandshakes)
+        .add_tag("instance_seq", instance_info.seq);
+        record_metric(&client, write_query).await;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_FINISHED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    event_recorder.finish(&client).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,200 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::slow_path_lookup::params::Params;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, enr_ip,
+    link_shape, listen_config, publish_and_collect, reconfigure_network_with_rules, record_metric,
+    InstanceInfo,
+};
+use chrono::Local;
+use discv5::Discv5;
+use ipnetwork::IpNetwork;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::network_conf::{LinkRule, LinkShape};
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("slow-path-lookup", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the slow node, which knows all the other nodes. #2 is the requester, which
+    // knows only the slow node, so that its lookups must traverse the slow node. The rest are the
+    // targets.
+    let slow_node = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Slow node")
+        .clone();
+
+    if instance_info.seq == slow_node.seq {
+        for p in participants.iter().filter(|p| p.seq != slow_node.seq) {
+            discv5.add_enr(p.enr.clone())?;
+        }
+    } else if instance_info.seq == 2 {
+        discv5.add_enr(slow_node.enr.clone())?;
+    }
+
+    // Put the slow node behind a high-latency link. The traffic from the slow node is delayed by
+    // its default link shape, and the traffic to it is delayed by a rule on the other nodes.
+    let slow_link_shape = LinkShape {
+        latency: params.slow_latency * 1_000_000, // Translate from millisecond to nanosecond
+        ..link_shape(&run_parameters.test_instance_params)?
+    };
+    let (default, rules) = if instance_info.seq == slow_node.seq {
+        (slow_link_shape, None)
+    } else {
+        let slow_ip = enr_ip(&slow_node.enr)?;
+        (
+            link_shape(&run_parameters.test_instance_params)?,
+            Some(vec![LinkRule {
+                link_shape: slow_link_shape,
+                subnet: IpNetwork::from(slow_ip),
+            }]),
+        )
+    };
+    reconfigure_network_with_rules(&client, default, rules, "state_slow_link".to_owned()).await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run FINDNODE queries through the slow node
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if instance_info.seq == 2 {
+        for target in participants.iter().filter(|p| p.seq > 2) {
+            let target_id = target.enr.node_id();
+            let started_at = Instant::now();
+            let result = tokio::time::timeout(
+                Duration::from_secs(params.lookup_timeout),
+                rpc_stats.find_node(&discv5, target_id),
+            )
+            .await;
+            let elapsed = started_at.elapsed();
+
+            // The query may legitimately fail because of discv5's request timeout, but it must
+            // not hang.
+            let (completed, found) = match result {
+                Ok(Ok(enrs)) => (true, enrs.iter().any(|enr| enr.node_id() == target_id)),
+                Ok(Err(e)) => {
+                    info!("The query has timed out in discv5: {e}");
+                    (true, false)
+                }
+                Err(_) => {
+                    error!(
+                        "The query hasn't completed within {} seconds.",
+                        params.lookup_timeout
+                    );
+                    failed = true;
+                    (false, false)
+                }
+            };
+            info!(
+                "target: #{}, found: {found}, elapsed: {}ms",
+                target.seq,
+                elapsed.as_millis()
+            );
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("slow_latency_ms", params.slow_latency)
+            .add_field("elapsed_ms", elapsed.as_millis() as u64)
+            .add_field("completed", completed)
+            .add_field("found", found)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("target_seq", target.seq);
+            record_metric(&client, write_query).await;
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,245 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::socket_update_validation::params::Params;
+use crate::teardown::teardown;
+use crate::utils::{
+    await_event, barrier_timeout, barrier_with_timeout, build_enr_with_key, check_enr_port,
+    collect_from_stream, enr_addresses, instance_key, ip_version, listen_config,
+    publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::{Discv5, Enr};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::net::SocketAddr;
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tokio::task;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_ESTABLISH_CONNECTIONS: &str = "state_completed_establish_connections";
+const STATE_COMPLETED: &str = "state_completed";
+const TOPIC_OBSERVED_SOCKET: &str = "observed_socket";
+
+// The socket address of #1 observed by a peer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Observation {
+    seq: u64,
+    socket: Option<SocketAddr>,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("socket-update-validation", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    // NOTE: #1 doesn't know its own address, so it learns it from the peers.
+    let enr_key = instance_key(&run_parameters, client.global_seq())?;
+    let enr = if client.global_seq() == 1 {
+        Enr::builder()
+            .build(&enr_key)
+            .map_err(|e| format!("Failed to construct an Enr: {e:?}"))?
+    } else {
+        build_enr_with_key(
+            &run_parameters,
+            client.global_seq(),
+            ip_version(&run_parameters.test_instance_params)?,
+            &enr_key,
+        )?
+    };
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+        .ping_interval(Duration::from_secs(params.ping_interval))
+        .build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+    let node1 = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("#1")
+        .clone();
+
+    // //////////////////////////////////////////////////////////////
+    // Observe events
+    // //////////////////////////////////////////////////////////////
+    // #1 waits for `SocketUpdated`, and the peers wait for a session with #1 to be established,
+    // which tells the source socket of #1's packets.
+    let mut event_stream = discv5.event_stream().await.expect("Discv5Event");
+    let is_node1 = instance_info.seq == node1.seq;
+    let node1_id = node1.enr.node_id();
+    let timeout = Duration::from_secs(params.wait_duration);
+    let handle = task::spawn(async move {
+        await_event(
+            &mut event_stream,
+            |event| match event {
+                discv5::Event::SocketUpdated(_) => is_node1,
+                discv5::Event::SessionEstablished(enr, _) => !is_node1 && enr.node_id() == node1_id,
+                _ => false,
+            },
+            timeout,
+        )
+        .await
+    });
+
+    // //////////////////////////////////////////////////////////////
+    // Establish connections
+    // //////////////////////////////////////////////////////////////
+    if is_node1 {
+        for p in participants.iter().filter(|p| p.seq != node1.seq) {
+            if let Err(e) = rpc_stats
+                .find_node_designated_peer(&discv5, p.enr.clone(), vec![0])
+                .await
+            {
+                error!("Failed to run FINDNODE request: {e}");
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_ESTABLISH_CONNECTIONS,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    let socket = match handle.await? {
+        Ok(discv5::Event::SocketUpdated(socket_addr)) => Some(socket_addr),
+        Ok(discv5::Event::SessionEstablished(_, socket_addr)) => Some(socket_addr),
+        Ok(_) => unreachable!(),
+        Err(e) => {
+            warn!("No socket has been observed: {e}");
+            None
+        }
+    };
+    info!("socket: {socket:?}");
+
+    // //////////////////////////////////////////////////////////////
+    // Compare the reported socket with the observations
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+
+    if is_node1 {
+        let mut mismatches = 0_u64;
+        let mut observations = 0_u64;
+
+        let reports: Vec<Observation> = collect_from_stream(
+            &client,
+            client
+                .subscribe(TOPIC_OBSERVED_SOCKET, u16::MAX.into())
+                .await,
+            run_parameters.test_instance_count - 1,
+        )
+        .await?;
+
+        for observation in reports {
+            if let Some(observed) = observation.socket {
+                observations += 1;
+                if Some(observed) != socket {
+                    error!(
+                        "Mismatch. #{} observed: {observed}, SocketUpdated: {socket:?}",
+                        observation.seq
+                    );
+                    mismatches += 1;
+                }
+            }
+        }
+
+        if socket.is_none() {
+            error!("Discv5Event::SocketUpdated hasn't been emitted.");
+            failed = true;
+        }
+        if mismatches > 0 {
+            failed = true;
+        }
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("observations", observations)
+        .add_field("mismatches", mismatches)
+        .add_tag("instance_seq", instance_info.seq);
+        record_metric(&client, write_query).await;
+    } else {
+        let observation = Observation {
+            seq: instance_info.seq,
+            socket,
+        };
+        client
+            .publish(
+                TOPIC_OBSERVED_SOCKET,
+                Cow::Owned(serde_json::to_value(&observation)?),
+            )
+            .await?;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,205 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::socket_update_vs_ping::params::Params;
+use crate::teardown::{final_barrier, teardown};
+use crate::utils::{
+    await_event, barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses,
+    ip_version, listen_config, publish_and_collect, record_metric, udp_port, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::CombinedKey;
+use discv5::{Discv5, Enr};
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tokio::task;
+use tracing::{error, info};
+
+const STATE_PEERS_STARTED: &str = "state_peers_started";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("socket-update-vs-ping", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+    let ip_version = ip_version(&run_parameters.test_instance_params)?;
+    let port = udp_port(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    // NOTE: #1 doesn't know its own address, so it learns it from the peers. It starts discv5 at
+    // each ping interval level later on.
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr.clone())?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // The peers run discv5 throughout the test.
+    let peer_discv5 = if instance_info.seq != 1 {
+        let listen_config = listen_config(&run_parameters)?;
+        check_enr_port(&enr, &listen_config)?;
+        let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+        let mut discv5: Discv5 = Discv5::new(enr, enr_key, config)?;
+        discv5
+            .start()
+            .await
+            .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+        Some(discv5)
+    } else {
+        None
+    };
+
+    barrier_with_timeout(
+        &client,
+        STATE_PEERS_STARTED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Measure the socket update delay at each ping interval level
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+    let mut results = vec![];
+    let rpc_stats = RpcStats::default();
+
+    for (i, ping_interval) in params.ping_interval_levels.iter().enumerate() {
+        if instance_info.seq == 1 {
+            // Start discv5 with a fresh key so that no session established at the previous level
+            // is reused. A different port is used to avoid waiting for the previous socket to be
+            // released.
+            let enr_key = CombinedKey::generate_secp256k1();
+            let enr = Enr::builder()
+                .build(&enr_key)
+                .map_err(|e| format!("Failed to construct an Enr: {e:?}"))?;
+            let port = u16::try_from(i)
+                .ok()
+                .and_then(|i| port.checked_add(i))
+                .ok_or("udp_port overflows with the ping interval levels.")?;
+            let listen_config = ip_version.listen_config(port);
+            let config = config_builder(listen_config, &run_parameters.test_instance_params)?
+                .ping_interval(Duration::from_secs(*ping_interval))
+                .build();
+            let mut discv5: Discv5 = Discv5::new(enr, enr_key, config)?;
+            discv5
+                .start()
+                .await
+                .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+            let started_up_at = Instant::now();
+
+            let mut event_stream = discv5.event_stream().await.expect("Discv5Event");
+            let timeout = Duration::from_secs(params.socket_update_timeout);
+            let handle = task::spawn(async move {
+                await_event(
+                    &mut event_stream,
+                    |event| matches!(event, discv5::Event::SocketUpdated(_)),
+                    timeout,
+                )
+                .await
+            });
+
+            // Establish connections.
+            for p in participants.iter().filter(|p| p.seq != instance_info.seq) {
+                if let Err(e) = rpc_stats
+                    .find_node_designated_peer(&discv5, p.enr.clone(), vec![0])
+                    .await
+                {
+                    error!("Failed to run FINDNODE request: {e}");
+                }
+            }
+
+            let delay = match handle.await? {
+                Ok(_) => Some(started_up_at.elapsed()),
+                Err(e) => {
+                    error!("Failed to wait for Discv5Event::SocketUpdated: {e}");
+                    failed = true;
+                    None
+                }
+            };
+            info!("ping_interval: {ping_interval}s, socket_update_delay: {delay:?}");
+            results.push((*ping_interval, delay.map(|d| d.as_millis())));
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let mut write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("ping_interval", *ping_interval)
+            .add_field("socket_updated", delay.is_some())
+            .add_tag("instance_seq", instance_info.seq);
+            if let Some(delay) = delay {
+                write_query =
+                    write_query.add_field("socket_update_delay_ms", delay.as_millis() as u64);
+            }
+            record_metric(&client, write_query).await;
+
+            discv5.shutdown();
+        }
+
+        barrier_with_timeout(
+            &client,
+            format!("state_completed_level_{i}"),
+            run_parameters.test_instance_count,
+            barrier_timeout(&client)?,
+        )
+        .await?;
+    }
+
+    if instance_info.seq == 1 {
+        client.record_message(format!(
+            "(ping_interval, socket_update_delay_ms): {:?}",
+            results
+        ));
+    }
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    // #1 shuts down its discv5 at each level, so it only takes part in the final barrier.
+    match peer_discv5 {
+        Some(discv5) => {
+            teardown(
+                &client,
+                discv5,
+                instance_info.seq,
+                run_parameters.test_instance_count,
+            )
+            .await?
+        }
+        None => final_barrier(&client, run_parameters.test_instance_count).await?,
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
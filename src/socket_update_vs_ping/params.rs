@@ -0,0 +1,28 @@
+use crate::utils::get_param;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub ping_interval_levels: Vec<u64>,
+    pub socket_update_timeout: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        let ping_interval_levels = get_param::<String>("ping_interval_levels", instance_params)?
+            .split(',')
+            .map(|level| {
+                level
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|_| format!("Failed to parse ping interval: {level}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Params {
+            ping_interval_levels,
+            socket_update_timeout: get_param::<u64>("socket_update_timeout", instance_params)?,
+        })
+    }
+}
@@ -0,0 +1,135 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::final_barrier;
+use crate::utils::{
+    build_enr_with_key, check_enr_port, get_param, ip_version, record_metric, udp_port,
+    WAIT_FOR_SHUTDOWN,
+};
+use chrono::Local;
+use discv5::enr::{CombinedKey, NodeId};
+use discv5::Discv5;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, UdpSocket};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("start-stop-loop", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let ip_version = ip_version(&run_parameters.test_instance_params)?;
+    let port = udp_port(&run_parameters.test_instance_params)?;
+    let iterations = get_param::<u64>("iterations", &run_parameters.test_instance_params)?;
+
+    let mut failed = false;
+    let mut samples = vec![];
+
+    let rpc_stats = RpcStats::default();
+
+    for i in 0..iterations {
+        // ////////////////////////
+        // Construct local Enr
+        // ////////////////////////
+        let enr_key = CombinedKey::generate_secp256k1();
+        let enr = build_enr_with_key(&run_parameters, client.global_seq(), ip_version, &enr_key)?;
+
+        // ////////////////////////
+        // Start discv5
+        // ////////////////////////
+        let listen_config = ip_version.listen_config(port);
+        check_enr_port(&enr, &listen_config)?;
+        let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+        let mut discv5: Discv5 = Discv5::new(enr, enr_key, config)?;
+        if let Err(e) = discv5.start().await {
+            error!("Failed to start discv5 at iteration {i}: {e:?}");
+            failed = true;
+            break;
+        }
+
+        // Run a trivial operation. The routing table is empty so the query completes immediately.
+        if let Err(e) = rpc_stats.find_node(&discv5, NodeId::random()).await {
+            error!("Failed to run FINDNODE query at iteration {i}: {e}");
+            failed = true;
+        }
+
+        // ////////////////////////
+        // Shutdown discv5
+        // ////////////////////////
+        discv5.shutdown();
+        drop(discv5);
+        tokio::time::sleep(WAIT_FOR_SHUTDOWN).await;
+
+        // ////////////////////////
+        // Sample resources
+        // ////////////////////////
+        // The port is released once it can be bound again on all the IP versions discv5 listens on.
+        let port_released = [
+            (ip_version.has_ipv4(), IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            (ip_version.has_ipv6(), IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        ]
+        .into_iter()
+        .filter(|(listens, _)| *listens)
+        .all(|(_, ip)| UdpSocket::bind((ip, port)).is_ok());
+        if !port_released {
+            error!("The port {port} hasn't been released at iteration {i}.");
+            failed = true;
+        }
+        let rss_kb = resident_memory_kb();
+        info!("iteration: {i}, port_released: {port_released}, rss_kb: {rss_kb:?}");
+        samples.push((i, port_released, rss_kb));
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let mut write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("iteration", i)
+        .add_field("port_released", port_released)
+        .add_tag("instance_seq", client.global_seq());
+        if let Some(rss_kb) = rss_kb {
+            write_query = write_query.add_field("rss_kb", rss_kb);
+        }
+        record_metric(&client, write_query).await;
+    }
+
+    client.record_message(format!("(iteration, port_released, rss_kb): {:?}", samples));
+
+    rpc_stats.finish(&client, client.global_seq()).await;
+
+    // Each iteration shuts down its own discv5 as that's what is measured, so there's nothing left
+    // to tear down.
+    final_barrier(&client, run_parameters.test_instance_count).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// Read the resident set size of this process from procfs.
+fn resident_memory_kb() -> Option<u64> {
+    std::fs::read_to_string("/proc/self/status")
+        .ok()?
+        .lines()
+        .find(|line| line.starts_with("VmRSS:"))?
+        .split_whitespace()
+        .nth(1)?
+        .parse::<u64>()
+        .ok()
+}
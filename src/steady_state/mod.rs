@@ -0,0 +1,222 @@
+mod params;
+
+use crate::assertions::{Assertions, Thresholds};
+use crate::client_emulation::{ClientEmulation, EmulationConfig};
+use crate::config::build_config;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::steady_state::params::Params;
+use crate::teardown::teardown;
+use crate::topology::{join_topology, Topology};
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, collect_instance_info,
+    listen_config, populated_bucket_count, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::Discv5;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tokio::time::Instant;
+use tracing::info;
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+
+// The health of the table over the whole run, sampled every `sample_interval`.
+#[derive(Default)]
+struct Horizon {
+    samples: u64,
+    connected_peers_sum: u64,
+    connected_peers_min: Option<u64>,
+    added_entries: u64,
+    removed_entries: u64,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("steady-state", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+    let emulation_config = EmulationConfig {
+        duration: Duration::from_secs(params.duration),
+        ..EmulationConfig::from_params(&run_parameters.test_instance_params)?
+    };
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5: Discv5 = Discv5::new(
+        enr.clone(),
+        enr_key,
+        build_config(listen_config, &run_parameters)?,
+    )?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let discv5 = Arc::new(discv5);
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    let other_instances = collect_instance_info(&client, &instance_info).await?;
+    let participants = other_instances
+        .iter()
+        .map(|i| i.enr.node_id())
+        .collect::<HashSet<_>>();
+
+    // //////////////////////////////////////////////////////////////
+    // Star topology
+    // //////////////////////////////////////////////////////////////
+    let rpc_stats = RpcStats::default();
+    join_topology(
+        &discv5,
+        &rpc_stats,
+        Topology::Star,
+        &instance_info,
+        &other_instances,
+    )
+    .await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Emulate the client and sample the health of the table
+    // //////////////////////////////////////////////////////////////
+    let emulation = ClientEmulation::start(
+        &client,
+        discv5.clone(),
+        rpc_stats.clone(),
+        instance_info.seq,
+        emulation_config,
+    );
+    let started_at = Instant::now();
+    let mut horizon = Horizon::default();
+    let mut previous = HashSet::new();
+    let mut interval = tokio::time::interval(Duration::from_secs(params.sample_interval));
+
+    while !emulation.is_finished() {
+        interval.tick().await;
+
+        let statuses = discv5
+            .kbuckets()
+            .iter()
+            .map(|entry| (*entry.node.key.preimage(), entry.status.is_connected()))
+            .collect::<HashMap<NodeId, bool>>();
+        let entries = statuses.keys().copied().collect::<HashSet<_>>();
+        let connected = statuses.values().filter(|connected| **connected).count() as u64;
+        let known_participants = entries.intersection(&participants).count() as u64;
+        let added = entries.difference(&previous).count() as u64;
+        let removed = previous.difference(&entries).count() as u64;
+        previous = entries;
+
+        horizon.samples += 1;
+        horizon.connected_peers_sum += connected;
+        horizon.connected_peers_min = Some(
+            horizon
+                .connected_peers_min
+                .map_or(connected, |min| min.min(connected)),
+        );
+        horizon.added_entries += added;
+        horizon.removed_entries += removed;
+
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("elapsed_secs", started_at.elapsed().as_secs())
+        .add_field("table_entries", statuses.len() as u64)
+        .add_field("connected_entries", connected)
+        .add_field("disconnected_entries", statuses.len() as u64 - connected)
+        .add_field("known_participants", known_participants)
+        .add_field(
+            "unknown_entries",
+            statuses.len() as u64 - known_participants,
+        )
+        .add_field("populated_buckets", populated_bucket_count(&discv5) as u64)
+        .add_field("added_entries", added)
+        .add_field("removed_entries", removed)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("source", "health");
+        record_metric(&client, write_query).await;
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Record the summary of the whole run
+    // //////////////////////////////////////////////////////////////
+    let stats = emulation.stats();
+    drop(emulation);
+    let connected_peers = discv5.connected_peers();
+    let connected_peers_mean = if horizon.samples == 0 {
+        0.0
+    } else {
+        horizon.connected_peers_sum as f64 / horizon.samples as f64
+    };
+    info!(
+        "connected peers: {connected_peers} (mean: {connected_peers_mean:.1}, min: {:?}), table churn: +{}/-{}, emulation: {stats:?}",
+        horizon.connected_peers_min, horizon.added_entries, horizon.removed_entries
+    );
+
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("duration_secs", started_at.elapsed().as_secs())
+    .add_field("connected_peers", connected_peers as u64)
+    .add_field("connected_peers_mean", connected_peers_mean)
+    .add_field("added_entries", horizon.added_entries)
+    .add_field("removed_entries", horizon.removed_entries)
+    .add_tag("instance_seq", instance_info.seq)
+    .add_tag("source", "summary");
+    let mut write_query = stats.add_fields(write_query);
+    if let Some(min) = horizon.connected_peers_min {
+        write_query = write_query.add_field("connected_peers_min", min);
+    }
+    record_metric(&client, write_query).await;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // The health of the table over the run is the output itself, so the test case only fails
+    // against the thresholds given.
+    let thresholds = Thresholds::from_params(&run_parameters.test_instance_params)?;
+    let mut assertions = Assertions::new();
+    assertions.assert_at_least_opt(
+        "peers_discovered",
+        connected_peers,
+        thresholds.min_peers_discovered,
+    );
+    assertions.record(&client).await
+}
@@ -0,0 +1,24 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub duration: u64,
+    pub sample_interval: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        let params = Params {
+            duration: get_param_or::<u64>("duration", instance_params, 600)?,
+            sample_interval: get_param_or::<u64>("sample_interval", instance_params, 10)?,
+        };
+
+        if params.sample_interval == 0 {
+            return Err("sample_interval must be non-zero.".into());
+        }
+
+        Ok(params)
+    }
+}
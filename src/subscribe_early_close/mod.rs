@@ -0,0 +1,182 @@
+use crate::scenario::Registry;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, publish_and_collect, record_metric, Exchange,
+};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED: &str = "state_completed";
+const TOPIC_REPLAYED: &str = "replayed";
+const TOPIC_ROUNDS: &str = "rounds";
+const TOPIC_GROUP: &str = "group";
+const TOPIC_SUBSET: &str = "subset";
+
+// The message each test instance publishes. This test case runs no discv5, so the instances have
+// no ENRs to share.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Message {
+    // The sequence number of this test instance within the test.
+    seq: u64,
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("subscribe-early-close", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let message = Message {
+        seq: client.global_seq(),
+    };
+    let participants = publish_and_collect(&client, message).await?;
+    let messages = participants
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // //////////////////////////////////////////////////////////////
+    // Replay the collected messages through the exchange
+    // //////////////////////////////////////////////////////////////
+    // The messages are fed to the collection of an exchange as if they were delivered by its
+    // subscription.
+    let mut failed = false;
+    let count = run_parameters.test_instance_count;
+    let exchange = Exchange::new(&client, TOPIC_REPLAYED)?;
+
+    // All the messages are delivered, and the first one twice as if it were published by a retry.
+    let delivered = messages
+        .iter()
+        .chain(messages.first())
+        .cloned()
+        .map(Ok::<_, std::io::Error>);
+    match exchange
+        .collect::<Message, _, _>(&client, tokio_stream::iter(delivered))
+        .await
+    {
+        Ok(collected) => {
+            let seqs = collected.iter().map(|m| m.seq).collect::<Vec<_>>();
+            if seqs != (1..=count).collect::<Vec<_>>() {
+                error!("Unexpected messages collected from the complete stream. seqs: {seqs:?}");
+                failed = true;
+            }
+        }
+        Err(e) => {
+            error!("Failed to collect messages from the complete stream: {e}");
+            failed = true;
+        }
+    }
+
+    // The subscription is closed before the message of #1 is delivered. This should result in an
+    // error naming the missing seq rather than a panic.
+    let delivered = messages.into_iter().skip(1).map(Ok::<_, std::io::Error>);
+    let returned_error = match exchange
+        .collect::<Message, _, _>(&client, tokio_stream::iter(delivered))
+        .await
+    {
+        Ok(collected) => {
+            error!(
+                "Collected {} messages from the stream closed early.",
+                collected.len()
+            );
+            failed = true;
+            false
+        }
+        Err(e) => {
+            info!("The stream closed early has been handled: {e}");
+            if !e.to_string().contains("missing the seqs [1]") {
+                error!("The error doesn't name the missing seq 1: {e}");
+                failed = true;
+            }
+            true
+        }
+    };
+
+    // //////////////////////////////////////////////////////////////
+    // Exchange in scopes
+    // //////////////////////////////////////////////////////////////
+    // The exchanges repeated on the same topic are told apart by their scopes.
+    let mut collided = false;
+    for round in 0..2_u64 {
+        let collected = Exchange::new(&client, TOPIC_ROUNDS)?
+            .scoped(round)
+            .publish_and_collect(&client, round)
+            .await?;
+        if collected.iter().any(|r| *r != round) {
+            error!("Collected the messages of another round in round {round}: {collected:?}");
+            collided = true;
+        }
+    }
+
+    // Only the instances in the same group take part in the group-scoped exchange.
+    let group_id = run_parameters.test_group_id.clone();
+    let group = Exchange::new(&client, TOPIC_GROUP)?
+        .within_group(&client)
+        .publish_and_collect(&client, group_id.clone())
+        .await?;
+    let group_mismatch = group.len() as u64 != run_parameters.test_group_instance_count
+        || group.iter().any(|g| *g != group_id);
+    if group_mismatch {
+        error!("Unexpected messages in the exchange of the group {group_id}: {group:?}");
+    }
+
+    // Only the first half of the instances take part in the subset exchange.
+    let subset_count = (count + 1) / 2;
+    if client.global_seq() <= subset_count {
+        let subset = Exchange::new(&client, TOPIC_SUBSET)?
+            .count(subset_count)
+            .publish_and_collect(&client, client.global_seq())
+            .await?;
+        if subset != (1..=subset_count).collect::<Vec<_>>() {
+            error!("Unexpected messages in the subset exchange: {subset:?}");
+            failed = true;
+        }
+    }
+
+    if collided || group_mismatch {
+        failed = true;
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("returned_error", returned_error)
+    .add_field("collided", collided)
+    .add_field("group_mismatch", group_mismatch)
+    .add_tag("instance_seq", client.global_seq());
+    record_metric(&client, write_query).await;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use discv5::enr::{CombinedKey, EnrKey, NodeId};
+use discv5::Key;
+use std::time::{Duration, Instant};
+
+/// Brute-force keys taken from `generate_key` until the resulting node ids land at each of
+/// `distances`, i.e. the log2-distances from `target`, so that the sybils can be placed in the
+/// chosen buckets of the victim's routing table. A distance may be listed multiple times to place
+/// more than one sybil in the bucket.
+///
+/// The closer the distance, the more keys it takes: a node id lands at distance `d` with the
+/// probability of 2^(d - 257). Grinding stops once `budget` has elapsed, so the returned keys
+/// may cover only some of `distances`. The keys are returned along with their distances, in the
+/// order they have been found.
+pub(crate) fn grind_keys(
+    target: &NodeId,
+    distances: &[u64],
+    budget: Duration,
+    mut generate_key: impl FnMut() -> CombinedKey,
+) -> Vec<(u64, CombinedKey)> {
+    let target_key: Key<NodeId> = (*target).into();
+    let started_at = Instant::now();
+    let mut pending = distances.to_vec();
+    let mut found = vec![];
+
+    while !pending.is_empty() && started_at.elapsed() < budget {
+        let enr_key = generate_key();
+        let node_id = NodeId::from(enr_key.public());
+        let Some(distance) = Key::from(node_id).log2_distance(&target_key) else {
+            continue;
+        };
+        if let Some(i) = pending.iter().position(|d| *d == distance) {
+            pending.swap_remove(i);
+            found.push((distance, enr_key));
+        }
+    }
+
+    found
+}
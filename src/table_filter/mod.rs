@@ -0,0 +1,256 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::table_filter::params::Params;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr_with_fields, check_enr_port, enr_addresses,
+    enr_ip, listen_config, publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Enr};
+use ipnetwork::IpNetwork;
+use std::collections::HashSet;
+use std::sync::RwLock;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+// The ENR key of the eth2-style field, and the fork digest the conforming nodes advertise.
+const ETH2_KEY: &str = "eth2";
+const FORK_DIGEST: u64 = 0x4a26_c58b;
+
+// The subnets the ENRs are rejected from. `table_filter` accepts a function pointer only, so the
+// filter reads them from here.
+static DENIED_SUBNETS: RwLock<Vec<IpNetwork>> = RwLock::new(Vec::new());
+
+// The kinds of the participants, assigned in round-robin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    // Advertises the eth2 field, from an allowed subnet.
+    Conforming,
+    // Doesn't advertise the eth2 field.
+    MissingField,
+    // Advertises the eth2 field, but from a denied subnet.
+    DeniedSubnet,
+}
+
+impl Kind {
+    fn of(seq: u64) -> Kind {
+        match (seq - 1) % 3 {
+            0 => Kind::Conforming,
+            1 => Kind::MissingField,
+            _ => Kind::DeniedSubnet,
+        }
+    }
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("table-filter", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+    let kind = Kind::of(client.global_seq());
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr_with_fields(&run_parameters, client.global_seq(), |builder| {
+        if kind != Kind::MissingField {
+            builder.add_value(ETH2_KEY, &FORK_DIGEST);
+        }
+    })?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    // Only the conforming nodes install the filter, so that the NODES responses of the others
+    // carry the non-conforming ENRs to them.
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5_config = config_builder(listen_config, &run_parameters.test_instance_params)?;
+    if kind == Kind::Conforming {
+        discv5_config.table_filter(table_filter);
+    }
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, discv5_config.build())?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}, kind: {:?}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr),
+        kind
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // The subnets of the denied nodes, i.e. their /32s, are denied.
+    {
+        let mut denied = DENIED_SUBNETS.write().expect("Lock the denied subnets");
+        for p in participants
+            .iter()
+            .filter(|p| Kind::of(p.seq) == Kind::DeniedSubnet)
+        {
+            denied.push(IpNetwork::from(enr_ip(&p.enr)?));
+        }
+    }
+    let non_conforming = participants
+        .iter()
+        .filter(|p| Kind::of(p.seq) != Kind::Conforming)
+        .map(|p| p.enr.node_id())
+        .collect::<HashSet<_>>();
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is conforming, and knows all the other participants, which know #1 only. The
+    // non-conforming ENRs should be rejected by the filter of #1.
+    let mut rejected = 0;
+    if instance_info.seq == 1 {
+        for p in participants.iter().filter(|p| p.seq != instance_info.seq) {
+            if let Err(e) = discv5.add_enr(p.enr.clone()) {
+                info!(
+                    "Rejected the ENR of #{} ({:?}): {e}",
+                    p.seq,
+                    Kind::of(p.seq)
+                );
+                rejected += 1;
+            }
+        }
+    } else {
+        let node1 = participants.iter().find(|p| p.seq == 1).expect("#1");
+        discv5.add_enr(node1.enr.clone())?;
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run FINDNODE queries
+    // //////////////////////////////////////////////////////////////
+    // Every node introduces itself to #1 by the self-lookup, and then the lookups for random
+    // targets spread the ENRs through the NODES responses.
+    if let Err(e) = rpc_stats
+        .find_node(&discv5, instance_info.enr.node_id())
+        .await
+    {
+        warn!("Failed to run the self-lookup: {e}");
+    }
+    for _ in 0..params.lookups {
+        if let Err(e) = rpc_stats.find_node(&discv5, NodeId::random()).await {
+            warn!("Failed to run FINDNODE query: {e}");
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Check the routing table
+    // //////////////////////////////////////////////////////////////
+    let (conforming, violating): (Vec<Enr>, Vec<Enr>) = discv5
+        .table_entries_enr()
+        .into_iter()
+        .partition(|enr| !non_conforming.contains(&enr.node_id()));
+    client.record_message(format!(
+        "conforming: {}, non_conforming: {}, rejected: {rejected}",
+        conforming.len(),
+        violating.len()
+    ));
+
+    // //////////////////////////////////////////////////////////////
+    // Record metrics
+    // //////////////////////////////////////////////////////////////
+    let write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("conforming", conforming.len() as u64)
+    .add_field("non_conforming", violating.len() as u64)
+    .add_field("rejected", rejected as u64)
+    .add_tag("instance_seq", instance_info.seq)
+    .add_tag("kind", format!("{kind:?}"));
+    record_metric(&client, write_query).await;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    // The nodes without the filter are expected to have anyone.
+    if kind != Kind::Conforming || violating.is_empty() {
+        client.record_success().await?;
+    } else {
+        error!(
+            "The routing table contains non-conforming nodes: {:?}",
+            violating
+                .iter()
+                .map(|enr| enr.node_id())
+                .collect::<Vec<_>>()
+        );
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    }
+
+    Ok(())
+}
+
+// The filter composed of the two predicates below.
+fn table_filter(enr: &Enr) -> bool {
+    has_eth2_field(enr) && !in_denied_subnet(enr)
+}
+
+fn has_eth2_field(enr: &Enr) -> bool {
+    enr.get_decodable::<u64>(ETH2_KEY)
+        .and_then(|digest| digest.ok())
+        == Some(FORK_DIGEST)
+}
+
+fn in_denied_subnet(enr: &Enr) -> bool {
+    let Ok(ip) = enr_ip(enr) else {
+        return false;
+    };
+    DENIED_SUBNETS
+        .read()
+        .expect("Lock the denied subnets")
+        .iter()
+        .any(|subnet| subnet.contains(ip))
+}
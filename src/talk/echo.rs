@@ -0,0 +1,244 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, get_param_or,
+    listen_config, publish_and_collect, record_metric, InstanceInfo, MAX_DISTANCE,
+};
+use chrono::Local;
+use discv5::{Discv5, Event};
+use rand::RngCore;
+use std::collections::HashMap;
+use std::time::Instant;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info, warn};
+
+const STATE_READY_TO_TALK: &str = "state_ready_to_talk";
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_TALK: &str = "state_completed_to_talk";
+
+const PROTOCOL: &[u8] = b"echo";
+
+struct Params {
+    payload_size: usize,
+}
+
+impl Params {
+    fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            payload_size: get_param_or::<usize>("payload_size", instance_params, 64)?,
+        })
+    }
+}
+
+pub(crate) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    // //////////////////////////////////////////////////////////////
+    // Register the echo handler
+    // //////////////////////////////////////////////////////////////
+    // Every instance responds to TALKREQs of the echo protocol with the request body as is.
+    let mut event_stream = discv5.event_stream().await?;
+    let handler = tokio::spawn(async move {
+        while let Some(event) = event_stream.recv().await {
+            if let Event::TalkRequest(talk_request) = event {
+                if talk_request.protocol() != PROTOCOL {
+                    warn!("Unknown protocol: {:?}", talk_request.protocol());
+                    continue;
+                }
+
+                let body = talk_request.body().to_vec();
+                if let Err(e) = talk_request.respond(body) {
+                    error!("Failed to respond to TALKREQ: {e}");
+                }
+            }
+        }
+    });
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}, is_bootstrap_node: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr),
+        instance_info.is_bootstrap_node,
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_READY_TO_TALK,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Discover peers
+    // //////////////////////////////////////////////////////////////
+    // The bootstrap nodes know all the other nodes, and the other nodes discover their peers via
+    // the bootstrap nodes.
+    if instance_info.is_bootstrap_node {
+        for p in participants.iter().filter(|p| p.seq != instance_info.seq) {
+            if let Err(e) = discv5.add_enr(p.enr.clone()) {
+                error!("Failed to add the ENR of #{}: {e}", p.seq);
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    let rpc_stats = RpcStats::default();
+    let mut peers = vec![];
+    if !instance_info.is_bootstrap_node {
+        for b in participants.iter().filter(|p| p.is_bootstrap_node) {
+            match rpc_stats
+                .find_node_designated_peer(&discv5, b.enr.clone(), (1..=MAX_DISTANCE).collect())
+                .await
+            {
+                Ok(enrs) => peers.extend(enrs),
+                Err(e) => warn!("Failed to run FINDNODE request to #{}: {e}", b.seq),
+            }
+            peers.push(b.enr.clone());
+        }
+        peers.sort_by_key(|enr| enr.node_id());
+        peers.dedup_by_key(|enr| enr.node_id());
+        peers.retain(|enr| enr.node_id() != instance_info.enr.node_id());
+    }
+
+    // //////////////////////////////////////////////////////////////
+    // Exchange payloads
+    // //////////////////////////////////////////////////////////////
+    let mut failures = 0_u64;
+    for peer in peers.iter() {
+        let mut payload = vec![0_u8; params.payload_size];
+        rand::thread_rng().fill_bytes(&mut payload);
+
+        let started_at = Instant::now();
+        let result = rpc_stats
+            .talk_req(&discv5, peer.clone(), PROTOCOL.to_vec(), payload.clone())
+            .await;
+        let elapsed = started_at.elapsed();
+
+        let success = match result {
+            Ok(response) if response == payload => {
+                info!("{} echoed the payload.", peer.node_id());
+                true
+            }
+            Ok(response) => {
+                error!(
+                    "Invalid response from {}. expected: {} bytes, actual: {} bytes",
+                    peer.node_id(),
+                    payload.len(),
+                    response.len()
+                );
+                false
+            }
+            Err(e) => {
+                error!("Failed to send TALKREQ to {}: {e}", peer.node_id());
+                false
+            }
+        };
+        if !success {
+            failures += 1;
+        }
+
+        // //////////////////////////////////////////////////////////////
+        // Record metrics
+        // //////////////////////////////////////////////////////////////
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("latency_ms", elapsed.as_millis() as u64)
+        .add_field("success", success)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("payload_size", params.payload_size as u64);
+        record_metric(&client, write_query).await;
+    }
+
+    if !instance_info.is_bootstrap_node {
+        client.record_message(format!(
+            "Sent TALKREQs to {} peers, {failures} of which have failed.",
+            peers.len()
+        ));
+        let write_query = WriteQuery::new(
+            Local::now().into(),
+            format!(
+                "discv5-testground_{}_{}",
+                run_parameters.test_case, run_parameters.test_run
+            ),
+        )
+        .add_field("requests", peers.len() as u64)
+        .add_field("failures", failures)
+        .add_tag("instance_seq", instance_info.seq)
+        .add_tag("payload_size", params.payload_size as u64);
+        record_metric(&client, write_query).await;
+    }
+    rpc_stats.finish(&client, instance_info.seq).await;
+    let failed = failures > 0 || (!instance_info.is_bootstrap_node && peers.is_empty());
+
+    // Keep responding until all the instances have finished their exchanges.
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_TALK,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+    handler.abort();
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
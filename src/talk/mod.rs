@@ -1,18 +1,23 @@
-use crate::utils::publish_and_collect;
-use discv5::enr::CombinedKey;
-use discv5::{Discv5, Enr, Event, ListenConfig};
-use serde::{Deserialize, Serialize};
+pub(crate) mod echo;
+
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, listen_config,
+    publish_and_collect, unsupported_seq, InstanceInfo,
+};
+use discv5::{Discv5, Event};
 use testground::client::Client;
 use tracing::{debug, info};
 
 const STATE_READY_TO_START_SIM: &str = "state_ready_to_start_sim";
 const STATE_FINISHED: &str = "state_finished";
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-struct InstanceInfo {
-    // The sequence number of this test instance within the test.
-    seq: u64,
-    enr: Enr,
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("talk", run);
+    registry.register("talk-request", echo::run);
 }
 
 pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
@@ -23,14 +28,7 @@ pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
     // ////////////////////////
     // Construct local Enr
     // ////////////////////////
-    let enr_key = CombinedKey::generate_secp256k1();
-    let enr = Enr::builder()
-        .ip(run_parameters
-            .data_network_ip()?
-            .expect("IP address for the data network"))
-        .udp4(9000)
-        .build(&enr_key)
-        .expect("Construct an Enr");
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
 
     info!("ENR: {:?}", enr);
     info!("NodeId: {}", enr.node_id());
@@ -38,20 +36,22 @@ pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
     // //////////////////////////////////////////////////////////////
     // Start Discovery v5 server
     // //////////////////////////////////////////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
     let mut discv5: Discv5 = Discv5::new(
         enr,
         enr_key,
-        discv5::ConfigBuilder::new(ListenConfig::default()).build(),
+        config_builder(listen_config, &run_parameters.test_instance_params)?.build(),
     )?;
-    discv5.start().await.expect("Start Discovery v5 server");
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
 
     // //////////////////////////////////////////////////////////////
     // Collect information of all participants in the test case
     // //////////////////////////////////////////////////////////////
-    let instance_info = InstanceInfo {
-        seq: client.global_seq(),
-        enr: discv5.local_enr(),
-    };
+    let instance_info = InstanceInfo::new(&client, discv5.local_enr())?;
     debug!("instance_info: {:?}", instance_info);
 
     let another_node = publish_and_collect(&client, instance_info.clone())
@@ -60,19 +60,29 @@ pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
         .find(|info| info.seq != client.global_seq())
         .unwrap();
 
-    client
-        .signal_and_wait(STATE_READY_TO_START_SIM, run_parameters.test_instance_count)
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_READY_TO_START_SIM,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
 
     let protocol = "PROTOCOL".as_bytes();
     let request = "A REQUEST".as_bytes();
     let response = "A RESPONSE".as_bytes();
 
+    let rpc_stats = RpcStats::default();
     let test_result = match client.global_seq() {
         1 => {
             // Send TALKREQ
-            match discv5
-                .talk_req(another_node.enr, protocol.to_vec(), request.to_vec())
+            match rpc_stats
+                .talk_req(
+                    &discv5,
+                    another_node.enr,
+                    protocol.to_vec(),
+                    request.to_vec(),
+                )
                 .await
             {
                 Ok(talk_response) => {
@@ -110,12 +120,25 @@ pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>
             }
             result
         }
-        _ => unreachable!(),
+        seq => return Err(unsupported_seq(seq)),
     };
 
-    client
-        .signal_and_wait(STATE_FINISHED, client.run_parameters().test_instance_count)
-        .await?;
+    barrier_with_timeout(
+        &client,
+        STATE_FINISHED,
+        client.run_parameters().test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, client.global_seq()).await;
+    teardown(
+        &client,
+        discv5,
+        client.global_seq(),
+        run_parameters.test_instance_count,
+    )
+    .await?;
 
     if let Err(e) = test_result {
         client.record_failure(e).await.unwrap();
@@ -0,0 +1,111 @@
+use crate::report::write_json;
+use crate::utils::{barrier_timeout, barrier_with_timeout, bucket_histogram};
+use discv5::Discv5;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use testground::client::Client;
+use tracing::{info, warn};
+
+const STATE_TEARDOWN: &str = "state_teardown";
+// The file name of the snapshot in the outputs directory of the instance.
+const FINAL_STATE_FILE: &str = "final_state.json";
+
+/// The state of discv5 at the end of a test case, which is written to `final_state.json` in the
+/// outputs directory of the instance, so that a failed run can be diagnosed offline.
+///
+/// NOTE: discv5 doesn't expose the sessions themselves, so only the number of them is taken.
+#[derive(Debug, Serialize)]
+struct FinalState {
+    seq: u64,
+    enr: String,
+    node_id: String,
+    connected_peers: usize,
+    table: Vec<TableEntry>,
+    // The number of entries of each occupied bucket, see `bucket_histogram`.
+    buckets: BTreeMap<u8, usize>,
+    active_sessions: usize,
+    bytes_sent: usize,
+    bytes_recv: usize,
+    unsolicited_requests_per_second: f32,
+}
+
+#[derive(Debug, Serialize)]
+struct TableEntry {
+    node_id: String,
+    enr: String,
+    connected: bool,
+}
+
+impl FinalState {
+    fn new(discv5: &Discv5, seq: u64) -> Self {
+        let enr = discv5.local_enr();
+        let metrics = discv5.metrics();
+        FinalState {
+            seq,
+            enr: enr.to_base64(),
+            node_id: enr.node_id().to_string(),
+            connected_peers: discv5.connected_peers(),
+            table: discv5
+                .kbuckets()
+                .iter()
+                .map(|entry| TableEntry {
+                    node_id: entry.node.key.preimage().to_string(),
+                    enr: entry.node.value.to_base64(),
+                    connected: entry.status.is_connected(),
+                })
+                .collect(),
+            buckets: bucket_histogram(discv5),
+            active_sessions: metrics.active_sessions,
+            bytes_sent: metrics.bytes_sent,
+            bytes_recv: metrics.bytes_recv,
+            unsolicited_requests_per_second: metrics.unsolicited_requests_per_second,
+        }
+    }
+}
+
+/// The final phase of a test case: wait at a final barrier for `count` instances, write the
+/// snapshot of `discv5`, see `FinalState`, and shut it down. Everything running along with discv5,
+/// e.g. the `MetricsPoller`, must have been dropped beforehand, as the server can only be shut down
+/// once this is the last reference to it. Otherwise it's dropped as is, with a warning.
+pub(crate) async fn teardown(
+    client: &Client,
+    discv5: impl Into<Arc<Discv5>>,
+    instance_seq: u64,
+    count: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let discv5 = discv5.into();
+    final_barrier(client, count).await?;
+
+    let final_state = FinalState::new(&discv5, instance_seq);
+    info!(
+        "Final state: {} entries, {} connected peers, {} active sessions.",
+        final_state.table.len(),
+        final_state.connected_peers,
+        final_state.active_sessions
+    );
+    write_json(
+        &Path::new(&client.run_parameters().test_outputs_path).join(FINAL_STATE_FILE),
+        &final_state,
+    );
+
+    match Arc::try_unwrap(discv5) {
+        Ok(mut discv5) => discv5.shutdown(),
+        Err(discv5) => warn!(
+            "Discv5 is still shared by {} other references, so it's dropped without the shutdown.",
+            Arc::strong_count(&discv5) - 1
+        ),
+    }
+    Ok(())
+}
+
+/// The final barrier of `teardown`, for the instances that don't run discv5, e.g. the mocks, so
+/// that the instances running it don't wait for them until the barrier times out.
+pub(crate) async fn final_barrier(
+    client: &Client,
+    count: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    barrier_with_timeout(client, STATE_TEARDOWN, count, barrier_timeout(client)?).await?;
+    Ok(())
+}
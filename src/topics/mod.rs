@@ -0,0 +1,293 @@
+mod params;
+
+use crate::config::build_config;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::topics::params::Params;
+use crate::topology::{join_topology, Topology};
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, bootstrap_count, build_enr, check_enr_port,
+    collect_instance_info, listen_config, publish_and_collect_on, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::{Discv5, Enr, Topic};
+use std::collections::HashSet;
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_TO_QUERY: &str = "state_completed_to_query";
+const TOPIC_STORED_ADS: &str = "stored_ads";
+
+// The roles of the participants, assigned by seq. Every node serves as a registrar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+    Bootstrap,
+    // Registers the topic.
+    Advertiser,
+    // Queries the topic.
+    Querier,
+}
+
+impl Role {
+    fn of(seq: u64, bootstrap_count: u64, advertisers: u64) -> Role {
+        if seq <= bootstrap_count {
+            Role::Bootstrap
+        } else if seq <= bootstrap_count + advertisers {
+            Role::Advertiser
+        } else {
+            Role::Querier
+        }
+    }
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("topics", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+    let bootstrap_count = bootstrap_count(&client)?;
+    if run_parameters.test_instance_count <= bootstrap_count + params.advertisers {
+        return Err("`advertisers` must leave at least one querier.".into());
+    }
+    let role = Role::of(client.global_seq(), bootstrap_count, params.advertisers);
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let mut discv5: Discv5 = Discv5::new(
+        enr.clone(),
+        enr_key,
+        build_config(listen_config, &run_parameters)?,
+    )?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, role: {:?}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        role
+    ));
+    let other_instances = collect_instance_info(&client, &instance_info).await?;
+    let advertisers = other_instances
+        .iter()
+        .filter(|i| Role::of(i.seq, bootstrap_count, params.advertisers) == Role::Advertiser)
+        .map(|i| i.enr.node_id())
+        .collect::<HashSet<_>>();
+
+    // //////////////////////////////////////////////////////////////
+    // Star topology
+    // //////////////////////////////////////////////////////////////
+    let rpc_stats = RpcStats::default();
+    join_topology(
+        &discv5,
+        &rpc_stats,
+        Topology::Star,
+        &instance_info,
+        &other_instances,
+    )
+    .await?;
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Register and query the topic
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+    let started_at = Instant::now();
+    let deadline = started_at + Duration::from_secs(params.duration);
+
+    match role {
+        Role::Advertiser => {
+            let registered = match register_topic(&discv5, &params.topic).await {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("Failed to register the topic `{}`: {e}", params.topic);
+                    failed = true;
+                    false
+                }
+            };
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("registered", registered)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("source", "registration");
+            record_metric(&client, write_query).await;
+
+            // Keep the advertisement alive while the queriers query it.
+            tokio::time::sleep_until(deadline).await;
+        }
+        Role::Querier => {
+            let mut interval = tokio::time::interval(Duration::from_secs(params.query_interval));
+            let mut recall = 0.0;
+            while Instant::now() < deadline {
+                interval.tick().await;
+
+                let found = match topic_query(&discv5, &params.topic).await {
+                    Ok(enrs) => enrs
+                        .iter()
+                        .map(|enr| enr.node_id())
+                        .filter(|node_id| advertisers.contains(node_id))
+                        .collect::<HashSet<_>>(),
+                    Err(e) => {
+                        warn!("Failed to query the topic `{}`: {e}", params.topic);
+                        HashSet::new()
+                    }
+                };
+                recall = found.len() as f64 / advertisers.len() as f64;
+
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("elapsed_secs", started_at.elapsed().as_secs())
+                .add_field("found", found.len() as u64)
+                .add_field("recall", recall)
+                .add_tag("instance_seq", instance_info.seq)
+                .add_tag("source", "query");
+                record_metric(&client, write_query).await;
+            }
+
+            info!("The final recall of the topic: {recall:.3}");
+            if recall < params.min_recall {
+                error!(
+                    "The final recall {recall:.3} is below {}.",
+                    params.min_recall
+                );
+                failed = true;
+            }
+        }
+        Role::Bootstrap => tokio::time::sleep_until(deadline).await,
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Aggregate the ad placement across the registrars
+    // //////////////////////////////////////////////////////////////
+    let ads = match stored_ads(&discv5, &params.topic).await {
+        Ok(ads) => ads as u64,
+        Err(e) => {
+            warn!("Failed to count the stored ads: {e}");
+            0
+        }
+    };
+    let placement =
+        publish_and_collect_on(&client, TOPIC_STORED_ADS, (instance_info.seq, ads)).await?;
+
+    let mut write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("ads", ads)
+    .add_tag("instance_seq", instance_info.seq)
+    .add_tag("role", format!("{role:?}"))
+    .add_tag("source", "placement");
+    if instance_info.seq == 1 {
+        let total = placement.iter().map(|(_, ads)| ads).sum::<u64>();
+        let registrars = placement.iter().filter(|(_, ads)| *ads > 0).count() as u64;
+        let max = placement.iter().map(|(_, ads)| *ads).max().unwrap_or(0);
+        client.record_message(format!(
+            "ads: {total}, registrars: {registrars}/{}, max: {max}",
+            placement.len()
+        ));
+        write_query = write_query
+            .add_field("ads_total", total)
+            .add_field("registrars_with_ads", registrars)
+            .add_field("ads_max", max)
+            .add_field("ads_mean", total as f64 / placement.len() as f64);
+    }
+    record_metric(&client, write_query).await;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
+
+// ////////////////////////
+// Topic API
+// ////////////////////////
+// NOTE: discv5 hasn't released the topics, and their API differs among the branches which have
+// them enabled. The test case calls the branch through the functions below only, which follow
+// `register_topic`, `topic_query_req` and `active_topics` of the topic branch of sigp/discv5.
+
+async fn register_topic(discv5: &Discv5, topic: &str) -> Result<(), String> {
+    discv5
+        .register_topic(topic)
+        .await
+        .map_err(|e| format!("{e:?}"))
+}
+
+async fn topic_query(discv5: &Discv5, topic: &str) -> Result<Vec<Enr>, String> {
+    discv5
+        .topic_query_req(topic)
+        .await
+        .map_err(|e| format!("{e:?}"))
+}
+
+// The number of the ads for `topic` this node stores as a registrar.
+async fn stored_ads(discv5: &Discv5, topic: &str) -> Result<usize, String> {
+    let ads = discv5.active_topics().await.map_err(|e| format!("{e:?}"))?;
+    Ok(ads.get_ad_nodes(Topic::new(topic).hash()).count())
+}
@@ -0,0 +1,33 @@
+use crate::utils::get_param_or;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub topic: String,
+    pub advertisers: u64,
+    pub duration: u64,
+    pub query_interval: u64,
+    pub min_recall: f64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        let params = Params {
+            topic: get_param_or::<String>("topic", instance_params, "lighthouse".to_owned())?,
+            advertisers: get_param_or::<u64>("advertisers", instance_params, 3)?,
+            duration: get_param_or::<u64>("duration", instance_params, 60)?,
+            query_interval: get_param_or::<u64>("query_interval", instance_params, 5)?,
+            min_recall: get_param_or::<f64>("min_recall", instance_params, 0.0)?,
+        };
+
+        if params.advertisers == 0 {
+            return Err("`advertisers` must be greater than 0.".into());
+        }
+        if params.query_interval == 0 {
+            return Err("`query_interval` must be greater than 0.".into());
+        }
+
+        Ok(params)
+    }
+}
@@ -0,0 +1,138 @@
+use crate::rpc_stats::RpcStats;
+use crate::utils::{get_param_or, InstanceInfo};
+use discv5::enr::NodeId;
+use discv5::{Discv5, Key};
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::{error, info};
+
+/// The initial topology, i.e. which peers each node adds to its routing table at startup.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Topology {
+    /// The bootstrap nodes know all the other nodes, and each of the other nodes knows one of the
+    /// bootstrap nodes chosen at random.
+    Star,
+    /// Each node knows the node with the previous seq.
+    Chain,
+    /// Same as `Chain`, but the first node knows the last one.
+    Ring,
+    /// Each node knows `k` nodes chosen at random.
+    RandomK(usize),
+}
+
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "star" => Ok(Topology::Star),
+            "chain" => Ok(Topology::Chain),
+            "ring" => Ok(Topology::Ring),
+            _ => s
+                .strip_prefix("random-")
+                .and_then(|k| k.parse::<usize>().ok())
+                .filter(|k| *k > 0)
+                .map(Topology::RandomK)
+                .ok_or(format!(
+                    "Invalid topology: {s}. It must be one of star, chain, ring or random-<k>."
+                )),
+        }
+    }
+}
+
+impl Topology {
+    /// The topology given by the optional `topology` param. Defaults to `star`.
+    pub(crate) fn from_params(instance_params: &HashMap<String, String>) -> Result<Self, String> {
+        get_param_or::<String>("topology", instance_params, "star".to_owned())?.parse()
+    }
+
+    /// The peers `instance_info` adds at startup, out of `other_instances`.
+    pub(crate) fn peers<'a>(
+        &self,
+        instance_info: &InstanceInfo,
+        other_instances: &'a [InstanceInfo],
+    ) -> Vec<&'a InstanceInfo> {
+        let mut rng = rand::thread_rng();
+        let last_seq = other_instances
+            .iter()
+            .map(|i| i.seq)
+            .max()
+            .unwrap_or(instance_info.seq)
+            .max(instance_info.seq);
+
+        match self {
+            Topology::Star if instance_info.is_bootstrap_node => other_instances.iter().collect(),
+            Topology::Star => {
+                let bootstrap_nodes = other_instances
+                    .iter()
+                    .filter(|i| i.is_bootstrap_node)
+                    .collect::<Vec<_>>();
+                bootstrap_nodes
+                    .choose(&mut rng)
+                    .copied()
+                    .into_iter()
+                    .collect()
+            }
+            Topology::Chain => other_instances
+                .iter()
+                .filter(|i| i.seq + 1 == instance_info.seq)
+                .collect(),
+            Topology::Ring => {
+                let previous = if instance_info.seq == 1 {
+                    last_seq
+                } else {
+                    instance_info.seq - 1
+                };
+                other_instances
+                    .iter()
+                    .filter(|i| i.seq == previous)
+                    .collect()
+            }
+            Topology::RandomK(k) => other_instances.choose_multiple(&mut rng, *k).collect(),
+        }
+    }
+}
+
+/// Add the peers of `topology` to the routing table, and introduce this node to them with a
+/// FINDNODE request, accounted by `rpc_stats`, so that they know this node too. The bootstrap
+/// nodes of the star topology don't introduce themselves, as all the other nodes contact them.
+pub(crate) async fn join_topology(
+    discv5: &Discv5,
+    rpc_stats: &RpcStats,
+    topology: Topology,
+    instance_info: &InstanceInfo,
+    other_instances: &[InstanceInfo],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let peers = topology.peers(instance_info, other_instances);
+    info!(
+        "topology: {topology:?}, peers: {:?}",
+        peers.iter().map(|p| p.seq).collect::<Vec<_>>()
+    );
+
+    for peer in peers.iter() {
+        discv5.add_enr(peer.enr.clone())?;
+    }
+    if matches!(topology, Topology::Star) && instance_info.is_bootstrap_node {
+        return Ok(());
+    }
+
+    let key: Key<NodeId> = discv5.local_enr().node_id().into();
+    for peer in peers {
+        // Emit distance to the peer.
+        let peer_key: Key<NodeId> = peer.enr.node_id().into();
+        info!(
+            "Distance between `self` and #{}: {}",
+            peer.seq,
+            key.log2_distance(&peer_key).expect("Distance")
+        );
+
+        if let Err(e) = rpc_stats
+            .find_node_designated_peer(discv5, peer.enr.clone(), vec![0])
+            .await
+        {
+            error!("Failed to run FIND_NODE query against #{}: {e}", peer.seq);
+        }
+    }
+    Ok(())
+}
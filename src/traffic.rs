@@ -0,0 +1,186 @@
+use crate::utils::{get_param_or, record_metric};
+use chrono::Local;
+use discv5::Discv5;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use testground::client::Client;
+use testground::WriteQuery;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// The traffic of an instance. The bytes are the ones of the discv5 socket, taken from the discv5
+/// metrics. discv5 doesn't count the packets, so they are taken from the statistics of the data
+/// network interface, which carries nothing but the discv5 traffic in Testground.
+#[derive(Clone, Copy, Debug)]
+struct Traffic {
+    bytes_sent: u64,
+    bytes_recv: u64,
+    // `None` if the statistics of the interface aren't available.
+    packets_sent: Option<u64>,
+    packets_recv: Option<u64>,
+}
+
+impl Traffic {
+    // The traffic since `earlier`.
+    fn since(&self, earlier: &Traffic) -> Traffic {
+        let delta = |now: Option<u64>, earlier: Option<u64>| Some(now?.saturating_sub(earlier?));
+        Traffic {
+            bytes_sent: self.bytes_sent.saturating_sub(earlier.bytes_sent),
+            bytes_recv: self.bytes_recv.saturating_sub(earlier.bytes_recv),
+            packets_sent: delta(self.packets_sent, earlier.packets_sent),
+            packets_recv: delta(self.packets_recv, earlier.packets_recv),
+        }
+    }
+
+    fn add_fields(&self, write_query: WriteQuery) -> WriteQuery {
+        let mut write_query = write_query
+            .add_field("bytes_sent", self.bytes_sent)
+            .add_field("bytes_recv", self.bytes_recv);
+        if let Some(packets_sent) = self.packets_sent {
+            write_query = write_query.add_field("packets_sent", packets_sent);
+        }
+        if let Some(packets_recv) = self.packets_recv {
+            write_query = write_query.add_field("packets_recv", packets_recv);
+        }
+        write_query
+    }
+}
+
+/// Measures the traffic of discv5 from the moment this is started. The traffic of each interval is
+/// recorded with `source = traffic` every `traffic_interval` seconds if the optional param is
+/// non-zero, and the total with `source = traffic_summary` when finished. The background task is
+/// cancelled when this is dropped.
+pub(crate) struct TrafficMeter {
+    discv5: Arc<Discv5>,
+    instance_seq: u64,
+    interface: Option<String>,
+    started: Traffic,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for TrafficMeter {
+    fn drop(&mut self) {
+        if let Some(handle) = &self.handle {
+            handle.abort();
+        }
+    }
+}
+
+impl TrafficMeter {
+    pub(crate) fn start(
+        client: &Client,
+        discv5: Arc<Discv5>,
+        instance_seq: u64,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let run_parameters = client.run_parameters();
+        let interval =
+            get_param_or::<u64>("traffic_interval", &run_parameters.test_instance_params, 0)?;
+        let interface = match run_parameters.data_network_ip()? {
+            Some(ip) => interface_of(ip)?,
+            None => None,
+        };
+        if interface.is_none() {
+            warn!("The data network interface isn't found, so the packets aren't counted.");
+        }
+        let started = snapshot(&discv5, interface.as_deref());
+
+        let handle = if interval == 0 {
+            None
+        } else {
+            let client = client.clone();
+            let discv5 = discv5.clone();
+            let interface = interface.clone();
+            Some(tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+                // The first tick completes immediately.
+                ticker.tick().await;
+                let mut previous = started;
+                loop {
+                    ticker.tick().await;
+
+                    let current = snapshot(&discv5, interface.as_deref());
+                    let write_query = current.since(&previous).add_fields(
+                        WriteQuery::new(
+                            Local::now().into(),
+                            format!(
+                                "discv5-testground_{}_{}",
+                                run_parameters.test_case, run_parameters.test_run
+                            ),
+                        )
+                        .add_field("interval_secs", interval)
+                        .add_tag("instance_seq", instance_seq)
+                        .add_tag("source", "traffic"),
+                    );
+                    record_metric(&client, write_query).await;
+                    previous = current;
+                }
+            }))
+        };
+
+        Ok(TrafficMeter {
+            discv5,
+            instance_seq,
+            interface,
+            started,
+            handle,
+        })
+    }
+
+    /// Record the total traffic, along with the bytes per lookup among the `lookups` run, and per
+    /// session maintained at the end, so that the protocol overhead can be compared across runs.
+    pub(crate) async fn finish(self, client: &Client, lookups: u64) {
+        let total = snapshot(&self.discv5, self.interface.as_deref()).since(&self.started);
+        let sessions = self.discv5.metrics().active_sessions as u64;
+        let bytes = total.bytes_sent + total.bytes_recv;
+
+        let run_parameters = client.run_parameters();
+        let mut write_query = total.add_fields(
+            WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("lookups", lookups)
+            .add_field("active_sessions", sessions)
+            .add_tag("instance_seq", self.instance_seq)
+            .add_tag("source", "traffic_summary"),
+        );
+        if lookups > 0 {
+            write_query = write_query.add_field("bytes_per_lookup", bytes as f64 / lookups as f64);
+        }
+        if sessions > 0 {
+            write_query =
+                write_query.add_field("bytes_per_session", bytes as f64 / sessions as f64);
+        }
+        record_metric(client, write_query).await;
+    }
+}
+
+// The name of the interface which `ip` is assigned to.
+fn interface_of(ip: IpAddr) -> Result<Option<String>, std::io::Error> {
+    Ok(if_addrs::get_if_addrs()?
+        .into_iter()
+        .find(|interface| interface.ip() == ip)
+        .map(|interface| interface.name))
+}
+
+fn snapshot(discv5: &Discv5, interface: Option<&str>) -> Traffic {
+    let metrics = discv5.metrics();
+    Traffic {
+        bytes_sent: metrics.bytes_sent as u64,
+        bytes_recv: metrics.bytes_recv as u64,
+        packets_sent: interface.and_then(|name| interface_statistic(name, "tx_packets")),
+        packets_recv: interface.and_then(|name| interface_statistic(name, "rx_packets")),
+    }
+}
+
+fn interface_statistic(interface: &str, statistic: &str) -> Option<u64> {
+    std::fs::read_to_string(format!("/sys/class/net/{interface}/statistics/{statistic}"))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
@@ -0,0 +1,243 @@
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, listen_config,
+    publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::enr::NodeId;
+use discv5::{Discv5, Key};
+use std::collections::HashSet;
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_CONNECT: &str = "state_completed_to_connect";
+const STATE_COMPLETED_TO_SHUTDOWN: &str = "state_completed_to_shutdown";
+const STATE_COMPLETED_TO_DISCONNECT: &str = "state_completed_to_disconnect";
+const STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY: &str = "state_completed_to_run_find_node_query";
+
+// NOTE: #1 is the responder and #2 is the querier. Half of the rest become unreachable.
+fn is_unreachable(seq: u64) -> bool {
+    seq > 2 && seq % 2 == 1
+}
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("unreachable-in-results", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    let responder = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Responder")
+        .clone();
+    let entries = participants
+        .iter()
+        .filter(|p| p.seq > 2)
+        .collect::<Vec<_>>();
+
+    // //////////////////////////////////////////////////////////////
+    // Establish sessions between the responder and the entries
+    // //////////////////////////////////////////////////////////////
+    if instance_info.seq == responder.seq {
+        for p in entries.iter() {
+            discv5.add_enr(p.enr.clone())?;
+            if let Err(e) = discv5.send_ping(p.enr.clone()).await {
+                error!("Failed to send PING: {e}");
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_CONNECT,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Make some of the entries unreachable
+    // //////////////////////////////////////////////////////////////
+    if is_unreachable(instance_info.seq) {
+        discv5.shutdown();
+        client.record_message("Shut down discv5 to become unreachable.");
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_SHUTDOWN,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    let mut failed = false;
+
+    if instance_info.seq == responder.seq {
+        // The failed PINGs mark the unreachable entries as disconnected.
+        for p in entries.iter().filter(|p| is_unreachable(p.seq)) {
+            if discv5.send_ping(p.enr.clone()).await.is_ok() {
+                error!("The PING to the unreachable node #{} has succeeded.", p.seq);
+                failed = true;
+            }
+        }
+
+        let entry_count = discv5.kbuckets().iter().count();
+        let connected = discv5
+            .kbuckets()
+            .iter()
+            .filter(|entry| entry.status.is_connected())
+            .count();
+        client.record_message(format!(
+            "The responder's entries. connected: {connected}, disconnected: {}",
+            entry_count - connected
+        ));
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_DISCONNECT,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Run FINDNODE request for all the distances of the entries
+    // //////////////////////////////////////////////////////////////
+    if instance_info.seq == 2 {
+        let responder_key: Key<NodeId> = responder.enr.node_id().into();
+        let mut distances = entries
+            .iter()
+            .filter_map(|p| responder_key.log2_distance(&Key::from(p.enr.node_id())))
+            .collect::<Vec<_>>();
+        distances.sort_unstable();
+        distances.dedup();
+
+        let reachable_ids = entries
+            .iter()
+            .filter(|p| !is_unreachable(p.seq))
+            .map(|p| p.enr.node_id())
+            .collect::<HashSet<_>>();
+        let unreachable_ids = entries
+            .iter()
+            .filter(|p| is_unreachable(p.seq))
+            .map(|p| p.enr.node_id())
+            .collect::<HashSet<_>>();
+
+        match rpc_stats
+            .find_node_designated_peer(&discv5, responder.enr.clone(), distances)
+            .await
+        {
+            Ok(enrs) => {
+                let returned_reachable = enrs
+                    .iter()
+                    .filter(|enr| reachable_ids.contains(&enr.node_id()))
+                    .count();
+                let returned_unreachable = enrs
+                    .iter()
+                    .filter(|enr| unreachable_ids.contains(&enr.node_id()))
+                    .count();
+                info!(
+                    "returned: {}, reachable: {returned_reachable}/{}, unreachable: {returned_unreachable}/{}",
+                    enrs.len(),
+                    reachable_ids.len(),
+                    unreachable_ids.len()
+                );
+                client.record_message(format!(
+                    "returned_reachable: {returned_reachable}, returned_unreachable: {returned_unreachable}"
+                ));
+
+                // //////////////////////////////////////////////////////////////
+                // Record metrics
+                // //////////////////////////////////////////////////////////////
+                let write_query = WriteQuery::new(
+                    Local::now().into(),
+                    format!(
+                        "discv5-testground_{}_{}",
+                        run_parameters.test_case, run_parameters.test_run
+                    ),
+                )
+                .add_field("returned", enrs.len() as u64)
+                .add_field("reachable", reachable_ids.len() as u64)
+                .add_field("unreachable", unreachable_ids.len() as u64)
+                .add_field("returned_reachable", returned_reachable as u64)
+                .add_field("returned_unreachable", returned_unreachable as u64)
+                .add_tag("instance_seq", instance_info.seq);
+                record_metric(&client, write_query).await;
+            }
+            Err(e) => {
+                error!("Failed to run FINDNODE request: {e}");
+                failed = true;
+            }
+        }
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_RUN_FIND_NODE_QUERY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
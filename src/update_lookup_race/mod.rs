@@ -0,0 +1,210 @@
+mod params;
+
+use crate::config::config_builder;
+use crate::rpc_stats::RpcStats;
+use crate::scenario::Registry;
+use crate::teardown::teardown;
+use crate::update_lookup_race::params::Params;
+use crate::utils::{
+    barrier_timeout, barrier_with_timeout, build_enr, check_enr_port, enr_addresses, listen_config,
+    publish_and_collect, record_metric, InstanceInfo,
+};
+use chrono::Local;
+use discv5::Discv5;
+use std::time::{Duration, Instant};
+use testground::client::Client;
+use testground::WriteQuery;
+use tracing::{error, info};
+
+const STATE_COMPLETED_TO_BUILD_TOPOLOGY: &str = "state_completed_to_build_topology";
+const STATE_COMPLETED_RACE: &str = "state_completed_race";
+
+pub(super) fn register(registry: &mut Registry) {
+    registry.register("update-lookup-race", run);
+}
+
+pub(super) async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let params = Params::new(&run_parameters.test_instance_params)?;
+
+    // ////////////////////////
+    // Construct local Enr
+    // ////////////////////////
+    let (enr, enr_key) = build_enr(&run_parameters, client.global_seq())?;
+
+    // ////////////////////////
+    // Start discv5
+    // ////////////////////////
+    let listen_config = listen_config(&run_parameters)?;
+    check_enr_port(&enr, &listen_config)?;
+    let config = config_builder(listen_config, &run_parameters.test_instance_params)?.build();
+    let mut discv5: Discv5 = Discv5::new(enr.clone(), enr_key, config)?;
+    discv5
+        .start()
+        .await
+        .map_err(|e| format!("Failed to start the Discovery v5 server: {e:?}"))?;
+    let rpc_stats = RpcStats::default();
+
+    // //////////////////////////////////////////////////////////////
+    // Collect information of all participants in the test case
+    // //////////////////////////////////////////////////////////////
+    let instance_info = InstanceInfo::new(&client, enr)?;
+    client.record_message(format!(
+        "seq: {}, node_id: {}, ip: {}",
+        instance_info.seq,
+        instance_info.enr.node_id(),
+        enr_addresses(&instance_info.enr)
+    ));
+
+    let participants = publish_and_collect(&client, instance_info.clone()).await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Construct topology
+    // //////////////////////////////////////////////////////////////
+    // NOTE: #1 is the updater and #2 is the querier. The rest are the intermediaries, which know
+    // the updater, and the querier knows only the intermediaries so that it has to look the
+    // updater up.
+    let updater = participants
+        .iter()
+        .find(|p| p.seq == 1)
+        .expect("Updater")
+        .clone();
+    let intermediaries = participants
+        .iter()
+        .filter(|p| p.seq > 2)
+        .collect::<Vec<_>>();
+
+    match instance_info.seq {
+        1 => {}
+        2 => {
+            for p in intermediaries.iter() {
+                discv5.add_enr(p.enr.clone())?;
+            }
+        }
+        _ => discv5.add_enr(updater.enr.clone())?,
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_TO_BUILD_TOPOLOGY,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Update the ENR and look it up concurrently
+    // //////////////////////////////////////////////////////////////
+    let mut failed = false;
+    let race_duration = Duration::from_millis(params.update_count * params.update_interval);
+
+    if instance_info.seq == updater.seq {
+        for _ in 0..params.update_count {
+            tokio::time::sleep(Duration::from_millis(params.update_interval)).await;
+            discv5
+                .enr_insert("race", &discv5.local_enr().seq())
+                .map_err(|e| format!("Failed to update the local ENR: {e:?}"))?;
+
+            // Notify the intermediaries of the new sequence number.
+            for p in intermediaries.iter() {
+                if let Err(e) = discv5.send_ping(p.enr.clone()).await {
+                    error!("Failed to send PING: {e}");
+                }
+            }
+        }
+        client.record_message(format!(
+            "Updated the local ENR. seq: {} -> {}",
+            updater.enr.seq(),
+            discv5.local_enr().seq()
+        ));
+    } else if instance_info.seq == 2 {
+        let target_id = updater.enr.node_id();
+        // Every update bumps the sequence number by one.
+        let max_seq = updater.enr.seq() + params.update_count;
+        let mut last_seq = updater.enr.seq();
+        let mut lookups = 0_u64;
+        let mut found = 0_u64;
+        let mut invalid = 0_u64;
+
+        let started_at = Instant::now();
+        while started_at.elapsed() < race_duration {
+            lookups += 1;
+            let enrs = match rpc_stats.find_node(&discv5, target_id).await {
+                Ok(enrs) => enrs,
+                Err(e) => {
+                    error!("Failed to run FINDNODE query: {e}");
+                    continue;
+                }
+            };
+            let Some(observed) = enrs.into_iter().find(|enr| enr.node_id() == target_id) else {
+                continue;
+            };
+            found += 1;
+
+            // The observed ENR must be either an old one or the new one, properly signed, and its
+            // sequence number must not go backwards.
+            let observed_seq = observed.seq();
+            let valid = observed.verify() && observed_seq >= last_seq && observed_seq <= max_seq;
+            if valid {
+                last_seq = observed_seq;
+            } else {
+                error!(
+                    "Observed an invalid ENR. last seq: {last_seq}, observed seq: {observed_seq}, enr: {observed}"
+                );
+                invalid += 1;
+                failed = true;
+            }
+
+            // //////////////////////////////////////////////////////////////
+            // Record metrics
+            // //////////////////////////////////////////////////////////////
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("observed_seq", observed_seq)
+            .add_field("valid", valid)
+            .add_tag("instance_seq", instance_info.seq)
+            .add_tag("lookup", lookups);
+            record_metric(&client, write_query).await;
+        }
+
+        info!("lookups: {lookups}, found: {found}, invalid: {invalid}, last seq: {last_seq}");
+        client.record_message(format!(
+            "lookups: {lookups}, found: {found}, invalid: {invalid}, last_seq: {last_seq}"
+        ));
+    }
+
+    barrier_with_timeout(
+        &client,
+        STATE_COMPLETED_RACE,
+        run_parameters.test_instance_count,
+        barrier_timeout(&client)?,
+    )
+    .await?;
+
+    rpc_stats.finish(&client, instance_info.seq).await;
+    teardown(
+        &client,
+        discv5,
+        instance_info.seq,
+        run_parameters.test_instance_count,
+    )
+    .await?;
+
+    // //////////////////////////////////////////////////////////////
+    // Record result of this test
+    // //////////////////////////////////////////////////////////////
+    if failed {
+        client
+            .record_failure("Failures have happened, please check error logs for details.")
+            .await?;
+    } else {
+        client.record_success().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use crate::utils::get_param;
+use std::collections::HashMap;
+
+pub(crate) struct Params {
+    pub update_count: u64,
+    pub update_interval: u64,
+}
+
+impl Params {
+    pub(crate) fn new(
+        instance_params: &HashMap<String, String>,
+    ) -> Result<Params, Box<dyn std::error::Error>> {
+        Ok(Params {
+            update_count: get_param::<u64>("update_count", instance_params)?,
+            update_interval: get_param::<u64>("update_interval", instance_params)?,
+        })
+    }
+}
@@ -1,33 +1,477 @@
+use chrono::Local;
+use discv5::enr::k256::sha2::{Digest, Sha256};
+use discv5::enr::{k256, Builder, CombinedKey, CombinedPublicKey, NodeId};
+use discv5::{Discv5, Enr, Event, Key, ListenConfig};
+use ipnetwork::IpNetwork;
+use rand::{RngCore, SeedableRng};
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use testground::client::Client;
-use tokio_stream::StreamExt;
+use testground::network_conf::{
+    FilterAction, LinkRule, LinkShape, NetworkConfiguration, RoutingPolicyType,
+    DEFAULT_DATA_NETWORK,
+};
+use testground::{RunParameters, WriteQuery};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::{Stream, StreamExt};
+use tracing::warn;
+
+// The number of metrics that have failed to be recorded.
+static METRIC_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// The name of this implementation of discv5, which the other implementations are told apart from
+/// in the interop runs.
+pub(crate) const RUST_IMPLEMENTATION: &str = "rust";
+// The implementation of the instances which publish their information without naming it.
+const EXTERNAL_IMPLEMENTATION: &str = "external";
+
+/// The role a test instance plays in a test case. A test case plays a subset of them, and the
+/// roles are assigned either by the testground groups, see `from_group_id`, or by seq. The roles
+/// specific to a test case are defined in its module instead, e.g. in `rate-limiter`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Role {
+    Bootnode,
+    Victim,
+    Honest,
+    Attacker,
+    #[default]
+    Client,
+}
+
+impl Role {
+    /// The role assigned to the testground group `test_group_id`, if the group is named after one.
+    pub(crate) fn from_group_id(test_group_id: &str) -> Option<Role> {
+        match test_group_id {
+            "bootnodes" => Some(Role::Bootnode),
+            "victim" | "victims" => Some(Role::Victim),
+            "honest" => Some(Role::Honest),
+            "attackers" => Some(Role::Attacker),
+            "clients" => Some(Role::Client),
+            _ => None,
+        }
+    }
+
+    /// The role assigned to the testground group `test_group_id`, which must be one of `supported`
+    /// by the test case. Returns an error otherwise, e.g. for a composition with an unexpected
+    /// group name.
+    pub(crate) fn from_supported_group_id(
+        test_group_id: &str,
+        supported: &[Role],
+    ) -> Result<Role, String> {
+        match Role::from_group_id(test_group_id) {
+            Some(role) if supported.contains(&role) => Ok(role),
+            _ => Err(format!(
+                "The group `{test_group_id}` isn't named after any of the roles of this test case: {supported:?}"
+            )),
+        }
+    }
+
+    /// An error for `self` played by a peer although it isn't a role of the test case.
+    pub(crate) fn unsupported(&self) -> Box<dyn std::error::Error> {
+        format!("The role {self:?} isn't supported by this test case.").into()
+    }
+}
+
+/// An error for the instance `seq` in a test case which assigns the roles by seq, but has no role
+/// for it, e.g. for a composition with more instances than the test case supports.
+pub(crate) fn unsupported_seq(seq: u64) -> Box<dyn std::error::Error> {
+    format!("The instance #{seq} has no role in this test case, so it has too many instances.")
+        .into()
+}
+
+/// Information of a test instance, which is shared with all the other instances.
+///
+/// The instances of the other implementations of discv5 can take part by publishing the JSON with
+/// `seq` and `enr` in text form at least. The missing fields are defaulted, and the unknown ones
+/// are ignored. An instance which doesn't name its `implementation` is taken as an external one,
+/// so the instances of this implementation always set it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct InstanceInfo {
+    // The sequence number of this test instance within the test.
+    pub seq: u64,
+    pub enr: Enr,
+    #[serde(default)]
+    pub role: Role,
+    #[serde(default)]
+    pub is_bootstrap_node: bool,
+    // The testground group this test instance belongs to, e.g. to compare the groups with
+    // different link shapes.
+    #[serde(default)]
+    pub group_id: String,
+    // The implementation of discv5 this test instance runs, e.g. `go`.
+    #[serde(default = "external_implementation")]
+    pub implementation: String,
+}
+
+impl InstanceInfo {
+    pub(crate) fn new(client: &Client, enr: Enr) -> Result<Self, Box<dyn std::error::Error>> {
+        let seq = get_instance_seq(client);
+        let group_id = client.run_parameters().test_group_id;
+
+        // NOTE: The role is given by the group if the composition names the groups after the
+        // roles, e.g. `bootnodes` and `clients`. Otherwise the first `bootstrap_count` instances
+        // are the bootstrap nodes.
+        let role = match Role::from_group_id(&group_id) {
+            Some(role) => role,
+            None if seq <= bootstrap_count(client)? => Role::Bootnode,
+            None => Role::Client,
+        };
+
+        Ok(InstanceInfo {
+            seq,
+            enr,
+            role,
+            is_bootstrap_node: role == Role::Bootnode,
+            group_id,
+            implementation: RUST_IMPLEMENTATION.to_owned(),
+        })
+    }
+
+    /// The information of a test instance in a test case which assigns the roles by itself, e.g.
+    /// by seq, rather than by the groups.
+    pub(crate) fn with_role(
+        client: &Client,
+        enr: Enr,
+        role: Role,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(InstanceInfo {
+            role,
+            is_bootstrap_node: role == Role::Bootnode,
+            ..InstanceInfo::new(client, enr)?
+        })
+    }
+
+    /// Whether this test instance runs another implementation of discv5, which takes part in the
+    /// exchange of the information only. It doesn't signal the barriers.
+    pub(crate) fn is_external(&self) -> bool {
+        self.implementation != RUST_IMPLEMENTATION
+    }
+}
+
+fn external_implementation() -> String {
+    EXTERNAL_IMPLEMENTATION.to_owned()
+}
+
+/// The number of bootstrap nodes, given by the optional `bootstrap_count` param. Defaults to 1.
+pub(crate) fn bootstrap_count(client: &Client) -> Result<u64, Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let bootstrap_count =
+        get_param_or::<u64>("bootstrap_count", &run_parameters.test_instance_params, 1)?;
+
+    if bootstrap_count == 0 || bootstrap_count > run_parameters.test_instance_count {
+        return Err(format!(
+            "bootstrap_count must be between 1 and the number of instances ({}), but got {}.",
+            run_parameters.test_instance_count, bootstrap_count
+        )
+        .into());
+    }
+
+    Ok(bootstrap_count)
+}
+
+/// The sequence number of this test instance within the test.
+pub(crate) fn get_instance_seq(client: &Client) -> u64 {
+    client.global_seq()
+}
+
+/// The sequence number of this test instance within its group.
+pub(crate) fn get_group_seq(client: &Client) -> u64 {
+    client.group_seq()
+}
+
+// The default timeout of the barriers, which is long enough for the longest phase of the test
+// cases.
+const DEFAULT_BARRIER_TIMEOUT_SECS: u64 = 1800;
+
+/// The timeout of the barriers, from the optional `barrier_timeout_secs` param.
+pub(crate) fn barrier_timeout(client: &Client) -> Result<Duration, String> {
+    match get_param_or::<u64>(
+        "barrier_timeout_secs",
+        &client.run_parameters().test_instance_params,
+        DEFAULT_BARRIER_TIMEOUT_SECS,
+    )? {
+        0 => Err("barrier_timeout_secs must be non-zero.".to_owned()),
+        secs => Ok(Duration::from_secs(secs)),
+    }
+}
+
+/// Signal `state` and wait until `count` instances have signalled it. If the barrier isn't reached
+/// within `timeout`, e.g. because an instance has crashed, a failure naming the barrier is recorded
+/// and an error is returned, instead of hanging until the whole run times out.
+pub(crate) async fn barrier_with_timeout(
+    client: &Client,
+    state: impl Into<Cow<'static, str>>,
+    count: u64,
+    timeout: Duration,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let state = state.into();
+    match tokio::time::timeout(timeout, client.signal_and_wait(state.clone(), count)).await {
+        Ok(result) => Ok(result?),
+        Err(_) => {
+            let message = format!(
+                "The barrier `{state}` has not been reached by {count} instances within {} seconds.",
+                timeout.as_secs()
+            );
+            client.record_failure(message.clone()).await?;
+            Err(message.into())
+        }
+    }
+}
 
 pub(crate) async fn publish_and_collect<T: Serialize + DeserializeOwned>(
     client: &Client,
     info: T,
 ) -> Result<Vec<T>, Box<dyn std::error::Error>> {
-    const TOPIC: &str = "publish_and_collect";
+    publish_and_collect_on(client, "publish_and_collect", info).await
+}
 
-    client
-        .publish(TOPIC, Cow::Owned(serde_json::to_value(&info)?))
-        .await?;
+/// Same as `publish_and_collect`, but on `topic`, so that something other than the instance
+/// information can be exchanged later in a test.
+pub(crate) async fn publish_and_collect_on<T: Serialize + DeserializeOwned>(
+    client: &Client,
+    topic: &str,
+    info: T,
+) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+    Exchange::new(client, topic)?
+        .publish_and_collect(client, info)
+        .await
+}
 
-    let mut stream = client.subscribe(TOPIC, u16::MAX.into()).await;
+// The number of attempts to publish a message to the sync service before giving up.
+const PUBLISH_ATTEMPTS: u32 = 3;
+
+// A message of an `Exchange`, tagged with the seq of its publisher so that the missing instances
+// can be named when the collection fails.
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    seq: u64,
+    info: T,
+}
+
+impl<T: DeserializeOwned> Envelope<T> {
+    // Opens a message, which is either an envelope, or a plain one with a top-level `seq` field,
+    // e.g. the `InstanceInfo` an external peer publishes as is.
+    fn open(message: serde_json::Value) -> Result<Self, serde_json::Error> {
+        match message.get("seq").and_then(serde_json::Value::as_u64) {
+            Some(seq) if message.get("info").is_none() => Ok(Envelope {
+                seq,
+                info: serde_json::from_value(message)?,
+            }),
+            _ => serde_json::from_value(message),
+        }
+    }
+}
+
+/// An exchange of messages among the instances via the sync service. By default all the instances
+/// take part in it, and the collection times out after the optional `collect_timeout_secs` param,
+/// which defaults to the timeout of the barriers.
+#[derive(Clone, Debug)]
+pub(crate) struct Exchange {
+    topic: String,
+    // The number of the instances taking part in the exchange, whose seqs are `1..=count`.
+    count: u64,
+    // The seq of this instance within the exchange.
+    seq: u64,
+    timeout: Duration,
+}
+
+impl Exchange {
+    pub(crate) fn new(client: &Client, topic: &str) -> Result<Self, String> {
+        let run_parameters = client.run_parameters();
+        let timeout = match get_param_or::<u64>(
+            "collect_timeout_secs",
+            &run_parameters.test_instance_params,
+            barrier_timeout(client)?.as_secs(),
+        )? {
+            0 => return Err("collect_timeout_secs must be non-zero.".to_owned()),
+            secs => Duration::from_secs(secs),
+        };
+
+        Ok(Exchange {
+            topic: topic.to_owned(),
+            count: run_parameters.test_instance_count,
+            seq: client.global_seq(),
+            timeout,
+        })
+    }
+
+    /// Scope the topic by `scope`, e.g. a round, so that the repeated exchanges in a test don't
+    /// collide.
+    pub(crate) fn scoped(mut self, scope: impl fmt::Display) -> Self {
+        self.topic = format!("{}_{scope}", self.topic);
+        self
+    }
+
+    /// Scope the exchange to the group of this instance, i.e. only the instances in the group take
+    /// part in it, identified by their group seqs.
+    pub(crate) fn within_group(mut self, client: &Client) -> Self {
+        let run_parameters = client.run_parameters();
+        self.topic = format!("{}_group_{}", self.topic, run_parameters.test_group_id);
+        self.count = run_parameters.test_group_instance_count;
+        self.seq = client.group_seq();
+        self
+    }
+
+    /// Only the instances whose seqs are `1..=count` take part in the exchange.
+    pub(crate) fn count(mut self, count: u64) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Publish `info` and collect the messages of all the instances taking part in the exchange,
+    /// ordered by seq. If they haven't been collected within the timeout, a failure naming the
+    /// missing seqs is recorded and an error is returned.
+    pub(crate) async fn publish_and_collect<T: Serialize + DeserializeOwned>(
+        &self,
+        client: &Client,
+        info: T,
+    ) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        let message = serde_json::to_value(Envelope {
+            seq: self.seq,
+            info: &info,
+        })?;
+        let mut attempt = 1;
+        while let Err(e) = client
+            .publish(self.topic.as_str(), Cow::Owned(message.clone()))
+            .await
+        {
+            if attempt >= PUBLISH_ATTEMPTS {
+                return Err(Box::new(e));
+            }
+            warn!(
+                "Failed to publish to `{}` (attempt {attempt}/{PUBLISH_ATTEMPTS}): {e}",
+                self.topic
+            );
+            attempt += 1;
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        let stream = client.subscribe(self.topic.as_str(), u16::MAX.into()).await;
+        self.collect(client, stream).await
+    }
+
+    /// Collect the messages of the exchange from `stream`, i.e. the subscription to the topic,
+    /// ordered by seq. Returns an error naming the missing seqs if the stream ends before all of
+    /// them have been collected, or if they haven't been collected within the timeout, in which
+    /// case a failure is recorded as well.
+    pub(crate) async fn collect<T, S, E>(
+        &self,
+        client: &Client,
+        mut stream: S,
+    ) -> Result<Vec<T>, Box<dyn std::error::Error>>
+    where
+        T: DeserializeOwned,
+        S: Stream<Item = Result<serde_json::Value, E>> + Unpin,
+        E: std::error::Error + 'static,
+    {
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        // NOTE: A message published by a retry may be delivered twice, so they're keyed by seq.
+        let mut collected = BTreeMap::new();
+
+        while (collected.len() as u64) < self.count {
+            match tokio::time::timeout_at(deadline, stream.next()).await {
+                Ok(Some(Ok(message))) => {
+                    let envelope = Envelope::<T>::open(message)?;
+                    collected.entry(envelope.seq).or_insert(envelope.info);
+                }
+                Ok(Some(Err(e))) => return Err(Box::new(e)),
+                Ok(None) => {
+                    return Err(self
+                        .missing("The subscription has been closed", &collected)
+                        .into())
+                }
+                Err(_) => {
+                    let message = self.missing(
+                        &format!("Timed out after {} seconds", self.timeout.as_secs()),
+                        &collected,
+                    );
+                    client.record_failure(message.clone()).await?;
+                    return Err(message.into());
+                }
+            }
+        }
+
+        Ok(collected.into_values().collect())
+    }
+
+    fn missing<T>(&self, reason: &str, collected: &BTreeMap<u64, T>) -> String {
+        let missing = (1..=self.count)
+            .filter(|seq| !collected.contains_key(seq))
+            .collect::<Vec<_>>();
+        format!(
+            "{reason} while collecting on `{}`: received {} of {} messages, missing the seqs {missing:?}.",
+            self.topic,
+            collected.len(),
+            self.count
+        )
+    }
+}
 
+/// Publish `instance_info` and collect the information of all the other instances.
+pub(crate) async fn collect_instance_info(
+    client: &Client,
+    instance_info: &InstanceInfo,
+) -> Result<Vec<InstanceInfo>, Box<dyn std::error::Error>> {
+    let mut info = publish_and_collect(client, instance_info.clone()).await?;
+
+    if let Some(pos) = info.iter().position(|i| i.seq == instance_info.seq) {
+        info.remove(pos);
+    }
+
+    Ok(info)
+}
+
+/// Collect `count` messages from `stream`. Returns an error if the stream ends before `count`
+/// messages have been received, or if they haven't been received within the timeout of the
+/// barriers, in which case a failure is recorded.
+pub(crate) async fn collect_from_stream<T, S, E>(
+    client: &Client,
+    mut stream: S,
+    count: u64,
+) -> Result<Vec<T>, Box<dyn std::error::Error>>
+where
+    T: DeserializeOwned,
+    S: Stream<Item = Result<serde_json::Value, E>> + Unpin,
+    E: std::error::Error + 'static,
+{
+    let timeout = barrier_timeout(client)?;
+    let deadline = tokio::time::Instant::now() + timeout;
     let mut vec: Vec<T> = vec![];
 
-    for _ in 0..client.run_parameters().test_instance_count {
-        match stream.next().await {
-            Some(Ok(other)) => {
+    while (vec.len() as u64) < count {
+        match tokio::time::timeout_at(deadline, stream.next()).await {
+            Ok(Some(Ok(other))) => {
                 let info: T = serde_json::from_value(other)?;
                 vec.push(info);
             }
-            Some(Err(e)) => return Err(Box::new(e)),
-            None => unreachable!(),
+            Ok(Some(Err(e))) => return Err(Box::new(e)),
+            Ok(None) => {
+                return Err(format!(
+                    "The subscription has been closed after receiving {} of {} messages.",
+                    vec.len(),
+                    count
+                )
+                .into())
+            }
+            Err(_) => {
+                let message = format!(
+                    "Timed out after {} seconds after receiving {} of {} messages.",
+                    timeout.as_secs(),
+                    vec.len(),
+                    count
+                );
+                client.record_failure(message.clone()).await?;
+                return Err(message.into());
+            }
         }
     }
 
@@ -44,3 +488,1069 @@ pub(crate) fn get_param<T: FromStr>(
         .parse::<T>()
         .map_err(|_| format!("Failed to parse instance_param. key: {}", k))
 }
+
+/// Same as `get_param`, but returns `default` if the param is not specified.
+pub(crate) fn get_param_or<T: FromStr>(
+    k: &str,
+    instance_params: &HashMap<String, String>,
+    default: T,
+) -> Result<T, String> {
+    if instance_params.contains_key(k) {
+        get_param(k, instance_params)
+    } else {
+        Ok(default)
+    }
+}
+
+/// The IPv6 address of the data network, i.e. the one assigned to the same interface as the IPv4
+/// address of the data network. Link-local addresses are ignored.
+pub(crate) fn data_network_ipv6(
+    run_parameters: &RunParameters,
+) -> Result<Option<Ipv6Addr>, Box<dyn std::error::Error>> {
+    let Some(ipv4) = run_parameters.data_network_ip()? else {
+        return Ok(None);
+    };
+
+    let interfaces = if_addrs::get_if_addrs()?;
+    let Some(name) = interfaces
+        .iter()
+        .find(|interface| interface.ip() == ipv4)
+        .map(|interface| interface.name.clone())
+    else {
+        return Ok(None);
+    };
+
+    Ok(interfaces
+        .iter()
+        .filter(|interface| interface.name == name)
+        .find_map(|interface| match interface.ip() {
+            // fe80::/10 is the link-local unicast range.
+            IpAddr::V6(ip) if ip.segments()[0] & 0xffc0 != 0xfe80 => Some(ip),
+            _ => None,
+        }))
+}
+
+/// The IP version(s) which the ENR advertises and discv5 listens on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum IpVersion {
+    V4,
+    V6,
+    Dual,
+}
+
+impl IpVersion {
+    pub(crate) fn has_ipv4(&self) -> bool {
+        matches!(self, IpVersion::V4 | IpVersion::Dual)
+    }
+
+    pub(crate) fn has_ipv6(&self) -> bool {
+        matches!(self, IpVersion::V6 | IpVersion::Dual)
+    }
+
+    /// The unspecified address to bind a raw socket to, i.e. the IPv4 one unless this is IPv6
+    /// only, matching the socket addresses `enr_socket` prefers.
+    pub(crate) fn unspecified(&self) -> IpAddr {
+        if self.has_ipv4() {
+            IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+        } else {
+            IpAddr::V6(Ipv6Addr::UNSPECIFIED)
+        }
+    }
+
+    /// The config to listen on `port` of all the addresses of this IP version.
+    pub(crate) fn listen_config(&self, port: u16) -> ListenConfig {
+        match self {
+            IpVersion::V4 => ListenConfig::Ipv4 {
+                ip: Ipv4Addr::UNSPECIFIED,
+                port,
+            },
+            IpVersion::V6 => ListenConfig::Ipv6 {
+                ip: Ipv6Addr::UNSPECIFIED,
+                port,
+            },
+            IpVersion::Dual => ListenConfig::DualStack {
+                ipv4: Ipv4Addr::UNSPECIFIED,
+                ipv4_port: port,
+                ipv6: Ipv6Addr::UNSPECIFIED,
+                ipv6_port: port,
+            },
+        }
+    }
+}
+
+impl FromStr for IpVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The values of the `ip-mode` param are accepted as well, see `ip_version`.
+        match s {
+            "v4" | "ip4" => Ok(IpVersion::V4),
+            "v6" | "ip6" => Ok(IpVersion::V6),
+            "dual" => Ok(IpVersion::Dual),
+            _ => Err(format!("Unknown IP version: {s}")),
+        }
+    }
+}
+
+/// The type of the key which the local ENR is signed with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum KeyType {
+    Secp256k1,
+    Ed25519,
+    /// secp256k1 for the instances with an odd seq, and ed25519 for the ones with an even seq.
+    Mixed,
+}
+
+impl KeyType {
+    // The key is derived from `(seed, seq)` if `seed` is given, otherwise generated at random.
+    pub(crate) fn generate(&self, seed: Option<u64>, seq: u64) -> CombinedKey {
+        let ed25519 = match self {
+            KeyType::Secp256k1 => false,
+            KeyType::Ed25519 => true,
+            KeyType::Mixed => seq % 2 == 0,
+        };
+        match (seed, ed25519) {
+            (Some(seed), false) => deterministic_key(seed, seq),
+            (Some(seed), true) => deterministic_ed25519_key(seed, seq),
+            (None, false) => CombinedKey::generate_secp256k1(),
+            (None, true) => CombinedKey::generate_ed25519(),
+        }
+    }
+}
+
+impl FromStr for KeyType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "secp256k1" => Ok(KeyType::Secp256k1),
+            "ed25519" => Ok(KeyType::Ed25519),
+            "mixed" => Ok(KeyType::Mixed),
+            _ => Err(format!("Unknown key_type: {s}")),
+        }
+    }
+}
+
+/// Derive a secp256k1 key deterministically from `seed` and `seq`, so that the node ids are stable
+/// across runs.
+pub(crate) fn deterministic_key(seed: u64, seq: u64) -> CombinedKey {
+    key_from_rng_seed(mix_seed(seed, seq))
+}
+
+/// Derive a secp256k1 key from the seed of the RNG as is, in the same way as the tests of discv5,
+/// so that the seeds pre-computed there, e.g. by `find_seed_same_bucket()`, can be used.
+pub(crate) fn key_from_rng_seed(rng_seed: u64) -> CombinedKey {
+    let rng = &mut rand_xorshift::XorShiftRng::seed_from_u64(rng_seed);
+    let mut b = [0; 32];
+    let sk = loop {
+        // until a value is given within the curve order
+        rng.fill_bytes(&mut b);
+        if let Ok(k) = k256::ecdsa::SigningKey::from_slice(&b) {
+            break k;
+        }
+    };
+    CombinedKey::from(sk)
+}
+
+/// Same as `deterministic_key`, but derives an ed25519 key.
+pub(crate) fn deterministic_ed25519_key(seed: u64, seq: u64) -> CombinedKey {
+    let rng = &mut rand_xorshift::XorShiftRng::seed_from_u64(mix_seed(seed, seq));
+    let mut b = [0; 32];
+    rng.fill_bytes(&mut b);
+    CombinedKey::ed25519_from_bytes(&mut b).expect("Valid as an ed25519 secret key")
+}
+
+// The seed of the RNG for `(seed, seq)`, i.e. the first 8 bytes of `sha256(seed || seq)`. Unlike
+// `seed + seq`, the seeds of different pairs don't collide, e.g. `(1, 2)` and `(2, 1)`, so the
+// nodes derived from neighbouring seeds don't share the keys.
+fn mix_seed(seed: u64, seq: u64) -> u64 {
+    let digest = Sha256::new()
+        .chain_update(seed.to_be_bytes())
+        .chain_update(seq.to_be_bytes())
+        .finalize();
+    u64::from_be_bytes(digest[..8].try_into().expect("8 bytes of the digest"))
+}
+
+/// The seed given by the optional `seed` param, which the keys of the instances are derived from.
+pub(crate) fn seed(run_parameters: &RunParameters) -> Result<Option<u64>, String> {
+    let instance_params = &run_parameters.test_instance_params;
+    if instance_params.contains_key("seed") {
+        Ok(Some(get_param::<u64>("seed", instance_params)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The key type of the ENRs, given by the optional `key_type` param. Defaults to secp256k1.
+pub(crate) fn key_type(instance_params: &HashMap<String, String>) -> Result<KeyType, String> {
+    get_param_or::<KeyType>("key_type", instance_params, KeyType::Secp256k1)
+}
+
+/// The name of the type of the key `enr` is signed with.
+pub(crate) fn enr_key_type(enr: &Enr) -> &'static str {
+    match enr.public_key() {
+        CombinedPublicKey::Secp256k1(_) => "secp256k1",
+        CombinedPublicKey::Ed25519(_) => "ed25519",
+    }
+}
+
+/// The key of the instance whose seq is `seq`, of the type given by the `key_type` param. If the
+/// `seed` param is specified, the key is derived from `(seed, seq)`, so that the node ids, and
+/// therefore the bucket placements, are reproducible across runs. Otherwise the key is generated
+/// at random.
+pub(crate) fn instance_key(
+    run_parameters: &RunParameters,
+    seq: u64,
+) -> Result<CombinedKey, String> {
+    Ok(key_type(&run_parameters.test_instance_params)?.generate(seed(run_parameters)?, seq))
+}
+
+/// The UDP port which discv5 listens on and the ENR advertises, given by the optional `udp_port`
+/// param. Defaults to 9000.
+pub(crate) fn udp_port(instance_params: &HashMap<String, String>) -> Result<u16, String> {
+    let port = match get_param_or::<u16>("udp_port", instance_params, 9000)? {
+        0 => return Err("udp_port must be non-zero.".to_owned()),
+        port => port,
+    };
+
+    // The instances spawned by the loopback runner share the loopback address.
+    #[cfg(feature = "loopback")]
+    let port = port
+        .checked_add(crate::loopback::port_offset()?)
+        .ok_or("udp_port overflows with the port offset of the loopback runner.")?;
+
+    Ok(port)
+}
+
+/// Check that the UDP ports advertised in `enr` are the ones discv5 listens on with
+/// `listen_config`, so that the other nodes can reach this node at the advertised sockets.
+pub(crate) fn check_enr_port(enr: &Enr, listen_config: &ListenConfig) -> Result<(), String> {
+    let (ipv4_port, ipv6_port) = match listen_config {
+        ListenConfig::Ipv4 { port, .. } => (Some(*port), None),
+        ListenConfig::Ipv6 { port, .. } => (None, Some(*port)),
+        ListenConfig::DualStack {
+            ipv4_port,
+            ipv6_port,
+            ..
+        } => (Some(*ipv4_port), Some(*ipv6_port)),
+    };
+
+    for (version, advertised, listening) in [
+        ("IPv4", enr.udp4(), ipv4_port),
+        ("IPv6", enr.udp6(), ipv6_port),
+    ] {
+        match (advertised, listening) {
+            (Some(advertised), Some(listening)) if advertised != listening => {
+                return Err(format!(
+                    "The ENR advertises the {version} UDP port {advertised}, but discv5 listens on {listening}."
+                ));
+            }
+            (Some(advertised), None) => {
+                return Err(format!(
+                    "The ENR advertises the {version} UDP port {advertised}, but discv5 doesn't listen on {version}."
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// The IP version(s) of this instance, given by the optional `ip-mode` param (`ip4`, `ip6` or
+/// `dual`), or by its older name `ip_version` (`v4`, `v6` or `dual`). `ip-mode` takes precedence if
+/// both are given. Defaults to v4.
+pub(crate) fn ip_version(instance_params: &HashMap<String, String>) -> Result<IpVersion, String> {
+    if instance_params.contains_key("ip-mode") {
+        get_param::<IpVersion>("ip-mode", instance_params)
+    } else {
+        get_param_or::<IpVersion>("ip_version", instance_params, IpVersion::V4)
+    }
+}
+
+/// The config to listen on the `udp_port` of the IP version(s) given by the `ip_version` param.
+pub(crate) fn listen_config(run_parameters: &RunParameters) -> Result<ListenConfig, String> {
+    let instance_params = &run_parameters.test_instance_params;
+    Ok(ip_version(instance_params)?.listen_config(udp_port(instance_params)?))
+}
+
+/// Build the local ENR and its key based on the optional `key_type` (default: secp256k1), `seed`,
+/// `ip_version` (default: v4), `udp_port` (default: 9000) and `no_ip_seq` params. The ENR of the
+/// instance whose seq is `no_ip_seq` is built without any socket address.
+pub(crate) fn build_enr(
+    run_parameters: &RunParameters,
+    seq: u64,
+) -> Result<(Enr, CombinedKey), Box<dyn std::error::Error>> {
+    build_enr_with_ip_version(
+        run_parameters,
+        seq,
+        ip_version(&run_parameters.test_instance_params)?,
+    )
+}
+
+/// Same as `build_enr`, but advertises `ip_version` regardless of the `ip_version` param.
+pub(crate) fn build_enr_with_ip_version(
+    run_parameters: &RunParameters,
+    seq: u64,
+    ip_version: IpVersion,
+) -> Result<(Enr, CombinedKey), Box<dyn std::error::Error>> {
+    let enr_key = instance_key(run_parameters, seq)?;
+    let enr = build_enr_with_key(run_parameters, seq, ip_version, &enr_key)?;
+
+    Ok((enr, enr_key))
+}
+
+/// Same as `build_enr_with_ip_version`, but the ENR is signed with `enr_key` instead of a key of
+/// the `key_type` param, e.g. to restart a node with the same identity.
+pub(crate) fn build_enr_with_key(
+    run_parameters: &RunParameters,
+    seq: u64,
+    ip_version: IpVersion,
+    enr_key: &CombinedKey,
+) -> Result<Enr, Box<dyn std::error::Error>> {
+    let udp_port = udp_port(&run_parameters.test_instance_params)?;
+    build_enr_on_port(run_parameters, seq, ip_version, enr_key, udp_port)
+}
+
+/// Same as `build_enr_with_key`, but advertises `udp_port` instead of the `udp_port` param, e.g.
+/// for the multiple nodes of an instance.
+pub(crate) fn build_enr_on_port(
+    run_parameters: &RunParameters,
+    seq: u64,
+    ip_version: IpVersion,
+    enr_key: &CombinedKey,
+    udp_port: u16,
+) -> Result<Enr, Box<dyn std::error::Error>> {
+    build_enr_on_port_with(run_parameters, seq, ip_version, enr_key, udp_port, |_| {})
+}
+
+/// Same as `build_enr`, but `add_fields` adds the custom fields to the ENR, e.g. a fork digest.
+pub(crate) fn build_enr_with_fields(
+    run_parameters: &RunParameters,
+    seq: u64,
+    add_fields: impl FnOnce(&mut Builder<CombinedKey>),
+) -> Result<(Enr, CombinedKey), Box<dyn std::error::Error>> {
+    let enr_key = instance_key(run_parameters, seq)?;
+    let enr = build_enr_on_port_with(
+        run_parameters,
+        seq,
+        ip_version(&run_parameters.test_instance_params)?,
+        &enr_key,
+        udp_port(&run_parameters.test_instance_params)?,
+        add_fields,
+    )?;
+
+    Ok((enr, enr_key))
+}
+
+fn build_enr_on_port_with(
+    run_parameters: &RunParameters,
+    seq: u64,
+    ip_version: IpVersion,
+    enr_key: &CombinedKey,
+    udp_port: u16,
+    add_fields: impl FnOnce(&mut Builder<CombinedKey>),
+) -> Result<Enr, Box<dyn std::error::Error>> {
+    let no_ip_seq = get_param_or::<u64>("no_ip_seq", &run_parameters.test_instance_params, 0)?;
+
+    let mut builder = Enr::builder();
+    if seq != no_ip_seq {
+        if ip_version.has_ipv4() {
+            let Some(IpAddr::V4(ip)) = run_parameters.data_network_ip()? else {
+                return Err("No IPv4 address for the data network.".into());
+            };
+            builder.ip4(ip).udp4(udp_port);
+        }
+        if ip_version.has_ipv6() {
+            let Some(ip) = data_network_ipv6(run_parameters)? else {
+                return Err("No IPv6 address for the data network.".into());
+            };
+            builder.ip6(ip).udp6(udp_port);
+        }
+    }
+    add_fields(&mut builder);
+    let enr = builder
+        .build(enr_key)
+        .map_err(|e| format!("Failed to construct an Enr: {e:?}"))?;
+
+    Ok(enr)
+}
+
+/// A readable summary of the IP addresses in `enr`, which doesn't assume any of them is present.
+pub(crate) fn enr_addresses(enr: &Enr) -> String {
+    match (enr.ip4(), enr.ip6()) {
+        (Some(ip4), Some(ip6)) => format!("{ip4}, {ip6}"),
+        (Some(ip4), None) => ip4.to_string(),
+        (None, Some(ip6)) => ip6.to_string(),
+        (None, None) => format!("<no-ip> (node_id: {})", enr.node_id()),
+    }
+}
+
+/// The IP address of `enr`, preferring IPv4. Returns an error if the ENR has no IP address.
+pub(crate) fn enr_ip(enr: &Enr) -> Result<IpAddr, String> {
+    enr.ip4()
+        .map(IpAddr::V4)
+        .or_else(|| enr.ip6().map(IpAddr::V6))
+        .ok_or_else(|| format!("The ENR has no IP address. node_id: {}", enr.node_id()))
+}
+
+/// All the IP addresses of `enr`, e.g. both of them if it's dual-stack.
+pub(crate) fn enr_ips(enr: &Enr) -> Vec<IpAddr> {
+    [enr.ip4().map(IpAddr::V4), enr.ip6().map(IpAddr::V6)]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// The UDP socket address of `enr`, preferring IPv4. Returns an error if the ENR has no UDP socket
+/// address.
+pub(crate) fn enr_socket(enr: &Enr) -> Result<SocketAddr, String> {
+    enr.udp4_socket()
+        .map(SocketAddr::V4)
+        .or_else(|| enr.udp6_socket().map(SocketAddr::V6))
+        .ok_or_else(|| {
+            format!(
+                "The ENR has no UDP socket address. node_id: {}",
+                enr.node_id()
+            )
+        })
+}
+
+/// Build the default `LinkShape` of the data network based on the `latency` param, and the
+/// optional `bandwidth`, `jitter`, `loss`, `corrupt`, `reorder`, `reorder_corr` and `duplicate`
+/// params.
+///
+/// The rates are percentages between 0 and 100, as Testground takes them, e.g. `loss=5` for 5%
+/// loss and `loss=0.5` for 0.5% loss.
+pub(crate) fn link_shape(
+    instance_params: &HashMap<String, String>,
+) -> Result<LinkShape, Box<dyn std::error::Error>> {
+    link_shape_with(instance_params, |link_shape| link_shape)
+}
+
+/// Build the default `LinkShape` like `link_shape`, modified by `f`, e.g. to apply a step of a
+/// sweep. The rates of the resulting `LinkShape` are validated as well.
+pub(crate) fn link_shape_with(
+    instance_params: &HashMap<String, String>,
+    f: impl FnOnce(LinkShape) -> LinkShape,
+) -> Result<LinkShape, Box<dyn std::error::Error>> {
+    let link_shape = f(default_link_shape(instance_params)?);
+    for (k, v) in [
+        ("loss", link_shape.loss),
+        ("corrupt", link_shape.corrupt),
+        ("corrupt_corr", link_shape.corrupt_corr),
+        ("reorder", link_shape.reorder),
+        ("reorder_corr", link_shape.reorder_corr),
+        ("duplicate", link_shape.duplicate),
+        ("duplicate_corr", link_shape.duplicate_corr),
+    ] {
+        if !(0.0..=100.0).contains(&v) {
+            return Err(format!("{k} must be a percentage between 0 and 100, but got {v}.").into());
+        }
+    }
+
+    Ok(link_shape)
+}
+
+fn default_link_shape(
+    instance_params: &HashMap<String, String>,
+) -> Result<LinkShape, Box<dyn std::error::Error>> {
+    Ok(LinkShape {
+        latency: get_param::<u64>("latency", instance_params)? * 1_000_000, // Translate from millisecond to nanosecond
+        jitter: get_param_or::<u64>("jitter", instance_params, 0)? * 1_000_000, // Translate from millisecond to nanosecond
+        bandwidth: get_param_or::<u64>("bandwidth", instance_params, 1048576)?, // 1Mib by default
+        filter: FilterAction::Accept,
+        loss: get_param_or::<f32>("loss", instance_params, 0.0)?,
+        corrupt: get_param_or::<f32>("corrupt", instance_params, 0.0)?,
+        corrupt_corr: 0.0,
+        reorder: get_param_or::<f32>("reorder", instance_params, 0.0)?,
+        reorder_corr: get_param_or::<f32>("reorder_corr", instance_params, 0.0)?,
+        duplicate: get_param_or::<f32>("duplicate", instance_params, 0.0)?,
+        duplicate_corr: 0.0,
+    })
+}
+
+/// Record the configuration an instance runs with, both as a message and as a metric tagged with
+/// `source = run_config`, so that every run is self-describing. `resolved` is the params as they
+/// are parsed, keyed by the param names.
+///
+/// This doesn't fail, so that it can be called before any fallible setup.
+pub(crate) async fn record_run_config(
+    client: &Client,
+    run_parameters: &RunParameters,
+    resolved: &HashMap<String, String>,
+) {
+    let seq = get_instance_seq(client);
+    let is_bootstrap_node = bootstrap_count(client).ok().map(|count| seq <= count);
+    let params = resolved.iter().collect::<BTreeMap<_, _>>();
+
+    client.record_message(format!(
+        "Run config: test_case: {}, seq: {seq}, is_bootstrap_node: {}, params: {params:?}",
+        run_parameters.test_case,
+        is_bootstrap_node.map_or("unknown".to_owned(), |b| b.to_string()),
+    ));
+
+    let mut write_query = WriteQuery::new(
+        Local::now().into(),
+        format!(
+            "discv5-testground_{}_{}",
+            run_parameters.test_case, run_parameters.test_run
+        ),
+    )
+    .add_field("test_case", run_parameters.test_case.clone())
+    .add_tag("instance_seq", seq)
+    .add_tag("source", "run_config");
+    if let Some(is_bootstrap_node) = is_bootstrap_node {
+        write_query = write_query.add_field("is_bootstrap_node", is_bootstrap_node);
+    }
+    for (k, v) in params {
+        write_query = write_query.add_field(format!("param_{k}"), v.clone());
+    }
+    record_metric(client, write_query).await;
+}
+
+/// The name of the data network of this instance, from the optional `data_network` param.
+pub(crate) fn data_network(instance_params: &HashMap<String, String>) -> Result<String, String> {
+    get_param_or::<String>(
+        "data_network",
+        instance_params,
+        DEFAULT_DATA_NETWORK.to_owned(),
+    )
+}
+
+/// The name of the extra data network which this instance joins in addition to its data network,
+/// from the optional `extra_data_network` param.
+pub(crate) fn extra_data_network(
+    instance_params: &HashMap<String, String>,
+) -> Result<Option<String>, String> {
+    let network = get_param_or::<String>("extra_data_network", instance_params, String::new())?;
+    Ok(if network.is_empty() {
+        None
+    } else {
+        Some(network)
+    })
+}
+
+/// Apply `link_shape` to the data network of all the instances, and wait until all of them have
+/// done it.
+pub(crate) async fn reconfigure_network(
+    client: &Client,
+    link_shape: LinkShape,
+    callback_state: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    reconfigure_network_with_rules(client, link_shape, None, callback_state).await
+}
+
+/// Same as `reconfigure_network`, but also applies `rules`, which override `link_shape` for the
+/// traffic to specific subnets.
+pub(crate) async fn reconfigure_network_with_rules(
+    client: &Client,
+    link_shape: LinkShape,
+    rules: Option<Vec<LinkRule>>,
+    callback_state: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    client
+        .configure_network(NetworkConfiguration {
+            network: data_network(&client.run_parameters().test_instance_params)?,
+            ipv4: None,
+            ipv6: None,
+            enable: true,
+            default: link_shape,
+            rules,
+            callback_state,
+            callback_target: None,
+            routing_policy: RoutingPolicyType::DenyAll,
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// The rules which drop the traffic to `ips`, leaving the other traffic shaped by the default
+/// `LinkShape`.
+pub(crate) fn drop_rules(
+    instance_params: &HashMap<String, String>,
+    ips: &[IpAddr],
+) -> Result<Vec<LinkRule>, Box<dyn std::error::Error>> {
+    let mut rules = vec![];
+    for ip in ips.iter() {
+        rules.push(LinkRule {
+            link_shape: LinkShape {
+                filter: FilterAction::Drop,
+                ..link_shape(instance_params)?
+            },
+            subnet: IpNetwork::from(*ip),
+        });
+    }
+    Ok(rules)
+}
+
+/// Cut this instance off from `ips` in the middle of a test, and wait until all the instances
+/// have applied their partition. Call `heal_network` to undo it.
+pub(crate) async fn partition_network(
+    client: &Client,
+    ips: &[IpAddr],
+    callback_state: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let instance_params = &client.run_parameters().test_instance_params;
+    reconfigure_network_with_rules(
+        client,
+        link_shape(instance_params)?,
+        Some(drop_rules(instance_params, ips)?),
+        callback_state,
+    )
+    .await
+}
+
+/// Restore the default `LinkShape`, removing the rules applied by `partition_network`, and wait
+/// until all the instances have done it.
+pub(crate) async fn heal_network(
+    client: &Client,
+    callback_state: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let link_shape = link_shape(&client.run_parameters().test_instance_params)?;
+    reconfigure_network(client, link_shape, callback_state).await
+}
+
+/// Record a metric on a best-effort basis. Metrics are auxiliary to the test result, so a failure
+/// to record them, e.g. because the metrics backend is unavailable, is logged and counted instead
+/// of failing the test.
+pub(crate) async fn record_metric(client: &Client, write_query: WriteQuery) {
+    if let Err(e) = client.record_metric(write_query).await {
+        METRIC_FAILURES.fetch_add(1, Ordering::Relaxed);
+        warn!("Failed to record metric: {e}");
+    }
+}
+
+/// The number of metrics that have failed to be recorded so far.
+pub(crate) fn metric_failures() -> u64 {
+    METRIC_FAILURES.load(Ordering::Relaxed)
+}
+
+/// A background task which periodically records the metrics of discv5. The task is cancelled
+/// when this is dropped, so that it doesn't outlive the discv5 server.
+pub(crate) struct MetricsPoller {
+    handle: JoinHandle<()>,
+}
+
+impl Drop for MetricsPoller {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Spawn a task which records a snapshot of `discv5.metrics()` every `interval`.
+pub(crate) fn spawn_metrics_poller(
+    client: &Client,
+    discv5: Arc<Discv5>,
+    instance_seq: u64,
+    interval: Duration,
+) -> Result<MetricsPoller, Box<dyn std::error::Error>> {
+    if interval.is_zero() {
+        return Err("The interval of the metrics poller must be greater than zero.".into());
+    }
+
+    let client = client.clone();
+    let run_parameters = client.run_parameters();
+    let handle = tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            let metrics = discv5.metrics();
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("active_sessions", metrics.active_sessions as u64)
+            .add_field(
+                "unsolicited_requests_per_second",
+                metrics.unsolicited_requests_per_second,
+            )
+            .add_field("bytes_sent", metrics.bytes_sent as u64)
+            .add_field("bytes_recv", metrics.bytes_recv as u64)
+            .add_tag("instance_seq", instance_seq)
+            .add_tag("test_case", run_parameters.test_case.clone())
+            .add_tag("source", "metrics_poller");
+            record_metric(&client, write_query).await;
+        }
+    });
+
+    Ok(MetricsPoller { handle })
+}
+
+/// A background task which periodically records snapshots of the routing table, cancelled when
+/// this is dropped like `MetricsPoller`.
+pub(crate) struct TableSampler {
+    handle: JoinHandle<()>,
+}
+
+impl Drop for TableSampler {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Spawn a task which records a snapshot of `discv5.kbuckets()` every `table_sample_interval`
+/// seconds, so that the convergence of the routing table can be plotted over time. The bucket
+/// occupancy, the connected/disconnected and incoming/outgoing entries and the number of connected
+/// peers are recorded with `source = table_sampler`. A test case opts in by calling this, and the
+/// sampler is disabled unless the optional `table_sample_interval` param is non-zero.
+pub(crate) fn spawn_table_sampler(
+    client: &Client,
+    discv5: Arc<Discv5>,
+    instance_seq: u64,
+) -> Result<Option<TableSampler>, Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let interval = get_param_or::<u64>(
+        "table_sample_interval",
+        &run_parameters.test_instance_params,
+        0,
+    )?;
+    if interval == 0 {
+        return Ok(None);
+    }
+
+    let client = client.clone();
+    let started_at = std::time::Instant::now();
+    let handle = tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+        loop {
+            ticker.tick().await;
+
+            let entries = discv5
+                .kbuckets()
+                .iter()
+                .map(|entry| entry.status)
+                .collect::<Vec<_>>();
+            let connected = entries
+                .iter()
+                .filter(|status| status.is_connected())
+                .count();
+            let incoming = entries.iter().filter(|status| status.is_incoming()).count();
+            let write_query = WriteQuery::new(
+                Local::now().into(),
+                format!(
+                    "discv5-testground_{}_{}",
+                    run_parameters.test_case, run_parameters.test_run
+                ),
+            )
+            .add_field("elapsed_secs", started_at.elapsed().as_secs())
+            .add_field("connected_entries", connected as u64)
+            .add_field("disconnected_entries", (entries.len() - connected) as u64)
+            .add_field("incoming_entries", incoming as u64)
+            .add_field("outgoing_entries", (entries.len() - incoming) as u64)
+            .add_field("connected_peers", discv5.connected_peers() as u64)
+            .add_tag("instance_seq", instance_seq)
+            .add_tag("source", "table_sampler");
+            record_metric(
+                &client,
+                add_bucket_histogram(write_query, &bucket_histogram(&discv5)),
+            )
+            .await;
+        }
+    });
+
+    Ok(Some(TableSampler { handle }))
+}
+
+/// The farthest log2 distance in the node id space, i.e. the last bucket.
+pub(crate) const MAX_DISTANCE: u64 = 256;
+
+/// Parse the comma-separated log2 distances, each of which must be within 0..=256.
+pub(crate) fn parse_distances(s: &str) -> Result<Vec<u64>, String> {
+    s.split(',')
+        .map(|d| {
+            let distance = d
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| format!("Failed to parse the distance: {d}"))?;
+            if distance > MAX_DISTANCE {
+                return Err(format!(
+                    "The distance must be within 0..={MAX_DISTANCE}, but got {distance}."
+                ));
+            }
+            Ok(distance)
+        })
+        .collect()
+}
+
+/// The nearest-rank `p`th percentile of `sorted`, which must be sorted in ascending order.
+pub(crate) fn percentile(sorted: &[u64], p: usize) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p as f64 / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.max(1) - 1).copied()
+}
+
+/// The maximum number of entries per bucket in the discv5 routing table.
+pub(crate) const MAX_ENTRIES_PER_BUCKET: usize = 16;
+
+/// Duration to wait for the background tasks of a discv5 server to release its socket after
+/// shutting down, e.g. before restarting it on the same port.
+pub(crate) const WAIT_FOR_SHUTDOWN: Duration = Duration::from_secs(1);
+
+/// Check that the routing table has at least `min_peers` connected entries.
+pub(crate) fn assert_routing_table(discv5: &Discv5, min_peers: usize) -> Result<(), String> {
+    let connected = discv5
+        .kbuckets()
+        .iter()
+        .filter(|entry| entry.status.is_connected())
+        .count();
+
+    if connected < min_peers {
+        return Err(format!(
+            "The routing table has {connected} connected entries, but at least {min_peers} are expected."
+        ));
+    }
+
+    Ok(())
+}
+
+/// Count the buckets in the routing table that contain at least one entry.
+pub(crate) fn populated_bucket_count(discv5: &Discv5) -> usize {
+    discv5
+        .kbuckets()
+        .buckets_iter()
+        .filter(|bucket| bucket.num_entries() > 0)
+        .count()
+}
+
+/// Count the entries of each bucket in the routing table. The keys are the bucket indexes, i.e. the
+/// log2-distances from the local node id minus one. Empty buckets are omitted.
+pub(crate) fn bucket_histogram(discv5: &Discv5) -> BTreeMap<u8, usize> {
+    discv5
+        .kbuckets()
+        .buckets_iter()
+        .enumerate()
+        .filter(|(_, bucket)| bucket.num_entries() > 0)
+        .map(|(i, bucket)| (i as u8, bucket.num_entries()))
+        .collect()
+}
+
+/// Add `histogram` to `write_query` as one field per occupied bucket, along with the total number
+/// of entries so that the point has a field even if the routing table is empty.
+pub(crate) fn add_bucket_histogram(
+    write_query: WriteQuery,
+    histogram: &BTreeMap<u8, usize>,
+) -> WriteQuery {
+    let total = histogram.values().sum::<usize>();
+    histogram.iter().fold(
+        write_query.add_field("total_entries", total as u64),
+        |write_query, (index, entries)| {
+            write_query.add_field(format!("bucket_{index}"), *entries as u64)
+        },
+    )
+}
+
+/// The routing table of an instance, which is published for the topology report.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct TableSummary {
+    seq: u64,
+    node_id: String,
+    peers: Vec<String>,
+}
+
+/// The overlay graph of a run, i.e. the routing tables of all the instances.
+#[derive(Debug, Serialize)]
+struct TopologyReport {
+    nodes: Vec<TableSummary>,
+    // The seqs of the instances whose routing tables haven't been received.
+    missing: Vec<u64>,
+}
+
+// The maximum duration the coordinator waits for the routing tables after the barrier.
+const TOPOLOGY_COLLECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Publish the routing table of this instance once the `count` instances taking part, e.g. all
+/// the instances but the external peers, have signalled that their routing tables are stable. The
+/// instance #1 collects their routing tables and records a consolidated topology report, so that
+/// the overlay graph can be reconstructed offline.
+pub(crate) async fn report_topology(
+    client: &Client,
+    discv5: &Discv5,
+    count: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    const STATE_TABLES_STABILIZED: &str = "state_tables_stabilized";
+    const TOPIC: &str = "topology";
+
+    barrier_with_timeout(
+        client,
+        STATE_TABLES_STABILIZED,
+        count,
+        barrier_timeout(client)?,
+    )
+    .await?;
+
+    let seq = get_instance_seq(client);
+    let summary = TableSummary {
+        seq,
+        node_id: discv5.local_enr().node_id().to_string(),
+        peers: discv5
+            .table_entries_id()
+            .iter()
+            .map(|node_id| node_id.to_string())
+            .collect(),
+    };
+    client
+        .publish(TOPIC, Cow::Owned(serde_json::to_value(&summary)?))
+        .await?;
+
+    if seq != 1 {
+        return Ok(());
+    }
+
+    // Collect as many routing tables as possible, as some instances may have failed to publish.
+    let mut stream = client.subscribe(TOPIC, u16::MAX.into()).await;
+    let mut nodes: Vec<TableSummary> = vec![];
+    let collect = async {
+        while (nodes.len() as u64) < count {
+            match stream.next().await {
+                Some(Ok(value)) => match serde_json::from_value(value) {
+                    Ok(summary) => nodes.push(summary),
+                    Err(e) => warn!("Failed to deserialize a routing table summary: {e}"),
+                },
+                Some(Err(e)) => {
+                    warn!("Failed to receive a routing table summary: {e}");
+                    break;
+                }
+                None => break,
+            }
+        }
+    };
+    let _ = tokio::time::timeout(TOPOLOGY_COLLECT_TIMEOUT, collect).await;
+
+    nodes.sort_by_key(|n| n.seq);
+    let missing = (1..=count)
+        .filter(|seq| !nodes.iter().any(|n| n.seq == *seq))
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        warn!("No routing table has been received from the instances {missing:?}.");
+    }
+
+    client.record_message(format!(
+        "Topology: {}",
+        serde_json::to_string(&TopologyReport { nodes, missing })?
+    ));
+
+    Ok(())
+}
+
+/// Wait for the first event on `event_stream` which matches `predicate`. Returns an error if no
+/// such event has been emitted within `timeout`.
+pub(crate) async fn await_event<F>(
+    event_stream: &mut mpsc::Receiver<Event>,
+    mut predicate: F,
+    timeout: Duration,
+) -> Result<Event, String>
+where
+    F: FnMut(&Event) -> bool,
+{
+    let wait = async {
+        while let Some(event) = event_stream.recv().await {
+            if predicate(&event) {
+                return Ok(event);
+            }
+        }
+        Err("The event stream has been closed.".to_owned())
+    };
+
+    tokio::time::timeout(timeout, wait).await.map_err(|_| {
+        format!(
+            "No matching event has been emitted within {} seconds.",
+            timeout.as_secs()
+        )
+    })?
+}
+
+/// Summary of the pairwise log2-distances among a set of node ids.
+pub(crate) struct DistanceSummary {
+    pub pairs: usize,
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+}
+
+impl fmt::Display for DistanceSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pairs: {}, min: {}, max: {}, mean: {:.2}",
+            self.pairs, self.min, self.max, self.mean
+        )
+    }
+}
+
+/// Compute the pairwise log2-distances among `node_ids`. Returns `None` if there is no pair.
+pub(crate) fn distance_summary(node_ids: &[NodeId]) -> Option<DistanceSummary> {
+    let mut distances = vec![];
+    for (i, a) in node_ids.iter().enumerate() {
+        let key_a: Key<NodeId> = (*a).into();
+        for b in node_ids.iter().skip(i + 1) {
+            if let Some(distance) = key_a.log2_distance(&Key::from(*b)) {
+                distances.push(distance);
+            }
+        }
+    }
+
+    Some(DistanceSummary {
+        pairs: distances.len(),
+        min: *distances.iter().min()?,
+        max: *distances.iter().max()?,
+        mean: distances.iter().sum::<u64>() as f64 / distances.len() as f64,
+    })
+}
+
+/// RLP-encode `bytes` as a string.
+pub(crate) fn rlp_string(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return bytes.to_vec();
+    }
+    let mut encoded = rlp_length_prefix(bytes.len(), 0x80);
+    encoded.extend_from_slice(bytes);
+    encoded
+}
+
+/// RLP-encode the already encoded `items` as a list.
+pub(crate) fn rlp_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+    let mut encoded = rlp_length_prefix(payload.len(), 0xc0);
+    encoded.extend(payload);
+    encoded
+}
+
+fn rlp_length_prefix(length: usize, offset: u8) -> Vec<u8> {
+    if length <= 55 {
+        vec![offset + length as u8]
+    } else {
+        let length = length
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|b| *b == 0)
+            .collect::<Vec<_>>();
+        let mut prefix = vec![offset + 55 + length.len() as u8];
+        prefix.extend(length);
+        prefix
+    }
+}
+
+/// URL-safe base64 without padding, as used in the text form of ENRs.
+pub(crate) fn base64_url(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0_u32, |n, (i, b)| n | ((*b as u32) << (16 - i * 8)));
+        for i in 0..=chunk.len() {
+            encoded.push(ALPHABET[((n >> (18 - i * 6)) & 0x3f) as usize] as char);
+        }
+    }
+    encoded
+}
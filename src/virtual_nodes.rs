@@ -0,0 +1,103 @@
+use crate::config::build_config;
+use crate::utils::{
+    bootstrap_count, build_enr_on_port, check_enr_port, get_param_or, instance_key, ip_version,
+    publish_and_collect, udp_port, InstanceInfo, Role, RUST_IMPLEMENTATION,
+};
+use discv5::Discv5;
+use std::collections::HashMap;
+use testground::client::Client;
+use tracing::info;
+
+/// A discv5 node, out of the `nodes_per_instance` nodes a test instance runs.
+///
+/// The virtual nodes are numbered across the whole run: the nodes of the instance #`seq` have the
+/// virtual seqs `(seq - 1) * nodes_per_instance + 1..=seq * nodes_per_instance`, so that
+/// `info.seq` identifies a node in the metrics and the results, like the seq of an instance does
+/// in the test cases with a node per instance.
+pub(crate) struct VirtualNode {
+    pub info: InstanceInfo,
+    pub discv5: Discv5,
+}
+
+/// The number of discv5 nodes each test instance runs, given by the optional `nodes_per_instance`
+/// param. Defaults to 1.
+pub(crate) fn nodes_per_instance(instance_params: &HashMap<String, String>) -> Result<u64, String> {
+    match get_param_or::<u64>("nodes_per_instance", instance_params, 1)? {
+        0 => Err("nodes_per_instance must be non-zero.".to_owned()),
+        n => Ok(n),
+    }
+}
+
+/// Start the `nodes_per_instance` nodes of this instance, each with its own key and ENR, and
+/// listening on its own port counted up from the `udp_port` param.
+///
+/// The first nodes of the bootstrap instances are the bootstrap nodes, so that the number of the
+/// bootstrap nodes is `bootstrap_count` regardless of `nodes_per_instance`.
+pub(crate) async fn start_virtual_nodes(
+    client: &Client,
+) -> Result<Vec<VirtualNode>, Box<dyn std::error::Error>> {
+    let run_parameters = client.run_parameters();
+    let instance_params = &run_parameters.test_instance_params;
+    let nodes_per_instance = nodes_per_instance(instance_params)?;
+    let ip_version = ip_version(instance_params)?;
+    let seq = client.global_seq();
+    let is_bootstrap_instance = seq <= bootstrap_count(client)?;
+
+    let base_port = udp_port(instance_params)?;
+
+    let mut nodes = vec![];
+    for index in 0..nodes_per_instance {
+        let virtual_seq = (seq - 1) * nodes_per_instance + index + 1;
+        let port = u16::try_from(index)
+            .ok()
+            .and_then(|index| base_port.checked_add(index))
+            .ok_or("udp_port overflows with nodes_per_instance.")?;
+
+        let enr_key = instance_key(&run_parameters, virtual_seq)?;
+        let enr = build_enr_on_port(&run_parameters, virtual_seq, ip_version, &enr_key, port)?;
+        let listen_config = ip_version.listen_config(port);
+        check_enr_port(&enr, &listen_config)?;
+        let mut discv5 = Discv5::new(
+            enr.clone(),
+            enr_key,
+            build_config(listen_config, &run_parameters)?,
+        )?;
+        discv5.start().await.map_err(|e| {
+            format!("Failed to start the Discovery v5 server #{virtual_seq}: {e:?}")
+        })?;
+
+        let role = if is_bootstrap_instance && index == 0 {
+            Role::Bootnode
+        } else {
+            Role::Client
+        };
+        nodes.push(VirtualNode {
+            info: InstanceInfo {
+                seq: virtual_seq,
+                enr,
+                role,
+                is_bootstrap_node: role == Role::Bootnode,
+                group_id: run_parameters.test_group_id.clone(),
+                implementation: RUST_IMPLEMENTATION.to_owned(),
+            },
+            discv5,
+        });
+    }
+    info!(
+        "Started {} nodes on the ports from {base_port}.",
+        nodes.len()
+    );
+
+    Ok(nodes)
+}
+
+/// Publish the information of the nodes of this instance and collect the information of all the
+/// nodes in the test, ordered by virtual seq.
+pub(crate) async fn collect_virtual_nodes(
+    client: &Client,
+    nodes: &[VirtualNode],
+) -> Result<Vec<InstanceInfo>, Box<dyn std::error::Error>> {
+    let infos = nodes.iter().map(|n| n.info.clone()).collect::<Vec<_>>();
+    let collected = publish_and_collect(client, infos).await?;
+    Ok(collected.into_iter().flatten().collect())
+}